@@ -34,7 +34,9 @@
 //! let result = engine.execute_script_str(script_text);
 //! ```
 
+use crate::terminal::keyboard::{encode_key, KeyEventKind};
 use crate::terminal::pty::{PtyId, PtyManager};
+use iced::keyboard::{self, key::Named, Modifiers};
 use regex::Regex;
 use std::collections::HashMap;
 use std::time::Duration;
@@ -115,6 +117,68 @@ impl Key {
         }
     }
 
+    /// Convert key to terminal bytes with the kitty keyboard protocol flags in effect
+    ///
+    /// Uses the same encoder as typed keys, so scripts see the CSI-u sequences an
+    /// application requested with `CSI > flags u`. Keys the protocol leaves alone
+    /// keep the legacy bytes from [`Key::to_bytes`].
+    pub fn to_bytes_with_flags(&self, keyboard_flags: u8) -> Vec<u8> {
+        let (key, modifiers) = match self {
+            Key::Enter => (keyboard::Key::Named(Named::Enter), Modifiers::empty()),
+            Key::Tab => (keyboard::Key::Named(Named::Tab), Modifiers::empty()),
+            Key::Backspace => (keyboard::Key::Named(Named::Backspace), Modifiers::empty()),
+            Key::Escape => (keyboard::Key::Named(Named::Escape), Modifiers::empty()),
+            Key::Up => (keyboard::Key::Named(Named::ArrowUp), Modifiers::empty()),
+            Key::Down => (keyboard::Key::Named(Named::ArrowDown), Modifiers::empty()),
+            Key::Right => (keyboard::Key::Named(Named::ArrowRight), Modifiers::empty()),
+            Key::Left => (keyboard::Key::Named(Named::ArrowLeft), Modifiers::empty()),
+            Key::F(n) => {
+                let named = match n {
+                    1 => Named::F1,
+                    2 => Named::F2,
+                    3 => Named::F3,
+                    4 => Named::F4,
+                    5 => Named::F5,
+                    6 => Named::F6,
+                    7 => Named::F7,
+                    8 => Named::F8,
+                    9 => Named::F9,
+                    10 => Named::F10,
+                    11 => Named::F11,
+                    12 => Named::F12,
+                    _ => return self.to_bytes(),
+                };
+                (keyboard::Key::Named(named), Modifiers::empty())
+            }
+            Key::Home => (keyboard::Key::Named(Named::Home), Modifiers::empty()),
+            Key::End => (keyboard::Key::Named(Named::End), Modifiers::empty()),
+            Key::PageUp => (keyboard::Key::Named(Named::PageUp), Modifiers::empty()),
+            Key::PageDown => (keyboard::Key::Named(Named::PageDown), Modifiers::empty()),
+            Key::Insert => (keyboard::Key::Named(Named::Insert), Modifiers::empty()),
+            Key::Delete => (keyboard::Key::Named(Named::Delete), Modifiers::empty()),
+            Key::Ctrl(c) => (
+                keyboard::Key::Character(c.to_lowercase().to_string().into()),
+                Modifiers::CTRL,
+            ),
+            Key::Alt(c) => (
+                keyboard::Key::Character(c.to_string().into()),
+                Modifiers::ALT,
+            ),
+            Key::Char(c) => {
+                let modifiers = if c.is_uppercase() {
+                    Modifiers::SHIFT
+                } else {
+                    Modifiers::empty()
+                };
+                (keyboard::Key::Character(c.to_string().into()), modifiers)
+            }
+        };
+        match encode_key(&key, modifiers, KeyEventKind::Press, keyboard_flags) {
+            Some(sequence) => sequence.into_bytes(),
+            None => self.to_bytes(),
+        }
+    }
+
     /// Parse key from string representation
     pub fn parse_str(s: &str) -> Option<Self> {
         match s.to_uppercase().as_str() {
@@ -480,6 +544,8 @@ pub struct AutomationEngine {
     pty_id: PtyId,
     /// Execution context
     context: ExecutionContext,
+    /// Kitty keyboard protocol flags of the session's screen
+    keyboard_flags: u8,
 }
 
 impl AutomationEngine {
@@ -489,6 +555,7 @@ impl AutomationEngine {
             pty_manager,
             pty_id,
             context: ExecutionContext::default(),
+            keyboard_flags: 0,
         }
     }
 
@@ -502,9 +569,18 @@ impl AutomationEngine {
             pty_manager,
             pty_id,
             context: ExecutionContext::new(variables),
+            keyboard_flags: 0,
         }
     }
 
+    /// Set the kitty keyboard protocol flags used to encode `SendKeys`
+    ///
+    /// Callers pass the session screen's
+    /// [`keyboard_flags`](crate::terminal::screen::TerminalScreen::keyboard_flags).
+    pub fn set_keyboard_flags(&mut self, flags: u8) {
+        self.keyboard_flags = flags;
+    }
+
     /// Execute a single command
     #[instrument(skip(self), fields(pty_id = %self.pty_id))]
     pub fn execute_command(&mut self, command: &AutomationCommand) -> Result<CommandResult, AutomationError> {
@@ -530,7 +606,7 @@ impl AutomationEngine {
             AutomationCommand::SendKeys(keys) => {
                 let mut data = Vec::new();
                 for key in keys {
-                    data.extend(key.to_bytes());
+                    data.extend(key.to_bytes_with_flags(self.keyboard_flags));
                 }
                 self.pty_manager.write(&self.pty_id, &data)?;
                 CommandResult {
@@ -941,6 +1017,28 @@ mod tests {
         assert_eq!(Key::Char('a').to_bytes(), vec![b'a']);
     }
 
+    #[test]
+    fn test_key_to_bytes_with_keyboard_flags() {
+        use crate::terminal::keyboard::{
+            DISAMBIGUATE_ESCAPE_CODES, REPORT_ALL_KEYS_AS_ESCAPE_CODES,
+        };
+
+        // Without flags the legacy bytes are used
+        assert_eq!(Key::Ctrl('c').to_bytes_with_flags(0), vec![3]);
+        assert_eq!(Key::Up.to_bytes_with_flags(0), b"\x1B[A");
+
+        let flags = DISAMBIGUATE_ESCAPE_CODES;
+        assert_eq!(Key::Escape.to_bytes_with_flags(flags), b"\x1b[27u");
+        assert_eq!(Key::Ctrl('C').to_bytes_with_flags(flags), b"\x1b[99;5u");
+        assert_eq!(Key::Alt('x').to_bytes_with_flags(flags), b"\x1b[120;3u");
+        assert_eq!(Key::Char('a').to_bytes_with_flags(flags), b"a");
+        assert_eq!(Key::Enter.to_bytes_with_flags(flags), b"\r");
+
+        let flags = DISAMBIGUATE_ESCAPE_CODES | REPORT_ALL_KEYS_AS_ESCAPE_CODES;
+        assert_eq!(Key::Char('a').to_bytes_with_flags(flags), b"\x1b[97u");
+        assert_eq!(Key::Enter.to_bytes_with_flags(flags), b"\x1b[13u");
+    }
+
     #[test]
     fn test_key_parse_str() {
         assert_eq!(Key::parse_str("ENTER"), Some(Key::Enter));
@@ -130,6 +130,9 @@ pub struct SessionConfig {
     /// Prompt user before restoring crashed session
     #[serde(default = "default_true")]
     pub prompt_on_recovery: bool,
+    /// Maximum scrollback lines saved per tab (0 = don't save scrollback)
+    #[serde(default = "default_session_scrollback_lines")]
+    pub scrollback_lines: usize,
 }
 
 impl Default for SessionConfig {
@@ -142,6 +145,7 @@ impl Default for SessionConfig {
             max_backups: default_max_backups(),
             session_file: None,
             prompt_on_recovery: true,
+            scrollback_lines: default_session_scrollback_lines(),
         }
     }
 }
@@ -262,6 +266,12 @@ pub struct ScrollbackConfig {
     /// Save scrollback to file on exit (future feature)
     #[serde(default = "default_false")]
     pub save_to_file: bool,
+    /// Spill old scrollback lines to a file in the cache directory
+    #[serde(default = "default_false")]
+    pub spill_to_disk: bool,
+    /// Lines kept in memory before the oldest are spilled to disk
+    #[serde(default = "default_scrollback_memory_lines")]
+    pub memory_lines: usize,
 }
 
 impl Default for ScrollbackConfig {
@@ -270,6 +280,27 @@ impl Default for ScrollbackConfig {
             max_lines: default_scrollback_lines(),
             compression: true,
             save_to_file: false,
+            spill_to_disk: false,
+            memory_lines: default_scrollback_memory_lines(),
+        }
+    }
+}
+
+impl ScrollbackConfig {
+    /// Convert to the scrollback configuration of a terminal screen
+    ///
+    /// Spilled lines go to `agterm/scrollback` in the user cache directory.
+    pub fn to_screen_config(&self) -> crate::terminal::screen::ScrollbackConfig {
+        let disk_dir = self
+            .spill_to_disk
+            .then(dirs::cache_dir)
+            .flatten()
+            .map(|dir| dir.join("agterm").join("scrollback"));
+
+        crate::terminal::screen::ScrollbackConfig {
+            max_lines: self.max_lines,
+            disk_dir,
+            memory_lines: self.memory_lines,
         }
     }
 }
@@ -935,6 +966,9 @@ pub struct ShellConfig {
     /// Working directory (None = current directory)
     #[serde(default)]
     pub working_directory: Option<PathBuf>,
+    /// Inject shell integration into bash, zsh and fish when they start
+    #[serde(default = "default_true")]
+    pub integration: bool,
 }
 
 impl Default for ShellConfig {
@@ -945,6 +979,7 @@ impl Default for ShellConfig {
             env: HashMap::new(),
             login_shell: true,
             working_directory: None,
+            integration: true,
         }
     }
 }
@@ -1376,6 +1411,10 @@ fn default_scrollback_lines() -> usize {
     10000
 }
 
+fn default_scrollback_memory_lines() -> usize {
+    1000 // Below max_lines, so spilling to disk moves most of the scrollback
+}
+
 fn default_cursor_style() -> CursorStyle {
     CursorStyle::Block
 }
@@ -1505,6 +1544,10 @@ fn default_max_backups() -> usize {
     5 // Keep up to 5 backup files
 }
 
+fn default_session_scrollback_lines() -> usize {
+    10000 // Same as the scrollback limit of a terminal screen
+}
+
 fn default_encoding() -> String {
     "UTF-8".to_string()
 }
@@ -0,0 +1,347 @@
+//! Terminal conformance harness
+//!
+//! Replays captured PTY byte streams (`.vt` fixtures) into a headless
+//! [`TerminalScreen`] and compares a textual dump of the result against a
+//! golden snapshot stored next to the fixture (`name.vt` -> `name.snap`).
+//!
+//! A fixture is the raw byte stream, optionally preceded by a header line
+//! selecting the screen size (80x24 by default):
+//!
+//! ```text
+//! #vt size=40x10
+//! ```
+//!
+//! The dump lists the screen size, cursor, set modes, window title, the text
+//! of every row and the attributes of every styled run of cells, so an
+//! emulator bug can be filed as a fixture plus the snapshot it should produce.
+//! Snapshots are (re)written instead of compared when blessing, see
+//! [`BLESS_ENV`].
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::diff_view::{DiffLineType, MyersDiff};
+use crate::terminal::screen::{AnsiColor, Cell, Mode, TerminalScreen, UnderlineStyle};
+
+/// Environment variable that switches the test runner to bless mode
+pub const BLESS_ENV: &str = "AGTERM_BLESS";
+
+/// File extension of fixtures
+pub const FIXTURE_EXTENSION: &str = "vt";
+
+/// File extension of golden snapshots
+pub const SNAPSHOT_EXTENSION: &str = "snap";
+
+/// Prefix of the optional fixture header line
+const HEADER_PREFIX: &[u8] = b"#vt ";
+
+/// Modes listed in the dump, with the number used to set them
+const DUMPED_MODES: &[(Mode, &str)] = &[
+    (Mode::Insert, "4"),
+    (Mode::LineFeedNewLine, "20"),
+    (Mode::ApplicationCursorKeys, "?1"),
+    (Mode::ReverseScreen, "?5"),
+    (Mode::Origin, "?6"),
+    (Mode::AutoWrap, "?7"),
+    (Mode::X10Mouse, "?9"),
+    (Mode::CursorBlink, "?12"),
+    (Mode::ShowCursor, "?25"),
+    (Mode::AlternateScreen, "?47"),
+    (Mode::NormalMouse, "?1000"),
+    (Mode::ButtonEventMouse, "?1002"),
+    (Mode::AnyEventMouse, "?1003"),
+    (Mode::FocusEvents, "?1004"),
+    (Mode::Utf8Mouse, "?1005"),
+    (Mode::SgrMouse, "?1006"),
+    (Mode::UrxvtMouse, "?1015"),
+    (Mode::SgrPixelsMouse, "?1016"),
+    (Mode::AlternateScreenClear, "?1047"),
+    (Mode::AlternateScreenSaveCursor, "?1049"),
+    (Mode::BracketedPaste, "?2004"),
+    (Mode::SynchronizedOutput, "?2026"),
+];
+
+/// A captured byte stream and the screen size to replay it at
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fixture {
+    /// Screen width in columns
+    pub cols: usize,
+    /// Screen height in rows
+    pub rows: usize,
+    /// Bytes fed to the terminal
+    pub bytes: Vec<u8>,
+}
+
+impl Fixture {
+    /// Parse a fixture, splitting off the optional `#vt` header line
+    ///
+    /// Unknown header keys are ignored so that fixtures stay loadable when
+    /// new keys are added.
+    pub fn parse(data: &[u8]) -> Self {
+        let mut fixture = Self {
+            cols: 80,
+            rows: 24,
+            bytes: data.to_vec(),
+        };
+        if !data.starts_with(HEADER_PREFIX) {
+            return fixture;
+        }
+
+        let end = data.iter().position(|&b| b == b'\n').unwrap_or(data.len());
+        let header = String::from_utf8_lossy(&data[HEADER_PREFIX.len()..end]);
+        for (key, value) in header
+            .split_whitespace()
+            .filter_map(|kv| kv.split_once('='))
+        {
+            if key == "size" {
+                if let Some((cols, rows)) = value.split_once('x') {
+                    if let (Ok(cols), Ok(rows)) = (cols.parse(), rows.parse()) {
+                        fixture.cols = cols;
+                        fixture.rows = rows;
+                    }
+                }
+            }
+        }
+        fixture.bytes = data[(end + 1).min(data.len())..].to_vec();
+        fixture
+    }
+
+    /// Load a fixture from a file
+    pub fn load(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read(path)?))
+    }
+
+    /// Replay the fixture into a fresh screen
+    pub fn replay(&self) -> TerminalScreen {
+        let mut screen = TerminalScreen::new(self.cols, self.rows);
+        screen.process(&self.bytes);
+        screen
+    }
+}
+
+/// Result of checking a fixture against its snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The dump matches the snapshot
+    Match,
+    /// The snapshot was written (bless mode)
+    Blessed,
+    /// There is no snapshot yet
+    Missing {
+        /// Dump of the replayed screen
+        actual: String,
+    },
+    /// The dump differs from the snapshot
+    Mismatch {
+        /// Line diff from the snapshot to the dump
+        diff: String,
+    },
+}
+
+/// Path of the snapshot belonging to a fixture
+pub fn snapshot_path(fixture: &Path) -> PathBuf {
+    fixture.with_extension(SNAPSHOT_EXTENSION)
+}
+
+/// Replay a fixture file and compare (or, when `bless` is set, write) its snapshot
+pub fn check_fixture(path: &Path, bless: bool) -> io::Result<Outcome> {
+    let actual = dump(&Fixture::load(path)?.replay());
+    let snapshot = snapshot_path(path);
+
+    if bless {
+        fs::write(&snapshot, &actual)?;
+        return Ok(Outcome::Blessed);
+    }
+
+    match fs::read_to_string(&snapshot) {
+        Ok(expected) if expected == actual => Ok(Outcome::Match),
+        Ok(expected) => Ok(Outcome::Mismatch {
+            diff: diff(&expected, &actual),
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Outcome::Missing { actual }),
+        Err(e) => Err(e),
+    }
+}
+
+/// List the fixtures in a directory, sorted by path
+pub fn fixtures_in(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut fixtures = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == FIXTURE_EXTENSION) {
+            fixtures.push(path);
+        }
+    }
+    fixtures.sort();
+    Ok(fixtures)
+}
+
+/// Textual dump of the visible screen state
+///
+/// Row text has trailing blanks trimmed and skips the placeholder half of
+/// wide characters. Attribute runs are listed as `row:first-last attrs` with
+/// inclusive columns.
+pub fn dump(screen: &TerminalScreen) -> String {
+    let (cols, rows) = screen.dimensions();
+    let (cursor_row, cursor_col) = screen.cursor_position();
+    let mut out = String::new();
+
+    let _ = writeln!(out, "size: {cols}x{rows}");
+    let _ = writeln!(
+        out,
+        "cursor: {cursor_row},{cursor_col} {} shape={}",
+        if screen.cursor_visible() {
+            "visible"
+        } else {
+            "hidden"
+        },
+        screen.cursor_shape()
+    );
+    let modes: Vec<&str> = DUMPED_MODES
+        .iter()
+        .filter(|(mode, _)| screen.is_mode_set(*mode))
+        .map(|(_, number)| *number)
+        .collect();
+    let _ = writeln!(out, "modes: {}", modes.join(" "));
+    if let Some(title) = screen.window_title() {
+        let _ = writeln!(out, "title: {title}");
+    }
+
+    let lines = screen.current_buffer();
+    out.push_str("screen:\n");
+    for (row, line) in lines.iter().enumerate() {
+        let mut text = String::new();
+        for cell in line.iter().filter(|cell| !cell.placeholder) {
+            cell.push_grapheme(&mut text);
+        }
+        let _ = writeln!(out, "{row:3}|{}", text.trim_end());
+    }
+
+    out.push_str("attributes:\n");
+    for (row, line) in lines.iter().enumerate() {
+        let mut start = 0;
+        while start < line.len() {
+            let attrs = describe(&line[start]);
+            let mut end = start;
+            while end + 1 < line.len() && describe(&line[end + 1]) == attrs {
+                end += 1;
+            }
+            if !attrs.is_empty() {
+                let _ = writeln!(out, "{row:3}:{start}-{end} {attrs}");
+            }
+            start = end + 1;
+        }
+    }
+    out
+}
+
+/// Line diff from `expected` to `actual`, `-` for expected and `+` for actual lines
+pub fn diff(expected: &str, actual: &str) -> String {
+    let to_lines = |text: &str| text.lines().map(str::to_string).collect::<Vec<_>>();
+    let result = MyersDiff::new(to_lines(expected), to_lines(actual)).compute();
+
+    let mut out = String::new();
+    for line in result.lines {
+        let left = line.left_content.unwrap_or_default();
+        let right = line.right_content.unwrap_or_default();
+        match line.line_type {
+            DiffLineType::Unchanged => {}
+            DiffLineType::Removed => {
+                let _ = writeln!(out, "-{left}");
+            }
+            DiffLineType::Added => {
+                let _ = writeln!(out, "+{right}");
+            }
+            DiffLineType::Modified => {
+                let _ = writeln!(out, "-{left}\n+{right}");
+            }
+        }
+    }
+    out
+}
+
+/// Non-default attributes of a cell, empty for a plain cell
+fn describe(cell: &Cell) -> String {
+    let mut attrs = Vec::new();
+    if let Some(fg) = cell.fg {
+        attrs.push(format!("fg={}", color(fg)));
+    }
+    if let Some(bg) = cell.bg {
+        attrs.push(format!("bg={}", color(bg)));
+    }
+    let flags = [
+        (cell.bold, "bold"),
+        (cell.dim, "dim"),
+        (cell.italic, "italic"),
+        (cell.reverse, "reverse"),
+        (cell.blink, "blink"),
+        (cell.hidden, "hidden"),
+        (cell.strikethrough, "strike"),
+        (cell.overline, "overline"),
+    ];
+    attrs.extend(
+        flags
+            .iter()
+            .filter(|(set, _)| *set)
+            .map(|(_, name)| name.to_string()),
+    );
+    if cell.underline {
+        attrs.push(match cell.underline_style {
+            UnderlineStyle::Single => "underline".to_string(),
+            style => format!("underline={}", format!("{style:?}").to_lowercase()),
+        });
+    }
+    if let Some(underline_color) = cell.underline_color {
+        attrs.push(format!("ul={}", color(underline_color)));
+    }
+    if let Some(url) = &cell.hyperlink {
+        attrs.push(format!("link={url}"));
+    }
+    attrs.join(" ")
+}
+
+/// Compact color notation: `1` (16 colors), `256:208` (256 colors), `#ff8000` (RGB)
+fn color(color: AnsiColor) -> String {
+    match color {
+        AnsiColor::Indexed(index) => index.to_string(),
+        AnsiColor::Palette256(index) => format!("256:{index}"),
+        AnsiColor::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_header() {
+        let fixture = Fixture::parse(b"#vt size=20x4\nhello");
+        assert_eq!((fixture.cols, fixture.rows), (20, 4));
+        assert_eq!(fixture.bytes, b"hello");
+
+        let fixture = Fixture::parse(b"# root prompt");
+        assert_eq!((fixture.cols, fixture.rows), (80, 24));
+        assert_eq!(fixture.bytes, b"# root prompt");
+    }
+
+    #[test]
+    fn test_dump_text_and_attributes() {
+        let screen = Fixture::parse(b"#vt size=10x2\nab\x1b[1;31mcd\x1b[0m").replay();
+        assert_eq!(
+            dump(&screen),
+            "size: 10x2\n\
+             cursor: 0,4 visible shape=0\n\
+             modes: ?7 ?12 ?25\n\
+             screen:\n  0|abcd\n  1|\n\
+             attributes:\n  0:2-3 fg=1 bold\n"
+        );
+    }
+
+    #[test]
+    fn test_diff_marks_changed_lines() {
+        assert_eq!(diff("a\nb\nc\n", "a\nx\nc\n"), "-b\n+x\n");
+        assert_eq!(diff("a\n", "a\n"), "");
+    }
+}
@@ -24,6 +24,7 @@
 //! - Legacy protocol from DEC terminals
 //! - Limited color palette
 //! - Good for simple graphics
+//! - Decoded natively to RGBA by [`SixelDecoder`]
 
 use std::collections::HashMap;
 use thiserror::Error;
@@ -32,15 +33,19 @@ use thiserror::Error;
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum ImageFormat {
     /// PNG format (Portable Network Graphics)
+    #[allow(dead_code)]
     PNG,
     /// JPEG format
+    #[allow(dead_code)]
     JPEG,
     /// GIF format (animated GIFs not yet supported)
+    #[allow(dead_code)]
     GIF,
     /// SIXEL format (legacy terminal graphics)
     SIXEL,
 }
 
+#[allow(dead_code)]
 impl ImageFormat {
     /// Detect format from magic bytes
     pub fn from_bytes(data: &[u8]) -> Option<Self> {
@@ -80,7 +85,7 @@ impl ImageFormat {
     pub fn is_supported(&self) -> bool {
         matches!(
             self,
-            ImageFormat::PNG | ImageFormat::JPEG | ImageFormat::GIF
+            ImageFormat::PNG | ImageFormat::JPEG | ImageFormat::GIF | ImageFormat::SIXEL
         )
     }
 }
@@ -89,23 +94,28 @@ impl ImageFormat {
 #[derive(Debug, Clone)]
 pub struct ImageData {
     /// Image format
+    #[allow(dead_code)]
     pub format: ImageFormat,
     /// Raw image bytes
     pub data: Vec<u8>,
     /// Image dimensions in pixels (width, height)
     pub dimensions: Option<(u32, u32)>,
     /// Display size in terminal cells (cols, rows)
+    #[allow(dead_code)]
     pub cell_size: Option<(u16, u16)>,
     /// Placement ID for tracking (Kitty protocol)
     pub placement_id: Option<u32>,
     /// Whether to preserve aspect ratio when scaling
+    #[allow(dead_code)]
     pub preserve_aspect: bool,
     /// Position in terminal (col, row)
+    #[allow(dead_code)]
     pub position: Option<(u16, u16)>,
 }
 
 impl ImageData {
     /// Create new image data from raw bytes
+    #[allow(dead_code)]
     pub fn new(data: Vec<u8>) -> Result<Self, ImageError> {
         let format = ImageFormat::from_bytes(&data)
             .ok_or_else(|| ImageError::UnsupportedFormat("Unknown image format".to_string()))?;
@@ -141,6 +151,7 @@ impl ImageData {
     }
 
     /// Set cell size for display
+    #[allow(dead_code)]
     pub fn with_cell_size(mut self, cols: u16, rows: u16) -> Self {
         self.cell_size = Some((cols, rows));
         self
@@ -153,17 +164,20 @@ impl ImageData {
     }
 
     /// Set position in terminal
+    #[allow(dead_code)]
     pub fn with_position(mut self, col: u16, row: u16) -> Self {
         self.position = Some((col, row));
         self
     }
 
     /// Get data size in bytes
+    #[allow(dead_code)]
     pub fn size(&self) -> usize {
         self.data.len()
     }
 
     /// Check if format is supported
+    #[allow(dead_code)]
     pub fn is_supported(&self) -> bool {
         self.format.is_supported()
     }
@@ -173,18 +187,22 @@ impl ImageData {
 #[derive(Error, Debug)]
 pub enum ImageError {
     #[error("Unsupported image format: {0}")]
+    #[allow(dead_code)]
     UnsupportedFormat(String),
 
     #[error("Invalid base64 encoding: {0}")]
     Base64Error(#[from] base64::DecodeError),
 
     #[error("Invalid protocol data: {0}")]
+    #[allow(dead_code)]
     InvalidProtocol(String),
 
     #[error("Missing required parameter: {0}")]
+    #[allow(dead_code)]
     MissingParameter(String),
 
     #[error("Image decode error: {0}")]
+    #[allow(dead_code)]
     DecodeError(String),
 
     #[error("Invalid dimensions: {0}")]
@@ -193,6 +211,7 @@ pub enum ImageError {
 
 /// iTerm2 image protocol parameters
 #[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
 pub struct ITermParams {
     /// Filename (optional)
     pub name: Option<String>,
@@ -206,6 +225,7 @@ pub struct ITermParams {
     pub inline: bool,
 }
 
+#[allow(dead_code)]
 impl ITermParams {
     /// Parse iTerm2 parameters from key=value pairs
     pub fn parse(params: &str) -> Self {
@@ -249,6 +269,7 @@ impl ITermParams {
 
 /// Kitty graphics protocol parameters
 #[derive(Debug, Clone, Default)]
+#[allow(dead_code)]
 pub struct KittyParams {
     /// Action (transmit, display, query, delete)
     pub action: Option<char>,
@@ -274,6 +295,7 @@ pub struct KittyParams {
     pub more: bool,
 }
 
+#[allow(dead_code)]
 impl KittyParams {
     /// Parse Kitty protocol parameters from key=value pairs
     pub fn parse(params: &str) -> Self {
@@ -302,14 +324,366 @@ impl KittyParams {
     }
 }
 
+/// Maximum SIXEL image size in pixels (per axis)
+const MAX_SIXEL_DIMENSION: usize = 4096;
+
+/// Number of SIXEL color registers
+const SIXEL_COLOR_REGISTERS: usize = 256;
+
+/// Default VT340 color registers (RGB percentages 0-100)
+const SIXEL_DEFAULT_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),    // 0: Black
+    (20, 20, 80), // 1: Blue
+    (80, 13, 13), // 2: Red
+    (20, 80, 20), // 3: Green
+    (80, 20, 80), // 4: Magenta
+    (20, 80, 80), // 5: Cyan
+    (80, 80, 20), // 6: Yellow
+    (53, 53, 53), // 7: Gray 50%
+    (26, 26, 26), // 8: Gray 25%
+    (33, 33, 60), // 9: Blue*
+    (60, 26, 26), // 10: Red*
+    (33, 60, 33), // 11: Green*
+    (60, 33, 60), // 12: Magenta*
+    (33, 60, 60), // 13: Cyan*
+    (60, 60, 33), // 14: Yellow*
+    (80, 80, 80), // 15: Gray 75%
+];
+
+/// Decoded SIXEL image as RGBA pixels
+#[derive(Debug, Clone, PartialEq)]
+pub struct SixelImage {
+    /// Width in pixels
+    pub width: u32,
+    /// Height in pixels
+    pub height: u32,
+    /// RGBA pixel data (row-major, 4 bytes per pixel)
+    pub pixels: Vec<u8>,
+}
+
+/// SIXEL graphics decoder
+///
+/// Decodes the data part of `ESC P P1 ; P2 ; P3 q <data> ESC \` into RGBA pixels.
+/// Supports raster attributes (`"`), color register definition and selection (`#`,
+/// RGB and HLS), repeat introducers (`!`), graphics carriage return (`$`) and
+/// graphics new line (`-`).
+///
+/// The pixel aspect ratio is taken from the raster attributes; the legacy P1
+/// aspect parameter is ignored like in most modern terminals.
+#[derive(Debug)]
+pub struct SixelDecoder {
+    /// Color registers (RGB)
+    palette: Vec<[u8; 3]>,
+    /// Currently selected color register
+    color: usize,
+    /// Current column in pixels
+    x: usize,
+    /// Top pixel row of the current sixel band
+    y: usize,
+    /// Pixel rows per sixel bit (from raster attributes Pan/Pad)
+    aspect: usize,
+    /// Leave unpainted pixels transparent (P2 = 1)
+    transparent: bool,
+    /// Current image width in pixels
+    width: usize,
+    /// Current image height in pixels
+    height: usize,
+    /// Allocated canvas width in pixels (row stride of `pixels`)
+    capacity_width: usize,
+    /// Allocated canvas height in pixels
+    capacity_height: usize,
+    /// RGBA canvas, `capacity_width` x `capacity_height`
+    pixels: Vec<u8>,
+}
+
+impl SixelDecoder {
+    /// Create a decoder; `transparent` leaves unpainted pixels transparent
+    pub fn new(transparent: bool) -> Self {
+        let mut palette = vec![[0u8; 3]; SIXEL_COLOR_REGISTERS];
+        for (register, &(r, g, b)) in SIXEL_DEFAULT_PALETTE.iter().enumerate() {
+            palette[register] = [
+                percent_to_u8(r as usize),
+                percent_to_u8(g as usize),
+                percent_to_u8(b as usize),
+            ];
+        }
+
+        Self {
+            palette,
+            color: 0,
+            x: 0,
+            y: 0,
+            aspect: 1,
+            transparent,
+            width: 0,
+            height: 0,
+            capacity_width: 0,
+            capacity_height: 0,
+            pixels: Vec::new(),
+        }
+    }
+
+    /// Create a decoder from the DCS parameters (P1; P2; P3)
+    pub fn from_params(params: &[u16]) -> Self {
+        Self::new(params.get(1).copied() == Some(1))
+    }
+
+    /// Decode SIXEL data into an RGBA image
+    pub fn decode(mut self, data: &[u8]) -> Result<SixelImage, ImageError> {
+        let mut pos = 0;
+
+        while pos < data.len() {
+            let byte = data[pos];
+            pos += 1;
+
+            match byte {
+                b'"' => {
+                    // Raster attributes: " Pan ; Pad ; Ph ; Pv
+                    let (params, next) = parse_sixel_params(data, pos);
+                    pos = next;
+                    let pan = params.first().copied().unwrap_or(1);
+                    let pad = params.get(1).copied().unwrap_or(1);
+                    if pan > 0 && pad > 0 {
+                        self.aspect = ((pan + pad / 2) / pad).clamp(1, 10);
+                    }
+                    if let (Some(&ph), Some(&pv)) = (params.get(2), params.get(3)) {
+                        self.ensure_size(ph, pv)?;
+                    }
+                }
+                b'#' => {
+                    // Color introducer: # Pc (select) or # Pc ; Pu ; Px ; Py ; Pz (define)
+                    let (params, next) = parse_sixel_params(data, pos);
+                    pos = next;
+                    let register = params.first().copied().unwrap_or(0) % SIXEL_COLOR_REGISTERS;
+                    if params.len() >= 5 {
+                        let (px, py, pz) = (params[2], params[3], params[4]);
+                        self.palette[register] = match params[1] {
+                            1 => hls_to_rgb(px, py, pz),
+                            _ => [percent_to_u8(px), percent_to_u8(py), percent_to_u8(pz)],
+                        };
+                    }
+                    self.color = register;
+                }
+                b'!' => {
+                    // Repeat introducer: ! Pn <sixel>
+                    let (params, next) = parse_sixel_params(data, pos);
+                    pos = next;
+                    let count = params.first().copied().unwrap_or(1).max(1);
+                    if let Some(&sixel @ 0x3F..=0x7E) = data.get(pos) {
+                        pos += 1;
+                        self.draw(sixel - 0x3F, count)?;
+                    }
+                }
+                b'$' => {
+                    // Graphics carriage return
+                    self.x = 0;
+                }
+                b'-' => {
+                    // Graphics new line
+                    self.x = 0;
+                    self.y += 6 * self.aspect;
+                }
+                0x3F..=0x7E => self.draw(byte - 0x3F, 1)?,
+                _ => {
+                    // Whitespace and unknown bytes are ignored
+                }
+            }
+        }
+
+        if self.width == 0 || self.height == 0 {
+            return Err(ImageError::InvalidDimensions(
+                "SIXEL image has no pixels".to_string(),
+            ));
+        }
+
+        let pixels = if self.width == self.capacity_width {
+            let mut pixels = self.pixels;
+            pixels.truncate(self.width * self.height * 4);
+            pixels
+        } else {
+            let row_len = self.width * 4;
+            let stride = self.capacity_width * 4;
+            let mut pixels = Vec::with_capacity(row_len * self.height);
+            for row in 0..self.height {
+                pixels.extend_from_slice(&self.pixels[row * stride..row * stride + row_len]);
+            }
+            pixels
+        };
+
+        Ok(SixelImage {
+            width: self.width as u32,
+            height: self.height as u32,
+            pixels,
+        })
+    }
+
+    /// Draw one sixel (6 vertical pixels) `count` times at the current position
+    fn draw(&mut self, bits: u8, count: usize) -> Result<(), ImageError> {
+        let mut max_row = 0;
+        for bit in 0..6 {
+            if bits & (1 << bit) != 0 {
+                max_row = (bit + 1) * self.aspect;
+            }
+        }
+        self.ensure_size(self.x + count, self.y + max_row)?;
+
+        let [r, g, b] = self.palette[self.color];
+        for bit in 0..6 {
+            if bits & (1 << bit) == 0 {
+                continue;
+            }
+            for sub_row in 0..self.aspect {
+                let py = self.y + bit * self.aspect + sub_row;
+                for px in self.x..self.x + count {
+                    let offset = (py * self.capacity_width + px) * 4;
+                    self.pixels[offset..offset + 4].copy_from_slice(&[r, g, b, 255]);
+                }
+            }
+        }
+
+        self.x += count;
+        Ok(())
+    }
+
+    /// Grow the image to at least `width` x `height`, filling with the background
+    ///
+    /// The backing canvas grows geometrically so that images streamed band by
+    /// band are not copied once per band. Raster attributes size it up front.
+    fn ensure_size(&mut self, width: usize, height: usize) -> Result<(), ImageError> {
+        if width <= self.width && height <= self.height {
+            return Ok(());
+        }
+        if width > MAX_SIXEL_DIMENSION || height > MAX_SIXEL_DIMENSION {
+            return Err(ImageError::InvalidDimensions(format!(
+                "SIXEL image exceeds {MAX_SIXEL_DIMENSION}x{MAX_SIXEL_DIMENSION} pixels"
+            )));
+        }
+
+        self.width = width.max(self.width);
+        self.height = height.max(self.height);
+        if self.width <= self.capacity_width && self.height <= self.capacity_height {
+            return Ok(());
+        }
+
+        let grow = |needed: usize, current: usize| {
+            if needed <= current {
+                current
+            } else {
+                needed.max(current * 2).min(MAX_SIXEL_DIMENSION)
+            }
+        };
+        let new_width = grow(self.width, self.capacity_width);
+        let new_height = grow(self.height, self.capacity_height);
+        let background = if self.transparent {
+            [0, 0, 0, 0]
+        } else {
+            let [r, g, b] = self.palette[0];
+            [r, g, b, 255]
+        };
+
+        if new_width == self.capacity_width {
+            // Same stride: rows can be appended in place
+            self.pixels
+                .extend(background.repeat(new_width * (new_height - self.capacity_height)));
+        } else {
+            let mut pixels = background.repeat(new_width * new_height);
+            let row_len = self.capacity_width * 4;
+            for row in 0..self.capacity_height {
+                let src = row * row_len;
+                let dst = row * new_width * 4;
+                pixels[dst..dst + row_len].copy_from_slice(&self.pixels[src..src + row_len]);
+            }
+            self.pixels = pixels;
+        }
+
+        self.capacity_width = new_width;
+        self.capacity_height = new_height;
+        Ok(())
+    }
+}
+
+/// Parse `;`-separated decimal parameters starting at `pos`
+///
+/// Returns the parameters and the position of the first byte after them.
+fn parse_sixel_params(data: &[u8], mut pos: usize) -> (Vec<usize>, usize) {
+    let mut params = Vec::new();
+    let mut current: Option<usize> = None;
+
+    while let Some(&byte) = data.get(pos) {
+        match byte {
+            b'0'..=b'9' => {
+                let digit = (byte - b'0') as usize;
+                current = Some(
+                    current
+                        .unwrap_or(0)
+                        .saturating_mul(10)
+                        .saturating_add(digit),
+                );
+            }
+            b';' => params.push(current.take().unwrap_or(0)),
+            _ => break,
+        }
+        pos += 1;
+    }
+    if let Some(value) = current {
+        params.push(value);
+    }
+
+    (params, pos)
+}
+
+/// Convert a SIXEL percentage (0-100) to an 8-bit channel value
+fn percent_to_u8(percent: usize) -> u8 {
+    ((percent.min(100) * 255 + 50) / 100) as u8
+}
+
+/// Convert a SIXEL HLS color to RGB
+///
+/// SIXEL hue is rotated compared to the usual HLS model: 0° is blue, 120° red
+/// and 240° green.
+fn hls_to_rgb(hue: usize, lightness: usize, saturation: usize) -> [u8; 3] {
+    let h = ((hue + 240) % 360) as f32 / 360.0;
+    let l = lightness.min(100) as f32 / 100.0;
+    let s = saturation.min(100) as f32 / 100.0;
+
+    if s == 0.0 {
+        let v = (l * 255.0).round() as u8;
+        return [v, v, v];
+    }
+
+    let q = if l < 0.5 {
+        l * (1.0 + s)
+    } else {
+        l + s - l * s
+    };
+    let p = 2.0 * l - q;
+    let channel = |t: f32| {
+        let t = t.rem_euclid(1.0);
+        let v = if t < 1.0 / 6.0 {
+            p + (q - p) * 6.0 * t
+        } else if t < 0.5 {
+            q
+        } else if t < 2.0 / 3.0 {
+            p + (q - p) * (2.0 / 3.0 - t) * 6.0
+        } else {
+            p
+        };
+        (v * 255.0).round() as u8
+    };
+
+    [channel(h + 1.0 / 3.0), channel(h), channel(h - 1.0 / 3.0)]
+}
+
 /// Image protocol handler
 #[derive(Debug, Default)]
 pub struct ImageProtocol {
     /// Cache of decoded images by placement ID
+    #[allow(dead_code)]
     images: HashMap<u32, ImageData>,
     /// Next available placement ID
     next_placement_id: u32,
     /// Accumulated chunks for multi-part transmissions (Kitty)
+    #[allow(dead_code)]
     chunks: HashMap<u32, Vec<u8>>,
 }
 
@@ -322,6 +696,7 @@ impl ImageProtocol {
     /// Parse iTerm2 image protocol sequence
     ///
     /// Format: ESC ] 1337 ; File=[args] : [base64-data] ^G
+    #[allow(dead_code)]
     pub fn parse_iterm2(&mut self, sequence: &str) -> Result<ImageData, ImageError> {
         // Expected format: "File=[params]:[base64]"
         let without_prefix = sequence
@@ -376,6 +751,7 @@ impl ImageProtocol {
     /// Parse Kitty graphics protocol sequence
     ///
     /// Format: ESC _G[key=value,...];[data]ESC\
+    #[allow(dead_code)]
     pub fn parse_kitty(&mut self, params_str: &str, data: &str) -> Result<ImageData, ImageError> {
         let params = KittyParams::parse(params_str);
 
@@ -436,34 +812,80 @@ impl ImageProtocol {
         Ok(image)
     }
 
-    /// Parse SIXEL graphics sequence (basic support)
+    /// Parse SIXEL graphics sequence
     ///
     /// Format: ESC P [params] q [sixel-data] ESC \
-    pub fn parse_sixel(&mut self, _data: &str) -> Result<ImageData, ImageError> {
-        // SIXEL is complex and requires full parser implementation
-        // For now, return unsupported
-        Err(ImageError::UnsupportedFormat(
-            "SIXEL format not yet implemented".to_string(),
-        ))
+    ///
+    /// `data` is the part after the `q`. Uses default DCS parameters; see
+    /// [`ImageProtocol::parse_sixel_dcs`] to pass P1;P2;P3.
+    #[allow(dead_code)]
+    pub fn parse_sixel(&mut self, data: &str) -> Result<ImageData, ImageError> {
+        self.parse_sixel_dcs(&[], data.as_bytes())
+    }
+
+    /// Decode a SIXEL DCS payload with its numeric parameters
+    ///
+    /// The returned image has format [`ImageFormat::SIXEL`] and holds decoded
+    /// RGBA pixels (4 bytes per pixel) rather than the raw sixel data.
+    ///
+    /// Library entry point that caches the image like the other protocols; the
+    /// terminal screen decodes through [`ImageProtocol::decode_sixel_dcs`].
+    #[allow(dead_code)]
+    pub fn parse_sixel_dcs(
+        &mut self,
+        params: &[u16],
+        data: &[u8],
+    ) -> Result<ImageData, ImageError> {
+        let image = self.decode_sixel_dcs(params, data)?;
+
+        // Cache the image
+        if let Some(placement_id) = image.placement_id {
+            self.images.insert(placement_id, image.clone());
+        }
+
+        Ok(image)
+    }
+
+    /// Decode a SIXEL DCS payload without caching the result
+    ///
+    /// Like [`ImageProtocol::parse_sixel_dcs`], but for callers that keep the
+    /// pixels themselves, e.g. the terminal screen splitting them into cells.
+    pub fn decode_sixel_dcs(
+        &mut self,
+        params: &[u16],
+        data: &[u8],
+    ) -> Result<ImageData, ImageError> {
+        let decoded = SixelDecoder::from_params(params).decode(data)?;
+
+        let placement_id = self.next_placement_id;
+        self.next_placement_id += 1;
+
+        Ok(ImageData::with_format(ImageFormat::SIXEL, decoded.pixels)
+            .with_dimensions(decoded.width, decoded.height)
+            .with_placement_id(placement_id))
     }
 
     /// Get cached image by placement ID
+    #[allow(dead_code)]
     pub fn get_image(&self, placement_id: u32) -> Option<&ImageData> {
         self.images.get(&placement_id)
     }
 
     /// Remove cached image
+    #[allow(dead_code)]
     pub fn remove_image(&mut self, placement_id: u32) -> Option<ImageData> {
         self.images.remove(&placement_id)
     }
 
     /// Clear all cached images
+    #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.images.clear();
         self.chunks.clear();
     }
 
     /// Get number of cached images
+    #[allow(dead_code)]
     pub fn image_count(&self) -> usize {
         self.images.len()
     }
@@ -471,6 +893,7 @@ impl ImageProtocol {
 
 /// Image renderer for calculating display properties
 #[derive(Debug)]
+#[allow(dead_code)]
 pub struct ImageRenderer {
     /// Character cell width in pixels
     cell_width: f32,
@@ -478,6 +901,7 @@ pub struct ImageRenderer {
     cell_height: f32,
 }
 
+#[allow(dead_code)]
 impl ImageRenderer {
     /// Create a new image renderer
     pub fn new(cell_width: f32, cell_height: f32) -> Self {
@@ -556,6 +980,7 @@ impl ImageRenderer {
 
 /// Display rectangle for image rendering
 #[derive(Debug, Clone, Copy)]
+#[allow(dead_code)]
 pub struct DisplayRect {
     /// X position in pixels
     pub x: f32,
@@ -767,7 +1192,7 @@ mod tests {
     fn test_invalid_base64() {
         let mut protocol = ImageProtocol::new();
         let sequence = "File=inline=1:invalid!!!base64";
-        let result = protocol.parse_iterm2(&sequence);
+        let result = protocol.parse_iterm2(sequence);
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), ImageError::Base64Error(_)));
     }
@@ -792,4 +1217,115 @@ mod tests {
             ImageError::InvalidProtocol(_)
         ));
     }
+
+    #[test]
+    fn test_sixel_decode_basic() {
+        // Register 1 = pure red, then two full sixels
+        let image = SixelDecoder::new(false)
+            .decode(b"#1;2;100;0;0#1~~")
+            .unwrap();
+        assert_eq!((image.width, image.height), (2, 6));
+        assert_eq!(&image.pixels[0..4], &[255, 0, 0, 255]);
+        assert_eq!(image.pixels.len(), 2 * 6 * 4);
+    }
+
+    #[test]
+    fn test_sixel_decode_repeat_and_newline() {
+        let image = SixelDecoder::new(false).decode(b"#2!5~-!3@").unwrap();
+        assert_eq!((image.width, image.height), (5, 7));
+
+        // '@' only sets the top bit of the second band (row 6)
+        let offset = (6 * 5 + 2) * 4;
+        assert_eq!(image.pixels[offset + 3], 255);
+        // Column 4 of row 6 was never painted and gets the background (register 0)
+        let offset = (6 * 5 + 4) * 4;
+        assert_eq!(&image.pixels[offset..offset + 4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_sixel_decode_carriage_return_overlays() {
+        // '@' = bit 0, 'A' = bit 1; '$' returns to column 0 on the same band
+        let image = SixelDecoder::new(true)
+            .decode(b"#1;2;0;100;0#1@$#2;2;0;0;100#2A")
+            .unwrap();
+        assert_eq!((image.width, image.height), (1, 2));
+        assert_eq!(&image.pixels[0..4], &[0, 255, 0, 255]);
+        assert_eq!(&image.pixels[4..8], &[0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn test_sixel_raster_attributes() {
+        let image = SixelDecoder::new(false).decode(b"\"1;1;10;12#1~").unwrap();
+        assert_eq!((image.width, image.height), (10, 12));
+
+        // Pan/Pad = 2 doubles the height of each sixel
+        let image = SixelDecoder::new(false).decode(b"\"2;1#1~").unwrap();
+        assert_eq!((image.width, image.height), (1, 12));
+    }
+
+    #[test]
+    fn test_sixel_transparent_background() {
+        let image = SixelDecoder::from_params(&[0, 1])
+            .decode(b"\"1;1;2;6#1@")
+            .unwrap();
+        // Painted pixel is opaque, untouched pixel stays transparent
+        assert_eq!(image.pixels[3], 255);
+        assert_eq!(image.pixels[7], 0);
+    }
+
+    #[test]
+    fn test_sixel_hls_color() {
+        // HLS hue 120 is red in the SIXEL color model
+        let image = SixelDecoder::new(false)
+            .decode(b"#3;1;120;50;100#3@")
+            .unwrap();
+        assert_eq!(&image.pixels[0..4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_sixel_dimension_limit() {
+        let result = SixelDecoder::new(false).decode(b"!5000~");
+        assert!(matches!(result, Err(ImageError::InvalidDimensions(_))));
+
+        let result = SixelDecoder::new(false).decode(b"");
+        assert!(matches!(result, Err(ImageError::InvalidDimensions(_))));
+    }
+
+    #[test]
+    fn test_sixel_protocol_parsing() {
+        let mut protocol = ImageProtocol::new();
+        let image = protocol.parse_sixel("#1;2;100;100;100#1!4~").unwrap();
+
+        assert_eq!(image.format, ImageFormat::SIXEL);
+        assert_eq!(image.dimensions, Some((4, 6)));
+        assert_eq!(image.data.len(), 4 * 6 * 4);
+        assert!(image.is_supported());
+        assert_eq!(protocol.image_count(), 1);
+
+        let uncached = protocol.decode_sixel_dcs(&[], b"#1~").unwrap();
+        assert_ne!(uncached.placement_id, image.placement_id);
+        assert_eq!(protocol.image_count(), 1);
+    }
+
+    #[test]
+    fn test_sixel_decode_many_bands() {
+        // Tall image streamed band by band, wider bands appearing later
+        let mut data = Vec::new();
+        for band in 0..200 {
+            data.extend_from_slice(b"#1");
+            data.extend_from_slice(&b"~~~~~~~"[..1 + band % 7]);
+            data.push(b'-');
+        }
+        let image = SixelDecoder::new(false).decode(&data).unwrap();
+        assert_eq!((image.width, image.height), (7, 199 * 6 + 6));
+        assert_eq!(image.pixels.len(), 7 * image.height as usize * 4);
+
+        // Band 0 painted one column: column 1 keeps the background
+        assert_eq!(image.pixels[3], 255);
+        assert_eq!(&image.pixels[4..8], &[0, 0, 0, 255]);
+        // Band 6 painted all seven columns
+        let row = 6 * 6;
+        let offset = (row * 7 + 6) * 4;
+        assert_eq!(image.pixels[offset..offset + 4], image.pixels[0..4]);
+    }
 }
@@ -18,6 +18,7 @@
 //! - Terminal image protocol support (iTerm2, Kitty, SIXEL)
 //! - Terminal automation API with scripting DSL
 //! - Diff viewer with Myers algorithm for text comparison
+//! - Conformance harness replaying captured PTY output against golden snapshots
 //! - Command completion engine with file and command suggestions
 //! - Debug panel with performance metrics and event logging
 //! - Command history management with persistence
@@ -43,6 +44,7 @@ pub mod broadcast;
 pub mod clipboard_history;
 pub mod completion;
 pub mod config;
+pub mod conformance;
 pub mod debug;
 pub mod diff_view;
 pub mod encoding;
@@ -3,11 +3,14 @@
 //! Native GPU-accelerated terminal emulator with AI agent orchestration.
 //! Inspired by Warp terminal's modern block-based interface.
 
+use iced::futures::StreamExt;
 use iced::keyboard::{self, Key, Modifiers};
 use iced::widget::text_input::Id as TextInputId;
 use iced::widget::{button, column, container, row, stack, text, text_input, Space};
 use iced::{Alignment, Border, Color, Element, Font, Length, Subscription, Task};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::ops::Range;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -16,15 +19,20 @@ mod config;
 mod debug;
 mod encoding;
 mod history;
+mod image_protocol;
 mod keybind;
 mod logging;
 mod macros;
 mod mouse_actions;
 mod notification;
+mod profiles;
 mod session;
+mod session_tags;
 mod shell;
+mod shell_integration;
 mod sound;
 mod ssh;
+mod tab_manager;
 mod terminal;
 mod terminal_canvas;
 mod theme;
@@ -43,13 +51,21 @@ use keybind::Action as KeyAction;
 use logging::{LogBuffer, LoggingConfig};
 use notification::NotificationManager;
 use shell::ShellInfo;
-use terminal_canvas::{CursorState, CursorStyle, TerminalCanvas, TerminalCanvasState};
+use ssh::RemoteHost;
+use shell_integration::{ShellEvent, ShellIntegration, ShellType};
+use terminal_canvas::{
+    CursorState, CursorStyle, TerminalCanvas, TerminalCanvasMessage, TerminalCanvasState,
+};
 use theme::Theme;
-use ui::palette::{palette_input_id, CommandPalette, PaletteMessage};
+use ui::palette::{palette_input_id, CommandPalette, PaletteItem, PaletteMessage};
+use ui::search_bar::{search_input_id, SearchBar, SearchBarMessage};
 
 use terminal::env::EnvironmentInfo;
-use terminal::pty::PtyManager;
-use terminal::screen::{Cell, TerminalScreen};
+use terminal::keyboard::{encode_key, KeyEventKind, REPORT_ALL_KEYS_AS_ESCAPE_CODES};
+use terminal::mouse::encode_mouse;
+use terminal::pty::{ChildExit, PtyManager, PtySignal, SpawnSpec};
+use terminal::screen::{AnsiColor, Cell, Mode, ScrollbackSnapshot, TerminalScreen, UnderlineStyle};
+use terminal::search::{BackgroundSearch, SearchScope};
 use trigger::TriggerManager;
 
 // ============================================================================
@@ -62,6 +78,12 @@ const D2CODING_FONT: &[u8] = include_bytes!("../assets/fonts/D2Coding.ttf");
 /// Monospace font with Korean/CJK support
 const MONO_FONT: Font = Font::with_name("D2Coding");
 
+/// How often child processes are checked for exit and foreground changes
+const PROCESS_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Scrollback lines paged back in from disk each time the view reaches the top
+const HISTORY_PAGE_LINES: usize = 1000;
+
 // ============================================================================
 // Warp-inspired Dark Theme Colors (inline constants for backward compatibility)
 // ============================================================================
@@ -177,6 +199,9 @@ struct TabState {
     title: Option<String>,
     /// Tab ID (for tracking)
     id: usize,
+    /// Hash of the scrollback side file, None if no scrollback was saved
+    #[serde(default)]
+    scrollback_hash: Option<String>,
 }
 
 /// Session state for persistence across app restarts
@@ -192,6 +217,43 @@ struct SessionState {
     font_size: f32,
 }
 
+/// Session state with each tab's scrollback, taken on the UI thread
+///
+/// Encoding and writing the scrollback may take a while, so auto-saves
+/// [`write`](Self::write) it on another thread.
+struct SessionSnapshot {
+    /// Session file, next to which the scrollback side files are written
+    path: std::path::PathBuf,
+    state: SessionState,
+    /// Scrollback of each tab in `state`, empty when scrollback is not saved
+    scrollback: Vec<ScrollbackSnapshot>,
+}
+
+impl SessionSnapshot {
+    /// Write each tab's scrollback to a side file, then the session file
+    fn write(mut self) {
+        let mut scrollback_files = Vec::new();
+        for (tab, scrollback) in self.state.tabs.iter_mut().zip(self.scrollback) {
+            let path = session::scrollback_path(&self.path, tab.id);
+            match session::write_scrollback(&path, &scrollback.encode()) {
+                Ok(hash) => {
+                    tab.scrollback_hash = Some(hash);
+                    scrollback_files.push(path);
+                }
+                Err(e) => tracing::warn!("Failed to save scrollback of tab {}: {}", tab.id, e),
+            }
+        }
+
+        if let Err(e) = session::prune_scrollback(&self.path, &scrollback_files) {
+            tracing::warn!("Failed to remove old scrollback files: {}", e);
+        }
+
+        if let Err(e) = self.state.save_to_file(&self.path) {
+            tracing::error!("Failed to save session: {}", e);
+        }
+    }
+}
+
 impl SessionState {
     /// Save session state to file
     fn save_to_file(&self, path: &std::path::Path) -> Result<(), std::io::Error> {
@@ -228,12 +290,60 @@ pub struct StyledSpan {
     pub bg: Option<Color>,
     pub bold: bool,
     pub underline: bool,
+    pub underline_style: UnderlineStyle,
+    pub underline_color: Option<Color>,
     pub dim: bool,
     pub italic: bool,
     pub strikethrough: bool,
+    pub blink: bool,
+    pub overline: bool,
     pub hyperlink: Option<std::sync::Arc<String>>,
 }
 
+/// Apply reverse screen mode (DECSCNM) by reversing every cell, with the
+/// active default colors standing in for unset ones
+fn reverse_screen_cells(cells: &[Cell], default_fg: AnsiColor, default_bg: AnsiColor) -> Vec<Cell> {
+    cells
+        .iter()
+        .map(|cell| Cell {
+            fg: Some(cell.fg.unwrap_or(default_fg)),
+            bg: Some(cell.bg.unwrap_or(default_bg)),
+            reverse: !cell.reverse,
+            ..cell.clone()
+        })
+        .collect()
+}
+
+/// Background of a tab tinted with its tab color (stronger on the active tab)
+fn tab_background(base: Color, tab_color: Option<Color>, is_active: bool) -> Color {
+    let Some(tint) = tab_color else {
+        return base;
+    };
+    let amount = if is_active { 0.45 } else { 0.25 };
+    let mix = |base: f32, tint: f32| base + (tint - base) * amount;
+    Color::from_rgb(
+        mix(base.r, tint.r),
+        mix(base.g, tint.g),
+        mix(base.b, tint.b),
+    )
+}
+
+/// Default foreground and background of a screen: the OSC 10/11 overrides,
+/// falling back to the theme's terminal colors
+fn screen_default_colors(screen: &TerminalScreen, theme: &Theme) -> (AnsiColor, AnsiColor) {
+    let resolve = |set: Option<(u8, u8, u8)>, fallback: &theme::ColorDef| {
+        set.map(|(r, g, b)| AnsiColor::Rgb(r, g, b))
+            .unwrap_or_else(|| {
+                let [r, g, b, _] = fallback.to_color().into_rgba8();
+                AnsiColor::Rgb(r, g, b)
+            })
+    };
+    (
+        resolve(screen.default_fg_color(), &theme.terminal.foreground),
+        resolve(screen.default_bg_color(), &theme.terminal.background),
+    )
+}
+
 /// Convert terminal cells to styled spans
 fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
     let mut spans = Vec::new();
@@ -242,9 +352,13 @@ fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
     let mut current_bg: Option<Color> = None;
     let mut current_bold = false;
     let mut current_underline = false;
+    let mut current_underline_style = UnderlineStyle::Single;
+    let mut current_underline_color: Option<Color> = None;
     let mut current_dim = false;
     let mut current_italic = false;
     let mut current_strikethrough = false;
+    let mut current_blink = false;
+    let mut current_overline = false;
     let mut current_hyperlink: Option<std::sync::Arc<String>> = None;
 
     for cell in cells {
@@ -264,15 +378,20 @@ fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
             let bg = cell.bg.as_ref().map(|c| c.to_color());
             (fg, bg)
         };
+        let underline_color = cell.underline_color.as_ref().map(|c| c.to_color());
 
         // If any style attribute changes, push current span and start new one
         if fg_color != current_color
             || bg_color != current_bg
             || cell.bold != current_bold
             || cell.underline != current_underline
+            || cell.underline_style != current_underline_style
+            || underline_color != current_underline_color
             || cell.dim != current_dim
             || cell.italic != current_italic
             || cell.strikethrough != current_strikethrough
+            || cell.blink != current_blink
+            || cell.overline != current_overline
             || cell.hyperlink != current_hyperlink
         {
             if !current_text.is_empty() {
@@ -282,9 +401,13 @@ fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
                     bg: current_bg,
                     bold: current_bold,
                     underline: current_underline,
+                    underline_style: current_underline_style,
+                    underline_color: current_underline_color,
                     dim: current_dim,
                     italic: current_italic,
                     strikethrough: current_strikethrough,
+                    blink: current_blink,
+                    overline: current_overline,
                     hyperlink: current_hyperlink.clone(),
                 });
             }
@@ -292,13 +415,22 @@ fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
             current_bg = bg_color;
             current_bold = cell.bold;
             current_underline = cell.underline;
+            current_underline_style = cell.underline_style;
+            current_underline_color = underline_color;
             current_dim = cell.dim;
             current_italic = cell.italic;
             current_strikethrough = cell.strikethrough;
+            current_blink = cell.blink;
+            current_overline = cell.overline;
             current_hyperlink = cell.hyperlink.clone();
         }
 
-        current_text.push(cell.c);
+        // Concealed text (SGR 8) keeps its cells but shows nothing
+        if cell.hidden {
+            current_text.push(' ');
+        } else {
+            cell.push_grapheme(&mut current_text);
+        }
     }
 
     // Push final span
@@ -309,9 +441,13 @@ fn cells_to_styled_spans(cells: &[Cell]) -> Vec<StyledSpan> {
             bg: current_bg,
             bold: current_bold,
             underline: current_underline,
+            underline_style: current_underline_style,
+            underline_color: current_underline_color,
             dim: current_dim,
             italic: current_italic,
             strikethrough: current_strikethrough,
+            blink: current_blink,
+            overline: current_overline,
             hyperlink: current_hyperlink,
         });
     }
@@ -401,11 +537,62 @@ fn raw_input_id() -> TextInputId {
     TextInputId::new("raw_terminal_input")
 }
 
+/// Whether the raw mode text input receives a key press (and writes it to the PTY)
+///
+/// Plain and shifted text, Space, Enter and Backspace are typed into the text
+/// input for IME support; Ctrl, Alt and Super combinations are not.
+fn typed_by_text_input(key: &Key, modifiers: Modifiers) -> bool {
+    match key.as_ref() {
+        Key::Named(keyboard::key::Named::Enter | keyboard::key::Named::Backspace) => true,
+        Key::Character(_) | Key::Named(keyboard::key::Named::Space) => {
+            !(modifiers.control() || modifiers.alt() || modifiers.logo())
+        }
+        _ => false,
+    }
+}
+
 /// Get global configuration
 fn get_config() -> AppConfig {
     APP_CONFIG.get().cloned().unwrap_or_else(AppConfig::default)
 }
 
+/// Create a terminal screen with the configured scrollback
+fn new_screen(cols: usize, rows: usize) -> TerminalScreen {
+    let scrollback = get_config().terminal.scrollback.to_screen_config();
+    TerminalScreen::with_scrollback(cols, rows, scrollback)
+}
+
+/// Spawn spec of a new shell, with the shell integration if enabled
+fn shell_spec(profile: Option<&profiles::Profile>) -> SpawnSpec {
+    SpawnSpec {
+        shell_integration: get_config().shell.integration,
+        ..profile
+            .map(profiles::Profile::spawn_spec)
+            .unwrap_or_default()
+    }
+}
+
+/// Type commands into a freshly spawned shell
+///
+/// They wait in the terminal's input queue until the shell reads them.
+fn type_commands(pty_manager: &PtyManager, session_id: &uuid::Uuid, commands: &[String]) {
+    for command in commands {
+        let _ = pty_manager.write(session_id, format!("{command}\r").as_bytes());
+    }
+}
+
+/// Message for a palette command added at runtime (SSH hosts)
+fn dynamic_palette_message(command_id: &str) -> Option<Message> {
+    command_id
+        .strip_prefix("ssh:")
+        .map(|name| Message::OpenSshProfile(name.to_string()))
+}
+
+/// Tab-manager record holding a tab's color and metadata (e.g. its SSH theme)
+fn new_tab_meta() -> tab_manager::Tab {
+    tab_manager::Tab::new(String::new(), std::path::PathBuf::new(), String::new())
+}
+
 /// Convert config cursor style to terminal canvas cursor style
 fn convert_cursor_style(style: config::CursorStyle) -> CursorStyle {
     match style {
@@ -415,6 +602,16 @@ fn convert_cursor_style(style: config::CursorStyle) -> CursorStyle {
     }
 }
 
+/// Cursor style requested by the application via DECSCUSR, or the configured style
+fn screen_cursor_style(shape: u16, default: config::CursorStyle) -> CursorStyle {
+    match shape {
+        1 | 2 => CursorStyle::Block,
+        3 | 4 => CursorStyle::Underline,
+        5 | 6 => CursorStyle::Bar,
+        _ => convert_cursor_style(default),
+    }
+}
+
 /// Main application state
 struct AgTerm {
     tabs: Vec<TerminalTab>,
@@ -428,15 +625,12 @@ struct AgTerm {
     last_pty_activity: Instant,
     /// Last cursor blink toggle time
     last_cursor_blink: Instant,
+    /// Last time child processes were checked for exit and foreground changes
+    last_process_poll: Instant,
     /// Font size (8.0 ~ 24.0)
     font_size: f32,
-    /// Search state (reserved for future search functionality)
-    #[allow(dead_code)]
-    search_mode: bool,
-    #[allow(dead_code)]
-    search_query: String,
-    search_matches: Vec<(usize, usize, usize)>, // (line, start_col, end_col)
-    current_match_index: Option<usize>,
+    /// Scrollback search bar, shown while open
+    search_bar: Option<SearchBar>,
     /// Environment information (SSH, container, terminal capabilities, etc.)
     env_info: EnvironmentInfo,
     /// Bell sound player
@@ -455,8 +649,14 @@ struct AgTerm {
     tab_rename_input: String,
     /// Current theme
     current_theme: Theme,
+    /// Theme of tabs without an SSH profile theme (configured or switched to)
+    base_theme: Theme,
     /// Current keyboard modifiers (for Ctrl+Click URL opening)
     current_modifiers: Modifiers,
+    /// Whether the window has keyboard focus
+    window_focused: bool,
+    /// Tab (by ID) waiting for the user to confirm closing it, and why
+    close_confirmation: Option<(usize, String)>,
     /// Desktop notification manager
     notification_manager: NotificationManager,
     /// Key bindings manager
@@ -473,6 +673,16 @@ struct AgTerm {
     completion_visible: bool,
     /// Output trigger manager
     trigger_manager: TriggerManager,
+    /// SSH profiles, applied to tabs connected to their hosts
+    ssh_profiles: ssh::SshProfileManager,
+    /// Whether SSH sessions started in a tab are detected
+    detect_ssh_connection: bool,
+    /// Tags of sessions (from SSH profiles)
+    session_tags: session_tags::SessionTagManager,
+    /// Shell profiles; new tabs run the default one
+    profiles: profiles::ProfileManager,
+    /// Whether an auto-save is being written on another thread
+    saving_session: bool,
 }
 
 impl Default for AgTerm {
@@ -507,12 +717,27 @@ impl Default for AgTerm {
             suggested_settings.refresh_rate_ms
         );
 
+        let mut profiles = profiles::ProfileManager::new();
+        if let Err(e) = profiles.init() {
+            tracing::warn!("Failed to load profiles: {}", e);
+        }
+        let default_profile = profiles.get_default_profile();
+
+        // Scrollback spilled to disk by a crashed instance is never read again
+        if let Some(dir) = config.terminal.scrollback.to_screen_config().disk_dir {
+            match terminal::screen::ScrollbackBuffer::remove_stale_files(&dir) {
+                Ok(0) => {}
+                Ok(removed) => tracing::info!("Removed {} stale scrollback files", removed),
+                Err(e) => tracing::warn!("Failed to remove stale scrollback files: {}", e),
+            }
+        }
+
         let pty_manager = Arc::new(PtyManager::new());
 
         // Try to restore session first
         let (tabs, active_tab, font_size, next_tab_id) =
             if let Some((restored_tabs, restored_active, restored_font)) =
-                Self::restore_session(&config, &pty_manager)
+                Self::restore_session(&config, &pty_manager, default_profile)
             {
                 // Calculate next_tab_id from restored tabs
                 let max_id = restored_tabs.iter().map(|t| t.id).max().unwrap_or(0);
@@ -521,8 +746,16 @@ impl Default for AgTerm {
                 (restored_tabs, restored_active, restored_font, max_id + 1)
             } else {
                 // No session to restore, create a fresh tab
-                let session_result =
-                    pty_manager.create_session(config.pty.default_rows, config.pty.default_cols);
+                let spec = shell_spec(default_profile);
+                let shell = TabShell::new(&spec);
+                let session_result = pty_manager.create_session_with_spec(
+                    config.pty.default_rows,
+                    config.pty.default_cols,
+                    spec,
+                );
+                if let (Ok(session_id), Some(profile)) = (&session_result, default_profile) {
+                    type_commands(&pty_manager, session_id, &profile.startup_commands);
+                }
                 let cwd = config
                     .general
                     .default_working_dir
@@ -561,7 +794,10 @@ impl Default for AgTerm {
                     parsed_line_cache: Vec::new(),
                     canvas_state: TerminalCanvasState::new(),
                     content_version: 0,
-                    screen: TerminalScreen::new(
+                    frame_pending: false,
+                    history_lines: 0,
+                    first_rendered_line: 0,
+                    screen: new_screen(
                         config.pty.default_cols as usize,
                         config.pty.default_rows as usize,
                     ),
@@ -574,6 +810,13 @@ impl Default for AgTerm {
                     panes: Vec::new(),
                     focused_pane: 0,
                     title_info: terminal::title::TitleInfo::new(),
+                    child_exit: None,
+                    meta: new_tab_meta(),
+                    search: None,
+                    search_version: 0,
+                    scroll_request: None,
+                    shell,
+                    remote: RemoteHost::default(),
                 };
 
                 (vec![tab], 0, config.appearance.font.size, 1)
@@ -616,6 +859,26 @@ impl Default for AgTerm {
             }
         }
 
+        // Profiles from the app config take precedence over ~/.ssh/config
+        let mut ssh_profiles = if config.ssh.load_from_ssh_config {
+            ssh::SshProfileManager::load_from_ssh_config()
+        } else {
+            ssh::SshProfileManager::new()
+        };
+        for profile in &config.ssh.profiles {
+            ssh_profiles.add(profile.clone());
+        }
+
+        // SSH hosts each open from the command palette
+        let mut command_palette = CommandPalette::with_default_commands();
+        for profile in ssh_profiles.list() {
+            command_palette.add_item(PaletteItem::new(
+                format!("ssh:{}", profile.name),
+                format!("SSH: {}", profile.name),
+                "SSH",
+            ));
+        }
+
         tracing::info!("AgTerm application initialized");
         Self {
             tabs,
@@ -626,11 +889,9 @@ impl Default for AgTerm {
             debug_panel,
             last_pty_activity: Instant::now(),
             last_cursor_blink: Instant::now(),
+            last_process_poll: Instant::now(),
             font_size,
-            search_mode: false,
-            search_query: String::new(),
-            search_matches: Vec::new(),
-            current_match_index: None,
+            search_bar: None,
             env_info,
             bell_sound: sound::BellSound::new(),
             bell_flash_active: false,
@@ -639,17 +900,25 @@ impl Default for AgTerm {
             tab_context_menu: None,
             tab_rename_mode: None,
             tab_rename_input: String::new(),
+            base_theme: current_theme.clone(),
             current_theme,
             current_modifiers: Modifiers::default(),
+            window_focused: true,
+            close_confirmation: None,
             notification_manager,
             keybindings,
-            command_palette: CommandPalette::with_default_commands(),
+            command_palette,
             history_manager,
             completion_engine: CompletionEngine::new(),
             completion_items: Vec::new(),
             completion_selected: 0,
             completion_visible: false,
             trigger_manager: TriggerManager::from_config(&config.triggers),
+            ssh_profiles,
+            detect_ssh_connection: config.ssh.detect_ssh_connection,
+            session_tags: session_tags::SessionTagManager::with_defaults(),
+            profiles,
+            saving_session: false,
         }
     }
 }
@@ -657,7 +926,9 @@ impl Default for AgTerm {
 impl Drop for AgTerm {
     fn drop(&mut self) {
         // Save session state when application exits
-        self.save_session();
+        if get_config().general.session.save_on_exit {
+            self.save_session();
+        }
 
         // Save command history
         if let Err(e) = self.history_manager.save_to_file() {
@@ -744,7 +1015,6 @@ struct TabContextMenu {
 
 /// A single terminal tab with block-based output
 struct TerminalTab {
-    #[allow(dead_code)]
     id: usize,
     session_id: Option<uuid::Uuid>,
     raw_input: String, // Input buffer for Raw mode (IME support)
@@ -771,6 +1041,12 @@ struct TerminalTab {
     canvas_state: TerminalCanvasState,
     /// Content version for cache invalidation
     content_version: u64,
+    /// Screen changed since the last render (held back during synchronized updates)
+    frame_pending: bool,
+    /// Scrollback lines on disk rendered above the lines in memory
+    history_lines: usize,
+    /// Line number of the first line in `parsed_line_cache`
+    first_rendered_line: usize,
     /// Terminal screen buffer with VTE parser
     screen: TerminalScreen,
     /// Cursor blink state
@@ -781,6 +1057,20 @@ struct TerminalTab {
     title: Option<String>,
     /// Dynamic title information from OSC sequences and shell integration
     title_info: terminal::title::TitleInfo,
+    /// How the child process ended, once it has
+    child_exit: Option<ChildExit>,
+    /// Color and metadata; an SSH profile's tab color and theme go here
+    meta: tab_manager::Tab,
+    /// Shell integration state fed by the shell's OSC 7/133/633/1337 marks
+    shell: TabShell,
+    /// Remote host the tab is connected to, for applying its SSH profile
+    remote: RemoteHost,
+    /// Search for the search bar's query in this tab's lines
+    search: Option<BackgroundSearch>,
+    /// Content version the search last took a snapshot at
+    search_version: u64,
+    /// Line to center in the view and the request's serial number
+    scroll_request: Option<(usize, u64)>,
     /// Track last copied selection coordinates to avoid duplicate copies
     last_copied_selection: Option<(terminal_canvas::SelectionPoint, terminal_canvas::SelectionPoint)>,
     /// Bracket matching state
@@ -794,6 +1084,101 @@ struct TerminalTab {
     focused_pane: usize,
 }
 
+/// Commands, exit codes and directories a tab's shell reports through its marks
+#[derive(Debug)]
+struct TabShell {
+    integration: ShellIntegration,
+    /// Where the last prompt ended, i.e. where the command line starts
+    prompt_end: Option<(usize, usize)>,
+    /// Line the output of the running command starts on
+    output_start: Option<usize>,
+    /// Lines of the output of finished commands still in the scrollback, oldest first
+    command_output: VecDeque<Range<usize>>,
+}
+
+impl TabShell {
+    /// Integration for the shell the tab's spawn spec runs
+    fn new(spec: &SpawnSpec) -> Self {
+        let program = spec.program_or_default_shell();
+        // Match on the file name only, directories may contain shell names
+        let name = std::path::Path::new(&program)
+            .file_name()
+            .map_or(program.as_str(), |name| name.to_str().unwrap_or_default());
+        Self {
+            integration: ShellIntegration::new(ShellType::from_name(name)),
+            prompt_end: None,
+            output_start: None,
+            command_output: VecDeque::new(),
+        }
+    }
+
+    /// Lines of command output (133;C up to 133;D), including the running command's
+    fn output_scope(&self) -> SearchScope {
+        let running = self.output_start.map(|start| start..usize::MAX);
+        SearchScope::CommandOutput(self.command_output.iter().cloned().chain(running).collect())
+    }
+
+    /// Feed the marks the screen queued since the last call into the integration
+    fn update(&mut self, screen: &mut TerminalScreen) {
+        // Follow the text of the marks seen so far through column resizes
+        for map in screen.take_line_maps() {
+            self.prompt_end = self.prompt_end.map(|(line, col)| map.position(line, col));
+            self.output_start = self.output_start.map(|line| map.line(line));
+            for output in &mut self.command_output {
+                *output = map.line(output.start)..map.line(output.end);
+            }
+        }
+
+        for mark in screen.take_shell_marks() {
+            let Some(event) = self.integration.parse_osc(mark.command, &mark.data) else {
+                continue;
+            };
+            // Positions on the alternate screen are not lines of the scrollback
+            if mark.alternate_screen {
+                self.integration.handle_event(event);
+                continue;
+            }
+            match event {
+                ShellEvent::PromptStart => self.prompt_end = None,
+                ShellEvent::PromptEnd => self.prompt_end = Some((mark.line, mark.col)),
+                ShellEvent::OutputStart => {
+                    // Shells that only mark the output read the command line off the screen
+                    if self.integration.command_tracker.current_command().is_none() {
+                        if let Some(start) = self.prompt_end.take() {
+                            let command = screen.text_between(start, (mark.line, mark.col));
+                            let command = command.trim();
+                            if !command.is_empty() {
+                                self.integration
+                                    .handle_event(ShellEvent::CommandStarted(command.to_string()));
+                            }
+                        }
+                    }
+                    self.output_start = Some(mark.line);
+                }
+                ShellEvent::CommandFinished { .. } => {
+                    // Output not ended by a newline shares the line of the mark
+                    if let Some(start) = self.output_start.take() {
+                        let end = mark.line + usize::from(mark.col > 0);
+                        self.command_output.push_back(start..end.max(start));
+                    }
+                }
+                _ => {}
+            }
+            self.integration.handle_event(event);
+        }
+
+        // Forget the output of commands trimmed from the scrollback
+        let trimmed = screen.line_offset();
+        while self
+            .command_output
+            .front()
+            .is_some_and(|output| output.end <= trimmed)
+        {
+            self.command_output.pop_front();
+        }
+    }
+}
+
 /// Terminal input mode
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
 enum TerminalMode {
@@ -826,8 +1211,13 @@ enum Message {
     // Tab management
     NewTab,
     NewSshTab(ssh::SshProfile),
+    /// Open a tab connected to an SSH profile (by name)
+    OpenSshProfile(String),
     CloseTab(usize),
     CloseCurrentTab,
+    /// Close the tab waiting for confirmation
+    ConfirmCloseTab,
+    CancelCloseTab,
     SelectTab(usize),
     NextTab,
     PrevTab,
@@ -840,9 +1230,13 @@ enum Message {
 
     // Keyboard events
     KeyPressed(Key, Modifiers),
+    KeyReleased(Key, Modifiers),
+
+    // Terminal canvas events (mouse reports)
+    Canvas(TerminalCanvasMessage),
 
     // Signal sending
-    SendSignal(SignalType),
+    SendSignal(PtySignal),
 
     // Clipboard
     ClipboardContent(Option<String>),
@@ -855,6 +1249,7 @@ enum Message {
         width: u32,
         height: u32,
     },
+    WindowFocused(bool),
 
     // Terminal control
     ClearScreen,
@@ -864,12 +1259,19 @@ enum Message {
     // URL handling
     OpenUrl(String),
 
-    // Tick for PTY polling
+    // Tick for cursor blink, metrics and held back frames
     Tick,
 
+    // Output pushed by a PTY session's reader thread
+    PtyOutput(uuid::Uuid, Vec<u8>),
+
     // Bell flash animation tick
     BellFlashTick,
 
+    // Periodic session save (session.auto_save)
+    AutoSaveSession,
+    SessionSaved,
+
     // Debug panel
     ToggleDebugPanel,
     #[allow(dead_code)]
@@ -914,6 +1316,10 @@ enum Message {
     EndHistorySearch,
     CancelHistorySearch,
 
+    // Scrollback search (Cmd+Shift+F)
+    OpenSearch,
+    Search(SearchBarMessage),
+
     // Completion (Tab autocomplete)
     TriggerCompletion,
     CompletionNext,
@@ -922,6 +1328,12 @@ enum Message {
     CompletionCancel,
 }
 
+impl From<TerminalCanvasMessage> for Message {
+    fn from(message: TerminalCanvasMessage) -> Self {
+        Message::Canvas(message)
+    }
+}
+
 impl From<DebugPanelMessage> for Message {
     fn from(msg: DebugPanelMessage) -> Self {
         Message::DebugPanelMessage(msg)
@@ -947,6 +1359,11 @@ impl AgTerm {
             }
             // Resize screen buffer
             tab.screen.resize(new_cols as usize, new_rows as usize);
+            tab.shell.update(&mut tab.screen);
+            tab.screen.set_cell_pixel_size(
+                terminal_canvas::config::char_width(self.font_size).round() as u32,
+                terminal_canvas::config::line_height(self.font_size).round() as u32,
+            );
         }
     }
 
@@ -1004,6 +1421,457 @@ impl AgTerm {
         }
     }
 
+    /// Close a tab, first asking for confirmation if a command runs in it
+    ///
+    /// Closing the tab again while asked confirms. The last tab stays open.
+    fn request_close_tab(&mut self, index: usize) {
+        let Some(tab) = self.tabs.get(index) else {
+            return;
+        };
+        if self.tabs.len() <= 1 {
+            return;
+        }
+        let id = tab.id;
+        let confirmed = self
+            .close_confirmation
+            .as_ref()
+            .is_some_and(|(pending, _)| *pending == id);
+        match tab.meta.close_permission() {
+            tab_manager::ClosePermission::RequiresConfirmation(reason) if !confirmed => {
+                // Show the tab being asked about
+                self.close_confirmation = Some((id, reason));
+                self.active_tab = index;
+            }
+            tab_manager::ClosePermission::Denied(_) => {}
+            _ => self.close_tab(index),
+        }
+    }
+
+    /// Close a tab and its PTY session
+    fn close_tab(&mut self, index: usize) {
+        if self.tabs.len() <= 1 || index >= self.tabs.len() {
+            return;
+        }
+        let tab = self.tabs.remove(index);
+        if let Some(session_id) = &tab.session_id {
+            let _ = self.pty_manager.close_session(session_id);
+        }
+        if self
+            .close_confirmation
+            .as_ref()
+            .is_some_and(|(id, _)| *id == tab.id)
+        {
+            self.close_confirmation = None;
+        }
+        if self.active_tab >= self.tabs.len() {
+            self.active_tab = self.tabs.len() - 1;
+        }
+    }
+
+    /// Start a PTY session for `spec` in a new tab, typing `commands` into it
+    ///
+    /// Returns the index of the new tab.
+    fn open_tab(&mut self, spec: SpawnSpec, commands: &[String]) -> usize {
+        let id = self.next_tab_id;
+        self.next_tab_id += 1;
+
+        let cwd = spec
+            .cwd
+            .clone()
+            .or_else(|| std::env::current_dir().ok())
+            .map(|dir| dir.display().to_string())
+            .unwrap_or_else(|| "~".to_string());
+        let shell = TabShell::new(&spec);
+        let session_result = self.pty_manager.create_session_with_spec(24, 80, spec);
+
+        let (session_id, error_message) = match session_result {
+            Ok(id) => {
+                type_commands(&self.pty_manager, &id, commands);
+                (Some(id), None)
+            }
+            Err(e) => (None, Some(format!("Failed to create PTY session: {e}"))),
+        };
+
+        let tab = TerminalTab {
+            id,
+            session_id,
+            raw_input: String::new(),
+            input: String::new(),
+            cwd,
+            error_message,
+            history: Vec::new(),
+            history_index: None,
+            history_temp_input: String::new(),
+            mode: TerminalMode::Raw,
+            parsed_line_cache: Vec::new(),
+            canvas_state: TerminalCanvasState::new(),
+            content_version: 0,
+            frame_pending: false,
+            history_lines: 0,
+            first_rendered_line: 0,
+            screen: new_screen(80, 24),
+            cursor_blink_on: true,
+            bell_pending: false,
+            title: None,
+            last_copied_selection: None,
+            bracket_match: None,
+            pane_layout: PaneLayout::Single,
+            panes: Vec::new(),
+            focused_pane: 0,
+            title_info: terminal::title::TitleInfo::new(),
+            child_exit: None,
+            meta: new_tab_meta(),
+            search: None,
+            search_version: 0,
+            scroll_request: None,
+            shell,
+            remote: RemoteHost::default(),
+        };
+        self.tabs.push(tab);
+        self.tabs.len() - 1
+    }
+
+    /// Send a focus report (mode 1004) to the application in a tab
+    ///
+    /// Reports go out right away, not with the next PTY output.
+    fn report_tab_focus(&mut self, index: usize, focused: bool) {
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return;
+        };
+        tab.screen.report_focus(focused);
+        if let Some(session_id) = &tab.session_id {
+            for response in tab.screen.take_pending_responses() {
+                let _ = self.pty_manager.write(session_id, response.as_bytes());
+            }
+        }
+    }
+
+    /// Feed output of a PTY session into the screen of its tab
+    fn handle_pty_output(&mut self, session_id: uuid::Uuid, data: Vec<u8>) {
+        let Some(index) = self
+            .tabs
+            .iter()
+            .position(|tab| tab.session_id == Some(session_id))
+        else {
+            return;
+        };
+        let is_active = index == self.active_tab;
+        let tab = &mut self.tabs[index];
+
+        // Update PTY activity timestamp for dynamic tick optimization
+        self.last_pty_activity = Instant::now();
+
+        // Record PTY read metrics
+        self.debug_panel.metrics.record_pty_read(data.len());
+
+        // Process bytes through VTE parser
+        tab.screen.process(&data);
+        tab.shell.update(&mut tab.screen);
+
+        // Bells in background tabs are picked up by Tick and turned into notifications
+        let bell_triggered = is_active && tab.screen.take_bell_triggered();
+
+        // Update tab title from OSC sequences (OSC 0 or OSC 2)
+        if let Some(window_title) = tab.screen.window_title() {
+            tab.title = Some(window_title.to_string());
+        }
+
+        // Update CWD from OSC 7 for tab subtitle/info
+        if let Some(cwd) = tab.screen.cwd_from_shell() {
+            tab.cwd = cwd.to_string();
+        }
+
+        // A shell on another host (e.g. after ssh) reports it in OSC 7
+        let host_changed = tab.remote.set_reported_host(tab.screen.host_from_shell());
+
+        // Send pending responses (DA, DSR, CPR, OSC 52 query, etc.) to PTY
+        for response in tab.screen.take_pending_responses() {
+            let _ = self.pty_manager.write(&session_id, response.as_bytes());
+        }
+
+        // Handle OSC 52 clipboard set request
+        if let Some(clipboard_data) = tab.screen.take_clipboard_request() {
+            // Decode base64 and set clipboard
+            use base64::{engine::general_purpose::STANDARD, Engine as _};
+            if let Ok(decoded_bytes) = STANDARD.decode(&clipboard_data) {
+                if let Ok(text) = String::from_utf8(decoded_bytes) {
+                    if let Ok(mut clipboard) = arboard::Clipboard::new() {
+                        let _ = clipboard.set_text(text);
+                    }
+                }
+            }
+        }
+
+        // The next Tick paints the frame, so a burst of output chunks is
+        // converted and scanned for URLs once rather than once per chunk
+        tab.frame_pending = true;
+
+        if host_changed {
+            self.update_remote_host(index);
+        }
+
+        if is_active {
+            // Check triggers against new output
+            if let Ok(text) = String::from_utf8(data) {
+                self.check_triggers(&text);
+            }
+
+            if bell_triggered {
+                self.play_bell_sound();
+                self.trigger_bell_flash();
+            }
+        }
+    }
+
+    /// Check every tab's child for exit and track its foreground process
+    ///
+    /// The foreground command ("vim", "cargo build") labels the tab while it
+    /// runs; the shell waiting at its prompt does not. ssh in the foreground
+    /// connects the tab to its destination host.
+    fn poll_processes(&mut self) {
+        let mut remote_changed = Vec::new();
+        let active_tab = self.active_tab;
+        for (index, tab) in self.tabs.iter_mut().enumerate() {
+            let Some(session_id) = tab.session_id else {
+                continue;
+            };
+            if tab.child_exit.is_some() {
+                continue;
+            }
+
+            match self.pty_manager.check_status(&session_id) {
+                Ok(Some(exit)) => {
+                    tracing::info!(session_id = %session_id, %exit, "Child process ended");
+                    tab.screen
+                        .process(format!("\r\n[Process {exit}]\r\n").as_bytes());
+                    tab.title_info.set_current_command(None);
+                    tab.meta.update_exit(&exit);
+                    tab.frame_pending = true;
+                    tab.child_exit = Some(exit);
+                }
+                Ok(None) => {
+                    let foreground = self
+                        .pty_manager
+                        .foreground_process(&session_id)
+                        .ok()
+                        .flatten()
+                        .filter(|process| !process.is_session_child);
+                    let command = foreground.as_ref().map(|process| process.command());
+                    if command.as_deref() != tab.title_info.current_command() {
+                        tracing::debug!(
+                            pid = foreground.as_ref().map(|process| process.pid),
+                            command = ?command,
+                            "Foreground process changed"
+                        );
+                        tab.meta
+                            .update_process(command.clone(), index == active_tab);
+                        tab.title_info.set_current_command(command);
+
+                        let destination = foreground
+                            .filter(|process| process.name == "ssh")
+                            .and_then(|process| ssh::ssh_destination(&process.argv));
+                        tab.remote.set_ssh_destination(destination);
+                        remote_changed.push(index);
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(session_id = %session_id, error = %e, "Failed to check child status");
+                }
+            }
+        }
+
+        for index in remote_changed {
+            self.update_remote_host(index);
+        }
+
+        // Nothing left to confirm once the command has finished
+        if let Some((id, _)) = &self.close_confirmation {
+            let still_required = self.tabs.iter().any(|tab| {
+                tab.id == *id
+                    && matches!(
+                        tab.meta.close_permission(),
+                        tab_manager::ClosePermission::RequiresConfirmation(_)
+                    )
+            });
+            if !still_required {
+                self.close_confirmation = None;
+            }
+        }
+    }
+
+    /// Apply the SSH profile of the host a tab is connected to, if the host changed
+    fn update_remote_host(&mut self, index: usize) {
+        let Some(host) = self
+            .tabs
+            .get(index)
+            .and_then(|tab| tab.remote.changed_host(self.detect_ssh_connection))
+        else {
+            return;
+        };
+        let profile = host
+            .as_deref()
+            .and_then(|host| self.ssh_profiles.find_by_host(host))
+            .cloned();
+        self.apply_ssh_profile(index, host, profile.as_ref());
+    }
+
+    /// Give a tab the theme, tab color and tags of an SSH profile (None removes them)
+    fn apply_ssh_profile(
+        &mut self,
+        index: usize,
+        host: Option<String>,
+        profile: Option<&ssh::SshProfile>,
+    ) {
+        let Some(tab) = self.tabs.get_mut(index) else {
+            return;
+        };
+        let session_id = tab.session_id.map(|id| id.to_string());
+        tab.remote.apply_profile(
+            host,
+            profile,
+            &mut tab.meta,
+            &mut self.session_tags,
+            session_id.as_deref(),
+        );
+
+        if index == self.active_tab {
+            self.sync_theme();
+        }
+    }
+
+    /// Input for Ctrl+C/D/Z typed into the active tab
+    ///
+    /// The control byte lets the line discipline signal the foreground job.
+    /// With the kitty keyboard protocol the key is reported as an escape code.
+    fn control_key_input(&self, key: &Key, modifiers: Modifiers, signal: SignalType) -> Message {
+        let keyboard_flags = self
+            .tabs
+            .get(self.active_tab)
+            .map(|tab| tab.screen.keyboard_flags())
+            .unwrap_or(0);
+        let input = encode_key(key, modifiers, KeyEventKind::Press, keyboard_flags)
+            .unwrap_or_else(|| char::from(signal.as_byte()).to_string());
+        Message::RawInput(input)
+    }
+
+    /// Use the active tab's theme (from its SSH profile), or the base theme
+    fn sync_theme(&mut self) {
+        let profile_theme = self
+            .tabs
+            .get(self.active_tab)
+            .and_then(|tab| tab.meta.get_metadata("theme"));
+        let theme = match profile_theme {
+            Some(name) => Theme::by_name(name).unwrap_or_else(|| {
+                tracing::warn!("Theme '{}' not found, keeping current theme", name);
+                self.base_theme.clone()
+            }),
+            None => self.base_theme.clone(),
+        };
+        if theme.name == self.current_theme.name {
+            return;
+        }
+        self.current_theme = theme;
+
+        // Reverse screen cells are rendered with the theme's colors
+        if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+            tab.frame_pending = true;
+        }
+        self.flush_frame();
+    }
+
+    /// Search the active tab for the search bar's query
+    ///
+    /// Runs on every tick: collects the matches found so far and, once the
+    /// search has finished, searches the lines changed since.
+    fn update_search(&mut self) {
+        let Some(bar) = &mut self.search_bar else {
+            return;
+        };
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let changed = tab.search_version != tab.content_version;
+        if bar.search(&mut tab.search, &tab.screen, changed, || {
+            tab.shell.output_scope()
+        }) {
+            tab.search_version = tab.content_version;
+        }
+    }
+
+    /// Select the next or previous search match of the active tab and show it
+    fn select_search_match(&mut self, forward: bool) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+        let Some(search) = &mut tab.search else {
+            return;
+        };
+        search.poll();
+        let selected = if forward {
+            search.next_match()
+        } else {
+            search.prev_match()
+        };
+        let Some(index) = selected.and_then(|m| m.line.checked_sub(tab.screen.line_offset()))
+        else {
+            return;
+        };
+
+        // Page the match back in from disk when it is above the rendered lines
+        if index < tab.first_rendered_line {
+            tab.history_lines = tab.screen.first_memory_line() - index;
+            tab.frame_pending = true;
+        }
+        let serial = tab.scroll_request.map_or(1, |(_, serial)| serial + 1);
+        tab.scroll_request = Some((index, serial));
+        self.flush_frame();
+    }
+
+    /// Rebuild the render cache of the active tab if it has unpainted output
+    fn flush_frame(&mut self) {
+        let Some(tab) = self.tabs.get_mut(self.active_tab) else {
+            return;
+        };
+
+        // Hold back the frame while a synchronized update (mode 2026) is open,
+        // so TUIs are never painted half-updated
+        if !tab.frame_pending || tab.screen.is_synchronized_update() {
+            return;
+        }
+        tab.frame_pending = false;
+
+        // Detect URLs in terminal output
+        tab.screen.detect_urls();
+
+        // Convert the lines in memory, and any history paged back in from
+        // disk, to the parsed line cache for rendering
+        let first_memory_line = tab.screen.first_memory_line();
+        tab.history_lines = tab.history_lines.min(first_memory_line);
+        tab.first_rendered_line = first_memory_line - tab.history_lines;
+        let all_lines = tab
+            .screen
+            .get_lines(tab.first_rendered_line..tab.screen.total_lines());
+        let reverse_screen = tab.screen.is_mode_set(Mode::ReverseScreen);
+        let (default_fg, default_bg) = screen_default_colors(&tab.screen, &self.current_theme);
+        tab.parsed_line_cache = all_lines
+            .iter()
+            .map(|cells| {
+                if reverse_screen {
+                    cells_to_styled_spans(&reverse_screen_cells(cells, default_fg, default_bg))
+                } else {
+                    cells_to_styled_spans(cells)
+                }
+            })
+            .collect();
+
+        // Increment content version for canvas cache invalidation
+        tab.content_version += 1;
+
+        // Auto-scroll to bottom
+        tab.canvas_state
+            .scroll_to_bottom(tab.parsed_line_cache.len(), self.font_size);
+    }
+
     /// Check triggers against terminal output and execute matching actions
     fn check_triggers(&mut self, text: &str) {
         let matches = self.trigger_manager.check(text);
@@ -1094,35 +1962,44 @@ impl AgTerm {
         }
     }
 
-    /// Save current session state to file
+    /// Save current session state and each tab's scrollback to file
     fn save_session(&self) {
-        let config = get_config();
-        if !config.general.session.save_on_exit {
-            return;
-        }
+        self.session_snapshot().write();
+    }
 
-        let session_path = config.session_file_path();
+    /// Take the current session state and each tab's scrollback for saving
+    fn session_snapshot(&self) -> SessionSnapshot {
+        let config = get_config();
+        let scrollback_lines = config.general.session.scrollback_lines;
 
-        // Collect tab states
-        let tab_states: Vec<TabState> = self
+        let tabs = self
             .tabs
             .iter()
             .map(|tab| TabState {
                 cwd: tab.cwd.clone(),
                 title: tab.title.clone(),
                 id: tab.id,
+                scrollback_hash: None, // Set once the scrollback is written
             })
             .collect();
-
-        let session = SessionState {
-            tabs: tab_states,
-            active_tab: self.active_tab,
-            window_size: None, // Will be set from actual window size if available
-            font_size: self.font_size,
+        let scrollback = if scrollback_lines > 0 {
+            self.tabs
+                .iter()
+                .map(|tab| tab.screen.scrollback_snapshot(scrollback_lines))
+                .collect()
+        } else {
+            Vec::new()
         };
 
-        if let Err(e) = session.save_to_file(&session_path) {
-            tracing::error!("Failed to save session: {}", e);
+        SessionSnapshot {
+            path: config.session_file_path(),
+            state: SessionState {
+                tabs,
+                active_tab: self.active_tab,
+                window_size: None, // Will be set from actual window size if available
+                font_size: self.font_size,
+            },
+            scrollback,
         }
     }
 
@@ -1130,6 +2007,7 @@ impl AgTerm {
     fn restore_session(
         config: &AppConfig,
         pty_manager: &Arc<PtyManager>,
+        profile: Option<&profiles::Profile>,
     ) -> Option<(Vec<TerminalTab>, usize, f32)> {
         if !config.general.session.restore_on_startup {
             return None;
@@ -1155,8 +2033,24 @@ impl AgTerm {
 
                 let mut tabs = Vec::new();
                 for tab_state in session.tabs {
-                    let session_result = pty_manager
-                        .create_session(config.pty.default_rows, config.pty.default_cols);
+                    // Start the shell where the restored output left off, if that still exists
+                    let mut spec = shell_spec(profile);
+                    let saved_cwd = std::path::PathBuf::from(&tab_state.cwd);
+                    if saved_cwd.is_dir() {
+                        spec.cwd = Some(saved_cwd);
+                    }
+                    let cwd = spec
+                        .cwd
+                        .clone()
+                        .or_else(|| std::env::current_dir().ok())
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_else(|| "~".to_string());
+                    let shell = TabShell::new(&spec);
+                    let session_result = pty_manager.create_session_with_spec(
+                        config.pty.default_rows,
+                        config.pty.default_cols,
+                        spec,
+                    );
 
                     let (session_id, error_message) = match session_result {
                         Ok(id) => {
@@ -1169,12 +2063,40 @@ impl AgTerm {
                         }
                     };
 
+                    let mut screen = new_screen(
+                        config.pty.default_cols as usize,
+                        config.pty.default_rows as usize,
+                    );
+                    // Put the previous output back before the new shell prints its prompt
+                    if let Some(hash) = &tab_state.scrollback_hash {
+                        let path = session::scrollback_path(&session_path, tab_state.id);
+                        let restored = session::read_scrollback(&path, hash)
+                            .map_err(|e| e.to_string())
+                            .and_then(|data| {
+                                screen
+                                    .restore_scrollback(&data, "restored from previous session")
+                                    .map_err(|e| e.to_string())
+                            });
+                        match restored {
+                            Ok(lines) => tracing::info!(
+                                "Restored {} scrollback lines of tab {}",
+                                lines,
+                                tab_state.id
+                            ),
+                            Err(e) => tracing::warn!(
+                                "Failed to restore scrollback of tab {}: {}",
+                                tab_state.id,
+                                e
+                            ),
+                        }
+                    }
+
                     let tab = TerminalTab {
                         id: tab_state.id,
                         session_id,
                         raw_input: String::new(),
                         input: String::new(),
-                        cwd: tab_state.cwd,
+                        cwd,
                         error_message,
                         history: Vec::new(),
                         history_index: None,
@@ -1183,10 +2105,10 @@ impl AgTerm {
                         parsed_line_cache: Vec::new(),
                         canvas_state: TerminalCanvasState::new(),
                         content_version: 0,
-                        screen: TerminalScreen::new(
-                            config.pty.default_cols as usize,
-                            config.pty.default_rows as usize,
-                        ),
+                        frame_pending: true,
+                        history_lines: 0,
+                        first_rendered_line: 0,
+                        screen,
                         cursor_blink_on: true,
                         bell_pending: false,
                         title: tab_state.title,
@@ -1196,6 +2118,13 @@ impl AgTerm {
                         panes: Vec::new(),
                         focused_pane: 0,
                         title_info: terminal::title::TitleInfo::new(),
+                        child_exit: None,
+                        meta: new_tab_meta(),
+                        search: None,
+                        search_version: 0,
+                        scroll_request: None,
+                        shell,
+                        remote: RemoteHost::default(),
                     };
 
                     tabs.push(tab);
@@ -1214,74 +2143,62 @@ impl AgTerm {
     }
 
     fn update(&mut self, message: Message) -> Task<Message> {
-        match message {
+        let active_tab_id = self.tabs.get(self.active_tab).map(|tab| tab.id);
+        let task = match message {
             Message::NewTab => {
-                let id = self.next_tab_id;
-                self.next_tab_id += 1;
-
-                let session_result = self.pty_manager.create_session(24, 80);
-                let cwd = std::env::current_dir()
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|_| "~".to_string());
+                let profile = self.profiles.get_default_profile();
+                let spec = shell_spec(profile);
+                let commands = profile
+                    .map(|profile| profile.startup_commands.clone())
+                    .unwrap_or_default();
+                self.active_tab = self.open_tab(spec, &commands);
+                text_input::focus(raw_input_id())
+            }
 
-                let (session_id, error_message) = match session_result {
-                    Ok(id) => (Some(id), None),
-                    Err(e) => (None, Some(format!("Failed to create PTY session: {e}"))),
+            Message::OpenSshProfile(name) => {
+                let Some(profile) = self.ssh_profiles.get(&name).cloned() else {
+                    tracing::warn!("Unknown SSH profile: {}", name);
+                    return Task::none();
                 };
+                if !profile.needs_resolve {
+                    return self.update(Message::NewSshTab(profile));
+                }
 
-                let tab = TerminalTab {
-                    id,
-                    session_id,
-                    raw_input: String::new(),
-                    input: String::new(),
-                    cwd,
-                    error_message,
-                    history: Vec::new(),
-                    history_index: None,
-                    history_temp_input: String::new(),
-                    mode: TerminalMode::Raw,
-                    parsed_line_cache: Vec::new(),
-                    canvas_state: TerminalCanvasState::new(),
-                    content_version: 0,
-                    screen: TerminalScreen::new(80, 24),
-                    cursor_blink_on: true,
-                    bell_pending: false,
-                    title: None,
-                    last_copied_selection: None,
-                    bracket_match: None,
-                    pane_layout: PaneLayout::Single,
-                    panes: Vec::new(),
-                    focused_pane: 0,
-                    title_info: terminal::title::TitleInfo::new(),
-                };
-                self.tabs.push(tab);
-                self.active_tab = self.tabs.len() - 1;
-                text_input::focus(raw_input_id())
+                // Match exec commands in ~/.ssh/config may take a while to run
+                let (sender, receiver) = iced::futures::channel::oneshot::channel();
+                let listed = profile.clone();
+                let spawned = std::thread::Builder::new()
+                    .name("ssh-config".to_string())
+                    .spawn(move || {
+                        let _ = sender.send(profile.resolve_exec());
+                    });
+                if let Err(e) = spawned {
+                    tracing::warn!("Failed to resolve SSH profile {}: {}", name, e);
+                    return self.update(Message::NewSshTab(listed));
+                }
+                Task::perform(receiver, move |resolved| {
+                    Message::NewSshTab(resolved.unwrap_or_else(|_| listed.clone()))
+                })
             }
 
             Message::NewSshTab(profile) => {
                 let id = self.next_tab_id;
                 self.next_tab_id += 1;
 
-                let session_result = self.pty_manager.create_session(24, 80);
+                // Run ssh directly instead of typing the command into a shell
+                let spec = SpawnSpec::from_argv(profile.to_command());
+                let shell = TabShell::new(&spec);
+                let session_result = self.pty_manager.create_session_with_spec(24, 80, spec);
                 let cwd = std::env::current_dir()
                     .map(|p| p.display().to_string())
                     .unwrap_or_else(|_| "~".to_string());
 
                 let (session_id, error_message) = match session_result {
                     Ok(session_id) => {
-                        // Send SSH command to the PTY
-                        let ssh_command = profile.to_command();
-                        let command_line = ssh_command.join(" ");
-                        if let Err(e) = self.pty_manager.write(&session_id, format!("{command_line}\n").as_bytes()) {
-                            tracing::error!("Failed to write SSH command to session: {e}");
-                            (Some(session_id), Some(format!("Failed to start SSH: {e}")))
-                        } else {
-                            tracing::info!("SSH command sent: {command_line}");
-                            (Some(session_id), None)
-                        }
+                        tracing::info!("SSH session started: {}", profile.connection_string());
+                        (Some(session_id), None)
                     }
-                    Err(e) => (None, Some(format!("Failed to create PTY session: {e}"))),
+                    Err(e) => (None, Some(format!("Failed to start SSH: {e}"))),
                 };
 
                 let tab = TerminalTab {
@@ -1298,7 +2215,10 @@ impl AgTerm {
                     parsed_line_cache: Vec::new(),
                     canvas_state: TerminalCanvasState::new(),
                     content_version: 0,
-                    screen: TerminalScreen::new(80, 24),
+                    frame_pending: false,
+                    history_lines: 0,
+                    first_rendered_line: 0,
+                    screen: new_screen(80, 24),
                     cursor_blink_on: true,
                     bell_pending: false,
                     title: Some(format!("SSH: {}", profile.connection_string())),
@@ -1308,9 +2228,29 @@ impl AgTerm {
                     panes: Vec::new(),
                     focused_pane: 0,
                     title_info: terminal::title::TitleInfo::new(),
+                    child_exit: None,
+                    meta: new_tab_meta(),
+                    search: None,
+                    search_version: 0,
+                    scroll_request: None,
+                    shell,
+                    remote: RemoteHost::connected(profile.name.clone()),
                 };
                 self.tabs.push(tab);
                 self.active_tab = self.tabs.len() - 1;
+
+                // Profiles read from ~/.ssh/config carry no theme, color or
+                // tags; a configured profile for the same host may
+                let metadata = Some(&profile)
+                    .filter(|profile| profile.has_metadata())
+                    .or_else(|| self.ssh_profiles.find_by_host(&profile.name))
+                    .or_else(|| self.ssh_profiles.find_by_host(&profile.host))
+                    .cloned();
+                self.apply_ssh_profile(
+                    self.active_tab,
+                    Some(profile.name.clone()),
+                    metadata.as_ref(),
+                );
                 text_input::focus(raw_input_id())
             }
 
@@ -1320,7 +2260,6 @@ impl AgTerm {
                     let id = self.next_tab_id;
                     self.next_tab_id += 1;
 
-                    let session_result = self.pty_manager.create_session(24, 80);
                     // Get the working directory from the current tab's shell
                     let cwd = current_tab
                         .screen
@@ -1328,6 +2267,12 @@ impl AgTerm {
                         .map(|s| s.to_string())
                         .or_else(|| Some(current_tab.cwd.clone()))
                         .unwrap_or_else(|| "~".to_string());
+                    let spec = SpawnSpec {
+                        cwd: Some(std::path::PathBuf::from(&cwd)).filter(|dir| dir.is_dir()),
+                        ..shell_spec(self.profiles.get_default_profile())
+                    };
+                    let shell = TabShell::new(&spec);
+                    let session_result = self.pty_manager.create_session_with_spec(24, 80, spec);
 
                     let (session_id, error_message) = match session_result {
                         Ok(id) => (Some(id), None),
@@ -1348,11 +2293,21 @@ impl AgTerm {
                         parsed_line_cache: Vec::new(),
                         canvas_state: TerminalCanvasState::new(),
                         content_version: 0,
-                        screen: TerminalScreen::new(80, 24),
+                        frame_pending: false,
+                        history_lines: 0,
+                        first_rendered_line: 0,
+                        screen: new_screen(80, 24),
                         cursor_blink_on: true,
                         bell_pending: false,
                         title: None, // New tab starts with no custom title
                         title_info: terminal::title::TitleInfo::new(),
+                        child_exit: None,
+                        meta: new_tab_meta(),
+                        search: None,
+                        search_version: 0,
+                        scroll_request: None,
+                        shell,
+                        remote: RemoteHost::default(),
                         last_copied_selection: None,
                         bracket_match: None,
                         pane_layout: PaneLayout::Single,
@@ -1366,17 +2321,7 @@ impl AgTerm {
             }
 
             Message::CloseTab(index) => {
-                if self.tabs.len() > 1 {
-                    if let Some(tab) = self.tabs.get(index) {
-                        if let Some(session_id) = &tab.session_id {
-                            let _ = self.pty_manager.close_session(session_id);
-                        }
-                    }
-                    self.tabs.remove(index);
-                    if self.active_tab >= self.tabs.len() {
-                        self.active_tab = self.tabs.len() - 1;
-                    }
-                }
+                self.request_close_tab(index);
                 Task::none()
             }
 
@@ -1392,20 +2337,24 @@ impl AgTerm {
             }
 
             Message::CloseCurrentTab => {
-                if self.tabs.len() > 1 {
-                    if let Some(tab) = self.tabs.get(self.active_tab) {
-                        if let Some(session_id) = &tab.session_id {
-                            let _ = self.pty_manager.close_session(session_id);
-                        }
-                    }
-                    self.tabs.remove(self.active_tab);
-                    if self.active_tab >= self.tabs.len() {
-                        self.active_tab = self.tabs.len() - 1;
+                self.request_close_tab(self.active_tab);
+                text_input::focus(raw_input_id())
+            }
+
+            Message::ConfirmCloseTab => {
+                if let Some((id, _)) = self.close_confirmation.take() {
+                    if let Some(index) = self.tabs.iter().position(|tab| tab.id == id) {
+                        self.close_tab(index);
                     }
                 }
                 text_input::focus(raw_input_id())
             }
 
+            Message::CancelCloseTab => {
+                self.close_confirmation = None;
+                text_input::focus(raw_input_id())
+            }
+
             Message::NextTab => {
                 if !self.tabs.is_empty() {
                     self.active_tab = (self.active_tab + 1) % self.tabs.len();
@@ -1454,16 +2403,39 @@ impl AgTerm {
                     let new_len = new_input.chars().count();
 
                     if let Some(session_id) = &tab.session_id {
+                        // Kitty keyboard protocol: report text keys as escape codes
+                        let keyboard_flags = tab.screen.keyboard_flags();
+                        let all_keys = keyboard_flags & REPORT_ALL_KEYS_AS_ESCAPE_CODES != 0;
+
                         if new_len > old_len {
                             // Characters were added - send only the new chars to PTY
                             let added: String = new_input.chars().skip(old_len).collect();
-                            let _ = self.pty_manager.write(session_id, added.as_bytes());
+                            if all_keys {
+                                for c in added.chars() {
+                                    let modifiers = if c.is_uppercase() {
+                                        Modifiers::SHIFT
+                                    } else {
+                                        Modifiers::empty()
+                                    };
+                                    let key = Key::Character(c.to_string().into());
+                                    let input = encode_key(
+                                        &key,
+                                        modifiers,
+                                        KeyEventKind::Press,
+                                        keyboard_flags,
+                                    )
+                                    .unwrap_or_else(|| c.to_string());
+                                    let _ = self.pty_manager.write(session_id, input.as_bytes());
+                                }
+                            } else {
+                                let _ = self.pty_manager.write(session_id, added.as_bytes());
+                            }
                         } else if new_len < old_len {
                             // Characters were deleted - send backspace
                             let deleted_count = old_len - new_len;
+                            let backspace = if all_keys { "\x1b[127u" } else { "\x7f" };
                             for _ in 0..deleted_count {
-                                let _ = self.pty_manager.write(session_id, &[0x7f]);
-                                // Backspace
+                                let _ = self.pty_manager.write(session_id, backspace.as_bytes());
                             }
                         }
                     }
@@ -1488,7 +2460,17 @@ impl AgTerm {
                     }
 
                     if let Some(session_id) = &tab.session_id {
-                        let _ = self.pty_manager.write(session_id, b"\r");
+                        // Kitty keyboard protocol reports Enter as CSI 13 u with all keys,
+                        // LNM mode sends CR LF
+                        let enter =
+                            if tab.screen.keyboard_flags() & REPORT_ALL_KEYS_AS_ESCAPE_CODES != 0 {
+                                "\x1b[13u"
+                            } else if tab.screen.is_mode_set(Mode::LineFeedNewLine) {
+                                "\r\n"
+                            } else {
+                                "\r"
+                            };
+                        let _ = self.pty_manager.write(session_id, enter.as_bytes());
                     }
                     tab.raw_input.clear();
                 }
@@ -1528,6 +2510,24 @@ impl AgTerm {
                     }
                 }
 
+                // While the search bar is open, keys go to it instead of the PTY
+                if self.search_bar.is_some() {
+                    match key.as_ref() {
+                        Key::Named(keyboard::key::Named::Escape) => {
+                            return self.update(Message::Search(SearchBarMessage::Close));
+                        }
+                        Key::Character(c) if modifiers.command() && c.eq_ignore_ascii_case("g") => {
+                            return self.update(Message::Search(if modifiers.shift() {
+                                SearchBarMessage::Prev
+                            } else {
+                                SearchBarMessage::Next
+                            }));
+                        }
+                        _ if modifiers.command() => {}
+                        _ => return Task::none(),
+                    }
+                }
+
                 // Handle Ctrl+R: Start reverse history search
                 if modifiers.control() && matches!(key.as_ref(), Key::Character("r")) {
                     if self.history_manager.is_searching() {
@@ -1629,6 +2629,14 @@ impl AgTerm {
                     ));
                 }
 
+                // Handle Cmd+Shift+F: Search the scrollback
+                if modifiers.command()
+                    && modifiers.shift()
+                    && matches!(key.as_ref(), Key::Character("f" | "F"))
+                {
+                    return self.update(Message::OpenSearch);
+                }
+
                 // Handle Cmd+Shift+| (pipe): Split vertical
                 if modifiers.command()
                     && modifiers.shift()
@@ -1656,8 +2664,9 @@ impl AgTerm {
                         // Copy selection to clipboard
                         return self.update(Message::CopySelection);
                     } else if modifiers.control() {
-                        // Send interrupt signal (Ctrl+C)
-                        return self.update(Message::SendSignal(SignalType::Interrupt));
+                        // Send interrupt (Ctrl+C)
+                        let input = self.control_key_input(&key, modifiers, SignalType::Interrupt);
+                        return self.update(input);
                     }
                     // Cmd+C with no selection: do nothing
                     return Task::none();
@@ -1667,10 +2676,13 @@ impl AgTerm {
                 if modifiers.control() {
                     match key.as_ref() {
                         Key::Character("d") => {
-                            return self.update(Message::SendSignal(SignalType::EOF))
+                            let input = self.control_key_input(&key, modifiers, SignalType::EOF);
+                            return self.update(input);
                         }
                         Key::Character("z") => {
-                            return self.update(Message::SendSignal(SignalType::Suspend))
+                            let input =
+                                self.control_key_input(&key, modifiers, SignalType::Suspend);
+                            return self.update(input);
                         }
                         _ => {}
                     }
@@ -1720,6 +2732,25 @@ impl AgTerm {
                 // Raw mode: send special keys directly to PTY
                 // Regular characters are handled via RawInputChanged (for IME support)
                 if !modifiers.command() {
+                    // Kitty keyboard protocol: keys it does not encode fall back to legacy bytes
+                    let keyboard_flags = self
+                        .tabs
+                        .get(self.active_tab)
+                        .map(|tab| tab.screen.keyboard_flags())
+                        .unwrap_or(0);
+                    // Text, Space, Enter and Backspace are typed into the text input,
+                    // which encodes them itself when all keys are reported as escape codes
+                    if keyboard_flags & REPORT_ALL_KEYS_AS_ESCAPE_CODES != 0
+                        && typed_by_text_input(&key, modifiers)
+                    {
+                        return Task::none();
+                    }
+                    if let Some(input) =
+                        encode_key(&key, modifiers, KeyEventKind::Press, keyboard_flags)
+                    {
+                        return self.update(Message::RawInput(input));
+                    }
+
                     // Check application cursor keys mode for arrow keys
                     let app_cursor_keys = self
                         .tabs
@@ -1776,12 +2807,56 @@ impl AgTerm {
                 Task::none()
             }
 
-            Message::SendSignal(signal_type) => {
-                // Send signal to active PTY session
+            Message::KeyReleased(key, modifiers) => {
+                // Key releases are only reported with the kitty keyboard protocol
+                let keyboard_flags = self
+                    .tabs
+                    .get(self.active_tab)
+                    .map(|tab| tab.screen.keyboard_flags())
+                    .unwrap_or(0);
+                if modifiers.command() || self.command_palette.is_visible() {
+                    return Task::none();
+                }
+                match encode_key(&key, modifiers, KeyEventKind::Release, keyboard_flags) {
+                    Some(input) => self.update(Message::RawInput(input)),
+                    None => Task::none(),
+                }
+            }
+
+            Message::Canvas(TerminalCanvasMessage::Mouse(event)) => {
                 if let Some(tab) = self.tabs.get(self.active_tab) {
                     if let Some(session_id) = &tab.session_id {
-                        let signal_byte = signal_type.as_byte();
-                        let _ = self.pty_manager.write(session_id, &[signal_byte]);
+                        let mode = tab.screen.mouse_mode();
+                        let encoding = tab.screen.mouse_encoding();
+                        if let Some(report) = encode_mouse(&event, mode, encoding) {
+                            let _ = self.pty_manager.write(session_id, &report);
+                        }
+                    }
+                }
+                Task::none()
+            }
+
+            Message::Canvas(TerminalCanvasMessage::Scrolled(offset)) => {
+                // Page older scrollback back in from disk at the top of the view
+                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    if offset <= 0.0 && tab.first_rendered_line > 0 {
+                        tab.history_lines += HISTORY_PAGE_LINES;
+                        tab.frame_pending = true;
+                        self.flush_frame();
+                    }
+                }
+                Task::none()
+            }
+
+            Message::SendSignal(signal) => {
+                // Signal the foreground job of the active tab, even in raw mode
+                if let Some(session_id) = self
+                    .tabs
+                    .get(self.active_tab)
+                    .and_then(|tab| tab.session_id)
+                {
+                    if let Err(e) = self.pty_manager.signal(&session_id, signal) {
+                        tracing::warn!(session_id = %session_id, ?signal, error = %e, "Failed to send signal");
                     }
                 }
                 Task::none()
@@ -1898,6 +2973,10 @@ impl AgTerm {
             Message::ScrollToBottom => {
                 // Scroll to bottom of terminal output (Cmd+End)
                 if let Some(tab) = self.tabs.get_mut(self.active_tab) {
+                    if tab.history_lines > 0 {
+                        tab.history_lines = 0;
+                        tab.frame_pending = true;
+                    }
                     tab.canvas_state
                         .scroll_to_bottom(tab.parsed_line_cache.len(), self.font_size);
                 }
@@ -1928,10 +3007,17 @@ impl AgTerm {
                     }
                     // Resize screen buffer
                     tab.screen.resize(cols as usize, rows as usize);
+                    tab.shell.update(&mut tab.screen);
                 }
                 Task::none()
             }
 
+            Message::WindowFocused(focused) => {
+                self.window_focused = focused;
+                self.report_tab_focus(self.active_tab, focused);
+                Task::none()
+            }
+
             Message::ToggleDebugPanel => {
                 self.debug_panel.toggle();
                 Task::none()
@@ -1968,6 +3054,7 @@ impl AgTerm {
                         "clear_screen" => return self.update(Message::ClearScreen),
                         "scroll_to_top" => return self.update(Message::ScrollToTop),
                         "scroll_to_bottom" => return self.update(Message::ScrollToBottom),
+                        "search" => return self.update(Message::OpenSearch),
                         // Font
                         "increase_font" => return self.update(Message::IncreaseFontSize),
                         "decrease_font" => return self.update(Message::DecreaseFontSize),
@@ -1984,9 +3071,24 @@ impl AgTerm {
                         // Clipboard
                         "copy" => return self.update(Message::CopySelection),
                         "paste" => return iced::clipboard::read().map(Message::ClipboardContent),
-                        _ => {
-                            tracing::warn!("Unknown command palette ID: {}", command_id);
+                        // Signals
+                        "signal_interrupt" => {
+                            return self.update(Message::SendSignal(PtySignal::Interrupt))
+                        }
+                        "signal_suspend" => {
+                            return self.update(Message::SendSignal(PtySignal::Suspend))
                         }
+                        "signal_continue" => {
+                            return self.update(Message::SendSignal(PtySignal::Continue))
+                        }
+                        "signal_terminate" => {
+                            return self.update(Message::SendSignal(PtySignal::Terminate))
+                        }
+                        "signal_kill" => return self.update(Message::SendSignal(PtySignal::Kill)),
+                        id => match dynamic_palette_message(id) {
+                            Some(message) => return self.update(message),
+                            None => tracing::warn!("Unknown command palette ID: {}", command_id),
+                        },
                     }
                 }
 
@@ -2025,7 +3127,8 @@ impl AgTerm {
             Message::SwitchTheme(theme_name) => {
                 // Switch to a new theme by name
                 if let Some(new_theme) = theme::Theme::by_name(&theme_name) {
-                    self.current_theme = new_theme;
+                    self.base_theme = new_theme;
+                    self.sync_theme();
                     tracing::info!("Switched to theme: {}", theme_name);
                 } else {
                     tracing::warn!("Theme '{}' not found, keeping current theme", theme_name);
@@ -2047,6 +3150,37 @@ impl AgTerm {
                 Task::none()
             }
 
+            Message::PtyOutput(session_id, data) => {
+                self.handle_pty_output(session_id, data);
+                Task::none()
+            }
+
+            Message::AutoSaveSession => {
+                // Skip a tick while the previous save is still being written
+                if self.saving_session {
+                    return Task::none();
+                }
+                let snapshot = self.session_snapshot();
+                let (sender, receiver) = iced::futures::channel::oneshot::channel();
+                let spawned = std::thread::Builder::new()
+                    .name("session-save".to_string())
+                    .spawn(move || {
+                        snapshot.write();
+                        let _ = sender.send(());
+                    });
+                if let Err(e) = spawned {
+                    tracing::warn!("Failed to save session: {}", e);
+                    return Task::none();
+                }
+                self.saving_session = true;
+                Task::perform(receiver, |_| Message::SessionSaved)
+            }
+
+            Message::SessionSaved => {
+                self.saving_session = false;
+                Task::none()
+            }
+
             Message::Tick => {
                 // Record frame for metrics
                 self.debug_panel.metrics.record_frame();
@@ -2077,7 +3211,8 @@ impl AgTerm {
                 if self.last_cursor_blink.elapsed().as_millis() as u64 >= cursor_blink_interval {
                     self.last_cursor_blink = Instant::now();
                     if let Some(tab) = self.tabs.get_mut(self.active_tab) {
-                        if config.terminal.cursor_blink {
+                        // Applications can turn blinking off (DECSCUSR, ?12)
+                        if config.terminal.cursor_blink && tab.screen.cursor_blink_enabled() {
                             tab.cursor_blink_on = !tab.cursor_blink_on;
                         } else {
                             tab.cursor_blink_on = true; // Always on if blinking disabled
@@ -2093,94 +3228,16 @@ impl AgTerm {
                     Task::none()
                 };
 
-                // Poll PTY output only for active tab
-                let mut active_bell_triggered = false;
-                let mut trigger_text: Option<String> = None;
-                if let Some(tab) = self.tabs.get_mut(self.active_tab) {
-                    if let Some(session_id) = &tab.session_id {
-                        if let Ok(data) = self.pty_manager.read(session_id) {
-                            if !data.is_empty() {
-                                // Update PTY activity timestamp for dynamic tick optimization
-                                self.last_pty_activity = Instant::now();
-
-                                // Record PTY read metrics
-                                self.debug_panel.metrics.record_pty_read(data.len());
-
-                                // Process bytes through VTE parser
-                                tab.screen.process(&data);
-
-                                // Check for bell (BEL character) in active tab
-                                // Store result to play sound after releasing the borrow
-                                active_bell_triggered = tab.screen.take_bell_triggered();
-
-                                // Update tab title from OSC sequences (OSC 0 or OSC 2)
-                                if let Some(window_title) = tab.screen.window_title() {
-                                    tab.title = Some(window_title.to_string());
-                                }
-
-                                // Update CWD from OSC 7 for tab subtitle/info
-                                if let Some(cwd) = tab.screen.cwd_from_shell() {
-                                    tab.cwd = cwd.to_string();
-                                }
-
-                                // Send pending responses (DA, DSR, CPR, OSC 52 query, etc.) to PTY
-                                let pending_responses = tab.screen.take_pending_responses();
-                                for response in pending_responses {
-                                    let _ = self.pty_manager.write(session_id, response.as_bytes());
-                                }
-
-                                // Handle OSC 52 clipboard set request
-                                if let Some(clipboard_data) = tab.screen.take_clipboard_request() {
-                                    // Decode base64 and set clipboard
-                                    use base64::{engine::general_purpose::STANDARD, Engine as _};
-                                    if let Ok(decoded_bytes) = STANDARD.decode(&clipboard_data) {
-                                        if let Ok(text) = String::from_utf8(decoded_bytes) {
-                                            if let Ok(mut clipboard) = arboard::Clipboard::new() {
-                                                let _ = clipboard.set_text(text);
-                                            }
-                                        }
-                                    }
-                                }
-
-                                // Store text for trigger checking (done after releasing borrow)
-                                if let Ok(text) = String::from_utf8(data.clone()) {
-                                    trigger_text = Some(text);
-                                }
-
-                                // Detect URLs in terminal output
-                                tab.screen.detect_urls();
-
-                                // Convert screen buffer to parsed line cache for rendering
-                                let all_lines = tab.screen.get_all_lines();
-                                tab.parsed_line_cache = all_lines
-                                    .iter()
-                                    .map(|cells| cells_to_styled_spans(cells))
-                                    .collect();
-
-                                // Increment content version for canvas cache invalidation
-                                tab.content_version += 1;
-
-                                // Auto-scroll to bottom
-                                tab.canvas_state
-                                    .scroll_to_bottom(tab.parsed_line_cache.len(), self.font_size);
-                            }
-                        }
-                    }
+                if self.last_process_poll.elapsed() >= PROCESS_POLL_INTERVAL {
+                    self.last_process_poll = Instant::now();
+                    self.poll_processes();
                 }
 
-                // Update bracket matching after processing PTY output
+                // Paint the output received since the last frame, including output
+                // held back by a synchronized update once it ends or times out
+                self.flush_frame();
                 self.update_bracket_match();
-
-                // Check triggers against new output (after releasing tab borrow)
-                if let Some(text) = trigger_text {
-                    self.check_triggers(&text);
-                }
-
-                // Play bell sound and trigger flash if triggered in active tab (after releasing tab borrow)
-                if active_bell_triggered {
-                    self.play_bell_sound();
-                    self.trigger_bell_flash();
-                }
+                self.update_search();
 
                 // Check background tabs for bell notifications
                 let mut background_bell_triggered = false;
@@ -2419,6 +3476,44 @@ impl AgTerm {
                 Task::none()
             }
 
+            // Scrollback search messages
+            Message::OpenSearch => {
+                self.search_bar.get_or_insert_with(SearchBar::new);
+                text_input::focus(search_input_id())
+            }
+
+            Message::Search(SearchBarMessage::Close) => {
+                self.search_bar = None;
+                for tab in &mut self.tabs {
+                    tab.search = None;
+                }
+                text_input::focus(raw_input_id())
+            }
+
+            Message::Search(SearchBarMessage::Next) => {
+                self.select_search_match(true);
+                Task::none()
+            }
+
+            Message::Search(SearchBarMessage::Prev) => {
+                self.select_search_match(false);
+                Task::none()
+            }
+
+            Message::Search(search_message) => {
+                let changed = self
+                    .search_bar
+                    .as_mut()
+                    .is_some_and(|bar| bar.update(search_message));
+                if changed {
+                    for tab in &mut self.tabs {
+                        tab.search = None;
+                    }
+                    self.update_search();
+                }
+                Task::none()
+            }
+
             // Completion messages
             Message::TriggerCompletion => {
                 // Trigger tab completion
@@ -2482,7 +3577,24 @@ impl AgTerm {
                 self.completion_selected = 0;
                 Task::none()
             }
+        };
+
+        if self.tabs.get(self.active_tab).map(|tab| tab.id) != active_tab_id {
+            // Focus reports follow the active tab while the window has focus
+            if self.window_focused {
+                if let Some(index) = self
+                    .tabs
+                    .iter()
+                    .position(|tab| Some(tab.id) == active_tab_id)
+                {
+                    self.report_tab_focus(index, false);
+                }
+                self.report_tab_focus(self.active_tab, true);
+            }
+            // Tabs connected to an SSH profile's host may use their own theme
+            self.sync_theme();
         }
+        task
     }
 
     fn view(&self) -> Element<Message> {
@@ -2589,26 +3701,34 @@ impl AgTerm {
         let mut tab_elements = Vec::with_capacity(self.tabs.len());
         for (i, tab) in self.tabs.iter().enumerate() {
             let is_active = i == self.active_tab;
-            // Use custom title if set, otherwise use default "Terminal N"
+            // Use custom title if set, then the running command, otherwise "Terminal N"
             let label = tab
                 .title
-                .as_ref()
+                .as_deref()
+                .or(tab.title_info.current_command())
                 .map(|t| {
                     // Limit title length to 30 characters for display
-                    if t.len() > 30 {
-                        format!("{}...", &t[..27])
+                    if t.chars().count() > 30 {
+                        format!("{}...", t.chars().take(27).collect::<String>())
                     } else {
-                        t.clone()
+                        t.to_string()
                     }
                 })
                 .unwrap_or_else(|| format!("Terminal {}", i + 1));
             let can_close = self.tabs.len() > 1;
             let has_bell = tab.bell_pending;
-
-            let icon_color = if is_active {
-                inline_theme::TAB_ACTIVE
-            } else if has_bell {
+            // Tab color from an SSH profile, e.g. red for production hosts
+            let tab_color = tab
+                .meta
+                .color
+                .as_deref()
+                .and_then(config::parse_hex_color)
+                .map(|(r, g, b, a)| Color::from_rgba(r, g, b, a));
+
+            let icon_color = if has_bell && !is_active {
                 inline_theme::ACCENT_YELLOW // Yellow bell indicator for inactive tabs
+            } else if is_active {
+                inline_theme::TAB_ACTIVE
             } else {
                 inline_theme::TEXT_MUTED
             };
@@ -2653,7 +3773,7 @@ impl AgTerm {
                     }
                 };
                 button::Style {
-                    background: Some(bg.into()),
+                    background: Some(tab_background(bg, tab_color, is_active).into()),
                     text_color: inline_theme::TEXT_PRIMARY,
                     border: Border {
                         color: Color::TRANSPARENT,
@@ -2684,7 +3804,10 @@ impl AgTerm {
                             } else {
                                 inline_theme::BG_PRIMARY
                             };
-                            (bg, inline_theme::TEXT_MUTED)
+                            (
+                                tab_background(bg, tab_color, is_active),
+                                inline_theme::TEXT_MUTED,
+                            )
                         }
                     };
                     button::Style {
@@ -2712,16 +3835,14 @@ impl AgTerm {
             // Tab content with accent line
             let tab_content = column![
                 row![tab_label_button, close_button],
-                // Active tab bottom accent line (2px height)
+                // Bottom accent line (2px height) for the active tab and colored tabs
                 container(Space::new(Length::Fill, Length::Fixed(2.0)))
                     .width(Length::Fill)
                     .height(Length::Fixed(2.0))
                     .style(move |_| container::Style {
-                        background: if is_active {
-                            Some(inline_theme::TAB_ACTIVE.into())
-                        } else {
-                            None
-                        },
+                        background: tab_color
+                            .or(is_active.then_some(inline_theme::TAB_ACTIVE))
+                            .map(Into::into),
                         ..Default::default()
                     })
             ];
@@ -2782,33 +3903,102 @@ impl AgTerm {
             .into();
 
             let status_bar = self.view_status_bar();
+            let (_, default_bg) = screen_default_colors(&tab.screen, &self.current_theme);
+            let background = default_bg.to_color();
 
-            column![
-                container(
-                    column![
-                        terminal_output,
-                        raw_input_field // Hidden at bottom for IME
-                    ]
+            // Asking before closing this tab while a command runs in it
+            let close_prompt = self
+                .close_confirmation
+                .as_ref()
+                .filter(|(id, _)| *id == tab.id)
+                .map(|(_, reason)| Self::view_close_prompt(reason));
+            let search_bar = self
+                .search_bar
+                .as_ref()
+                .map(|bar| bar.view(tab.search.as_ref()).map(Message::Search));
+
+            column![]
+                .push_maybe(close_prompt)
+                .push_maybe(search_bar)
+                .push(
+                    container(
+                        column![
+                            terminal_output,
+                            raw_input_field // Hidden at bottom for IME
+                        ]
+                        .width(Length::Fill)
+                        .height(Length::Fill),
+                    )
+                    .padding([16, 12]) // Top padding for spacing from tab bar
                     .width(Length::Fill)
                     .height(Length::Fill)
+                    .style(move |_| container::Style {
+                        background: Some(background.into()),
+                        ..Default::default()
+                    }),
                 )
-                .padding([16, 12]) // Top padding for spacing from tab bar
+                .push(status_bar)
                 .width(Length::Fill)
                 .height(Length::Fill)
-                .style(|_| container::Style {
-                    background: Some(inline_theme::BG_SECONDARY.into()),
-                    ..Default::default()
-                }),
-                status_bar
-            ]
-            .width(Length::Fill)
-            .height(Length::Fill)
-            .into()
+                .into()
         } else {
             column![text("No terminal open").color(inline_theme::TEXT_PRIMARY)].into()
         }
     }
 
+    /// Render the bar asking to confirm closing a tab with a running command
+    fn view_close_prompt(reason: &str) -> Element<'static, Message> {
+        let prompt_button = |label, message, text_color| {
+            button(text(label).size(12))
+                .padding([4, 12])
+                .style(move |_, status| {
+                    let bg = match status {
+                        button::Status::Hovered => inline_theme::BG_BLOCK_HOVER,
+                        _ => inline_theme::BG_BLOCK,
+                    };
+                    button::Style {
+                        background: Some(bg.into()),
+                        text_color,
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                })
+                .on_press(message)
+        };
+
+        container(
+            row![
+                text(format!("{reason}. Close it anyway?"))
+                    .size(13)
+                    .color(inline_theme::TEXT_PRIMARY),
+                Space::with_width(Length::Fill),
+                prompt_button("Close", Message::ConfirmCloseTab, inline_theme::ACCENT_RED),
+                prompt_button(
+                    "Cancel",
+                    Message::CancelCloseTab,
+                    inline_theme::TEXT_SECONDARY
+                ),
+            ]
+            .spacing(8)
+            .align_y(Alignment::Center),
+        )
+        .padding([8, 12])
+        .width(Length::Fill)
+        .style(|_| container::Style {
+            background: Some(inline_theme::BG_PRIMARY.into()),
+            border: Border {
+                color: inline_theme::ACCENT_RED,
+                width: 1.0,
+                radius: 0.0.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+
     /// Render the status bar with shell name, mode, and shortcuts
     fn view_status_bar(&self) -> Element<Message> {
         let config = get_config();
@@ -2874,27 +4064,47 @@ impl AgTerm {
         let tab = &self.tabs[self.active_tab];
         let (cursor_row, cursor_col) = tab.screen.cursor_position();
 
+        // Default colors of the active theme, unless the program set its own
+        let (default_fg, _) = screen_default_colors(&tab.screen, &self.current_theme);
+
         // Create cursor state with config-defined style
         let config = get_config();
         let cursor = CursorState {
             row: cursor_row,
             col: cursor_col,
-            style: convert_cursor_style(config.terminal.cursor_style),
-            visible: tab.screen.cursor_visible(),
+            style: screen_cursor_style(tab.screen.cursor_shape(), config.terminal.cursor_style),
+            // The cursor may already be past the rendered frame during a synchronized update
+            visible: tab.screen.cursor_visible() && !tab.frame_pending,
             blink_on: tab.cursor_blink_on,
+            color: self.current_theme.terminal.cursor.to_color(),
         };
 
+        // Mouse reports are relative to the screen, below the scrollback
+        let (_, screen_rows) = tab.screen.dimensions();
+        let screen_top = parsed_cache.len().saturating_sub(screen_rows);
+
         // Create terminal canvas with all lines (virtual scrolling will handle visibility)
         let terminal_canvas = TerminalCanvas::new(
             parsed_cache,
             tab.content_version,
-            inline_theme::TEXT_PRIMARY,
+            default_fg.to_color(),
             MONO_FONT,
         )
         .with_cursor(cursor)
         .with_font_size(self.font_size)
-        .with_search_matches(&self.search_matches, self.current_match_index)
-        .with_bracket_match(tab.bracket_match);
+        .with_search_matches(
+            tab.search.as_ref().map_or(&[], |search| search.matches()),
+            tab.search
+                .as_ref()
+                .and_then(|search| search.current_index()),
+            tab.screen.line_offset(),
+        )
+        .with_scroll_request(tab.scroll_request)
+        .with_bracket_match(tab.bracket_match)
+        .with_text_blink(tab.cursor_blink_on)
+        .with_mouse_reporting(tab.screen.mouse_mode(), screen_top)
+        .with_first_line(tab.first_rendered_line)
+        .with_screen(&tab.screen);
 
         canvas(terminal_canvas)
             .width(Length::Fill)
@@ -2903,7 +4113,15 @@ impl AgTerm {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        // Dynamic tick interval based on PTY activity
+        // PTY output is pushed by the reader threads as it arrives
+        let pty_outputs = Subscription::batch(self.tabs.iter().filter_map(|tab| {
+            let session_id = tab.session_id?;
+            let output = PtyManager::output_stream(self.pty_manager.clone(), session_id)
+                .map(move |data| Message::PtyOutput(session_id, data));
+            Some(Subscription::run_with_id(session_id, output))
+        }));
+
+        // Dynamic tick interval based on PTY activity (cursor blink, held back frames)
         // Adjust base refresh rate based on environment (slower in SSH/container)
         let suggested_settings = self.env_info.suggested_settings();
         let base_refresh_ms = suggested_settings.refresh_rate_ms;
@@ -2924,17 +4142,22 @@ impl AgTerm {
 
         let keyboard =
             keyboard::on_key_press(|key, modifiers| Some(Message::KeyPressed(key, modifiers)));
+        let key_release =
+            keyboard::on_key_release(|key, modifiers| Some(Message::KeyReleased(key, modifiers)));
 
-        // Listen for window resize events
-        let window_events = iced::event::listen_with(|event, _status, _id| {
-            if let iced::Event::Window(iced::window::Event::Resized(size)) = event {
+        // Listen for window resize and focus events
+        let window_events = iced::event::listen_with(|event, _status, _id| match event {
+            iced::Event::Window(iced::window::Event::Resized(size)) => {
                 Some(Message::WindowResized {
                     width: size.width as u32,
                     height: size.height as u32,
                 })
-            } else {
-                None
             }
+            iced::Event::Window(iced::window::Event::Focused) => Some(Message::WindowFocused(true)),
+            iced::Event::Window(iced::window::Event::Unfocused) => {
+                Some(Message::WindowFocused(false))
+            }
+            _ => None,
         });
 
         // Fast timer for bell flash animation (60 FPS for smooth fade-out)
@@ -2944,7 +4167,24 @@ impl AgTerm {
             Subscription::none()
         };
 
-        Subscription::batch([timer, keyboard, window_events, flash_timer])
+        // Periodic session save, so a crash loses at most one interval
+        let session = get_config().general.session;
+        let auto_save = if session.auto_save && session.auto_save_interval_seconds > 0 {
+            let interval = Duration::from_secs(session.auto_save_interval_seconds);
+            iced::time::every(interval).map(|_| Message::AutoSaveSession)
+        } else {
+            Subscription::none()
+        };
+
+        Subscription::batch([
+            timer,
+            keyboard,
+            key_release,
+            window_events,
+            flash_timer,
+            auto_save,
+            pty_outputs,
+        ])
     }
 }
 
@@ -2977,11 +4217,21 @@ mod tests {
             parsed_line_cache: Vec::new(),
             canvas_state: TerminalCanvasState::new(),
             content_version: 0,
+            frame_pending: false,
+            history_lines: 0,
+            first_rendered_line: 0,
             screen: TerminalScreen::new(80, 24),
             cursor_blink_on: true,
             bell_pending: false,
             title: None,
             title_info: terminal::title::TitleInfo::new(),
+            child_exit: None,
+            meta: new_tab_meta(),
+            search: None,
+            search_version: 0,
+            scroll_request: None,
+            shell: TabShell::new(&SpawnSpec::default()),
+            remote: RemoteHost::default(),
             last_copied_selection: None,
             bracket_match: None,
             pane_layout: PaneLayout::Single,
@@ -2998,11 +4248,9 @@ mod tests {
             debug_panel: DebugPanel::new(),
             last_pty_activity: Instant::now(),
             last_cursor_blink: Instant::now(),
+            last_process_poll: Instant::now(),
             font_size: 14.0,
-            search_mode: false,
-            search_query: String::new(),
-            search_matches: Vec::new(),
-            current_match_index: None,
+            search_bar: None,
             env_info: EnvironmentInfo::detect(),
             bell_sound: sound::BellSound::new(),
             bell_flash_active: false,
@@ -3012,7 +4260,10 @@ mod tests {
             tab_rename_mode: None,
             tab_rename_input: String::new(),
             current_theme: theme::Theme::warp_dark(),
+            base_theme: theme::Theme::warp_dark(),
             current_modifiers: Modifiers::default(),
+            window_focused: true,
+            close_confirmation: None,
             notification_manager: NotificationManager::new(config::NotificationConfig::default()),
             keybindings: KeyBindings::default(),
             command_palette: CommandPalette::with_default_commands(),
@@ -3022,6 +4273,11 @@ mod tests {
             completion_selected: 0,
             completion_visible: false,
             trigger_manager: TriggerManager::new(),
+            ssh_profiles: ssh::SshProfileManager::new(),
+            detect_ssh_connection: true,
+            session_tags: session_tags::SessionTagManager::with_defaults(),
+            profiles: profiles::ProfileManager::new(),
+            saving_session: false,
         }
     }
 
@@ -3033,6 +4289,178 @@ mod tests {
         assert_eq!(app.tabs[0].cwd, "/test/path");
     }
 
+    #[test]
+    fn test_ssh_profile_applied_to_remote_host() {
+        let mut app = create_test_app();
+        app.tabs[0].session_id = Some(uuid::Uuid::new_v4());
+        let mut production = ssh::SshProfile::new("production".to_string(), String::new());
+        production.theme = Some("dracula".to_string());
+        production.tab_color = Some("#e53935".to_string());
+        production.tags = vec!["production".to_string()];
+        production.match_hosts = vec!["prod-*".to_string()];
+        app.ssh_profiles.add(production);
+
+        // A tag the user gave the session themselves
+        let session_id = app.tabs[0].session_id.unwrap().to_string();
+        app.session_tags
+            .tag_manager_mut()
+            .create_tag("billing".to_string(), (0, 128, 255), None)
+            .unwrap();
+        app.session_tags
+            .tag_session(session_id.clone(), "billing".to_string())
+            .unwrap();
+        let session_tags = |app: &AgTerm| -> Vec<String> {
+            app.session_tags
+                .get_session_tags(&session_id)
+                .iter()
+                .map(|tag| tag.name.clone())
+                .collect()
+        };
+
+        // ssh to a production host run from the local shell
+        app.tabs[0]
+            .remote
+            .set_ssh_destination(Some("prod-db1".to_string()));
+        app.update_remote_host(0);
+        assert_eq!(app.tabs[0].meta.color.as_deref(), Some("#e53935"));
+        assert_eq!(app.current_theme.name, theme::Theme::dracula().name);
+        assert_eq!(session_tags(&app), ["billing", "production"]);
+
+        // Back at the local prompt
+        app.tabs[0].remote.set_ssh_destination(None);
+        app.update_remote_host(0);
+        assert_eq!(app.tabs[0].meta.color, None);
+        assert_eq!(app.current_theme.name, theme::Theme::warp_dark().name);
+        assert_eq!(session_tags(&app), ["billing"]);
+
+        // A remote shell reporting its host through OSC 7
+        app.tabs[0]
+            .screen
+            .process(b"\x1b]7;file://prod-web3/srv\x1b\\");
+        let tab = &mut app.tabs[0];
+        tab.remote.set_reported_host(tab.screen.host_from_shell());
+        app.update_remote_host(0);
+        assert_eq!(app.tabs[0].meta.color.as_deref(), Some("#e53935"));
+    }
+
+    #[test]
+    fn test_shell_marks_tracked_per_tab() {
+        let mut app = create_test_app();
+        let session_id = uuid::Uuid::new_v4();
+        app.tabs[0].session_id = Some(session_id);
+
+        // A shell that marks the prompt and output but does not report the command line
+        app.handle_pty_output(
+            session_id,
+            b"\x1b]7;file:///tmp\x07\x1b]133;A\x07$ \x1b]133;B\x07ls -a\r\n\x1b]133;C\x07".to_vec(),
+        );
+        let integration = &app.tabs[0].shell.integration;
+        assert_eq!(integration.command_tracker.current_command(), Some("ls -a"));
+        assert_eq!(integration.cwd, std::path::PathBuf::from("/tmp"));
+
+        app.handle_pty_output(session_id, b"a.txt\r\n\x1b]133;D;2\x07".to_vec());
+        let integration = &app.tabs[0].shell.integration;
+        let execution = integration.command_tracker.history.last().unwrap();
+        assert_eq!(execution.command, "ls -a");
+        assert_eq!(execution.exit_code, 2);
+        assert_eq!(execution.cwd, std::path::PathBuf::from("/tmp"));
+        assert_eq!(integration.last_exit_code, Some(2));
+    }
+
+    #[test]
+    fn test_shell_marks_follow_reflow() {
+        let mut app = create_test_app();
+        let session_id = uuid::Uuid::new_v4();
+        app.tabs[0].session_id = Some(session_id);
+        let output = format!(
+            "\x1b]133;A\x07$ \x1b]133;B\x07cat\r\n\x1b]133;C\x07{}\r\n\x1b]133;D;0\x07",
+            "a".repeat(88)
+        );
+        app.handle_pty_output(session_id, output.into_bytes());
+        assert_eq!(app.tabs[0].shell.command_output[0], 1..3);
+
+        // The 88 column output line splits into three lines at 40 columns
+        let tab = &mut app.tabs[0];
+        tab.screen.resize(40, 24);
+        tab.shell.update(&mut tab.screen);
+        assert_eq!(tab.shell.command_output[0], 1..4);
+    }
+
+    #[test]
+    fn test_tab_shell_type_from_spawned_program() {
+        let spec = SpawnSpec::from_argv(vec!["/opt/nu/bin/fish".to_string()]);
+        assert_eq!(TabShell::new(&spec).integration.shell_type, ShellType::Fish);
+
+        // An SSH tab runs no local shell the integration could know
+        let spec = SpawnSpec::from_argv(vec!["ssh".to_string(), "bash-host".to_string()]);
+        assert_eq!(TabShell::new(&spec).integration.shell_type, ShellType::Unknown);
+    }
+
+    #[test]
+    fn test_search_bar_finds_command_output() {
+        let mut app = create_test_app();
+        let session_id = uuid::Uuid::new_v4();
+        app.tabs[0].session_id = Some(session_id);
+        app.handle_pty_output(
+            session_id,
+            b"\x1b]133;A\x07$ \x1b]133;B\x07echo needle\r\n\x1b]133;C\x07needle\r\n\x1b]133;D;0\x07"
+                .to_vec(),
+        );
+        app.flush_frame();
+        assert_eq!(app.tabs[0].shell.command_output.len(), 1);
+        assert_eq!(app.tabs[0].shell.command_output[0], 1..2);
+
+        let finish = |app: &mut AgTerm| {
+            let deadline = Instant::now() + Duration::from_secs(10);
+            while app.tabs[0]
+                .search
+                .as_ref()
+                .is_some_and(|search| search.is_running())
+            {
+                assert!(Instant::now() < deadline, "search did not finish");
+                std::thread::sleep(Duration::from_millis(1));
+                app.update_search();
+            }
+        };
+        let _ = app.update(Message::OpenSearch);
+        let _ = app.update(Message::Search(SearchBarMessage::InputChanged(
+            "needle".to_string(),
+        )));
+        finish(&mut app);
+        assert_eq!(app.tabs[0].search.as_ref().unwrap().matches().len(), 2);
+
+        // Only the output of echo, not its command line
+        let _ = app.update(Message::Search(SearchBarMessage::ToggleCommandOutput));
+        finish(&mut app);
+        let search = app.tabs[0].search.as_ref().unwrap();
+        let lines: Vec<usize> = search.matches().iter().map(|m| m.line).collect();
+        assert_eq!(lines, [1]);
+
+        let _ = app.update(Message::Search(SearchBarMessage::Next));
+        assert_eq!(app.tabs[0].scroll_request, Some((1, 1)));
+
+        let _ = app.update(Message::Search(SearchBarMessage::Close));
+        assert!(app.search_bar.is_none());
+        assert!(app.tabs[0].search.is_none());
+    }
+
+    #[test]
+    fn test_pty_output_painted_once_per_frame() {
+        let mut app = create_test_app();
+        let session_id = uuid::Uuid::new_v4();
+        app.tabs[0].session_id = Some(session_id);
+        let version = app.tabs[0].content_version;
+
+        app.handle_pty_output(session_id, b"one\r\n".to_vec());
+        app.handle_pty_output(session_id, b"two\r\n".to_vec());
+        assert!(app.tabs[0].frame_pending);
+        assert_eq!(app.tabs[0].content_version, version);
+
+        app.flush_frame();
+        assert!(!app.tabs[0].frame_pending);
+        assert_eq!(app.tabs[0].content_version, version + 1);
+    }
+
     #[test]
     fn test_new_tab() {
         let mut app = create_test_app();
@@ -3066,6 +4494,43 @@ mod tests {
         assert_eq!(app.tabs.len(), 1);
     }
 
+    #[test]
+    fn test_close_tab_with_running_command_asks_first() {
+        let mut app = create_test_app();
+        let _ = app.update(Message::NewTab);
+        let _ = app.update(Message::SelectTab(0));
+        app.tabs[1]
+            .meta
+            .update_process(Some("cargo build".to_string()), false);
+
+        // Asks, showing the tab
+        let _ = app.update(Message::CloseTab(1));
+        assert_eq!(app.tabs.len(), 2);
+        assert_eq!(app.active_tab, 1);
+        assert_eq!(
+            app.close_confirmation,
+            Some((app.tabs[1].id, "Tab is running cargo build".to_string()))
+        );
+        let _ = app.update(Message::CancelCloseTab);
+        assert_eq!(app.tabs.len(), 2);
+        assert_eq!(app.close_confirmation, None);
+
+        // Closing it again while asked confirms
+        let _ = app.update(Message::CloseCurrentTab);
+        let _ = app.update(Message::CloseCurrentTab);
+        assert_eq!(app.tabs.len(), 1);
+        assert_eq!(app.close_confirmation, None);
+
+        // Back at the prompt it closes right away
+        let _ = app.update(Message::NewTab);
+        app.tabs[1]
+            .meta
+            .update_process(Some("vim".to_string()), true);
+        app.tabs[1].meta.update_process(None, true);
+        let _ = app.update(Message::CloseCurrentTab);
+        assert_eq!(app.tabs.len(), 1);
+    }
+
     #[test]
     fn test_select_tab() {
         let mut app = create_test_app();
@@ -3080,6 +4545,37 @@ mod tests {
         assert_eq!(app.active_tab, 2);
     }
 
+    #[test]
+    fn test_focus_reports_follow_active_tab() {
+        let mut app = create_test_app();
+        let _ = app.update(Message::NewTab);
+        for tab in &mut app.tabs {
+            // Keep the reports queued on the screens instead of written to a PTY
+            tab.session_id = None;
+            tab.screen.process(b"\x1b[?1004h");
+        }
+
+        let _ = app.update(Message::SelectTab(0));
+        assert_eq!(app.tabs[0].screen.take_pending_responses(), vec!["\x1b[I"]);
+        assert_eq!(app.tabs[1].screen.take_pending_responses(), vec!["\x1b[O"]);
+
+        // Without window focus, switching tabs reports nothing
+        let _ = app.update(Message::WindowFocused(false));
+        assert_eq!(app.tabs[0].screen.take_pending_responses(), vec!["\x1b[O"]);
+        let _ = app.update(Message::SelectTab(1));
+        assert!(app.tabs[0].screen.take_pending_responses().is_empty());
+        assert!(app.tabs[1].screen.take_pending_responses().is_empty());
+    }
+
+    #[test]
+    fn test_dynamic_palette_commands() {
+        assert!(matches!(
+            dynamic_palette_message("ssh:prod-db1"),
+            Some(Message::OpenSshProfile(name)) if name == "prod-db1"
+        ));
+        assert!(dynamic_palette_message("unknown").is_none());
+    }
+
     #[test]
     fn test_select_invalid_tab() {
         let mut app = create_test_app();
@@ -3211,6 +4707,70 @@ mod tests {
         assert!(close_result.is_ok());
     }
 
+    #[test]
+    fn test_session_snapshot_written_as_taken() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut app = create_test_app();
+        app.tabs[0].screen.process(b"saved line\r\n");
+        let mut snapshot = app.session_snapshot();
+        snapshot.path = dir.path().join("session.json");
+        let path = snapshot.path.clone();
+
+        // Output arriving while the snapshot is written on another thread is not in it
+        app.tabs[0].screen.process(b"later line\r\n");
+        std::thread::spawn(move || snapshot.write()).join().unwrap();
+
+        let state = SessionState::load_from_file(&path).unwrap();
+        let hash = state.tabs[0].scrollback_hash.as_ref().unwrap();
+        let data = session::read_scrollback(&session::scrollback_path(&path, 0), hash).unwrap();
+        let mut screen = TerminalScreen::new(80, 24);
+        assert_eq!(screen.restore_scrollback(&data, "restored").unwrap(), 1);
+        let text: String = screen.get_all_lines()[0]
+            .iter()
+            .map(|cell| cell.c)
+            .collect();
+        assert_eq!(text.trim_end(), "saved line");
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_restore_session_spawns_in_saved_cwd() {
+        let dir = std::env::temp_dir();
+        let session_path = dir.join(format!("agterm-session-{}.json", uuid::Uuid::new_v4()));
+        let tab_state = |id, cwd: &str| TabState {
+            cwd: cwd.to_string(),
+            title: None,
+            id,
+            scrollback_hash: None,
+        };
+        SessionState {
+            tabs: vec![
+                tab_state(0, &dir.display().to_string()),
+                tab_state(1, "/nonexistent/agterm"),
+            ],
+            active_tab: 0,
+            window_size: None,
+            font_size: 14.0,
+        }
+        .save_to_file(&session_path)
+        .unwrap();
+
+        let mut config = AppConfig::default();
+        config.general.session.restore_on_startup = true;
+        config.general.session.session_file = Some(session_path.clone());
+        let pty_manager = Arc::new(PtyManager::new());
+        let (tabs, _, _) = AgTerm::restore_session(&config, &pty_manager, None).unwrap();
+        let _ = std::fs::remove_file(&session_path);
+
+        assert_eq!(tabs[0].cwd, dir.display().to_string());
+        // A directory that is gone falls back to the default one
+        assert_ne!(tabs[1].cwd, "/nonexistent/agterm");
+        assert!(tabs.iter().all(|tab| tab.session_id.is_some()));
+        for tab in &tabs {
+            let _ = pty_manager.close_session(tab.session_id.as_ref().unwrap());
+        }
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_pty_write_read() {
@@ -3231,6 +4791,197 @@ mod tests {
         let _ = pty_manager.close_session(&session_id);
     }
 
+    #[test]
+    #[cfg(unix)]
+    fn test_pty_output_subscription() {
+        use terminal::pty::PtyError;
+
+        let pty_manager = PtyManager::new();
+        let session_id = pty_manager.create_session(24, 80).unwrap();
+        let mut output = pty_manager.subscribe(&session_id).unwrap();
+
+        // Output can only be consumed once
+        assert!(matches!(
+            pty_manager.subscribe(&session_id),
+            Err(PtyError::AlreadySubscribed(_))
+        ));
+
+        pty_manager.write(&session_id, b"echo streamed\n").unwrap();
+
+        // Output is pushed without calling read
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut received = Vec::new();
+        while !String::from_utf8_lossy(&received).contains("streamed") && Instant::now() < deadline
+        {
+            match output.try_next() {
+                Ok(Some(chunk)) => received.extend(chunk),
+                _ => std::thread::sleep(Duration::from_millis(10)),
+            }
+        }
+        assert!(String::from_utf8_lossy(&received).contains("streamed"));
+
+        let _ = pty_manager.close_session(&session_id);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_close_flooding_session_does_not_block() {
+        use terminal::pty::SpawnSpec;
+
+        let pty_manager = PtyManager::new();
+        let session_id = pty_manager
+            .create_session_with_spec(24, 80, SpawnSpec::from_argv(vec!["yes".to_string()]))
+            .unwrap();
+        // Subscribed but not drained, like a stream held up by a busy UI thread
+        let output = pty_manager.subscribe(&session_id).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let started = Instant::now();
+        pty_manager.close_session(&session_id).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        drop(output);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_paste_into_flooding_session_does_not_block() {
+        use terminal::pty::SpawnSpec;
+
+        let pty_manager = PtyManager::new();
+        // Floods its output and never reads its input, which fills up in raw mode
+        let spec = SpawnSpec::from_argv(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "stty raw -echo; yes".to_string(),
+        ]);
+        let flooding = pty_manager.create_session_with_spec(24, 80, spec).unwrap();
+        let output = pty_manager.subscribe(&flooding).unwrap();
+        let session_id = pty_manager.create_session(24, 80).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+
+        let started = Instant::now();
+        pty_manager.write(&flooding, &vec![b'x'; 1024 * 1024]).unwrap();
+        assert!(started.elapsed() < Duration::from_secs(1));
+
+        // Other sessions still get their input
+        pty_manager.write(&session_id, b"echo other\n").unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut received = Vec::new();
+        while !String::from_utf8_lossy(&received).contains("other") && Instant::now() < deadline {
+            received.extend(pty_manager.read(&session_id).unwrap());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(String::from_utf8_lossy(&received).contains("other"));
+
+        let _ = pty_manager.close_session(&session_id);
+        pty_manager.close_session(&flooding).unwrap();
+        drop(output);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_pty_spawn_spec() {
+        use terminal::pty::PtyEnvironment;
+
+        let pty_manager = PtyManager::new();
+        let spec = SpawnSpec {
+            cwd: Some(std::env::temp_dir()),
+            environment: Some(PtyEnvironment {
+                inherit_env: true,
+                variables: [("SPAWN_TEST".to_string(), "set".to_string())].into(),
+                unset: vec!["HOME".to_string()],
+            }),
+            ..SpawnSpec::from_argv(vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo \"$SPAWN_TEST:${HOME-unset}:$(pwd)\"; sleep 1".to_string(),
+            ])
+        };
+        let session_id = pty_manager.create_session_with_spec(24, 80, spec).unwrap();
+
+        let temp_dir = std::env::temp_dir().canonicalize().unwrap();
+        let expected = format!("set:unset:{}", temp_dir.display());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut received = Vec::new();
+        while !String::from_utf8_lossy(&received).contains(&expected) && Instant::now() < deadline {
+            received.extend(pty_manager.read(&session_id).unwrap());
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert!(String::from_utf8_lossy(&received).contains(&expected));
+
+        let _ = pty_manager.close_session(&session_id);
+
+        // A missing working directory is reported instead of silently replaced
+        let spec = SpawnSpec {
+            cwd: Some("/nonexistent/agterm".into()),
+            ..SpawnSpec::default()
+        };
+        assert!(pty_manager.create_session_with_spec(24, 80, spec).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pty_process_lifecycle() {
+        let wait_for_exit = |pty_manager: &PtyManager, session_id| {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                if let Some(exit) = pty_manager.check_status(&session_id).unwrap() {
+                    return exit;
+                }
+                assert!(Instant::now() < deadline, "child did not exit");
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        };
+
+        let pty_manager = PtyManager::new();
+        let spec = SpawnSpec::from_argv(vec!["sleep".to_string(), "5".to_string()]);
+        let session_id = pty_manager.create_session_with_spec(24, 80, spec).unwrap();
+
+        // The child may not have exec'd yet
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let foreground = loop {
+            let foreground = pty_manager.foreground_process(&session_id).unwrap();
+            match foreground {
+                Some(process) if process.name == "sleep" => break process,
+                _ if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(10)),
+                other => panic!("unexpected foreground process {other:?}"),
+            }
+        };
+        assert!(foreground.is_session_child);
+        assert_eq!(pty_manager.check_status(&session_id).unwrap(), None);
+
+        pty_manager
+            .signal(&session_id, PtySignal::Terminate)
+            .unwrap();
+        let exit = wait_for_exit(&pty_manager, session_id);
+        assert!(matches!(exit, ChildExit::Signal(libc::SIGTERM)), "{exit}");
+        assert!(!exit.success());
+        let _ = pty_manager.close_session(&session_id);
+
+        let spec = SpawnSpec::from_argv(vec![
+            "sh".to_string(),
+            "-c".to_string(),
+            "exit 3".to_string(),
+        ]);
+        let session_id = pty_manager.create_session_with_spec(24, 80, spec).unwrap();
+        assert_eq!(wait_for_exit(&pty_manager, session_id), ChildExit::Code(3));
+        let _ = pty_manager.close_session(&session_id);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_child_exit_display() {
+        assert_eq!(ChildExit::Code(3).to_string(), "exited with code 3");
+
+        // Signal descriptions are platform text ("Terminated" vs "Terminated: 15")
+        // SAFETY: strsignal returns a NUL-terminated string
+        let description = unsafe { std::ffi::CStr::from_ptr(libc::strsignal(libc::SIGTERM)) };
+        assert_eq!(
+            ChildExit::Signal(libc::SIGTERM).to_string(),
+            format!("terminated by {}", description.to_string_lossy())
+        );
+    }
+
     // ========== Font Configuration Tests ==========
 
     #[test]
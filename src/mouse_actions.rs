@@ -30,6 +30,8 @@ pub enum MouseAction {
     Drag(MouseButton),
     Scroll(ScrollDirection),
     Release(MouseButton),
+    /// Pointer motion with no button held
+    Move,
 }
 
 /// Scroll direction
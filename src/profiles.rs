@@ -11,6 +11,7 @@
 
 use crate::keybind::{Action, KeyCombo, KeyModifiers};
 use crate::shell::ShellType;
+use crate::terminal::pty::{PtyEnvironment, SpawnSpec};
 use crate::theme::Theme;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -25,12 +26,14 @@ use uuid::Uuid;
 #[derive(Debug, Error)]
 pub enum ProfileError {
     #[error("Profile not found: {0}")]
+    #[allow(dead_code)]
     NotFound(String),
 
     #[error("Profile already exists: {0}")]
     AlreadyExists(String),
 
     #[error("Invalid profile name: {0}")]
+    #[allow(dead_code)]
     InvalidName(String),
 
     #[error("IO error: {0}")]
@@ -43,6 +46,7 @@ pub enum ProfileError {
     Deserialization(#[from] toml::de::Error),
 
     #[error("No default profile set")]
+    #[allow(dead_code)]
     NoDefaultProfile,
 }
 
@@ -327,6 +331,7 @@ impl ProfileManager {
     }
 
     /// Create a ProfileManager with custom storage directory
+    #[allow(dead_code)]
     pub fn with_storage_dir(dir: PathBuf) -> Self {
         Self {
             profiles: HashMap::new(),
@@ -361,11 +366,15 @@ impl ProfileManager {
             self.add_profile(default_profile)?;
         }
 
-        // Set default profile if not already set
+        // Set default profile if not already set: the built-in one, as
+        // profiles are kept in no particular order
         if self.default_profile_id.is_none() {
-            if let Some(profile) = self.profiles.values().next() {
-                self.default_profile_id = Some(profile.id.clone());
-            }
+            self.default_profile_id = self
+                .profiles
+                .values()
+                .find(|profile| profile.read_only && profile.name == "Default")
+                .or_else(|| self.profiles.values().next())
+                .map(|profile| profile.id.clone());
         }
 
         Ok(())
@@ -462,11 +471,13 @@ impl ProfileManager {
     }
 
     /// Get a profile by ID
+    #[allow(dead_code)]
     pub fn get_profile(&self, id: &str) -> Option<&Profile> {
         self.profiles.get(id)
     }
 
     /// Get a profile by name
+    #[allow(dead_code)]
     pub fn get_profile_by_name(&self, name: &str) -> Option<&Profile> {
         self.name_index
             .get(name)
@@ -481,6 +492,7 @@ impl ProfileManager {
     }
 
     /// Set the default profile by ID
+    #[allow(dead_code)]
     pub fn set_default_profile(&mut self, id: &str) -> ProfileResult<()> {
         if !self.profiles.contains_key(id) {
             return Err(ProfileError::NotFound(id.to_string()));
@@ -490,6 +502,7 @@ impl ProfileManager {
     }
 
     /// Set the default profile by name
+    #[allow(dead_code)]
     pub fn set_default_profile_by_name(&mut self, name: &str) -> ProfileResult<()> {
         let id = self
             .name_index
@@ -500,6 +513,7 @@ impl ProfileManager {
     }
 
     /// Update an existing profile
+    #[allow(dead_code)]
     pub fn update_profile(&mut self, id: &str, mut profile: Profile) -> ProfileResult<()> {
         if !self.profiles.contains_key(id) {
             return Err(ProfileError::NotFound(id.to_string()));
@@ -538,6 +552,7 @@ impl ProfileManager {
     }
 
     /// Delete a profile by ID
+    #[allow(dead_code)]
     pub fn delete_profile(&mut self, id: &str) -> ProfileResult<()> {
         let profile = self
             .profiles
@@ -571,6 +586,7 @@ impl ProfileManager {
     }
 
     /// Clone an existing profile with a new name
+    #[allow(dead_code)]
     pub fn clone_profile(&mut self, id: &str, new_name: String) -> ProfileResult<String> {
         let source = self
             .profiles
@@ -593,6 +609,7 @@ impl ProfileManager {
     }
 
     /// List all profile names
+    #[allow(dead_code)]
     pub fn list_profiles(&self) -> Vec<String> {
         let mut names: Vec<_> = self.name_index.keys().cloned().collect();
         names.sort();
@@ -600,6 +617,7 @@ impl ProfileManager {
     }
 
     /// List all profiles
+    #[allow(dead_code)]
     pub fn get_all_profiles(&self) -> Vec<&Profile> {
         self.profiles.values().collect()
     }
@@ -613,6 +631,7 @@ impl ProfileManager {
     }
 
     /// Export a profile to a TOML file
+    #[allow(dead_code)]
     pub fn export_profile(&self, id: &str, path: &Path) -> ProfileResult<()> {
         let profile = self
             .profiles
@@ -623,6 +642,7 @@ impl ProfileManager {
     }
 
     /// Import a profile from a TOML file
+    #[allow(dead_code)]
     pub fn import_profile(&mut self, path: &Path) -> ProfileResult<String> {
         let mut profile = Profile::load_from_file(path)?;
 
@@ -691,11 +711,13 @@ impl Profile {
     }
 
     /// Load the theme for this profile
+    #[allow(dead_code)]
     pub fn load_theme(&self) -> Option<Theme> {
         Theme::by_name(&self.colors.theme)
     }
 
     /// Apply this profile's key bindings
+    #[allow(dead_code)]
     pub fn apply_keybindings(&self) -> Vec<(KeyCombo, Action)> {
         self.keybindings
             .iter()
@@ -717,6 +739,7 @@ impl Profile {
     }
 
     /// Get shell type
+    #[allow(dead_code)]
     pub fn get_shell_type(&self) -> Option<ShellType> {
         self.shell.shell_type.as_ref().and_then(|s| match s.as_str() {
             "bash" => Some(ShellType::Bash),
@@ -728,6 +751,22 @@ impl Profile {
             _ => None,
         })
     }
+
+    /// Describe how to launch this profile's shell in a PTY session
+    pub fn spawn_spec(&self) -> SpawnSpec {
+        SpawnSpec {
+            program: self.shell.command.clone(),
+            args: self.shell.args.clone(),
+            cwd: self.working_directory.clone(),
+            environment: Some(PtyEnvironment {
+                inherit_env: true,
+                variables: self.environment.clone(),
+                unset: Vec::new(),
+            }),
+            login: self.shell.login_shell,
+            ..SpawnSpec::default()
+        }
+    }
 }
 
 impl Default for Profile {
@@ -850,6 +889,21 @@ mod tests {
         assert_eq!(profile.colors.theme, default_theme());
     }
 
+    #[test]
+    fn test_profile_spawn_spec() {
+        let mut profile = Profile::new("Login".to_string());
+        profile.shell.command = Some("/bin/bash".to_string());
+        profile.shell.args = vec!["--norc".to_string()];
+        profile.shell.login_shell = true;
+        profile.working_directory = Some(PathBuf::from("/tmp"));
+
+        let spec = profile.spawn_spec();
+        assert_eq!(spec.program, Some("/bin/bash".to_string()));
+        assert_eq!(spec.args, vec!["--norc".to_string()]);
+        assert_eq!(spec.cwd, Some(PathBuf::from("/tmp")));
+        assert!(spec.login);
+    }
+
     #[test]
     fn test_profile_serialization() {
         let profile = Profile::new("Serialize Test".to_string());
@@ -933,7 +987,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_add_profile() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
         let profile = Profile::new("Test".to_string());
 
         let id = manager.add_profile(profile).unwrap();
@@ -943,7 +998,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_duplicate_name() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
         let profile1 = Profile::new("Test".to_string());
         let profile2 = Profile::new("Test".to_string());
 
@@ -955,7 +1011,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_update_profile() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
         let mut profile = Profile::new("Original".to_string());
         profile.read_only = false;
 
@@ -1002,7 +1059,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_clone_profile() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
         let mut profile = Profile::new("Original".to_string());
         profile.description = Some("Original profile".to_string());
         profile.font.size = 20.0;
@@ -1022,7 +1080,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_default_profile() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
         let profile = Profile::new("Default Test".to_string());
 
         let id = manager.add_profile(profile).unwrap();
@@ -1034,7 +1093,8 @@ mod tests {
 
     #[test]
     fn test_profile_manager_list_profiles() {
-        let mut manager = ProfileManager::new();
+        let temp_dir = TempDir::new().unwrap();
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
 
         manager
             .add_profile(Profile::new("Alpha".to_string()))
@@ -1185,6 +1245,27 @@ mod tests {
         assert!(manager.get_default_profile().is_some());
     }
 
+    #[test]
+    fn test_profile_manager_init_default_is_builtin() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["Alpha", "Beta", "Gamma", "Delta"] {
+            let mut profile = Profile::new(name.to_string());
+            profile.shell.command = Some("/bin/sh".to_string());
+            profile
+                .save_to_file(&temp_dir.path().join(format!("{}.toml", profile.id)))
+                .unwrap();
+        }
+
+        let mut manager = ProfileManager::with_storage_dir(temp_dir.path().to_path_buf());
+        manager.init().unwrap();
+
+        // User profiles on disk never become the default
+        let default = manager.get_default_profile().unwrap();
+        assert_eq!(default.name, "Default");
+        assert!(default.read_only);
+        assert_eq!(default.shell.command, None);
+    }
+
     #[test]
     fn test_profile_manager_export_import() {
         let temp_dir = TempDir::new().unwrap();
@@ -5,15 +5,21 @@
 //! - Periodic auto-save
 //! - Session versioning
 //! - Backup management
+//! - Scrollback contents in per-tab binary side files
 
+use crate::terminal::screen::{scrollback_version, SCROLLBACK_VERSION};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 use thiserror::Error;
 
 /// Version of the session file format
 const SESSION_VERSION: u32 = 1;
 
+/// File extension of the per-tab scrollback side files
+const SCROLLBACK_EXTENSION: &str = "scrollback";
+
 /// Session data structure containing all state needed to restore a terminal session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionData {
@@ -41,7 +47,7 @@ pub struct TabState {
     pub shell: Option<String>,
     /// Important environment variables to restore (filtered list)
     pub env_vars: Vec<(String, String)>,
-    /// Optional hash of scrollback content for verification
+    /// Hash of the scrollback side file, None if no scrollback was saved
     pub scrollback_hash: Option<String>,
     /// Tab ID for tracking
     pub id: usize,
@@ -341,6 +347,90 @@ impl WindowState {
     }
 }
 
+/// Path of a tab's scrollback side file, next to the session file
+/// (`session.json` -> `session.tab3.scrollback`)
+pub fn scrollback_path(session_path: &Path, tab_id: usize) -> PathBuf {
+    let stem = session_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_else(|| "session".into());
+    session_path.with_file_name(format!("{stem}.tab{tab_id}.{SCROLLBACK_EXTENSION}"))
+}
+
+/// Write a scrollback side file atomically, returning the hash to store in the session
+pub fn write_scrollback(path: &Path, data: &[u8]) -> Result<String, SessionError> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let temp_path = path.with_extension("tmp");
+    // A leftover temp file would keep its permissions
+    let _ = std::fs::remove_file(&temp_path);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // Scrollback may hold anything printed to the terminal, readable by the user only
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    options.open(&temp_path)?.write_all(data)?;
+    std::fs::rename(&temp_path, path)?;
+    Ok(scrollback_hash(data))
+}
+
+/// Read a scrollback side file, checking it against the hash stored in the session
+pub fn read_scrollback(path: &Path, hash: &str) -> Result<Vec<u8>, SessionError> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(SessionError::NotFound),
+        Err(e) => return Err(e.into()),
+    };
+    if scrollback_hash(&data) != hash {
+        return Err(SessionError::Corrupted);
+    }
+    // Written by a version of AgTerm with another encoding
+    match scrollback_version(&data) {
+        Some(version) if version != SCROLLBACK_VERSION => Err(SessionError::VersionMismatch {
+            expected: SCROLLBACK_VERSION.into(),
+            actual: version.into(),
+        }),
+        _ => Ok(data),
+    }
+}
+
+/// Remove scrollback side files of a session that are not in `keep`
+///
+/// Tabs closed since the last save would otherwise leave their files behind.
+pub fn prune_scrollback(session_path: &Path, keep: &[PathBuf]) -> Result<(), SessionError> {
+    let Some(dir) = session_path.parent().filter(|dir| dir.is_dir()) else {
+        return Ok(());
+    };
+    let stem = session_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let prefix = format!("{stem}.tab");
+
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        let is_side_file = path
+            .extension()
+            .is_some_and(|ext| ext == SCROLLBACK_EXTENSION)
+            && path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&prefix));
+        if is_side_file && !keep.contains(&path) {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// FNV-1a hash of scrollback contents, stable across builds
+fn scrollback_hash(data: &[u8]) -> String {
+    let hash = data.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
 // Custom serialization for SystemTime
 mod systemtime_serde {
     use serde::{Deserialize, Deserializer, Serialize, Serializer};
@@ -368,6 +458,7 @@ mod systemtime_serde {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::terminal::screen::TerminalScreen;
     use std::fs;
     use tempfile::TempDir;
 
@@ -489,6 +580,51 @@ mod tests {
         assert!(!SessionData::has_recovery_file());
     }
 
+    #[test]
+    fn test_scrollback_side_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let session_path = temp_dir.path().join("session.json");
+
+        let path = scrollback_path(&session_path, 3);
+        assert_eq!(path, temp_dir.path().join("session.tab3.scrollback"));
+        let hash = write_scrollback(&path, b"build output").unwrap();
+        assert_eq!(read_scrollback(&path, &hash).unwrap(), b"build output");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // A side file that does not match the session is rejected
+        fs::write(&path, b"other output").unwrap();
+        assert!(matches!(
+            read_scrollback(&path, &hash),
+            Err(SessionError::Corrupted)
+        ));
+        assert!(matches!(
+            read_scrollback(&scrollback_path(&session_path, 4), &hash),
+            Err(SessionError::NotFound)
+        ));
+
+        // As written by a version of AgTerm with another encoding
+        let mut data = TerminalScreen::new(20, 3).scrollback_snapshot(100).encode();
+        data[4] = SCROLLBACK_VERSION + 1;
+        let hash = write_scrollback(&path, &data).unwrap();
+        assert!(matches!(
+            read_scrollback(&path, &hash),
+            Err(SessionError::VersionMismatch { expected, actual })
+                if expected == SCROLLBACK_VERSION as u32 && actual == expected + 1
+        ));
+
+        // Files of closed tabs are pruned
+        let stale = scrollback_path(&session_path, 7);
+        fs::write(&stale, b"").unwrap();
+        prune_scrollback(&session_path, std::slice::from_ref(&path)).unwrap();
+        assert!(path.exists());
+        assert!(!stale.exists());
+    }
+
     #[test]
     fn test_tab_state_env_vars() {
         let mut tab = TabState::new(
@@ -47,6 +47,7 @@ impl Tag {
     }
 
     /// Set description
+    #[allow(dead_code)]
     pub fn with_description(mut self, description: String) -> Self {
         self.description = Some(description);
         self
@@ -117,6 +118,7 @@ impl SessionTag {
 
 /// Updates that can be applied to a tag
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub struct TagUpdate {
     /// New color (if specified)
     pub color: Option<(u8, u8, u8)>,
@@ -126,6 +128,7 @@ pub struct TagUpdate {
     pub description: Option<Option<String>>,
 }
 
+#[allow(dead_code)]
 impl TagUpdate {
     /// Create an empty update
     pub fn new() -> Self {
@@ -274,6 +277,7 @@ impl TagManager {
     }
 
     /// Delete a tag
+    #[allow(dead_code)]
     pub fn delete_tag(&mut self, name: &str) -> Result<(), TagError> {
         self.tags
             .remove(name)
@@ -282,6 +286,7 @@ impl TagManager {
     }
 
     /// Update a tag
+    #[allow(dead_code)]
     pub fn update_tag(&mut self, name: &str, updates: TagUpdate) -> Result<(), TagError> {
         let tag = self
             .tags
@@ -304,11 +309,13 @@ impl TagManager {
     }
 
     /// Get a tag by name
+    #[allow(dead_code)]
     pub fn get_tag(&self, name: &str) -> Option<&Tag> {
         self.tags.get(name)
     }
 
     /// List all tags
+    #[allow(dead_code)]
     pub fn list_tags(&self) -> Vec<&Tag> {
         let mut tags: Vec<&Tag> = self.tags.values().collect();
         tags.sort_by(|a, b| a.name.cmp(&b.name));
@@ -390,6 +397,7 @@ impl SessionTagManager {
     }
 
     /// Get all tags for a session
+    #[allow(dead_code)]
     pub fn get_session_tags(&self, session_id: &str) -> Vec<&Tag> {
         if let Some(session) = self.sessions.get(session_id) {
             session
@@ -403,6 +411,7 @@ impl SessionTagManager {
     }
 
     /// Get all sessions with a specific tag
+    #[allow(dead_code)]
     pub fn get_sessions_by_tag(&self, tag_name: &str) -> Vec<&SessionTag> {
         self.sessions
             .values()
@@ -411,6 +420,7 @@ impl SessionTagManager {
     }
 
     /// Set notes for a session
+    #[allow(dead_code)]
     pub fn set_session_note(&mut self, session_id: String, note: Option<String>) -> Result<(), TagError> {
         let session = self.get_or_create_session(&session_id);
         session.notes = note;
@@ -419,6 +429,7 @@ impl SessionTagManager {
     }
 
     /// Pin a session
+    #[allow(dead_code)]
     pub fn pin_session(&mut self, session_id: String) -> Result<(), TagError> {
         let session = self.get_or_create_session(&session_id);
         session.pinned = true;
@@ -427,6 +438,7 @@ impl SessionTagManager {
     }
 
     /// Unpin a session
+    #[allow(dead_code)]
     pub fn unpin_session(&mut self, session_id: &str) -> Result<(), TagError> {
         let session = self
             .sessions
@@ -438,6 +450,7 @@ impl SessionTagManager {
     }
 
     /// Get all pinned sessions
+    #[allow(dead_code)]
     pub fn get_pinned_sessions(&self) -> Vec<&SessionTag> {
         let mut pinned: Vec<&SessionTag> = self
             .sessions
@@ -451,6 +464,7 @@ impl SessionTagManager {
     }
 
     /// Search sessions by query (matches session ID, tags, or notes)
+    #[allow(dead_code)]
     pub fn search_sessions(&self, query: &str) -> Vec<&SessionTag> {
         let query_lower = query.to_lowercase();
 
@@ -485,6 +499,7 @@ impl SessionTagManager {
     }
 
     /// Remove a session completely
+    #[allow(dead_code)]
     pub fn remove_session(&mut self, session_id: &str) -> Result<(), TagError> {
         self.sessions
             .remove(session_id)
@@ -493,6 +508,7 @@ impl SessionTagManager {
     }
 
     /// Get tag manager (for tag operations)
+    #[allow(dead_code)]
     pub fn tag_manager(&self) -> &TagManager {
         &self.tag_manager
     }
@@ -503,6 +519,7 @@ impl SessionTagManager {
     }
 
     /// Save to file
+    #[allow(dead_code)]
     pub fn save_to_file(&self, path: &PathBuf) -> Result<(), TagError> {
         // Create parent directory if it doesn't exist
         if let Some(parent) = path.parent() {
@@ -522,6 +539,7 @@ impl SessionTagManager {
     }
 
     /// Load from file
+    #[allow(dead_code)]
     pub fn load_from_file(path: &PathBuf) -> Result<Self, TagError> {
         if !path.exists() {
             // Return default if file doesn't exist
@@ -536,6 +554,7 @@ impl SessionTagManager {
     }
 
     /// Get default storage path
+    #[allow(dead_code)]
     pub fn default_path() -> PathBuf {
         dirs::data_local_dir()
             .unwrap_or_else(|| PathBuf::from("."))
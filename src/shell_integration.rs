@@ -1,7 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 /// Supported shell types
@@ -52,8 +52,11 @@ pub enum Feature {
 /// Main shell integration state
 #[derive(Debug)]
 pub struct ShellIntegration {
+    #[allow(dead_code)]
     pub shell_type: ShellType,
+    #[allow(dead_code)]
     pub enabled_features: HashSet<Feature>,
+    #[allow(dead_code)]
     pub prompt_command: Option<String>,
     pub cwd: PathBuf,
     pub last_command: Option<String>,
@@ -91,6 +94,7 @@ impl ShellIntegration {
     pub fn handle_event(&mut self, event: ShellEvent) {
         match event {
             ShellEvent::CwdChanged(path) => {
+                self.command_tracker.set_cwd(path.clone());
                 self.cwd = path.clone();
                 self.prompt_info.cwd = path.clone();
                 self.prompt_info.cwd_short = abbreviate_path(&path);
@@ -102,6 +106,11 @@ impl ShellIntegration {
                 self.command_tracker.start_command(&cmd);
             }
             ShellEvent::CommandFinished { exit_code, duration } => {
+                // Measure the command when the shell does not report its duration
+                let duration = match self.command_start_time {
+                    Some(start) if duration.is_zero() => start.elapsed(),
+                    _ => duration,
+                };
                 self.last_exit_code = Some(exit_code);
                 self.prompt_info.last_exit_code = Some(exit_code);
                 self.prompt_info.execution_time = Some(duration);
@@ -140,6 +149,10 @@ impl ShellIntegration {
             133 => {
                 return parse_osc133_mark(data);
             }
+            // OSC 633: VS Code-style integration (command line, cwd)
+            633 => {
+                return parse_osc633(data);
+            }
             // OSC 1337: iTerm2-style integration
             1337 => {
                 return parse_osc1337(data);
@@ -150,21 +163,25 @@ impl ShellIntegration {
     }
 
     /// Enable a feature
+    #[allow(dead_code)]
     pub fn enable_feature(&mut self, feature: Feature) {
         self.enabled_features.insert(feature);
     }
 
     /// Disable a feature
+    #[allow(dead_code)]
     pub fn disable_feature(&mut self, feature: Feature) {
         self.enabled_features.remove(&feature);
     }
 
     /// Check if a feature is enabled
+    #[allow(dead_code)]
     pub fn is_feature_enabled(&self, feature: Feature) -> bool {
         self.enabled_features.contains(&feature)
     }
 
     /// Get integration script for current shell
+    #[allow(dead_code)]
     pub fn get_integration_script(&self) -> String {
         integration_script(self.shell_type)
     }
@@ -180,18 +197,22 @@ pub enum ShellEvent {
     PromptEnd,
     OutputStart,
     OutputEnd,
+    #[allow(dead_code)]
     GitStatusChanged(GitStatus),
 }
 
 /// Prompt information
 #[derive(Debug, Clone, Default)]
 pub struct PromptInfo {
+    #[allow(dead_code)]
     pub user: Option<String>,
+    #[allow(dead_code)]
     pub hostname: Option<String>,
     pub cwd: PathBuf,
     pub cwd_short: String,
     pub git_branch: Option<String>,
     pub git_status: Option<GitStatus>,
+    #[allow(dead_code)]
     pub virtualenv: Option<String>,
     pub last_exit_code: Option<i32>,
     pub execution_time: Option<Duration>,
@@ -212,6 +233,7 @@ pub struct GitStatus {
     pub stash_count: u32,
 }
 
+#[allow(dead_code)]
 impl GitStatus {
     pub fn new(branch: String) -> Self {
         Self {
@@ -278,6 +300,7 @@ impl CommandTracker {
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_max_history(max_history: usize) -> Self {
         Self {
             history: Vec::new(),
@@ -322,11 +345,13 @@ impl CommandTracker {
     }
 
     /// Get duration of last command
+    #[allow(dead_code)]
     pub fn last_duration(&self) -> Option<Duration> {
         self.history.last().map(|e| e.duration)
     }
 
     /// Calculate success rate
+    #[allow(dead_code)]
     pub fn success_rate(&self) -> f64 {
         if self.history.is_empty() {
             return 0.0;
@@ -342,6 +367,7 @@ impl CommandTracker {
     }
 
     /// Get commands by exit code
+    #[allow(dead_code)]
     pub fn commands_by_exit_code(&self, exit_code: i32) -> Vec<&CommandExecution> {
         self.history
             .iter()
@@ -350,6 +376,7 @@ impl CommandTracker {
     }
 
     /// Get average execution time
+    #[allow(dead_code)]
     pub fn average_duration(&self) -> Option<Duration> {
         if self.history.is_empty() {
             return None;
@@ -385,6 +412,7 @@ impl DirectoryHistory {
         }
     }
 
+    #[allow(dead_code)]
     pub fn with_max_history(max_history: usize) -> Self {
         Self {
             history: VecDeque::new(),
@@ -418,6 +446,7 @@ impl DirectoryHistory {
     }
 
     /// Get recent directories
+    #[allow(dead_code)]
     pub fn recent(&self, limit: usize) -> Vec<&PathBuf> {
         self.history
             .iter()
@@ -427,6 +456,7 @@ impl DirectoryHistory {
     }
 
     /// Get most frequent directories
+    #[allow(dead_code)]
     pub fn frequent(&self, limit: usize) -> Vec<&PathBuf> {
         let mut entries: Vec<_> = self.frequency.iter().collect();
         entries.sort_by(|a, b| b.1.cmp(a.1));
@@ -438,6 +468,7 @@ impl DirectoryHistory {
     }
 
     /// Fuzzy find directory
+    #[allow(dead_code)]
     pub fn jump(&self, query: &str) -> Option<&PathBuf> {
         let query_lower = query.to_lowercase();
 
@@ -455,6 +486,7 @@ impl DirectoryHistory {
     }
 
     /// Navigate back in history
+    #[allow(dead_code)]
     pub fn back(&mut self) -> Option<&PathBuf> {
         if self.current_index > 0 {
             self.current_index -= 1;
@@ -465,6 +497,7 @@ impl DirectoryHistory {
     }
 
     /// Navigate forward in history
+    #[allow(dead_code)]
     pub fn forward(&mut self) -> Option<&PathBuf> {
         if self.current_index < self.history.len().saturating_sub(1) {
             self.current_index += 1;
@@ -475,11 +508,13 @@ impl DirectoryHistory {
     }
 
     /// Get current directory
+    #[allow(dead_code)]
     pub fn current(&self) -> Option<&PathBuf> {
         self.history.back()
     }
 
     /// Clear history
+    #[allow(dead_code)]
     pub fn clear(&mut self) {
         self.history.clear();
         self.frequency.clear();
@@ -514,19 +549,26 @@ fn parse_osc7_cwd(data: &str) -> Option<PathBuf> {
 /// Parse OSC 133 (shell integration marks)
 fn parse_osc133_mark(data: &str) -> Option<ShellEvent> {
     // Format: 133;X where X is A (prompt start), B (prompt end), C (output start), D (output end)
+    // OSC 633 uses the same marks
     let parts: Vec<&str> = data.split(';').collect();
-    if parts.len() >= 2 && parts[0] == "133" {
+    if parts.len() >= 2 && matches!(parts[0], "133" | "633") {
         match parts[1] {
             "A" => return Some(ShellEvent::PromptStart),
             "B" => return Some(ShellEvent::PromptEnd),
             "C" => return Some(ShellEvent::OutputStart),
             "D" => {
-                // OSC 133;D;exit_code
+                // OSC 133;D;exit_code[;duration=milliseconds]
                 if parts.len() >= 3 {
                     if let Ok(exit_code) = parts[2].parse::<i32>() {
+                        let duration = parts[3..]
+                            .iter()
+                            .find_map(|option| option.strip_prefix("duration="))
+                            .and_then(|ms| ms.parse().ok())
+                            .map(Duration::from_millis)
+                            .unwrap_or_default();
                         return Some(ShellEvent::CommandFinished {
                             exit_code,
-                            duration: Duration::from_secs(0),
+                            duration,
                         });
                     }
                 }
@@ -538,6 +580,48 @@ fn parse_osc133_mark(data: &str) -> Option<ShellEvent> {
     None
 }
 
+/// Parse OSC 633 (VS Code-style integration)
+fn parse_osc633(data: &str) -> Option<ShellEvent> {
+    // Format: 633;E;command_line[;nonce], 633;P;Cwd=path or a 133-style mark
+    let parts: Vec<&str> = data.split(';').collect();
+    if parts.len() < 3 || parts[0] != "633" {
+        return parse_osc133_mark(data);
+    }
+    match parts[1] {
+        "E" => Some(ShellEvent::CommandStarted(unescape_osc633(parts[2]))),
+        "P" => parts[2]
+            .strip_prefix("Cwd=")
+            .map(|path| ShellEvent::CwdChanged(PathBuf::from(unescape_osc633(path)))),
+        _ => parse_osc133_mark(data),
+    }
+}
+
+/// Undo the OSC 633 escaping of `\\` and `\xHH` (used for `;` and control characters)
+fn unescape_osc633(text: &str) -> String {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        if byte != b'\\' {
+            bytes.push(byte);
+        } else if rest.first() == Some(&b'\\') {
+            bytes.push(b'\\');
+            rest = &rest[1..];
+        } else if let Some(value) = rest
+            .strip_prefix(b"x")
+            .and_then(|hex| hex.get(..2))
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            bytes.push(value);
+            rest = &rest[3..];
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
 /// Parse OSC 1337 (iTerm2-style integration)
 fn parse_osc1337(data: &str) -> Option<ShellEvent> {
     // Format: 1337;key=value
@@ -696,10 +780,12 @@ function __agterm_prompt_end
     printf '\e]133;B\a'
 end
 
-# Add prompt marker to prompt
-if not contains __agterm_prompt_end $fish_prompt
+# Add prompt marker after the prompt
+if not functions -q __agterm_original_fish_prompt
+    functions -q fish_prompt; and functions -c fish_prompt __agterm_original_fish_prompt
     function fish_prompt
-        printf '%s%s' (fish_prompt) (__agterm_prompt_end)
+        functions -q __agterm_original_fish_prompt; and __agterm_original_fish_prompt
+        __agterm_prompt_end
     end
 end
 
@@ -717,18 +803,203 @@ end
 "#.to_string()
 }
 
+/// Generate PowerShell integration script
+///
+/// The command line is reported as `OSC 633;E` with `\`, `;` and control
+/// characters escaped as `\\`, `\x3b` and `\xHH`, and the duration of the
+/// command in milliseconds as a `duration=` option of `OSC 133;D`.
+pub fn generate_powershell_integration() -> String {
+    r#"# AgTerm Shell Integration for PowerShell
+
+if ($Global:__AgTermIntegrated) {
+    return
+}
+$Global:__AgTermIntegrated = $true
+$Global:__AgTermOriginalPrompt = $function:prompt
+$Global:__AgTermCommandRunning = $false
+$Global:__AgTermLastHistoryId = -1
+
+# Wrap a payload in an OSC sequence
+function Global:__AgTerm-Osc([string]$Payload) {
+    "$([char]0x1b)]$Payload$([char]0x07)"
+}
+
+# Escape a value for OSC 633 (backslash, semicolon and control characters)
+function Global:__AgTerm-Escape([string]$Value) {
+    $builder = [System.Text.StringBuilder]::new()
+    foreach ($c in $Value.ToCharArray()) {
+        if ($c -eq '\') {
+            [void]$builder.Append('\\')
+        } elseif ($c -eq ';') {
+            [void]$builder.Append('\x3b')
+        } elseif ([int]$c -lt 0x20) {
+            [void]$builder.Append(('\x{0:x2}' -f [int]$c))
+        } else {
+            [void]$builder.Append($c)
+        }
+    }
+    $builder.ToString()
+}
+
+# Report current directory
+function Global:__AgTerm-ReportCwd {
+    if ($PWD.Provider.Name -ne 'FileSystem') {
+        return ''
+    }
+    $path = $PWD.ProviderPath -replace '\\', '/'
+    if (-not $path.StartsWith('/')) {
+        $path = "/$path"
+    }
+    $path = ($path -split '/' | ForEach-Object { [System.Uri]::EscapeDataString($_) }) -join '/'
+    __AgTerm-Osc "7;file://$([System.Net.Dns]::GetHostName())$path"
+}
+
+# Command end marker, directory report and prompt markers
+function Global:prompt {
+    $success = $?
+    $exitCode = if ($success) { 0 } elseif ($Global:LASTEXITCODE) { $Global:LASTEXITCODE } else { 1 }
+    $out = ''
+    if ($Global:__AgTermCommandRunning) {
+        $Global:__AgTermCommandRunning = $false
+        $duration = 0
+        $last = Get-History -Count 1
+        if ($last -and $last.Id -ne $Global:__AgTermLastHistoryId) {
+            $Global:__AgTermLastHistoryId = $last.Id
+            $duration = [long]($last.EndExecutionTime - $last.StartExecutionTime).TotalMilliseconds
+        }
+        $out += __AgTerm-Osc "133;D;$exitCode;duration=$duration"
+    }
+    $out += __AgTerm-ReportCwd
+    $out += __AgTerm-Osc '133;A'
+    $out += & $Global:__AgTermOriginalPrompt
+    $out += __AgTerm-Osc '133;B'
+    $out
+}
+
+# Command line and command start marker, once the line is accepted
+if (Get-Command PSConsoleHostReadLine -ErrorAction SilentlyContinue) {
+    $Global:__AgTermOriginalReadLine = $function:PSConsoleHostReadLine
+    function Global:PSConsoleHostReadLine {
+        $commandLine = & $Global:__AgTermOriginalReadLine
+        if (-not [string]::IsNullOrWhiteSpace($commandLine)) {
+            $Global:__AgTermCommandRunning = $true
+            [Console]::Write((__AgTerm-Osc "633;E;$(__AgTerm-Escape $commandLine)"))
+            [Console]::Write((__AgTerm-Osc '133;C'))
+        }
+        $commandLine
+    }
+}
+
+# Directory jump function
+function Global:j([string]$Query) {
+    if (-not $Query) {
+        Set-Location ~
+    } else {
+        [Console]::Write((__AgTerm-Osc "1337;Jump=$Query"))
+    }
+}
+"#.to_string()
+}
+
+/// Generate Nushell integration script
+///
+/// Reports the command line, exit code and duration the same way as the
+/// PowerShell script.
+pub fn generate_nushell_integration() -> String {
+    r#"# AgTerm Shell Integration for Nushell
+
+# Wrap a payload in an OSC sequence
+def __agterm_osc [payload: string] {
+    $"(char esc)]($payload)(char bel)"
+}
+
+# Escape a value for OSC 633 (backslash, semicolon and control characters)
+def __agterm_escape [value: string] {
+    $value
+    | str replace --all '\' '\\'
+    | str replace --all ';' '\x3b'
+    | str replace --all "\n" '\x0a'
+    | str replace --all "\r" '\x0d'
+    | str replace --all "\t" '\x09'
+}
+
+# Report current directory
+def __agterm_report_cwd [] {
+    let path = ($env.PWD | str replace --all '\' '/')
+    let path = if ($path | str starts-with '/') { $path } else { $"/($path)" }
+    print -n (__agterm_osc $"7;file://((sys host).hostname)($path | url encode)")
+}
+
+$env.__AGTERM_COMMAND_RUNNING = false
+
+# Command end marker and directory report
+$env.config.hooks.pre_prompt = ($env.config.hooks.pre_prompt? | default [] | append {||
+    if $env.__AGTERM_COMMAND_RUNNING {
+        $env.__AGTERM_COMMAND_RUNNING = false
+        let exit_code = $env.LAST_EXIT_CODE
+        let duration = ($env.CMD_DURATION_MS? | default "0")
+        print -n (__agterm_osc $"133;D;($exit_code);duration=($duration)")
+    }
+    __agterm_report_cwd
+})
+
+# Command line and command start marker
+$env.config.hooks.pre_execution = ($env.config.hooks.pre_execution? | default [] | append {||
+    let command = (commandline)
+    if ($command | str trim | is-empty) {
+        return
+    }
+    $env.__AGTERM_COMMAND_RUNNING = true
+    print -n (__agterm_osc $"633;E;(__agterm_escape $command)")
+    print -n (__agterm_osc "133;C")
+})
+
+# Prompt or indicator text, set as a string or a closure
+def __agterm_render [value: any] {
+    if ($value | describe | str starts-with "closure") { do $value } else { $value }
+}
+
+# Prompt start marker before the existing prompt, command start marker after
+# the indicator nushell prints behind it
+$env.__AGTERM_ORIGINAL_PROMPT = ($env.PROMPT_COMMAND? | default "")
+$env.__AGTERM_ORIGINAL_INDICATOR = ($env.PROMPT_INDICATOR? | default "> ")
+$env.__AGTERM_ORIGINAL_VI_INSERT = ($env.PROMPT_INDICATOR_VI_INSERT? | default ": ")
+$env.__AGTERM_ORIGINAL_VI_NORMAL = ($env.PROMPT_INDICATOR_VI_NORMAL? | default "> ")
+$env.PROMPT_COMMAND = {||
+    $"(__agterm_osc '133;A')(__agterm_render $env.__AGTERM_ORIGINAL_PROMPT)"
+}
+$env.PROMPT_INDICATOR = {||
+    $"(__agterm_render $env.__AGTERM_ORIGINAL_INDICATOR)(__agterm_osc '133;B')"
+}
+$env.PROMPT_INDICATOR_VI_INSERT = {||
+    $"(__agterm_render $env.__AGTERM_ORIGINAL_VI_INSERT)(__agterm_osc '133;B')"
+}
+$env.PROMPT_INDICATOR_VI_NORMAL = {||
+    $"(__agterm_render $env.__AGTERM_ORIGINAL_VI_NORMAL)(__agterm_osc '133;B')"
+}
+
+# Initial directory report
+__agterm_report_cwd
+
+# Directory jump function
+def --env j [query?: string] {
+    if $query == null {
+        cd ~
+    } else {
+        print -n (__agterm_osc $"1337;Jump=($query)")
+    }
+}
+"#.to_string()
+}
+
 /// Get integration script for a specific shell
 pub fn integration_script(shell: ShellType) -> String {
     match shell {
         ShellType::Bash => generate_bash_integration(),
         ShellType::Zsh => generate_zsh_integration(),
         ShellType::Fish => generate_fish_integration(),
-        ShellType::PowerShell => {
-            "# PowerShell integration not yet implemented".to_string()
-        }
-        ShellType::Nushell => {
-            "# Nushell integration not yet implemented".to_string()
-        }
+        ShellType::PowerShell => generate_powershell_integration(),
+        ShellType::Nushell => generate_nushell_integration(),
         ShellType::Unknown => {
             "# Unknown shell type - integration not available".to_string()
         }
@@ -738,7 +1009,7 @@ pub fn integration_script(shell: ShellType) -> String {
 // Helper functions
 
 /// Abbreviate a path for display
-fn abbreviate_path(path: &PathBuf) -> String {
+fn abbreviate_path(path: &Path) -> String {
     let path_str = path.to_string_lossy();
 
     // Replace home directory with ~
@@ -842,6 +1113,45 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_osc133_duration() {
+        let event = parse_osc133_mark("133;D;1;duration=1500");
+        assert!(matches!(
+            event,
+            Some(ShellEvent::CommandFinished { exit_code: 1, duration }) if duration == Duration::from_millis(1500)
+        ));
+
+        let event = parse_osc133_mark("133;D;0;aid=42");
+        assert!(matches!(
+            event,
+            Some(ShellEvent::CommandFinished { duration, .. }) if duration.is_zero()
+        ));
+    }
+
+    #[test]
+    fn test_osc633_command_line() {
+        let mut integration = ShellIntegration::new(ShellType::PowerShell);
+        let event = integration.parse_osc(633, r"633;E;echo a\x3b b \\ c\x0a;nonce");
+        assert!(matches!(
+            event,
+            Some(ShellEvent::CommandStarted(cmd)) if cmd == "echo a; b \\ c\n"
+        ));
+
+        let event = integration.parse_osc(633, "633;P;Cwd=/home/user");
+        assert!(
+            matches!(event, Some(ShellEvent::CwdChanged(path)) if path == std::path::Path::new("/home/user"))
+        );
+        let event = integration.parse_osc(633, "633;D;2");
+        assert!(matches!(
+            event,
+            Some(ShellEvent::CommandFinished { exit_code: 2, .. })
+        ));
+        assert!(matches!(
+            integration.parse_osc(633, "633;C"),
+            Some(ShellEvent::OutputStart)
+        ));
+    }
+
     #[test]
     fn test_osc1337_current_dir() {
         let event = parse_osc1337("1337;CurrentDir=/home/user");
@@ -1019,6 +1329,15 @@ mod tests {
         let fish_script = generate_fish_integration();
         assert!(fish_script.contains("AgTerm"));
         assert!(fish_script.contains("fish_prompt"));
+
+        for script in [
+            generate_powershell_integration(),
+            generate_nushell_integration(),
+        ] {
+            for mark in ["133;A", "133;B", "133;C", "133;D;", "633;E;", "7;file://"] {
+                assert!(script.contains(mark), "missing {mark}");
+            }
+        }
     }
 
     #[test]
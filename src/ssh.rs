@@ -2,15 +2,19 @@
 //!
 //! This module provides SSH connection profile management with support for:
 //! - Profile creation and management
-//! - SSH config file parsing
+//! - SSH config file resolution (OpenSSH semantics, see [`SshConfig`])
 //! - SSH command generation with various options
+//! - Per-host theme, tab color and tags, also for ssh run from a local shell
 
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs;
 use std::path::PathBuf;
 use tracing::{debug, warn};
 
+mod config;
+mod remote;
+pub use config::{HostConfig, SshConfig};
+pub use remote::RemoteHost;
+
 /// SSH connection profile
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct SshProfile {
@@ -33,8 +37,28 @@ pub struct SshProfile {
     /// Additional SSH options (e.g., "StrictHostKeyChecking=no")
     #[serde(default)]
     pub extra_options: Vec<String>,
+    /// Theme used while connected (a name known to `Theme::by_name`)
+    #[serde(default)]
+    pub theme: Option<String>,
+    /// Tab color while connected (e.g. "#e53935")
+    #[serde(default)]
+    pub tab_color: Option<String>,
+    /// Tags given to the session while connected
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Host patterns (ssh_config syntax, e.g. "prod-* !prod-test") the
+    /// theme, tab color and tags also apply to
+    #[serde(default)]
+    pub match_hosts: Vec<String>,
+    /// Listed from the SSH config without running its `Match exec` commands,
+    /// see [`SshProfile::resolve_exec`]
+    #[serde(skip)]
+    pub needs_resolve: bool,
 }
 
+/// Options of a resolved host that are profile fields rather than `-o` options
+const PROFILE_OPTIONS: &[&str] = &["hostname", "port", "user", "forwardagent", "proxyjump"];
+
 fn default_ssh_port() -> u16 {
     22
 }
@@ -51,6 +75,11 @@ impl SshProfile {
             forward_agent: false,
             proxy_jump: None,
             extra_options: Vec::new(),
+            theme: None,
+            tab_color: None,
+            tags: Vec::new(),
+            match_hosts: Vec::new(),
+            needs_resolve: false,
         }
     }
 
@@ -99,45 +128,85 @@ impl SshProfile {
         args
     }
 
-    /// Parse SSH config file entry into a profile
-    /// Returns None if hostname not found or parsing fails
-    pub fn from_ssh_config(hostname: &str) -> Option<Self> {
-        let config_path = ssh_config_path()?;
-        if !config_path.exists() {
-            debug!("SSH config file not found at {:?}", config_path);
-            return None;
+    /// Build a profile from the options resolved for a host alias
+    ///
+    /// ssh is run with the host name rather than the alias, so the options
+    /// that are not profile fields (and further identity files) are kept in
+    /// `extra_options`.
+    pub fn from_host_config(name: &str, config: &HostConfig) -> Self {
+        let identity_file = config.get("identityfile");
+        let extra_options = config
+            .iter()
+            .filter(|&(key, value)| {
+                !PROFILE_OPTIONS.contains(&key)
+                    && !(key == "identityfile" && Some(value) == identity_file)
+            })
+            .map(|(key, value)| format!("{key}={value}"))
+            .collect();
+        Self {
+            name: name.to_string(),
+            host: config.get("hostname").unwrap_or(name).to_string(),
+            port: config
+                .get("port")
+                .and_then(|p| p.parse::<u16>().ok())
+                .unwrap_or(22),
+            user: config.get("user").map(|s| s.to_string()),
+            identity_file: identity_file.map(|s| {
+                let expanded = shellexpand::tilde(s);
+                PathBuf::from(expanded.as_ref())
+            }),
+            forward_agent: config
+                .get("forwardagent")
+                .map(|v| v.to_lowercase() == "yes")
+                .unwrap_or(false),
+            proxy_jump: config.get("proxyjump").map(|s| s.to_string()),
+            extra_options,
+            theme: None,
+            tab_color: None,
+            tags: Vec::new(),
+            match_hosts: Vec::new(),
+            needs_resolve: false,
         }
+    }
 
-        let content = fs::read_to_string(&config_path).ok()?;
-        let parsed = parse_ssh_config(&content);
-
-        if let Some(entry) = parsed.get(hostname) {
-            Some(SshProfile {
-                name: hostname.to_string(),
-                host: entry
-                    .get("hostname")
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| hostname.to_string()),
-                port: entry
-                    .get("port")
-                    .and_then(|p| p.parse::<u16>().ok())
-                    .unwrap_or(22),
-                user: entry.get("user").map(|s| s.to_string()),
-                identity_file: entry.get("identityfile").map(|s| {
-                    let expanded = shellexpand::tilde(s);
-                    PathBuf::from(expanded.as_ref())
-                }),
-                forward_agent: entry
-                    .get("forwardagent")
-                    .map(|v| v.to_lowercase() == "yes")
-                    .unwrap_or(false),
-                proxy_jump: entry.get("proxyjump").map(|s| s.to_string()),
-                extra_options: Vec::new(),
-            })
-        } else {
+    /// Resolve a host from the SSH config file into a profile
+    /// Returns None if no config entry applies to the host or the file cannot be read
+    pub fn from_ssh_config(hostname: &str) -> Option<Self> {
+        let config = load_ssh_config()?;
+        let resolved = config.resolve(hostname);
+
+        if resolved.is_empty() {
             debug!("Host '{}' not found in SSH config", hostname);
-            None
+            return None;
         }
+        Some(Self::from_host_config(hostname, &resolved))
+    }
+
+    /// Resolve a profile listed from the SSH config again, running `Match exec` commands
+    ///
+    /// Blocks while the commands run. Other profiles, and hosts no longer in
+    /// the config, are returned as they are.
+    pub fn resolve_exec(&self) -> Self {
+        let resolved = self
+            .needs_resolve
+            .then(|| Self::from_ssh_config(&self.name))
+            .flatten();
+        resolved.unwrap_or_else(|| Self {
+            needs_resolve: false,
+            ..self.clone()
+        })
+    }
+
+    /// Check if the profile sets a theme, tab color or tags
+    pub fn has_metadata(&self) -> bool {
+        self.theme.is_some() || self.tab_color.is_some() || !self.tags.is_empty()
+    }
+
+    /// Check if the profile is for `host`: its name, its host or one of `match_hosts`
+    pub fn matches_host(&self, host: &str) -> bool {
+        self.name.eq_ignore_ascii_case(host)
+            || self.host.eq_ignore_ascii_case(host)
+            || config::matches_pattern_list(&self.match_hosts, host, true)
     }
 
     /// Get the connection string for display (e.g., "user@host:port")
@@ -191,60 +260,32 @@ impl SshProfileManager {
         self.profiles.len() < initial_len
     }
 
+    /// Find the profile for a host, preferring one with a theme, tab color or tags
+    pub fn find_by_host(&self, host: &str) -> Option<&SshProfile> {
+        let mut matching = self.profiles.iter().filter(|p| p.matches_host(host));
+        let first = matching.clone().next();
+        matching.find(|p| p.has_metadata()).or(first)
+    }
+
     /// List all profiles
     pub fn list(&self) -> &[SshProfile] {
         &self.profiles
     }
 
-    /// Load profiles from SSH config file
+    /// Load profiles for the host aliases in the SSH config file
+    ///
+    /// `Match exec` commands are not run for the list; opening a profile
+    /// resolves it with them ([`SshProfile::resolve_exec`]).
     pub fn load_from_ssh_config() -> Self {
-        let config_path = match ssh_config_path() {
-            Some(path) => path,
-            None => {
-                warn!("Could not determine SSH config path");
-                return Self::new();
-            }
-        };
-
-        if !config_path.exists() {
-            debug!("SSH config file not found at {:?}", config_path);
+        let Some(config) = load_ssh_config() else {
             return Self::new();
-        }
-
-        let content = match fs::read_to_string(&config_path) {
-            Ok(c) => c,
-            Err(e) => {
-                warn!("Failed to read SSH config: {}", e);
-                return Self::new();
-            }
         };
 
-        let parsed = parse_ssh_config(&content);
         let mut manager = Self::new();
-
-        for (hostname, entry) in parsed {
-            let profile = SshProfile {
-                name: hostname.clone(),
-                host: entry
-                    .get("hostname")
-                    .map(|s| s.to_string())
-                    .unwrap_or_else(|| hostname.clone()),
-                port: entry
-                    .get("port")
-                    .and_then(|p| p.parse::<u16>().ok())
-                    .unwrap_or(22),
-                user: entry.get("user").map(|s| s.to_string()),
-                identity_file: entry.get("identityfile").map(|s| {
-                    let expanded = shellexpand::tilde(s);
-                    PathBuf::from(expanded.as_ref())
-                }),
-                forward_agent: entry
-                    .get("forwardagent")
-                    .map(|v| v.to_lowercase() == "yes")
-                    .unwrap_or(false),
-                proxy_jump: entry.get("proxyjump").map(|s| s.to_string()),
-                extra_options: Vec::new(),
-            };
+        for alias in config.host_aliases() {
+            let mut profile =
+                SshProfile::from_host_config(&alias, &config.resolve_without_exec(&alias));
+            profile.needs_resolve = true;
             manager.add(profile);
         }
 
@@ -258,57 +299,102 @@ fn ssh_config_path() -> Option<PathBuf> {
     dirs::home_dir().map(|home| home.join(".ssh").join("config"))
 }
 
-/// Parse SSH config file content into a map of host entries
-/// Returns: HashMap<hostname, HashMap<key, value>>
-fn parse_ssh_config(content: &str) -> HashMap<String, HashMap<String, String>> {
-    let mut result = HashMap::new();
-    let mut current_host: Option<String> = None;
-    let mut current_entry = HashMap::new();
+/// Destination host of an ssh command line (`ssh [options] [user@]host [command]`)
+///
+/// Returns None if the command line has no destination, e.g. `ssh -V`.
+pub fn ssh_destination(argv: &[String]) -> Option<String> {
+    /// Options of ssh(1) that take an argument
+    const OPTIONS_WITH_ARG: &str = "BbcDEeFIiJLlmOoPpQRSWw";
+
+    let mut args = argv.iter().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            return args.next().map(|arg| destination_host(arg));
+        }
+        let Some(flags) = arg.strip_prefix('-') else {
+            return Some(destination_host(arg));
+        };
+        // Flags may be grouped ("-tt", "-p2222"); one taking an argument ends
+        // the group, with the argument attached or in the next word
+        for (i, flag) in flags.char_indices() {
+            if OPTIONS_WITH_ARG.contains(flag) {
+                if i + flag.len_utf8() == flags.len() {
+                    args.next();
+                }
+                break;
+            }
+        }
+    }
+    None
+}
 
-    for line in content.lines() {
-        let line = line.trim();
+/// Host part of an ssh destination (`[user@]host` or `ssh://[user@]host[:port]`)
+fn destination_host(destination: &str) -> String {
+    let (destination, is_uri) = match destination.strip_prefix("ssh://") {
+        Some(rest) => (rest, true),
+        None => (destination, false),
+    };
+    let host = destination
+        .rsplit_once('@')
+        .map_or(destination, |(_, host)| host);
+    let port = host
+        .rsplit_once(':')
+        .filter(|(_, port)| is_uri && port.chars().all(|c| c.is_ascii_digit()));
+    port.map_or(host, |(host, _)| host).to_string()
+}
 
-        // Skip comments and empty lines
-        if line.is_empty() || line.starts_with('#') {
-            continue;
-        }
+/// Check if a host name (e.g. from an OSC 7 `file://host/path` URI) is this machine
+pub fn is_local_host(host: &str) -> bool {
+    let host = host.to_lowercase();
+    if host.is_empty() || matches!(host.as_str(), "localhost" | "127.0.0.1" | "::1") {
+        return true;
+    }
+    let short_name = |name: &str| name.split('.').next().unwrap_or_default().to_string();
+    local_hostname().is_some_and(|local| {
+        let local = local.to_lowercase();
+        host == local || short_name(&host) == short_name(&local)
+    })
+}
 
-        // Split at first whitespace
-        let parts: Vec<&str> = line.splitn(2, char::is_whitespace).collect();
-        if parts.len() != 2 {
-            continue;
-        }
+/// Name of this machine
+#[cfg(unix)]
+fn local_hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: the buffer is valid for its length; gethostname NUL-terminates
+    // unless the name is truncated, which the length search handles
+    let result = unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) };
+    if result != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
 
-        let key = parts[0].to_lowercase();
-        let value = parts[1].trim().to_string();
+/// Name of this machine
+#[cfg(not(unix))]
+fn local_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
 
-        if key == "host" {
-            // Save previous host entry
-            if let Some(hostname) = current_host.take() {
-                if !current_entry.is_empty() {
-                    result.insert(hostname, current_entry);
-                    current_entry = HashMap::new();
-                }
-            }
+/// Load the user's SSH config file with its includes
+fn load_ssh_config() -> Option<SshConfig> {
+    let Some(config_path) = ssh_config_path() else {
+        warn!("Could not determine SSH config path");
+        return None;
+    };
 
-            // Start new host entry (skip wildcard patterns)
-            if !value.contains('*') && !value.contains('?') {
-                current_host = Some(value);
-            }
-        } else if current_host.is_some() {
-            // Add option to current host entry
-            current_entry.insert(key, value);
-        }
+    if !config_path.exists() {
+        debug!("SSH config file not found at {:?}", config_path);
+        return None;
     }
 
-    // Save last host entry
-    if let Some(hostname) = current_host {
-        if !current_entry.is_empty() {
-            result.insert(hostname, current_entry);
+    match SshConfig::load(&config_path) {
+        Ok(config) => Some(config),
+        Err(e) => {
+            warn!("Failed to read SSH config: {}", e);
+            None
         }
     }
-
-    result
 }
 
 #[cfg(test)]
@@ -395,23 +481,65 @@ Host *
     ServerAliveInterval 60
 "#;
 
-        let parsed = parse_ssh_config(config);
+        let parsed = SshConfig::parse(config, std::path::Path::new("/nonexistent"));
 
-        assert_eq!(parsed.len(), 2); // * is skipped
+        assert_eq!(parsed.host_aliases(), ["example", "bastion"]); // * is not an alias
 
-        let example = parsed.get("example").unwrap();
+        let example = parsed.resolve("example");
         assert_eq!(example.get("hostname").unwrap(), "example.com");
         assert_eq!(example.get("user").unwrap(), "alice");
         assert_eq!(example.get("port").unwrap(), "2222");
         assert_eq!(example.get("identityfile").unwrap(), "~/.ssh/id_rsa");
         assert_eq!(example.get("forwardagent").unwrap(), "yes");
+        assert_eq!(example.get("serveraliveinterval").unwrap(), "60");
 
-        let bastion = parsed.get("bastion").unwrap();
+        let bastion = parsed.resolve("bastion");
         assert_eq!(bastion.get("hostname").unwrap(), "10.0.0.1");
         assert_eq!(bastion.get("user").unwrap(), "admin");
         assert_eq!(bastion.get("proxyjump").unwrap(), "example");
     }
 
+    #[test]
+    fn test_profile_from_host_config() {
+        let config = SshConfig::parse(
+            "Host web-1\n  HostName %h.example.com\n  Port 2222\n\
+             Host *\n  User deploy\n  ForwardAgent yes\n",
+            std::path::Path::new("/nonexistent"),
+        );
+        let profile = SshProfile::from_host_config("web-1", &config.resolve("web-1"));
+        assert_eq!(profile.name, "web-1");
+        assert_eq!(profile.connection_string(), "deploy@web-1.example.com:2222");
+        assert!(profile.forward_agent);
+        assert!(profile.extra_options.is_empty());
+    }
+
+    #[test]
+    fn test_profile_from_host_config_keeps_other_options() {
+        let config = SshConfig::parse(
+            "Host db\n  HostName 10.0.0.5\n  ProxyJump bastion\n  IdentityFile /keys/db\n\
+             Host *\n  IdentityFile /keys/fallback\n  ProxyCommand none\n  ServerAliveInterval 30\n",
+            std::path::Path::new("/nonexistent"),
+        );
+        let profile = SshProfile::from_host_config("db", &config.resolve("db"));
+        assert_eq!(
+            profile.to_command(),
+            vec![
+                "ssh",
+                "-i",
+                "/keys/db",
+                "-J",
+                "bastion",
+                "-o",
+                "identityfile=/keys/fallback",
+                "-o",
+                "proxycommand=none",
+                "-o",
+                "serveraliveinterval=30",
+                "10.0.0.5"
+            ]
+        );
+    }
+
     #[test]
     fn test_profile_manager_add_get() {
         let mut manager = SshProfileManager::new();
@@ -469,4 +597,62 @@ Host *
         assert_eq!(profile1, profile2);
         assert_ne!(profile1, profile3);
     }
+
+    #[test]
+    fn test_find_by_host_prefers_metadata() {
+        let mut manager = SshProfileManager::new();
+        manager.add(SshProfile::new(
+            "prod-db1".to_string(),
+            "10.0.0.5".to_string(),
+        ));
+        let mut production = SshProfile::new("production".to_string(), String::new());
+        production.tab_color = Some("#e53935".to_string());
+        production.tags = vec!["production".to_string()];
+        production.match_hosts = vec!["prod-*".to_string(), "!prod-test".to_string()];
+        manager.add(production);
+
+        assert_eq!(manager.find_by_host("prod-db1").unwrap().name, "production");
+        assert_eq!(manager.find_by_host("PROD-web").unwrap().name, "production");
+        assert!(manager.find_by_host("prod-test").is_none());
+        assert_eq!(manager.find_by_host("10.0.0.5").unwrap().name, "prod-db1");
+    }
+
+    #[test]
+    fn test_resolve_exec_keeps_configured_profiles() {
+        let mut profile = SshProfile::new("staging".to_string(), "10.0.0.7".to_string());
+        profile.user = Some("deploy".to_string());
+        assert_eq!(profile.resolve_exec(), profile);
+    }
+
+    #[test]
+    fn test_ssh_destination() {
+        let argv = |line: &str| line.split(' ').map(str::to_string).collect::<Vec<_>>();
+        assert_eq!(
+            ssh_destination(&argv("ssh prod-db1")).as_deref(),
+            Some("prod-db1")
+        );
+        assert_eq!(
+            ssh_destination(&argv("ssh -p 2222 -A alice@prod-db1 uptime")).as_deref(),
+            Some("prod-db1")
+        );
+        assert_eq!(
+            ssh_destination(&argv("ssh -tt -p2222 -J bastion web-1")).as_deref(),
+            Some("web-1")
+        );
+        assert_eq!(
+            ssh_destination(&argv("ssh ssh://deploy@web-2:2200")).as_deref(),
+            Some("web-2")
+        );
+        assert_eq!(ssh_destination(&argv("ssh -V")), None);
+    }
+
+    #[test]
+    fn test_is_local_host() {
+        assert!(is_local_host(""));
+        assert!(is_local_host("LOCALHOST"));
+        if let Some(local) = local_hostname() {
+            assert!(is_local_host(&local));
+        }
+        assert!(!is_local_host("prod-db1.invalid"));
+    }
 }
@@ -0,0 +1,764 @@
+//! OpenSSH client config resolution
+//!
+//! Follows `ssh_config(5)`: each option takes the first value found in a
+//! matching `Host` or `Match` block, `Host` takes a list of `*`/`?` patterns
+//! where `!pattern` excludes hosts, and `Include` reads other files (globs
+//! allowed, relative paths resolve against the directory of the main config
+//! file, i.e. `~/.ssh`). `Match` supports the `all`, `canonical`, `final`,
+//! `host`, `originalhost`, `user`, `localuser` and `exec` criteria; others
+//! never match.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tracing::{debug, warn};
+
+/// Deepest nesting of `Include` directives, as in OpenSSH
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// Options that may be given several times, keeping every value
+const MULTI_VALUE_OPTIONS: &[&str] = &[
+    "certificatefile",
+    "dynamicforward",
+    "identityfile",
+    "localforward",
+    "remoteforward",
+    "sendenv",
+    "setenv",
+];
+
+/// A parsed SSH config file, with the files it includes
+#[derive(Debug, Clone, Default)]
+pub struct SshConfig {
+    lines: Vec<Line>,
+}
+
+/// A directive of a config file
+#[derive(Debug, Clone)]
+enum Line {
+    /// `Host` with its patterns
+    Host(Vec<String>),
+    /// `Match` with its criteria
+    Match(Vec<Criterion>),
+    /// `Include` with the files it expanded to
+    Include(Vec<SshConfig>),
+    /// Any other option: lowercase keyword and arguments
+    Option(String, Vec<String>),
+}
+
+/// One criterion of a `Match` line
+#[derive(Debug, Clone)]
+struct Criterion {
+    negated: bool,
+    kind: CriterionKind,
+}
+
+#[derive(Debug, Clone)]
+enum CriterionKind {
+    All,
+    Canonical,
+    Final,
+    Exec(String),
+    Host(String),
+    OriginalHost(String),
+    User(String),
+    LocalUser(String),
+    /// Criteria we cannot evaluate, which never match
+    Unsupported,
+}
+
+impl SshConfig {
+    /// Load a config file and the files it includes
+    ///
+    /// Relative `Include` paths resolve against the directory of `path`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = fs::read_to_string(path)?;
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        Ok(Self::parse(&content, base))
+    }
+
+    /// Parse config text, resolving relative `Include` paths against `base`
+    pub fn parse(content: &str, base: &Path) -> Self {
+        Self::parse_at_depth(content, base, 0)
+    }
+
+    fn parse_at_depth(content: &str, base: &Path, depth: usize) -> Self {
+        let mut lines = Vec::new();
+        for line in content.lines() {
+            let Some((keyword, args)) = split_line(line) else {
+                continue;
+            };
+            lines.push(match keyword.as_str() {
+                "host" => Line::Host(args),
+                "match" => Line::Match(parse_criteria(&args)),
+                "include" => Line::Include(include(&args, base, depth)),
+                _ => Line::Option(keyword, args),
+            });
+        }
+        Self { lines }
+    }
+
+    /// Resolve the effective options for `host` as given on the ssh command line
+    pub fn resolve(&self, host: &str) -> HostConfig {
+        self.resolve_as(host, &local_user())
+    }
+
+    /// Resolve the options for `host` without running `Match exec` commands
+    ///
+    /// `Match` blocks with an `exec` criterion do not apply, so this never
+    /// blocks on a command, e.g. when listing every host in the file.
+    pub fn resolve_without_exec(&self, host: &str) -> HostConfig {
+        self.resolve_with(host, &local_user(), false)
+    }
+
+    /// Resolve the options for `host` when logged in locally as `local_user`
+    pub fn resolve_as(&self, host: &str, local_user: &str) -> HostConfig {
+        self.resolve_with(host, local_user, true)
+    }
+
+    fn resolve_with(&self, host: &str, local_user: &str, run_exec: bool) -> HostConfig {
+        let mut resolver = Resolver {
+            original_host: host,
+            local_user,
+            run_exec,
+            final_pass: false,
+            wants_final_pass: false,
+            config: HostConfig::default(),
+        };
+        resolver.apply(self);
+        // As in OpenSSH, `Match final` or `Match canonical` triggers a second
+        // pass in which they match; options already set keep their value
+        if resolver.wants_final_pass {
+            resolver.final_pass = true;
+            resolver.apply(self);
+        }
+
+        let hostname = resolver.hostname();
+        let mut config = resolver.config;
+        if let Some(values) = config.options.get_mut("hostname") {
+            values[0] = hostname;
+        }
+        config
+    }
+
+    /// Host names listed in `Host` lines, skipping wildcard and negated patterns
+    pub fn host_aliases(&self) -> Vec<String> {
+        let mut aliases = Vec::new();
+        self.collect_aliases(&mut aliases);
+        aliases
+    }
+
+    fn collect_aliases(&self, aliases: &mut Vec<String>) {
+        for line in &self.lines {
+            match line {
+                Line::Host(patterns) => {
+                    for pattern in patterns.iter().flat_map(|arg| arg.split(',')) {
+                        let concrete = !pattern.contains(['*', '?', '!']) && !pattern.is_empty();
+                        if concrete && !aliases.iter().any(|alias| alias == pattern) {
+                            aliases.push(pattern.to_string());
+                        }
+                    }
+                }
+                Line::Include(files) => {
+                    for file in files {
+                        file.collect_aliases(aliases);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Effective options for one host
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostConfig {
+    /// Values by lowercase keyword; several only for [`MULTI_VALUE_OPTIONS`]
+    options: HashMap<String, Vec<String>>,
+}
+
+impl HostConfig {
+    /// Value of an option (keywords are case-insensitive)
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.get_all(key).first().map(String::as_str)
+    }
+
+    /// All values of an option, in config order (e.g. several `IdentityFile`s)
+    pub fn get_all(&self, key: &str) -> &[String] {
+        self.options
+            .get(&key.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// All values of all options as (keyword, value) pairs, sorted by keyword
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        let mut keys: Vec<&String> = self.options.keys().collect();
+        keys.sort();
+        keys.into_iter().flat_map(|key| {
+            self.options[key]
+                .iter()
+                .map(move |value| (key.as_str(), value.as_str()))
+        })
+    }
+
+    /// Check if no option applies to the host
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    fn set(&mut self, key: &str, args: &[String]) {
+        let value = args.join(" ");
+        if MULTI_VALUE_OPTIONS.contains(&key) {
+            let values = self.options.entry(key.to_string()).or_default();
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        } else {
+            self.options
+                .entry(key.to_string())
+                .or_insert_with(|| vec![value]);
+        }
+    }
+}
+
+/// State of resolving the options for one host
+struct Resolver<'a> {
+    /// Host as given on the command line
+    original_host: &'a str,
+    local_user: &'a str,
+    /// Whether `Match exec` commands run; blocks with them do not match otherwise
+    run_exec: bool,
+    final_pass: bool,
+    wants_final_pass: bool,
+    config: HostConfig,
+}
+
+impl Resolver<'_> {
+    fn apply(&mut self, file: &SshConfig) {
+        let mut active = true;
+        for line in &file.lines {
+            match line {
+                Line::Host(patterns) => {
+                    active = matches_pattern_list(patterns, self.original_host, true);
+                }
+                Line::Match(criteria) => active = self.matches(criteria),
+                // Included files apply only where the Include itself does
+                Line::Include(files) if active => {
+                    for file in files {
+                        self.apply(file);
+                    }
+                }
+                Line::Option(key, args) if active => self.config.set(key, args),
+                _ => {}
+            }
+        }
+    }
+
+    fn matches(&mut self, criteria: &[Criterion]) -> bool {
+        let mut result = true;
+        for criterion in criteria {
+            let matched = match &criterion.kind {
+                CriterionKind::All => true,
+                CriterionKind::Canonical | CriterionKind::Final => {
+                    self.wants_final_pass = true;
+                    self.final_pass
+                }
+                CriterionKind::Exec(_) if !self.run_exec => {
+                    result = false;
+                    continue;
+                }
+                // Commands only run if the criteria before them matched
+                CriterionKind::Exec(command) => result && self.exec(command),
+                CriterionKind::Host(list) => matches_pattern_list(&[list], &self.hostname(), true),
+                CriterionKind::OriginalHost(list) => {
+                    matches_pattern_list(&[list], self.original_host, true)
+                }
+                CriterionKind::User(list) => {
+                    matches_pattern_list(&[list], self.remote_user(), false)
+                }
+                CriterionKind::LocalUser(list) => {
+                    matches_pattern_list(&[list], self.local_user, false)
+                }
+                CriterionKind::Unsupported => false,
+            };
+            if matched == criterion.negated {
+                result = false;
+            }
+        }
+        result
+    }
+
+    /// Host to connect to: `HostName` with `%h` expanded, or the original host
+    fn hostname(&self) -> String {
+        match self.config.get("hostname") {
+            Some(hostname) => expand_tokens(hostname, |token| {
+                (token == 'h').then(|| self.original_host.to_string())
+            }),
+            None => self.original_host.to_string(),
+        }
+    }
+
+    /// User to log in as: `User`, or the local user
+    fn remote_user(&self) -> &str {
+        self.config.get("user").unwrap_or(self.local_user)
+    }
+
+    /// Run a `Match exec` command, matching if it exits successfully
+    fn exec(&self, command: &str) -> bool {
+        let command = expand_tokens(command, |token| match token {
+            'h' => Some(self.hostname()),
+            'n' => Some(self.original_host.to_string()),
+            'p' => Some(self.config.get("port").unwrap_or("22").to_string()),
+            'r' => Some(self.remote_user().to_string()),
+            'u' => Some(self.local_user.to_string()),
+            _ => None,
+        });
+        let status = shell_command(&command)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+        match status {
+            Ok(status) => status.success(),
+            Err(e) => {
+                warn!("Failed to run Match exec command {:?}: {}", command, e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+#[cfg(not(windows))]
+fn shell_command(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+/// Name of the local user
+fn local_user() -> String {
+    ["USER", "USERNAME", "LOGNAME"]
+        .iter()
+        .find_map(|var| std::env::var(var).ok())
+        .unwrap_or_default()
+}
+
+/// Split a line into its lowercase keyword and arguments
+///
+/// The keyword may be followed by `=`. Arguments are separated by
+/// whitespace unless double-quoted. Returns None for blank and comment lines.
+fn split_line(line: &str) -> Option<(String, Vec<String>)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let end = line
+        .find(|c: char| c.is_whitespace() || c == '=')
+        .unwrap_or(line.len());
+    let keyword = line[..end].to_lowercase();
+    let rest = line[end..].trim_start();
+    let rest = rest.strip_prefix('=').unwrap_or(rest);
+
+    let mut args = Vec::new();
+    let mut arg = String::new();
+    let mut in_arg = false;
+    let mut quoted = false;
+    for c in rest.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                in_arg = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                if in_arg {
+                    args.push(std::mem::take(&mut arg));
+                    in_arg = false;
+                }
+            }
+            c => {
+                arg.push(c);
+                in_arg = true;
+            }
+        }
+    }
+    if in_arg {
+        args.push(arg);
+    }
+    Some((keyword, args))
+}
+
+/// Parse the arguments of a `Match` line
+fn parse_criteria(args: &[String]) -> Vec<Criterion> {
+    let mut criteria = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let (negated, name) = match arg.strip_prefix('!') {
+            Some(name) => (true, name.to_lowercase()),
+            None => (false, arg.to_lowercase()),
+        };
+        let kind = match name.as_str() {
+            "all" => CriterionKind::All,
+            "canonical" => CriterionKind::Canonical,
+            "final" => CriterionKind::Final,
+            _ => match (name.as_str(), args.next().cloned()) {
+                ("exec", Some(command)) => CriterionKind::Exec(command),
+                ("host", Some(list)) => CriterionKind::Host(list),
+                ("originalhost", Some(list)) => CriterionKind::OriginalHost(list),
+                ("user", Some(list)) => CriterionKind::User(list),
+                ("localuser", Some(list)) => CriterionKind::LocalUser(list),
+                _ => {
+                    warn!("Unsupported Match criterion in SSH config: {}", name);
+                    CriterionKind::Unsupported
+                }
+            },
+        };
+        criteria.push(Criterion { negated, kind });
+    }
+    criteria
+}
+
+/// Parse the files an `Include` line names
+fn include(patterns: &[String], base: &Path, depth: usize) -> Vec<SshConfig> {
+    if depth >= MAX_INCLUDE_DEPTH {
+        warn!(
+            "SSH config includes nested too deeply, skipping {:?}",
+            patterns
+        );
+        return Vec::new();
+    }
+
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let path = base.join(shellexpand::tilde(pattern).as_ref());
+        for path in expand_glob(&path) {
+            match fs::read_to_string(&path) {
+                Ok(content) => files.push(SshConfig::parse_at_depth(&content, base, depth + 1)),
+                Err(e) => debug!("Skipping SSH config include {:?}: {}", path, e),
+            }
+        }
+    }
+    files
+}
+
+/// Paths matching a path with `*`/`?` wildcards in its components, sorted
+fn expand_glob(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+    for component in path.components() {
+        let part = component.as_os_str();
+        match part.to_str().filter(|part| part.contains(['*', '?'])) {
+            Some(pattern) => {
+                paths = paths
+                    .iter()
+                    .flat_map(|dir| {
+                        let mut matches: Vec<PathBuf> = fs::read_dir(dir)
+                            .into_iter()
+                            .flatten()
+                            .flatten()
+                            .filter(|entry| {
+                                entry.file_name().to_str().is_some_and(|name| {
+                                    // Like glob(3), wildcards skip hidden files
+                                    (!name.starts_with('.') || pattern.starts_with('.'))
+                                        && wildcard_match(pattern, name)
+                                })
+                            })
+                            .map(|entry| entry.path())
+                            .collect();
+                        matches.sort();
+                        matches
+                    })
+                    .collect();
+            }
+            None => {
+                for path in &mut paths {
+                    path.push(part);
+                }
+            }
+        }
+    }
+    paths.retain(|path| path.exists());
+    paths
+}
+
+/// Replace `%x` tokens through `lookup`; `%%` is a `%`, unknown tokens are kept
+fn expand_tokens(text: &str, lookup: impl Fn(char) -> Option<String>) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('%') => out.push('%'),
+            Some(token) => match lookup(token) {
+                Some(value) => out.push_str(&value),
+                None => {
+                    out.push('%');
+                    out.push(token);
+                }
+            },
+            None => out.push('%'),
+        }
+    }
+    out
+}
+
+/// Check a pattern list (whitespace or comma separated) against `text`
+///
+/// Matches if any pattern matches and no `!`-negated pattern does.
+pub(crate) fn matches_pattern_list<S: AsRef<str>>(
+    list: &[S],
+    text: &str,
+    ignore_case: bool,
+) -> bool {
+    let text = if ignore_case {
+        text.to_lowercase()
+    } else {
+        text.to_string()
+    };
+    let mut matched = false;
+    for pattern in list.iter().flat_map(|arg| arg.as_ref().split(',')) {
+        let (negated, pattern) = match pattern.strip_prefix('!') {
+            Some(pattern) => (true, pattern),
+            None => (false, pattern),
+        };
+        let pattern = if ignore_case {
+            pattern.to_lowercase()
+        } else {
+            pattern.to_string()
+        };
+        if wildcard_match(&pattern, &text) {
+            if negated {
+                return false;
+            }
+            matched = true;
+        }
+    }
+    matched
+}
+
+/// Match `text` against a pattern where `*` is any run of characters and `?` any one
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position after the last `*` and the text position it was tried at
+    let mut backtrack = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            p += 1;
+            backtrack = Some((p, t));
+        } else if let Some((star_p, star_t)) = backtrack {
+            p = star_p;
+            t = star_t + 1;
+            backtrack = Some((star_p, t));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ssh_config")
+    }
+
+    fn fixture() -> SshConfig {
+        SshConfig::load(&fixture_dir().join("config")).unwrap()
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("web-*", "web-1"));
+        assert!(wildcard_match("db-?", "db-1"));
+        assert!(!wildcard_match("db-?", "db-10"));
+        assert!(wildcard_match("*.example.*", "a.b.example.com"));
+        assert!(!wildcard_match("*.example.com", "example.com"));
+    }
+
+    #[test]
+    fn test_pattern_list_negation() {
+        let list = ["web-*", "db-*,!db-legacy"];
+        assert!(matches_pattern_list(&list, "WEB-1", true));
+        assert!(matches_pattern_list(&list, "db-2", true));
+        assert!(!matches_pattern_list(&list, "db-legacy", true));
+        assert!(!matches_pattern_list(&list, "cache-1", true));
+        assert!(!matches_pattern_list(&["!db-legacy"], "web-1", true));
+    }
+
+    #[test]
+    fn test_split_line() {
+        assert_eq!(split_line("  # comment"), None);
+        assert_eq!(
+            split_line("HostName=example.com"),
+            Some(("hostname".to_string(), vec!["example.com".to_string()]))
+        );
+        assert_eq!(
+            split_line(r#"Match exec "test -f /tmp/x" host a,b"#)
+                .unwrap()
+                .1,
+            ["exec", "test -f /tmp/x", "host", "a,b"]
+        );
+    }
+
+    #[test]
+    fn test_first_match_wins() {
+        let config = SshConfig::parse(
+            "Host web-1\n  User alice\n  IdentityFile ~/.ssh/a\n\
+             Host *\n  User nobody\n  Port 2222\n  IdentityFile ~/.ssh/b\n",
+            Path::new("/nonexistent"),
+        );
+        let resolved = config.resolve_as("web-1", "local");
+        assert_eq!(resolved.get("User"), Some("alice"));
+        assert_eq!(resolved.get("port"), Some("2222"));
+        assert_eq!(resolved.get_all("identityfile"), ["~/.ssh/a", "~/.ssh/b"]);
+        assert!(config.resolve_as("other", "local").get("user") == Some("nobody"));
+    }
+
+    #[test]
+    fn test_fixture_wildcards_and_includes() {
+        let config = fixture();
+
+        // Team file from `Include conf.d/*.conf` comes first
+        let web = config.resolve_as("web-1", "local");
+        assert_eq!(web.get("hostname"), Some("web-1.team-a.example.com"));
+        assert_eq!(web.get("port"), Some("2222"));
+        assert_eq!(web.get("user"), Some("deploy"));
+        assert_eq!(web.get("proxyjump"), Some("bastion"));
+        assert_eq!(
+            web.get_all("identityfile"),
+            ["~/.ssh/fleet", "~/.ssh/id_ed25519"]
+        );
+        assert_eq!(web.get("serveraliveinterval"), Some("60"));
+
+        // Negated pattern excludes db-legacy from the fleet block
+        let legacy = config.resolve_as("db-legacy", "local");
+        assert_eq!(legacy.get("hostname"), Some("10.0.9.9"));
+        assert_eq!(legacy.get("user"), Some("root"));
+        assert_eq!(legacy.get("proxyjump"), None);
+
+        let unknown = config.resolve_as("elsewhere", "local");
+        assert_eq!(unknown.get("user"), Some("nobody"));
+        assert_eq!(unknown.get("hostname"), None);
+    }
+
+    #[test]
+    fn test_fixture_match_host_and_user() {
+        let config = fixture();
+
+        // `Match host *.internal user deploy` sees HostName and User set earlier
+        let db = config.resolve_as("db-1", "local");
+        assert_eq!(db.get("hostname"), Some("db-1.internal"));
+        assert_eq!(db.get("port"), Some("2200"));
+
+        // `Match localuser`
+        let ci = config.resolve_as("elsewhere", "ci");
+        assert_eq!(ci.get("stricthostkeychecking"), Some("no"));
+        assert_eq!(
+            config
+                .resolve_as("elsewhere", "local")
+                .get("stricthostkeychecking"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_fixture_include_inside_host_block() {
+        let config = fixture();
+        assert_eq!(
+            config.resolve_as("special", "local").get("user"),
+            Some("special")
+        );
+        assert_eq!(
+            config.resolve_as("bastion", "local").get("user"),
+            Some("admin")
+        );
+    }
+
+    #[test]
+    fn test_fixture_host_aliases() {
+        assert_eq!(
+            fixture().host_aliases(),
+            ["web-1", "web-2", "bastion", "db-legacy", "special"]
+        );
+    }
+
+    #[test]
+    fn test_match_final_pass() {
+        let config = SshConfig::parse(
+            "Match final host real.example.com\n  User final\n\
+             Host alias\n  HostName real.example.com\n",
+            Path::new("/nonexistent"),
+        );
+        assert_eq!(
+            config.resolve_as("alias", "local").get("user"),
+            Some("final")
+        );
+        assert_eq!(config.resolve_as("other", "local").get("user"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_match_exec() {
+        let config = SshConfig::parse(
+            "Match exec \"test %h = exec-host\"\n  User matched\n\
+             Match !exec false\n  Port 2022\n",
+            Path::new("/nonexistent"),
+        );
+        let resolved = config.resolve_as("exec-host", "local");
+        assert_eq!(resolved.get("user"), Some("matched"));
+        assert_eq!(resolved.get("port"), Some("2022"));
+        assert_eq!(config.resolve_as("other", "local").get("user"), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_without_exec() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let marker = dir.path().join("ran");
+        let config = SshConfig::parse(
+            &format!(
+                "Match exec \"touch {}\"\n  User matched\n\
+                 Match !exec false\n  Port 2022\n\
+                 Host exec-host\n  Port 2200\n",
+                marker.display()
+            ),
+            Path::new("/nonexistent"),
+        );
+        let resolved = config.resolve_without_exec("exec-host");
+        assert!(!marker.exists());
+        assert_eq!(resolved.get("user"), None);
+        assert_eq!(resolved.get("port"), Some("2200"));
+    }
+
+    #[test]
+    fn test_include_depth_limit() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config");
+        fs::write(&path, "Include config\nUser looped\n").unwrap();
+        let config = SshConfig::load(&path).unwrap();
+        assert_eq!(
+            config.resolve_as("any", "local").get("user"),
+            Some("looped")
+        );
+    }
+}
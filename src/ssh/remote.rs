@@ -0,0 +1,176 @@
+//! Remote host of a tab's shell and the SSH profile applied for it
+//!
+//! The host is the destination of ssh while it runs in the foreground (or of
+//! an SSH tab's own session), else a remote host the shell reports in its
+//! OSC 7 working directory. The matching [`SshProfile`] gives the tab its
+//! theme, tab color and session tags.
+
+use super::{is_local_host, SshProfile};
+use crate::config::parse_hex_color;
+use crate::session_tags::SessionTagManager;
+use crate::tab_manager::Tab;
+use tracing::{info, warn};
+
+/// Color of session tags created for SSH profiles without a tab color
+pub const DEFAULT_TAG_COLOR: (u8, u8, u8) = (128, 128, 128);
+
+/// Where a tab's shell is connected to, from ssh in the foreground or OSC 7
+#[derive(Debug, Default)]
+pub struct RemoteHost {
+    /// Destination of ssh in the foreground, or of an SSH tab's own session
+    ssh_destination: Option<String>,
+    /// Host of the last OSC 7 working directory report
+    reported_host: Option<String>,
+    /// Remote host reported through OSC 7, forgotten when ssh exits
+    shell_host: Option<String>,
+    /// Host whose SSH profile is applied
+    applied: Option<String>,
+    /// Session tags added by the applied profile (the session's own tags are kept)
+    profile_tags: Vec<String>,
+}
+
+impl RemoteHost {
+    /// Remote host of a tab opened for an SSH profile
+    pub fn connected(destination: String) -> Self {
+        Self {
+            ssh_destination: Some(destination),
+            ..Self::default()
+        }
+    }
+
+    /// Record the host of the shell's latest OSC 7 report
+    ///
+    /// Returns true if it differs from the previous report.
+    pub fn set_reported_host(&mut self, host: Option<&str>) -> bool {
+        if host == self.reported_host.as_deref() {
+            return false;
+        }
+        self.reported_host = host.map(str::to_string);
+        self.shell_host = self.reported_host.clone();
+        true
+    }
+
+    /// Record the destination of ssh in the foreground (None when it is not)
+    pub fn set_ssh_destination(&mut self, destination: Option<String>) {
+        if destination.is_none() && self.ssh_destination.is_some() {
+            // Back from ssh: what the remote shell reported no longer applies
+            self.shell_host = None;
+        }
+        self.ssh_destination = destination;
+    }
+
+    /// Host the shell is connected to, if it differs from the applied one
+    ///
+    /// Returns `Some(None)` once the shell is back on this machine. Without
+    /// `detect_ssh_connection` no host is detected.
+    pub fn changed_host(&self, detect_ssh_connection: bool) -> Option<Option<String>> {
+        let host = self
+            .ssh_destination
+            .clone()
+            .or_else(|| self.shell_host.clone().filter(|host| !is_local_host(host)))
+            .filter(|_| detect_ssh_connection);
+        (host != self.applied).then_some(host)
+    }
+
+    /// Give a tab the theme, tab color and tags of the SSH profile of `host`
+    ///
+    /// `None` removes them. Only the tags the previous profile added are taken
+    /// off the session, tags set by the user stay.
+    pub fn apply_profile(
+        &mut self,
+        host: Option<String>,
+        profile: Option<&SshProfile>,
+        tab: &mut Tab,
+        session_tags: &mut SessionTagManager,
+        session_id: Option<&str>,
+    ) {
+        info!(
+            host = ?host,
+            profile = ?profile.map(|profile| &profile.name),
+            "Remote host changed"
+        );
+        self.applied = host;
+        tab.set_color(profile.and_then(|profile| profile.tab_color.clone()));
+        match profile.and_then(|profile| profile.theme.clone()) {
+            Some(theme) => tab.set_metadata("theme".to_string(), theme),
+            None => {
+                tab.metadata.remove("theme");
+            }
+        }
+
+        let previous_tags = std::mem::take(&mut self.profile_tags);
+        let Some(session_id) = session_id else {
+            return;
+        };
+        for tag in &previous_tags {
+            let _ = session_tags.untag_session(session_id, tag);
+        }
+        let tags = profile.map_or(&[][..], |profile| profile.tags.as_slice());
+        let color = profile
+            .and_then(|profile| profile.tab_color.as_deref())
+            .and_then(parse_hex_color)
+            .map_or(DEFAULT_TAG_COLOR, |(r, g, b, _)| {
+                ((r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8)
+            });
+        for tag in tags {
+            let tagged = session_tags
+                .get_session(session_id)
+                .is_some_and(|session| session.has_tag(tag));
+            if tagged {
+                continue;
+            }
+            let tag_manager = session_tags.tag_manager_mut();
+            if !tag_manager.has_tag(tag) {
+                if let Err(e) = tag_manager.create_tag(tag.clone(), color, None) {
+                    warn!("Failed to create tag '{}': {}", tag, e);
+                    continue;
+                }
+            }
+            match session_tags.tag_session(session_id.to_string(), tag.clone()) {
+                Ok(()) => self.profile_tags.push(tag.clone()),
+                Err(e) => warn!("Failed to tag session with '{}': {}", tag, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssh_destination_takes_precedence() {
+        let mut remote = RemoteHost::default();
+        assert_eq!(remote.changed_host(true), None);
+
+        assert!(remote.set_reported_host(Some("prod-web3")));
+        assert!(!remote.set_reported_host(Some("prod-web3")));
+        assert_eq!(
+            remote.changed_host(true),
+            Some(Some("prod-web3".to_string()))
+        );
+
+        remote.set_ssh_destination(Some("prod-db1".to_string()));
+        assert_eq!(
+            remote.changed_host(true),
+            Some(Some("prod-db1".to_string()))
+        );
+        assert_eq!(remote.changed_host(false), None);
+    }
+
+    #[test]
+    fn test_reported_host_forgotten_after_ssh() {
+        let mut remote = RemoteHost::connected("prod-db1".to_string());
+        remote.set_reported_host(Some("prod-db1"));
+        remote.applied = Some("prod-db1".to_string());
+        assert_eq!(remote.changed_host(true), None);
+
+        // Back at the local prompt, the remote shell's report no longer applies
+        remote.set_ssh_destination(None);
+        assert_eq!(remote.changed_host(true), Some(None));
+
+        // A local report is not a remote host
+        remote.set_reported_host(Some("localhost"));
+        assert_eq!(remote.changed_host(true), Some(None));
+    }
+}
@@ -17,7 +17,10 @@ use std::path::{Path, PathBuf};
 use thiserror::Error;
 use uuid::Uuid;
 
+use crate::terminal::pty::{signal_name, ChildExit};
+
 /// Maximum size for tab history
+#[allow(dead_code)]
 const MAX_HISTORY_SIZE: usize = 100;
 
 /// Unique identifier for tabs
@@ -31,6 +34,7 @@ impl TabId {
     }
 
     /// Get the underlying UUID
+    #[allow(dead_code)]
     pub fn as_uuid(&self) -> &Uuid {
         &self.0
     }
@@ -85,6 +89,7 @@ impl TabState {
     }
 
     /// Check if the tab has a bell notification
+    #[allow(dead_code)]
     pub fn has_bell(&self) -> bool {
         matches!(self, TabState::Bell)
     }
@@ -155,6 +160,7 @@ impl Tab {
     }
 
     /// Set the tab's title
+    #[allow(dead_code)]
     pub fn set_title(&mut self, title: String) {
         self.title = title;
         self.touch();
@@ -167,17 +173,20 @@ impl Tab {
     }
 
     /// Toggle the pinned state
+    #[allow(dead_code)]
     pub fn toggle_pin(&mut self) {
         self.pinned = !self.pinned;
         self.touch();
     }
 
     /// Set the tab's icon
+    #[allow(dead_code)]
     pub fn set_icon(&mut self, icon: Option<String>) {
         self.icon = icon;
     }
 
     /// Set the tab's working directory
+    #[allow(dead_code)]
     pub fn set_cwd(&mut self, cwd: PathBuf) {
         self.cwd = cwd;
         self.touch();
@@ -192,10 +201,57 @@ impl Tab {
     pub fn get_metadata(&self, key: &str) -> Option<&String> {
         self.metadata.get(key)
     }
+
+    /// Check if the tab can be closed, regardless of the other tabs
+    pub fn close_permission(&self) -> ClosePermission {
+        // Cannot close pinned tabs without confirmation
+        if self.pinned {
+            return ClosePermission::RequiresConfirmation("Tab is pinned".to_string());
+        }
+
+        // Require confirmation for running commands
+        if let TabState::Running(command) = &self.state {
+            return ClosePermission::RequiresConfirmation(format!("Tab is running {command}"));
+        }
+
+        ClosePermission::Allowed
+    }
+
+    /// Track the foreground process of the tab's PTY
+    ///
+    /// `Some(command)` marks the tab `Running`, `None` (the shell is back at
+    /// its prompt) returns it to `Active` or `Inactive`. Tabs that have
+    /// completed or failed are left alone.
+    pub fn update_process(&mut self, command: Option<String>, is_active: bool) {
+        if self.state.is_completed() || self.state.is_error() {
+            return;
+        }
+
+        let state = match command {
+            Some(command) => TabState::Running(command),
+            None if !self.state.is_running() => return,
+            None if is_active => TabState::Active,
+            None => TabState::Inactive,
+        };
+        if self.state != state {
+            self.set_state(state);
+        }
+    }
+
+    /// Record how the tab's child process ended
+    pub fn update_exit(&mut self, exit: &ChildExit) {
+        self.set_state(match exit {
+            ChildExit::Code(code) => TabState::Completed(*code),
+            ChildExit::Signal(signal) => {
+                TabState::Error(format!("Terminated by {}", signal_name(*signal)))
+            }
+        });
+    }
 }
 
 /// A group of tabs
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct TabGroup {
     /// Unique identifier for this group
     pub id: String,
@@ -209,6 +265,7 @@ pub struct TabGroup {
     pub tab_ids: Vec<TabId>,
 }
 
+#[allow(dead_code)]
 impl TabGroup {
     /// Create a new tab group
     pub fn new(id: String, name: String, color: String) -> Self {
@@ -256,6 +313,7 @@ impl TabGroup {
 
 /// Configuration for tab manager
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct TabManagerConfig {
     /// Maximum number of tabs (0 = unlimited)
     pub max_tabs: usize,
@@ -281,6 +339,7 @@ impl Default for TabManagerConfig {
     }
 }
 
+#[allow(dead_code)]
 impl TabManagerConfig {
     /// Create a new configuration with custom defaults
     pub fn new() -> Self {
@@ -326,11 +385,13 @@ pub enum ClosePermission {
     /// Tab requires confirmation with a reason
     RequiresConfirmation(String),
     /// Tab cannot be closed with a reason
+    #[allow(dead_code)]
     Denied(String),
 }
 
 /// Errors that can occur during tab operations
 #[derive(Debug, Error)]
+#[allow(dead_code)]
 pub enum TabError {
     #[error("Maximum number of tabs ({0}) reached")]
     MaxTabsReached(usize),
@@ -353,6 +414,7 @@ pub enum TabError {
 
 /// Event notifications for tab changes
 #[derive(Debug, Clone)]
+#[allow(dead_code)]
 pub enum TabEvent {
     /// A new tab was created
     Created(TabId),
@@ -372,6 +434,7 @@ pub enum TabEvent {
 
 /// Tab manager for handling all tab operations
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[allow(dead_code)]
 pub struct TabManager {
     /// List of all tabs
     tabs: Vec<Tab>,
@@ -386,6 +449,7 @@ pub struct TabManager {
     history: VecDeque<TabId>,
 }
 
+#[allow(dead_code)]
 impl TabManager {
     /// Create a new tab manager with configuration
     pub fn new(config: TabManagerConfig) -> Self {
@@ -589,6 +653,21 @@ impl TabManager {
         self.active_tab.and_then(|id| self.get_tab_mut(id))
     }
 
+    /// Track the foreground process of a tab's PTY, see [`Tab::update_process`]
+    pub fn update_process(&mut self, id: TabId, command: Option<String>) {
+        let is_active = self.active_tab == Some(id);
+        if let Some(tab) = self.get_tab_mut(id) {
+            tab.update_process(command, is_active);
+        }
+    }
+
+    /// Record how a tab's child process ended
+    pub fn update_exit(&mut self, id: TabId, exit: &ChildExit) {
+        if let Some(tab) = self.get_tab_mut(id) {
+            tab.update_exit(exit);
+        }
+    }
+
     /// Cycle to the next tab
     pub fn next_tab(&mut self) {
         if self.tabs.is_empty() {
@@ -685,23 +764,8 @@ impl TabManager {
             return ClosePermission::Denied("Cannot close the last tab".to_string());
         }
 
-        if let Some(tab) = self.get_tab(id) {
-            // Cannot close pinned tabs without confirmation
-            if tab.pinned {
-                return ClosePermission::RequiresConfirmation(
-                    "Tab is pinned".to_string(),
-                );
-            }
-
-            // Require confirmation for running commands
-            if tab.state.is_running() {
-                return ClosePermission::RequiresConfirmation(
-                    "Tab is running a command".to_string(),
-                );
-            }
-        }
-
-        ClosePermission::Allowed
+        self.get_tab(id)
+            .map_or(ClosePermission::Allowed, Tab::close_permission)
     }
 
     /// Find tabs by title (case-insensitive substring match)
@@ -1125,6 +1189,49 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_update_process() {
+        let mut manager = create_test_manager();
+        let tab1 = manager.create_tab(Some("Tab 1".to_string()), None).unwrap();
+        let tab2 = manager.create_tab(Some("Tab 2".to_string()), None).unwrap();
+
+        manager.update_process(tab2, Some("cargo build".to_string()));
+        assert_eq!(
+            manager.get_tab(tab2).unwrap().state,
+            TabState::Running("cargo build".to_string())
+        );
+        assert_eq!(
+            manager.can_close(tab2),
+            ClosePermission::RequiresConfirmation("Tab is running cargo build".to_string())
+        );
+
+        // Back at the prompt
+        manager.update_process(tab2, None);
+        assert_eq!(manager.get_tab(tab2).unwrap().state, TabState::Inactive);
+        manager.update_process(tab1, Some("vim".to_string()));
+        manager.update_process(tab1, None);
+        assert_eq!(manager.get_tab(tab1).unwrap().state, TabState::Active);
+    }
+
+    #[test]
+    fn test_update_exit() {
+        let mut manager = create_test_manager();
+        let tab1 = manager.create_tab(Some("Tab 1".to_string()), None).unwrap();
+        let tab2 = manager.create_tab(Some("Tab 2".to_string()), None).unwrap();
+
+        manager.update_exit(tab1, &ChildExit::Code(2));
+        assert_eq!(manager.get_tab(tab1).unwrap().state, TabState::Completed(2));
+        manager.update_exit(tab2, &ChildExit::Signal(9));
+        assert_eq!(
+            manager.get_tab(tab2).unwrap().state,
+            TabState::Error(format!("Terminated by {}", signal_name(9)))
+        );
+
+        // An exited tab stays exited
+        manager.update_process(tab1, Some("vim".to_string()));
+        assert_eq!(manager.get_tab(tab1).unwrap().state, TabState::Completed(2));
+    }
+
     #[test]
     fn test_tab_groups() {
         let mut manager = create_test_manager();
@@ -0,0 +1,402 @@
+//! Kitty keyboard protocol encoder
+//!
+//! Applications opt into the protocol with `CSI > flags u` (see
+//! [`TerminalScreen::keyboard_flags`](super::screen::TerminalScreen::keyboard_flags)).
+//! While any flag is set, keys are reported with the unambiguous `CSI ... u`
+//! encoding instead of the legacy xterm sequences, so e.g. Ctrl+I and Tab can be
+//! told apart and key releases can be reported.
+//!
+//! Reference: <https://sw.kovidgoyal.net/kitty/keyboard-protocol/>
+
+use iced::keyboard::key::Named;
+use iced::keyboard::{Key, Modifiers};
+
+/// Report Esc and modified text keys as escape codes
+pub const DISAMBIGUATE_ESCAPE_CODES: u8 = 0b1;
+/// Report key release (and repeat) events
+pub const REPORT_EVENT_TYPES: u8 = 0b10;
+/// Report the shifted key alongside the base key
+pub const REPORT_ALTERNATE_KEYS: u8 = 0b100;
+/// Report all keys, including plain text and Enter/Tab/Backspace, as escape codes
+pub const REPORT_ALL_KEYS_AS_ESCAPE_CODES: u8 = 0b1000;
+/// Report the text generated by a key as codepoints
+pub const REPORT_ASSOCIATED_TEXT: u8 = 0b10000;
+/// All flags defined by the protocol
+pub const ALL_FLAGS: u8 = 0b11111;
+
+/// Kind of key event being encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventKind {
+    Press,
+    Release,
+}
+
+/// How a key is numbered on the wire
+enum KeyCode {
+    /// `CSI code ; mods u` - text keys and most functional keys
+    Unicode(u32),
+    /// `CSI number ; mods ~` - Insert, Delete, PageUp/Down, F3, F5-F12
+    Tilde(u32),
+    /// `CSI 1 ; mods X` - arrows, Home, End, F1, F2, F4
+    Letter(char),
+}
+
+/// Encode a key event with the kitty keyboard protocol
+///
+/// Returns `None` if the protocol is off (`flags == 0`), if the event is not
+/// reported with the current flags, or if the key keeps its legacy encoding
+/// (plain text, unmodified Enter/Tab/Backspace and cursor/function keys).
+pub fn encode_key(
+    key: &Key,
+    modifiers: Modifiers,
+    kind: KeyEventKind,
+    flags: u8,
+) -> Option<String> {
+    if flags == 0 || (kind == KeyEventKind::Release && flags & REPORT_EVENT_TYPES == 0) {
+        return None;
+    }
+    let all_keys = flags & REPORT_ALL_KEYS_AS_ESCAPE_CODES != 0;
+    // Without disambiguation key presses keep the legacy encoding
+    if kind == KeyEventKind::Press && !all_keys && flags & DISAMBIGUATE_ESCAPE_CODES == 0 {
+        return None;
+    }
+    let mods = modifier_bits(modifiers);
+
+    let text = match key.as_ref() {
+        Key::Character(s) => {
+            let mut chars = s.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Some(c),
+                _ => return None,
+            }
+        }
+        Key::Named(Named::Space) => Some(' '),
+        _ => None,
+    };
+
+    let code = if let Some(c) = text {
+        // Text keys without modifiers (other than Shift) are sent as plain text
+        if !all_keys && kind == KeyEventKind::Press && mods & !SHIFT == 0 {
+            return None;
+        }
+
+        let base = single_lowercase(c);
+        let mut code = (base as u32).to_string();
+        if flags & REPORT_ALTERNATE_KEYS != 0 && mods & SHIFT != 0 && base != c {
+            code.push_str(&format!(":{}", c as u32));
+        }
+
+        // Associated text is only reported for key presses that produce text
+        let associated = (all_keys
+            && flags & REPORT_ASSOCIATED_TEXT != 0
+            && kind == KeyEventKind::Press
+            && mods & !SHIFT == 0)
+            .then(|| (c as u32).to_string());
+        return Some(csi(&code, mods, kind, associated.as_deref(), 'u'));
+    } else {
+        let Key::Named(named) = key.as_ref() else {
+            return None;
+        };
+        functional_key(named, all_keys)?
+    };
+
+    // Enter, Tab and Backspace keep their legacy bytes unless modified, and
+    // their releases are only reported together with all other keys
+    if let KeyCode::Unicode(13 | 9 | 127) = code {
+        if !all_keys && (mods == 0 || kind == KeyEventKind::Release) {
+            return None;
+        }
+    }
+
+    // Unmodified cursor and function keys keep their legacy (DECCKM aware) bytes
+    if let KeyCode::Tilde(_) | KeyCode::Letter(_) = code {
+        if !all_keys && mods == 0 && kind == KeyEventKind::Press {
+            return None;
+        }
+    }
+
+    Some(match code {
+        KeyCode::Unicode(code) => csi(&code.to_string(), mods, kind, None, 'u'),
+        KeyCode::Tilde(number) => csi(&number.to_string(), mods, kind, None, '~'),
+        KeyCode::Letter(letter) => {
+            let number = if mods != 0 || kind != KeyEventKind::Press {
+                "1"
+            } else {
+                ""
+            };
+            csi(number, mods, kind, None, letter)
+        }
+    })
+}
+
+const SHIFT: u8 = 0b1;
+const ALT: u8 = 0b10;
+const CTRL: u8 = 0b100;
+const SUPER: u8 = 0b1000;
+
+/// Convert iced modifiers to the protocol's modifier bits
+fn modifier_bits(modifiers: Modifiers) -> u8 {
+    let mut bits = 0;
+    if modifiers.shift() {
+        bits |= SHIFT;
+    }
+    if modifiers.alt() {
+        bits |= ALT;
+    }
+    if modifiers.control() {
+        bits |= CTRL;
+    }
+    if modifiers.logo() {
+        bits |= SUPER;
+    }
+    bits
+}
+
+/// Lowercase a character if it maps to exactly one character
+fn single_lowercase(c: char) -> char {
+    let mut lower = c.to_lowercase();
+    match (lower.next(), lower.next()) {
+        (Some(l), None) => l,
+        _ => c,
+    }
+}
+
+/// Build `CSI code [; mods[:event] [; text]] final`
+fn csi(code: &str, mods: u8, kind: KeyEventKind, text: Option<&str>, final_byte: char) -> String {
+    let mut seq = format!("\x1b[{code}");
+    if mods != 0 || kind != KeyEventKind::Press || text.is_some() {
+        seq.push_str(&format!(";{}", mods + 1));
+        if kind == KeyEventKind::Release {
+            seq.push_str(":3");
+        }
+    }
+    if let Some(text) = text {
+        seq.push_str(&format!(";{text}"));
+    }
+    seq.push(final_byte);
+    seq
+}
+
+/// Map a named (non-text) key to its protocol key code
+fn functional_key(named: Named, all_keys: bool) -> Option<KeyCode> {
+    let code = match named {
+        Named::Escape => KeyCode::Unicode(27),
+        Named::Enter => KeyCode::Unicode(13),
+        Named::Tab => KeyCode::Unicode(9),
+        Named::Backspace => KeyCode::Unicode(127),
+        Named::Insert => KeyCode::Tilde(2),
+        Named::Delete => KeyCode::Tilde(3),
+        Named::PageUp => KeyCode::Tilde(5),
+        Named::PageDown => KeyCode::Tilde(6),
+        Named::ArrowUp => KeyCode::Letter('A'),
+        Named::ArrowDown => KeyCode::Letter('B'),
+        Named::ArrowRight => KeyCode::Letter('C'),
+        Named::ArrowLeft => KeyCode::Letter('D'),
+        Named::Home => KeyCode::Letter('H'),
+        Named::End => KeyCode::Letter('F'),
+        Named::F1 => KeyCode::Letter('P'),
+        Named::F2 => KeyCode::Letter('Q'),
+        Named::F3 => KeyCode::Tilde(13),
+        Named::F4 => KeyCode::Letter('S'),
+        Named::F5 => KeyCode::Tilde(15),
+        Named::F6 => KeyCode::Tilde(17),
+        Named::F7 => KeyCode::Tilde(18),
+        Named::F8 => KeyCode::Tilde(19),
+        Named::F9 => KeyCode::Tilde(20),
+        Named::F10 => KeyCode::Tilde(21),
+        Named::F11 => KeyCode::Tilde(23),
+        Named::F12 => KeyCode::Tilde(24),
+        Named::F13 => KeyCode::Unicode(57376),
+        Named::F14 => KeyCode::Unicode(57377),
+        Named::F15 => KeyCode::Unicode(57378),
+        Named::F16 => KeyCode::Unicode(57379),
+        Named::F17 => KeyCode::Unicode(57380),
+        Named::F18 => KeyCode::Unicode(57381),
+        Named::F19 => KeyCode::Unicode(57382),
+        Named::F20 => KeyCode::Unicode(57383),
+        Named::CapsLock => KeyCode::Unicode(57358),
+        Named::ScrollLock => KeyCode::Unicode(57359),
+        Named::NumLock => KeyCode::Unicode(57360),
+        Named::PrintScreen => KeyCode::Unicode(57361),
+        Named::Pause => KeyCode::Unicode(57362),
+        Named::ContextMenu => KeyCode::Unicode(57363),
+        // Modifier keys on their own are only reported with all keys as escape codes
+        Named::Shift if all_keys => KeyCode::Unicode(57441),
+        Named::Control if all_keys => KeyCode::Unicode(57442),
+        Named::Alt if all_keys => KeyCode::Unicode(57443),
+        Named::Super if all_keys => KeyCode::Unicode(57444),
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(key: Key, modifiers: Modifiers, flags: u8) -> Option<String> {
+        encode_key(&key, modifiers, KeyEventKind::Press, flags)
+    }
+
+    fn char_key(s: &str) -> Key {
+        Key::Character(s.into())
+    }
+
+    #[test]
+    fn test_disabled_without_flags() {
+        assert_eq!(
+            press(Key::Named(Named::Escape), Modifiers::empty(), 0),
+            None
+        );
+        assert_eq!(press(char_key("a"), Modifiers::CTRL, 0), None);
+    }
+
+    #[test]
+    fn test_disambiguate_escape_and_ctrl_keys() {
+        let flags = DISAMBIGUATE_ESCAPE_CODES;
+        assert_eq!(
+            press(Key::Named(Named::Escape), Modifiers::empty(), flags).as_deref(),
+            Some("\x1b[27u")
+        );
+        // Ctrl+I is distinct from Tab
+        assert_eq!(
+            press(char_key("i"), Modifiers::CTRL, flags).as_deref(),
+            Some("\x1b[105;5u")
+        );
+        assert_eq!(
+            press(Key::Named(Named::Tab), Modifiers::empty(), flags),
+            None
+        );
+        assert_eq!(
+            press(Key::Named(Named::Tab), Modifiers::SHIFT, flags).as_deref(),
+            Some("\x1b[9;2u")
+        );
+        // Ctrl+Shift+A reports the base key with both modifiers
+        assert_eq!(
+            press(char_key("A"), Modifiers::CTRL | Modifiers::SHIFT, flags).as_deref(),
+            Some("\x1b[97;6u")
+        );
+    }
+
+    #[test]
+    fn test_plain_text_stays_text() {
+        let flags = DISAMBIGUATE_ESCAPE_CODES;
+        assert_eq!(press(char_key("a"), Modifiers::empty(), flags), None);
+        assert_eq!(press(char_key("A"), Modifiers::SHIFT, flags), None);
+        assert_eq!(
+            press(Key::Named(Named::Enter), Modifiers::empty(), flags),
+            None
+        );
+    }
+
+    #[test]
+    fn test_functional_keys() {
+        let flags = DISAMBIGUATE_ESCAPE_CODES;
+        assert_eq!(
+            press(Key::Named(Named::ArrowUp), Modifiers::empty(), flags),
+            None
+        );
+        assert_eq!(
+            press(Key::Named(Named::ArrowUp), Modifiers::CTRL, flags).as_deref(),
+            Some("\x1b[1;5A")
+        );
+        assert_eq!(
+            press(Key::Named(Named::Delete), Modifiers::ALT, flags).as_deref(),
+            Some("\x1b[3;3~")
+        );
+        assert_eq!(
+            press(Key::Named(Named::F3), Modifiers::SHIFT, flags).as_deref(),
+            Some("\x1b[13;2~")
+        );
+        assert_eq!(
+            press(Key::Named(Named::F13), Modifiers::empty(), flags).as_deref(),
+            Some("\x1b[57376u")
+        );
+    }
+
+    #[test]
+    fn test_event_types_alone_keep_legacy_presses() {
+        assert_eq!(
+            press(char_key("a"), Modifiers::CTRL, REPORT_EVENT_TYPES),
+            None
+        );
+        assert_eq!(
+            encode_key(
+                &char_key("a"),
+                Modifiers::CTRL,
+                KeyEventKind::Release,
+                REPORT_EVENT_TYPES
+            )
+            .as_deref(),
+            Some("\x1b[97;5:3u")
+        );
+    }
+
+    #[test]
+    fn test_release_events() {
+        let key = char_key("a");
+        assert_eq!(
+            encode_key(
+                &key,
+                Modifiers::empty(),
+                KeyEventKind::Release,
+                DISAMBIGUATE_ESCAPE_CODES
+            ),
+            None
+        );
+
+        let flags = DISAMBIGUATE_ESCAPE_CODES | REPORT_EVENT_TYPES;
+        assert_eq!(
+            encode_key(&key, Modifiers::empty(), KeyEventKind::Release, flags).as_deref(),
+            Some("\x1b[97;1:3u")
+        );
+        assert_eq!(
+            encode_key(
+                &Key::Named(Named::ArrowLeft),
+                Modifiers::empty(),
+                KeyEventKind::Release,
+                flags
+            )
+            .as_deref(),
+            Some("\x1b[1;1:3D")
+        );
+        // Enter release is only reported with all keys as escape codes
+        assert_eq!(
+            encode_key(
+                &Key::Named(Named::Enter),
+                Modifiers::empty(),
+                KeyEventKind::Release,
+                flags
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_report_all_keys_with_alternates_and_text() {
+        let flags = DISAMBIGUATE_ESCAPE_CODES
+            | REPORT_ALTERNATE_KEYS
+            | REPORT_ALL_KEYS_AS_ESCAPE_CODES
+            | REPORT_ASSOCIATED_TEXT;
+        assert_eq!(
+            press(char_key("a"), Modifiers::empty(), flags).as_deref(),
+            Some("\x1b[97;1;97u")
+        );
+        assert_eq!(
+            press(char_key("A"), Modifiers::SHIFT, flags).as_deref(),
+            Some("\x1b[97:65;2;65u")
+        );
+        assert_eq!(
+            press(Key::Named(Named::Enter), Modifiers::empty(), flags).as_deref(),
+            Some("\x1b[13u")
+        );
+        assert_eq!(
+            press(Key::Named(Named::ArrowUp), Modifiers::empty(), flags).as_deref(),
+            Some("\x1b[A")
+        );
+        assert_eq!(
+            press(Key::Named(Named::Shift), Modifiers::SHIFT, flags).as_deref(),
+            Some("\x1b[57441;2u")
+        );
+    }
+}
@@ -6,6 +6,8 @@ pub mod bracket;
 pub mod clipboard;
 pub mod env;
 pub mod hyperlink;
+pub mod keyboard;
+pub mod mouse;
 pub mod pty;
 pub mod screen;
 pub mod search;
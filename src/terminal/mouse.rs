@@ -0,0 +1,272 @@
+//! Mouse report encoder
+//!
+//! While an application has mouse tracking enabled (see
+//! [`TerminalScreen::mouse_mode`](super::screen::TerminalScreen::mouse_mode)),
+//! clicks, drags and wheel events are written to the PTY instead of driving
+//! local selection and scrolling. The tracking mode decides which events are
+//! reported and the encoding decides how they look on the wire:
+//! - X10 (default) - `CSI M Cb Cx Cy`, each value a single byte offset by 32
+//! - UTF-8 (1005) - like X10, with the values UTF-8 encoded
+//! - SGR (1006) - `CSI < Cb ; Cx ; Cy M`, releases end in `m`
+//! - urxvt (1015) - `CSI Cb ; Cx ; Cy M` with decimal values
+//! - SGR-Pixels (1016) - like SGR, with pixel instead of cell coordinates
+//!
+//! Reference: <https://invisible-island.net/xterm/ctlseqs/ctlseqs.html#h2-Mouse-Tracking>
+
+use super::screen::{MouseEncoding, MouseMode};
+use crate::mouse_actions::{MouseAction, MouseButton, MouseEvent, MouseModifiers, ScrollDirection};
+
+/// Button code of a release in the encodings that cannot name the button
+const RELEASE: u32 = 3;
+/// Added to the button code for motion events
+const MOTION: u32 = 32;
+/// Offset added to every value in the X10 and UTF-8 encodings
+const OFFSET: u32 = 32;
+/// Largest value a single byte can carry in the X10 encoding
+const X10_MAX: u32 = 255;
+/// Largest value the UTF-8 encoding can carry (two-byte sequences)
+const UTF8_MAX: u32 = 2047;
+
+/// Encode a mouse event as a report for the application
+///
+/// `event.position` holds 0-based screen cell coordinates, and for SGR-Pixels
+/// the pixel offset from the top-left corner of the screen in `x`/`y`.
+///
+/// Returns `None` if mouse reporting is off, if the tracking mode does not
+/// report this kind of event (X10 mode reports presses only, drags need
+/// button-event tracking, plain motion needs any-event tracking), or if the
+/// position does not fit the encoding.
+pub fn encode_mouse(
+    event: &MouseEvent,
+    mode: MouseMode,
+    encoding: MouseEncoding,
+) -> Option<Vec<u8>> {
+    let (button, released, motion) = match event.action {
+        MouseAction::Click(button)
+        | MouseAction::DoubleClick(button)
+        | MouseAction::TripleClick(button) => (button_code(button), false, false),
+        MouseAction::Release(button) => (button_code(button), true, false),
+        MouseAction::Scroll(direction) => (wheel_code(direction), false, false),
+        MouseAction::Drag(button) => match mode {
+            MouseMode::ButtonEvent | MouseMode::AnyEvent => (button_code(button), false, true),
+            MouseMode::None | MouseMode::X10 | MouseMode::Normal => return None,
+        },
+        MouseAction::Move if mode == MouseMode::AnyEvent => (RELEASE, false, true),
+        MouseAction::Move => return None,
+    };
+    if mode == MouseMode::None || (mode == MouseMode::X10 && released) {
+        return None;
+    }
+
+    // X10 compatibility mode reports presses without modifier bits
+    let mut flags = if mode == MouseMode::X10 {
+        0
+    } else {
+        modifier_bits(event.modifiers)
+    };
+    if motion {
+        flags |= MOTION;
+    }
+    // Only SGR can tell which button was released
+    let legacy_code = if released { RELEASE } else { button } | flags;
+    let sgr_code = button | flags;
+
+    let (col, row) = match encoding {
+        MouseEncoding::SgrPixels => (
+            event.position.x.max(0.0) as u32 + 1,
+            event.position.y.max(0.0) as u32 + 1,
+        ),
+        _ => (event.position.col as u32 + 1, event.position.row as u32 + 1),
+    };
+
+    match encoding {
+        MouseEncoding::Sgr | MouseEncoding::SgrPixels => {
+            let final_byte = if released { 'm' } else { 'M' };
+            Some(format!("\x1b[<{sgr_code};{col};{row}{final_byte}").into_bytes())
+        }
+        MouseEncoding::Urxvt => {
+            Some(format!("\x1b[{};{col};{row}M", legacy_code + OFFSET).into_bytes())
+        }
+        MouseEncoding::Default => {
+            let values = [legacy_code + OFFSET, col + OFFSET, row + OFFSET];
+            if values.iter().any(|&value| value > X10_MAX) {
+                return None;
+            }
+            let mut report = b"\x1b[M".to_vec();
+            report.extend(values.iter().map(|&value| value as u8));
+            Some(report)
+        }
+        MouseEncoding::Utf8 => {
+            let mut report = String::from("\x1b[M");
+            for value in [legacy_code + OFFSET, col + OFFSET, row + OFFSET] {
+                if value > UTF8_MAX {
+                    return None;
+                }
+                report.push(char::from_u32(value)?);
+            }
+            Some(report.into_bytes())
+        }
+    }
+}
+
+/// Button number for presses, drags and SGR releases
+fn button_code(button: MouseButton) -> u32 {
+    match button {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+        MouseButton::Back => 128,
+        MouseButton::Forward => 129,
+    }
+}
+
+/// Button number of a wheel event (buttons 4-7)
+fn wheel_code(direction: ScrollDirection) -> u32 {
+    match direction {
+        ScrollDirection::Up => 64,
+        ScrollDirection::Down => 65,
+        ScrollDirection::Left => 66,
+        ScrollDirection::Right => 67,
+    }
+}
+
+/// Modifier bits of the button code
+fn modifier_bits(modifiers: MouseModifiers) -> u32 {
+    let mut bits = 0;
+    if modifiers.shift {
+        bits |= 4;
+    }
+    if modifiers.alt || modifiers.meta {
+        bits |= 8;
+    }
+    if modifiers.ctrl {
+        bits |= 16;
+    }
+    bits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mouse_actions::MousePosition;
+
+    fn event(action: MouseAction, col: usize, row: usize) -> MouseEvent {
+        let position = MousePosition::new(0.0, 0.0, col, row, false);
+        MouseEvent::new(action, position, MouseModifiers::none(), 1)
+    }
+
+    #[test]
+    fn test_normal_press_and_release() {
+        let press = event(MouseAction::Click(MouseButton::Left), 0, 0);
+        assert_eq!(
+            encode_mouse(&press, MouseMode::Normal, MouseEncoding::Default),
+            Some(b"\x1b[M !!".to_vec())
+        );
+
+        let release = event(MouseAction::Release(MouseButton::Right), 9, 4);
+        assert_eq!(
+            encode_mouse(&release, MouseMode::Normal, MouseEncoding::Default),
+            Some(b"\x1b[M#*%".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_x10_out_of_range() {
+        let press = event(MouseAction::Click(MouseButton::Left), 300, 0);
+        assert_eq!(
+            encode_mouse(&press, MouseMode::Normal, MouseEncoding::Default),
+            None
+        );
+        // UTF-8 encoding carries larger coordinates
+        assert_eq!(
+            encode_mouse(&press, MouseMode::Normal, MouseEncoding::Utf8),
+            Some("\x1b[M \u{14d}!".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_x10_reports_presses_only() {
+        let mut press = event(MouseAction::Click(MouseButton::Left), 0, 0);
+        press.modifiers.ctrl = true;
+        press.modifiers.shift = true;
+        assert_eq!(
+            encode_mouse(&press, MouseMode::X10, MouseEncoding::Default),
+            Some(b"\x1b[M !!".to_vec())
+        );
+
+        let release = event(MouseAction::Release(MouseButton::Left), 0, 0);
+        assert_eq!(
+            encode_mouse(&release, MouseMode::X10, MouseEncoding::Default),
+            None
+        );
+        assert_eq!(
+            encode_mouse(&release, MouseMode::X10, MouseEncoding::Sgr),
+            None
+        );
+    }
+
+    #[test]
+    fn test_sgr_keeps_button_on_release() {
+        let mut press = event(MouseAction::Click(MouseButton::Middle), 4, 2);
+        press.modifiers.ctrl = true;
+        assert_eq!(
+            encode_mouse(&press, MouseMode::Normal, MouseEncoding::Sgr),
+            Some(b"\x1b[<17;5;3M".to_vec())
+        );
+
+        let release = event(MouseAction::Release(MouseButton::Middle), 4, 2);
+        assert_eq!(
+            encode_mouse(&release, MouseMode::Normal, MouseEncoding::Sgr),
+            Some(b"\x1b[<1;5;3m".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_urxvt_and_sgr_pixels() {
+        let wheel = event(MouseAction::Scroll(ScrollDirection::Down), 0, 0);
+        assert_eq!(
+            encode_mouse(&wheel, MouseMode::Normal, MouseEncoding::Urxvt),
+            Some(b"\x1b[97;1;1M".to_vec())
+        );
+
+        let mut press = event(MouseAction::Click(MouseButton::Left), 2, 1);
+        press.position.x = 20.5;
+        press.position.y = 30.0;
+        assert_eq!(
+            encode_mouse(&press, MouseMode::Normal, MouseEncoding::SgrPixels),
+            Some(b"\x1b[<0;21;31M".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_motion_depends_on_tracking_mode() {
+        let drag = event(MouseAction::Drag(MouseButton::Left), 1, 1);
+        assert_eq!(
+            encode_mouse(&drag, MouseMode::Normal, MouseEncoding::Sgr),
+            None
+        );
+        assert_eq!(
+            encode_mouse(&drag, MouseMode::ButtonEvent, MouseEncoding::Sgr),
+            Some(b"\x1b[<32;2;2M".to_vec())
+        );
+
+        let motion = event(MouseAction::Move, 1, 1);
+        assert_eq!(
+            encode_mouse(&motion, MouseMode::ButtonEvent, MouseEncoding::Sgr),
+            None
+        );
+        assert_eq!(
+            encode_mouse(&motion, MouseMode::AnyEvent, MouseEncoding::Sgr),
+            Some(b"\x1b[<35;2;2M".to_vec())
+        );
+    }
+
+    #[test]
+    fn test_reporting_off() {
+        let press = event(MouseAction::Click(MouseButton::Left), 0, 0);
+        assert_eq!(
+            encode_mouse(&press, MouseMode::None, MouseEncoding::Sgr),
+            None
+        );
+    }
+}
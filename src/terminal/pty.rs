@@ -6,9 +6,19 @@
 //!
 //! # Architecture
 //!
-//! - **Thread-based I/O**: PTY operations run in a dedicated background thread
+//! - **Thread-based I/O**: PTY operations run in a dedicated background thread,
+//!   input is written by a writer thread per session so a busy child never
+//!   blocks the other sessions
+//! - **Push-based output**: Each session's reader thread feeds a bounded channel
+//!   that can be consumed as a stream ([`PtyManager::output_stream`]) or polled
+//!   ([`PtyManager::read`])
 //! - **Session management**: Multiple concurrent PTY sessions with unique IDs
-//! - **Environment control**: Configurable environment variables and inheritance
+//! - **Process control**: Signals to the foreground job, exit codes vs signals,
+//!   foreground process detection (`tcgetpgrp` and `/proc`, or `sysctl` on macOS)
+//! - **Spawn control**: Program, arguments and working directory per session ([`SpawnSpec`])
+//! - **Environment control**: Configurable environment variables, inheritance and unsetting
+//! - **Shell integration**: Injected into bash, zsh and fish at spawn time
+//!   ([`SpawnSpec::shell_integration`])
 //! - **Auto-detection**: Automatically detects the default system shell
 //!
 //! # Examples
@@ -20,21 +30,42 @@
 //! let pty_id = manager.create_session(40, 120).unwrap();
 //! manager.write(&pty_id, b"echo hello\n").unwrap();
 //! let output = manager.read(&pty_id).unwrap();
+//!
+//! // Run a specific program instead of the default shell
+//! use agterm::terminal::pty::SpawnSpec;
+//!
+//! let spec = SpawnSpec {
+//!     cwd: Some("/tmp".into()),
+//!     ..SpawnSpec::from_argv(vec!["htop".to_string(), "-d".to_string(), "10".to_string()])
+//! };
+//! let htop_id = manager.create_session_with_spec(40, 120, spec).unwrap();
 //! ```
 
 use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
 use crate::shell::ShellInfo;
 use std::collections::HashMap;
+use std::fmt;
 use std::io::{Read, Write};
-use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
 use tracing::{debug, error, info, instrument, trace, warn};
 use uuid::Uuid;
 
+use iced::futures::channel::mpsc as async_mpsc;
+use iced::futures::executor::block_on;
+use iced::futures::{stream, SinkExt, Stream, StreamExt};
+
+mod inject;
+
 /// Unique identifier for a PTY session
 pub type PtyId = Uuid;
 
+/// Output of a PTY session, one item per chunk read from the PTY
+pub type PtyOutputStream = async_mpsc::Receiver<Vec<u8>>;
+
 /// Environment variable configuration for PTY sessions
 ///
 /// Controls how environment variables are set up for spawned shell processes.
@@ -45,17 +76,273 @@ pub struct PtyEnvironment {
     pub inherit_env: bool,
     /// Additional/override environment variables (with shell expansion support)
     pub variables: HashMap<String, String>,
-    /// Variables to unset/remove (Note: not fully supported by portable-pty)
+    /// Variables to unset/remove
     pub unset: Vec<String>,
 }
 
+/// Variables identifying the terminal to programs running in it
+const TERMINAL_ENV: &[(&str, &str)] = &[
+    ("TERM", "xterm-256color"),
+    ("COLORTERM", "truecolor"),
+    ("TERM_PROGRAM", "agterm"),
+    ("AGTERM_VERSION", env!("CARGO_PKG_VERSION")),
+];
+
+impl PtyEnvironment {
+    /// Final environment of the child process
+    ///
+    /// Starts from the parent environment (if inherited) and the terminal
+    /// variables (`TERM`, `COLORTERM`, ...), removes the unset variables, then
+    /// applies `variables` with shell expansion (`~`, `$VAR`).
+    pub fn resolve(&self) -> HashMap<String, String> {
+        let mut resolved: HashMap<String, String> = if self.inherit_env {
+            std::env::vars().collect()
+        } else {
+            HashMap::new()
+        };
+        for (key, value) in TERMINAL_ENV {
+            resolved.insert(key.to_string(), value.to_string());
+        }
+        for key in &self.unset {
+            resolved.remove(key);
+        }
+        for (key, value) in &self.variables {
+            let expanded = shellexpand::full(value)
+                .map(|s| s.to_string())
+                .unwrap_or_else(|_| value.clone());
+            resolved.insert(key.clone(), expanded);
+        }
+        resolved
+    }
+}
+
+/// What to run in a new PTY session
+///
+/// The default spec runs the auto-detected shell in the current directory
+/// with the terminal variables (`TERM`, `COLORTERM`, `TERM_PROGRAM`).
+#[derive(Debug, Clone, Default)]
+pub struct SpawnSpec {
+    /// Program to run (path or name looked up in `PATH`), None for the default shell
+    pub program: Option<String>,
+    /// Arguments passed to the program
+    pub args: Vec<String>,
+    /// Working directory, None for the current directory
+    pub cwd: Option<PathBuf>,
+    /// Environment of the child, None for the inherited environment plus the
+    /// terminal variables
+    pub environment: Option<PtyEnvironment>,
+    /// Start the program as a login shell (passes `-l`, understood by bash,
+    /// zsh, fish, nu and pwsh)
+    pub login: bool,
+    /// Inject the shell integration (prompt marks, cwd reporting) when the
+    /// program is bash, zsh or fish started without arguments
+    pub shell_integration: bool,
+}
+
+impl SpawnSpec {
+    /// Spec running `argv[0]` with the remaining arguments
+    ///
+    /// An empty `argv` runs the default shell.
+    pub fn from_argv(argv: Vec<String>) -> Self {
+        let mut argv = argv.into_iter();
+        Self {
+            program: argv.next(),
+            args: argv.collect(),
+            ..Self::default()
+        }
+    }
+
+    /// Program the spec runs, resolving None to the default shell
+    pub fn program_or_default_shell(&self) -> String {
+        self.program.clone().unwrap_or_else(default_shell)
+    }
+}
+
 /// Maximum output buffer size per session (1MB)
 const MAX_OUTPUT_BUFFER_SIZE: usize = 1024 * 1024;
 
+/// Output chunks queued per session before the reader thread stops reading
+///
+/// Once the queue is full the child blocks on its writes (backpressure)
+/// instead of output piling up in memory.
+const OUTPUT_CHANNEL_CAPACITY: usize = 64;
+
+/// Maximum number of queued chunks merged into one item of [`PtyManager::output_stream`]
+const OUTPUT_STREAM_BATCH: usize = 16;
+
 /// Maximum lines per command block output (reserved for future block-mode feature)
 #[allow(dead_code)]
 pub const MAX_OUTPUT_LINES: usize = 10000;
 
+/// How the child process of a session ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChildExit {
+    /// Exited with a status code
+    Code(i32),
+    /// Terminated by a signal, with its number
+    Signal(i32),
+}
+
+impl ChildExit {
+    /// Check if the child exited with code 0
+    #[allow(dead_code)]
+    pub fn success(&self) -> bool {
+        matches!(self, ChildExit::Code(0))
+    }
+}
+
+impl fmt::Display for ChildExit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChildExit::Code(code) => write!(f, "exited with code {code}"),
+            ChildExit::Signal(signal) => write!(f, "terminated by {}", signal_name(*signal)),
+        }
+    }
+}
+
+/// Describe a signal number as `strsignal` does (e.g. "Killed" for SIGKILL)
+pub fn signal_name(signal: i32) -> String {
+    #[cfg(unix)]
+    {
+        // SAFETY: strsignal returns a NUL-terminated string, copied before the next call
+        let name = unsafe { libc::strsignal(signal) };
+        if !name.is_null() {
+            return unsafe { std::ffi::CStr::from_ptr(name) }
+                .to_string_lossy()
+                .into_owned();
+        }
+    }
+    format!("signal {signal}")
+}
+
+/// POSIX signal delivered to the foreground job of a session
+///
+/// Unlike typing `^C` or `^Z`, which the line discipline turns into signals
+/// only when the application has not switched the terminal to raw mode,
+/// these always reach the processes.
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PtySignal {
+    /// SIGINT
+    Interrupt,
+    /// SIGQUIT
+    Quit,
+    /// SIGTSTP
+    Suspend,
+    /// SIGCONT
+    Continue,
+    /// SIGHUP
+    Hangup,
+    /// SIGTERM
+    Terminate,
+    /// SIGKILL
+    Kill,
+}
+
+#[cfg(unix)]
+impl PtySignal {
+    /// Signal number for `kill(2)`
+    fn number(self) -> libc::c_int {
+        match self {
+            PtySignal::Interrupt => libc::SIGINT,
+            PtySignal::Quit => libc::SIGQUIT,
+            PtySignal::Suspend => libc::SIGTSTP,
+            PtySignal::Continue => libc::SIGCONT,
+            PtySignal::Hangup => libc::SIGHUP,
+            PtySignal::Terminate => libc::SIGTERM,
+            PtySignal::Kill => libc::SIGKILL,
+        }
+    }
+}
+
+/// Process group in the foreground of a PTY, the job receiving keyboard input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ForegroundProcess {
+    /// Process group ID, the pid of the group leader
+    pub pid: i32,
+    /// Executable name (e.g. "vim")
+    pub name: String,
+    /// Command line including argv[0], empty if unavailable
+    pub argv: Vec<String>,
+    /// The group leader is the session's own child, typically the shell
+    /// waiting at its prompt
+    pub is_session_child: bool,
+}
+
+impl ForegroundProcess {
+    /// Command line with the executable name in place of argv[0] (e.g. "cargo build")
+    pub fn command(&self) -> String {
+        std::iter::once(self.name.as_str())
+            .chain(self.argv.iter().skip(1).map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Look up the executable name and command line of a process
+#[cfg(target_os = "linux")]
+fn process_info(pid: i32) -> Option<(String, Vec<String>)> {
+    let name = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok()?;
+    let argv = std::fs::read(format!("/proc/{pid}/cmdline"))
+        .map(|raw| {
+            raw.split(|&b| b == 0)
+                .filter(|arg| !arg.is_empty())
+                .map(|arg| String::from_utf8_lossy(arg).into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    Some((name.trim_end().to_string(), argv))
+}
+
+/// Look up the executable name and command line of a process
+#[cfg(target_os = "macos")]
+fn process_info(pid: i32) -> Option<(String, Vec<String>)> {
+    // KERN_PROCARGS2: argc, the executable path, NUL padding, then argv and the environment
+    let mut mib = [libc::CTL_KERN, libc::KERN_PROCARGS2, pid];
+    let mut sysctl = |buffer: *mut libc::c_void, size: &mut libc::size_t| {
+        // SAFETY: `mib` holds 3 names and `buffer` is null or holds `size` bytes
+        unsafe { libc::sysctl(mib.as_mut_ptr(), 3, buffer, size, std::ptr::null_mut(), 0) == 0 }
+    };
+    let mut size = 0;
+    if !sysctl(std::ptr::null_mut(), &mut size) {
+        return None;
+    }
+    let mut raw = vec![0u8; size];
+    if !sysctl(raw.as_mut_ptr().cast(), &mut size) {
+        return None;
+    }
+    raw.truncate(size);
+
+    let argc = i32::from_ne_bytes(raw.get(..4)?.try_into().ok()?).max(0) as usize;
+    let mut fields = raw[4..]
+        .split(|&b| b == 0)
+        .filter(|field| !field.is_empty())
+        .map(|field| String::from_utf8_lossy(field).into_owned());
+    let path = fields.next()?;
+    let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+    Some((name, fields.take(argc).collect()))
+}
+
+/// Look up the executable name and command line of a process
+#[cfg(all(unix, not(any(target_os = "linux", target_os = "macos"))))]
+fn process_info(pid: i32) -> Option<(String, Vec<String>)> {
+    // No /proc, ask ps
+    let ps = |field: &str| {
+        std::process::Command::new("ps")
+            .args(["-o", field, "-p", &pid.to_string()])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let comm = ps("comm=")?;
+    let name = comm.rsplit('/').next().unwrap_or(&comm).to_string();
+    let argv = ps("args=")
+        .map(|args| args.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    Some((name, argv))
+}
+
 /// Errors that can occur during PTY operations
 #[derive(Debug, thiserror::Error)]
 pub enum PtyError {
@@ -71,6 +358,9 @@ pub enum PtyError {
     /// Internal channel communication error
     #[error("Channel error: {0}")]
     Channel(String),
+    /// The session output is already consumed by a stream
+    #[error("Session output already subscribed: {0}")]
+    AlreadySubscribed(String),
 }
 
 #[allow(dead_code)]
@@ -79,7 +369,7 @@ enum PtyCommand {
         id: PtyId,
         rows: u16,
         cols: u16,
-        environment: Option<PtyEnvironment>,
+        spec: SpawnSpec,
         response: Sender<Result<(), PtyError>>,
     },
     Write {
@@ -91,6 +381,10 @@ enum PtyCommand {
         id: PtyId,
         response: Sender<Result<Vec<u8>, PtyError>>,
     },
+    Subscribe {
+        id: PtyId,
+        response: Sender<Result<PtyOutputStream, PtyError>>,
+    },
     Resize {
         id: PtyId,
         rows: u16,
@@ -103,19 +397,144 @@ enum PtyCommand {
     },
     CheckStatus {
         id: PtyId,
-        response: Sender<Result<Option<i32>, PtyError>>,
+        response: Sender<Result<Option<ChildExit>, PtyError>>,
+    },
+    Signal {
+        id: PtyId,
+        signal: PtySignal,
+        response: Sender<Result<(), PtyError>>,
+    },
+    Foreground {
+        id: PtyId,
+        response: Sender<Result<Option<ForegroundProcess>, PtyError>>,
     },
     Shutdown,
 }
 
 struct InternalPtySession {
-    #[allow(dead_code)]
     master: Box<dyn MasterPty + Send>,
-    writer: Box<dyn Write + Send>, // Cached writer for repeated writes
+    /// Input queued for the writer thread, which exits once this is dropped
+    input: Sender<Vec<u8>>,
     child: Box<dyn Child + Send + Sync>,
     output_buffer: Vec<u8>,
     reader_thread: Option<JoinHandle<()>>,
-    output_receiver: Receiver<Vec<u8>>,
+    /// Tells the reader thread to stop forwarding output
+    closed: Arc<AtomicBool>,
+    /// None once the output is consumed by a stream
+    output_receiver: Option<PtyOutputStream>,
+    /// Set once the child has been reaped
+    exit: Option<ChildExit>,
+}
+
+impl InternalPtySession {
+    /// Process group in the foreground of the PTY, or else the child's own group
+    #[cfg(unix)]
+    fn foreground_pgid(&self) -> Option<i32> {
+        self.master
+            .process_group_leader()
+            .or_else(|| match self.exit {
+                // Once reaped the pid may belong to another process
+                Some(_) => None,
+                None => self.child.process_id().map(|pid| pid as i32),
+            })
+    }
+
+    /// Send a signal to the foreground process group
+    fn signal(&mut self, signal: PtySignal) -> Result<(), PtyError> {
+        #[cfg(unix)]
+        {
+            let pgid = self
+                .foreground_pgid()
+                .ok_or_else(|| PtyError::Io("Child process has exited".to_string()))?;
+            // A negative pid signals every process in the group
+            if unsafe { libc::kill(-pgid, signal.number()) } == 0 {
+                Ok(())
+            } else {
+                Err(PtyError::Io(std::io::Error::last_os_error().to_string()))
+            }
+        }
+        #[cfg(not(unix))]
+        {
+            match signal {
+                PtySignal::Terminate | PtySignal::Kill => {
+                    self.child.kill().map_err(|e| PtyError::Io(e.to_string()))
+                }
+                _ => Err(PtyError::Io(format!(
+                    "{signal:?} is not supported on this platform"
+                ))),
+            }
+        }
+    }
+
+    /// Process group in the foreground of the PTY with its command line
+    fn foreground_process(&self) -> Option<ForegroundProcess> {
+        #[cfg(unix)]
+        {
+            let pgid = self.foreground_pgid()?;
+            let (name, argv) = process_info(pgid)?;
+            Some(ForegroundProcess {
+                pid: pgid,
+                name,
+                argv,
+                is_session_child: self.child.process_id() == Some(pgid as u32),
+            })
+        }
+        #[cfg(not(unix))]
+        {
+            None
+        }
+    }
+
+    /// Check whether the child has terminated, without blocking
+    fn try_wait(&mut self) -> std::io::Result<Option<ChildExit>> {
+        if let Some(exit) = &self.exit {
+            return Ok(Some(exit.clone()));
+        }
+        #[cfg(unix)]
+        let exit = {
+            // Reap it ourselves: portable-pty's status only carries the signal as text
+            let Some(pid) = self.child.process_id() else {
+                return Ok(None);
+            };
+            let mut status = 0;
+            match unsafe { libc::waitpid(pid as libc::pid_t, &mut status, libc::WNOHANG) } {
+                0 => return Ok(None),
+                -1 => return Err(std::io::Error::last_os_error()),
+                _ if libc::WIFSIGNALED(status) => ChildExit::Signal(libc::WTERMSIG(status)),
+                _ => ChildExit::Code(libc::WEXITSTATUS(status)),
+            }
+        };
+        #[cfg(not(unix))]
+        let exit = match self.child.try_wait()? {
+            Some(status) => ChildExit::Code(status.exit_code() as i32),
+            None => return Ok(None),
+        };
+        self.exit = Some(exit.clone());
+        Ok(Some(exit))
+    }
+
+    /// Hang up the foreground job, kill the child and stop the reader thread
+    fn shutdown(&mut self) -> std::io::Result<()> {
+        // Its pid may already belong to another process once reaped
+        let result = if self.exit.is_some() {
+            Ok(())
+        } else {
+            // Like closing a terminal window, so jobs started from the shell exit too
+            let _ = self.signal(PtySignal::Hangup);
+            self.child.kill()
+        };
+        self.closed.store(true, Ordering::Relaxed);
+        let handle = self.reader_thread.take();
+        // Dropping the receiver unblocks a reader thread waiting on a full channel
+        if self.output_receiver.take().is_some() {
+            if let Some(handle) = handle {
+                let _ = handle.join();
+            }
+        }
+        // A subscribed stream may only drain once the UI thread runs again,
+        // so its reader thread is left to exit on its own
+        result
+    }
 }
 
 /// Get the default shell path, using auto-detection if available
@@ -142,13 +561,87 @@ fn default_shell() -> String {
     }
 }
 
+/// Build the command for a spawn spec
+fn build_command(spec: SpawnSpec) -> Result<CommandBuilder, PtyError> {
+    let program = spec.program_or_default_shell();
+    debug!(program = %program, args = ?spec.args, "Using program");
+
+    let working_dir = match spec.cwd {
+        Some(dir) if dir.is_dir() => dir,
+        Some(dir) => {
+            return Err(PtyError::SpawnFailed(format!(
+                "Working directory does not exist: {}",
+                dir.display()
+            )))
+        }
+        None => std::env::current_dir().unwrap_or_else(|_| std::env::temp_dir()),
+    };
+
+    let environment = spec.environment.map(|env_config| {
+        if !env_config.inherit_env {
+            debug!("Not inheriting parent environment");
+        }
+        env_config.resolve()
+    });
+
+    // Only an interactive shell runs its startup files
+    let injection = if spec.shell_integration && spec.args.is_empty() {
+        let lookup = |key: &str| match &environment {
+            Some(resolved) => resolved.get(key).cloned(),
+            None => std::env::var(key).ok(),
+        };
+        inject::prepare(&program, spec.login, lookup, &inject::default_dir()).unwrap_or_else(|e| {
+            warn!(error = %e, "Failed to prepare shell integration, starting without it");
+            None
+        })
+    } else {
+        None
+    };
+    let login = injection
+        .as_ref()
+        .map_or(spec.login, |injection| injection.login);
+
+    let mut cmd = CommandBuilder::new(&program);
+    // Before `-l`, as bash only takes long options first
+    if let Some(injection) = &injection {
+        debug!(args = ?injection.args, "Injecting shell integration");
+        cmd.args(&injection.args);
+    }
+    if login {
+        cmd.arg("-l");
+    }
+    cmd.args(&spec.args);
+    cmd.cwd(&working_dir);
+
+    // Apply environment configuration
+    if let Some(resolved) = environment {
+        // Build the environment explicitly so inheritance and unset are honored
+        cmd.env_clear();
+        for (key, value) in resolved {
+            trace!(key = %key, value = %value, "Setting environment variable");
+            cmd.env(&key, &value);
+        }
+    } else {
+        // Default enhanced shell integration environment variables
+        for (key, value) in TERMINAL_ENV {
+            cmd.env(key, value);
+        }
+    }
+    for (key, value) in injection.into_iter().flat_map(|injection| injection.env) {
+        cmd.env(key, value);
+    }
+
+    Ok(cmd)
+}
+
 #[instrument(skip_all, fields(rows = rows, cols = cols))]
 fn create_pty_session(
     rows: u16,
     cols: u16,
-    environment: Option<PtyEnvironment>,
+    spec: SpawnSpec,
 ) -> Result<InternalPtySession, PtyError> {
     debug!("Creating PTY session");
+    let cmd = build_command(spec)?;
     let pty_system = native_pty_system();
     let size = PtySize {
         rows,
@@ -162,44 +655,6 @@ fn create_pty_session(
         PtyError::SpawnFailed(e.to_string())
     })?;
 
-    let shell = default_shell();
-    debug!(shell = %shell, "Using shell");
-    let working_dir = std::env::current_dir().unwrap_or_else(|_| std::env::temp_dir());
-
-    let mut cmd = CommandBuilder::new(&shell);
-    cmd.cwd(&working_dir);
-
-    // Apply environment configuration
-    if let Some(env_config) = environment {
-        // If not inheriting, clear all environment variables
-        if !env_config.inherit_env {
-            // Note: portable-pty doesn't have a direct way to clear env,
-            // so we'll just set the ones we want
-            debug!("Not inheriting parent environment");
-        }
-
-        // Apply custom environment variables
-        for (key, value) in env_config.variables {
-            let expanded = shellexpand::full(&value)
-                .map(|s| s.to_string())
-                .unwrap_or(value);
-            debug!(key = %key, value = %expanded, "Setting environment variable");
-            cmd.env(&key, &expanded);
-        }
-
-        // Note: Unsetting variables isn't directly supported by portable-pty's CommandBuilder
-        // The unset functionality would require a different approach
-        if !env_config.unset.is_empty() {
-            warn!("Unsetting environment variables is not supported by portable-pty");
-        }
-    } else {
-        // Default enhanced shell integration environment variables
-        cmd.env("TERM", "xterm-256color");
-        cmd.env("COLORTERM", "truecolor");
-        cmd.env("TERM_PROGRAM", "agterm");
-        cmd.env("AGTERM_VERSION", env!("CARGO_PKG_VERSION"));
-    }
-
     let child = pair.slave.spawn_command(cmd).map_err(|e| {
         error!(error = %e, "Failed to spawn shell command");
         PtyError::SpawnFailed(e.to_string())
@@ -212,43 +667,59 @@ fn create_pty_session(
         .try_clone_reader()
         .map_err(|e| PtyError::SpawnFailed(e.to_string()))?;
 
-    // Get writer upfront and cache it for repeated writes
-    let writer = pair
+    let mut writer = pair
         .master
         .take_writer()
         .map_err(|e| PtyError::SpawnFailed(e.to_string()))?;
 
     drop(pair.slave);
 
-    // Create channel for PTY output
-    let (output_tx, output_rx) = mpsc::channel();
+    // Create bounded channel for PTY output
+    let (mut output_tx, output_rx) = async_mpsc::channel(OUTPUT_CHANNEL_CAPACITY);
 
     // Spawn reader thread that continuously reads PTY output
+    let closed = Arc::new(AtomicBool::new(false));
+    let reader_closed = closed.clone();
     let reader_thread = thread::spawn(move || {
         let mut buf = [0u8; 4096];
         loop {
             match reader.read(&mut buf) {
                 Ok(0) => break, // EOF
                 Ok(n) => {
-                    if output_tx.send(buf[..n].to_vec()).is_err() {
-                        break; // Channel closed
+                    // Blocks while the channel is full, fails once the receiver is dropped
+                    if reader_closed.load(Ordering::Relaxed)
+                        || block_on(output_tx.send(buf[..n].to_vec())).is_err()
+                    {
+                        return; // Channel or session closed
                     }
                 }
-                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                    thread::sleep(Duration::from_millis(10));
-                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
                 Err(_) => break,
             }
         }
     });
 
+    // Spawn writer thread, so a child that doesn't read its input only blocks
+    // its own writes
+    let (input, input_rx) = mpsc::channel::<Vec<u8>>();
+    thread::spawn(move || {
+        for data in input_rx {
+            if let Err(e) = writer.write_all(&data).and_then(|_| writer.flush()) {
+                warn!(error = %e, "Failed to write to PTY");
+                return; // Later writes fail as the input queue is closed
+            }
+        }
+    });
+
     Ok(InternalPtySession {
         master: pair.master,
-        writer,
+        input,
         child,
         output_buffer: Vec::with_capacity(4096), // Pre-allocate reasonable size
         reader_thread: Some(reader_thread),
-        output_receiver: output_rx,
+        closed,
+        output_receiver: Some(output_rx),
+        exit: None,
     })
 }
 
@@ -261,25 +732,21 @@ fn run_pty_thread(rx: Receiver<PtyCommand>) {
                 id,
                 rows,
                 cols,
-                environment,
+                spec,
                 response,
             }) => {
-                let result = create_pty_session(rows, cols, environment).map(|session| {
+                let result = create_pty_session(rows, cols, spec).map(|session| {
                     sessions.insert(id, session);
                 });
                 let _ = response.send(result);
             }
             Ok(PtyCommand::Write { id, data, response }) => {
-                let result = if let Some(session) = sessions.get_mut(&id) {
-                    // Use cached writer instead of take_writer()
-                    match session
-                        .writer
-                        .write_all(&data)
-                        .and_then(|_| session.writer.flush())
-                    {
-                        Ok(_) => Ok(()),
-                        Err(e) => Err(PtyError::Io(e.to_string())),
-                    }
+                let result = if let Some(session) = sessions.get(&id) {
+                    // Queued for the session's writer thread, which may block on the child
+                    session
+                        .input
+                        .send(data)
+                        .map_err(|_| PtyError::Io("PTY input is closed".to_string()))
                 } else {
                     Err(PtyError::SessionNotFound(id.to_string()))
                 };
@@ -288,19 +755,14 @@ fn run_pty_thread(rx: Receiver<PtyCommand>) {
             Ok(PtyCommand::Read { id, response }) => {
                 let result = if let Some(session) = sessions.get_mut(&id) {
                     // Drain all available output from the reader thread
-                    loop {
-                        match session.output_receiver.try_recv() {
-                            Ok(data) => {
-                                session.output_buffer.extend(data);
-                                // Enforce memory limit - keep only the tail if exceeded
-                                if session.output_buffer.len() > MAX_OUTPUT_BUFFER_SIZE {
-                                    let excess =
-                                        session.output_buffer.len() - MAX_OUTPUT_BUFFER_SIZE;
-                                    session.output_buffer.drain(0..excess);
-                                }
+                    if let Some(receiver) = session.output_receiver.as_mut() {
+                        while let Ok(Some(data)) = receiver.try_next() {
+                            session.output_buffer.extend(data);
+                            // Enforce memory limit - keep only the tail if exceeded
+                            if session.output_buffer.len() > MAX_OUTPUT_BUFFER_SIZE {
+                                let excess = session.output_buffer.len() - MAX_OUTPUT_BUFFER_SIZE;
+                                session.output_buffer.drain(0..excess);
                             }
-                            Err(TryRecvError::Empty) => break,
-                            Err(TryRecvError::Disconnected) => break,
                         }
                     }
                     // Return and clear the buffer
@@ -311,6 +773,16 @@ fn run_pty_thread(rx: Receiver<PtyCommand>) {
                 };
                 let _ = response.send(result);
             }
+            Ok(PtyCommand::Subscribe { id, response }) => {
+                let result = match sessions.get_mut(&id) {
+                    Some(session) => session
+                        .output_receiver
+                        .take()
+                        .ok_or_else(|| PtyError::AlreadySubscribed(id.to_string())),
+                    None => Err(PtyError::SessionNotFound(id.to_string())),
+                };
+                let _ = response.send(result);
+            }
             Ok(PtyCommand::Resize {
                 id,
                 rows,
@@ -336,15 +808,7 @@ fn run_pty_thread(rx: Receiver<PtyCommand>) {
             Ok(PtyCommand::Close { id, response }) => {
                 let result = if let Some(mut session) = sessions.remove(&id) {
                     // Kill child process (this will also cause reader thread to terminate)
-                    let kill_result = session
-                        .child
-                        .kill()
-                        .map_err(|e| PtyError::Io(e.to_string()));
-                    // Wait for reader thread to finish
-                    if let Some(handle) = session.reader_thread.take() {
-                        let _ = handle.join();
-                    }
-                    kill_result
+                    session.shutdown().map_err(|e| PtyError::Io(e.to_string()))
                 } else {
                     Ok(())
                 };
@@ -352,29 +816,34 @@ fn run_pty_thread(rx: Receiver<PtyCommand>) {
             }
             Ok(PtyCommand::CheckStatus { id, response }) => {
                 let result = if let Some(session) = sessions.get_mut(&id) {
-                    match session.child.try_wait() {
-                        Ok(Some(status)) => {
-                            // Child process has terminated, get exit code
-                            let exit_code = status.exit_code() as i32;
-                            Ok(Some(exit_code))
-                        }
-                        Ok(None) => {
-                            // Child process is still running
-                            Ok(None)
-                        }
-                        Err(e) => Err(PtyError::Io(e.to_string())),
-                    }
+                    // None while the child process is still running
+                    session.try_wait().map_err(|e| PtyError::Io(e.to_string()))
                 } else {
                     Err(PtyError::SessionNotFound(id.to_string()))
                 };
                 let _ = response.send(result);
             }
+            Ok(PtyCommand::Signal {
+                id,
+                signal,
+                response,
+            }) => {
+                let result = match sessions.get_mut(&id) {
+                    Some(session) => session.signal(signal),
+                    None => Err(PtyError::SessionNotFound(id.to_string())),
+                };
+                let _ = response.send(result);
+            }
+            Ok(PtyCommand::Foreground { id, response }) => {
+                let result = sessions
+                    .get(&id)
+                    .map(InternalPtySession::foreground_process)
+                    .ok_or_else(|| PtyError::SessionNotFound(id.to_string()));
+                let _ = response.send(result);
+            }
             Ok(PtyCommand::Shutdown) | Err(_) => {
                 for (_, mut session) in sessions.drain() {
-                    let _ = session.child.kill();
-                    if let Some(handle) = session.reader_thread.take() {
-                        let _ = handle.join();
-                    }
+                    let _ = session.shutdown();
                 }
                 break;
             }
@@ -403,17 +872,36 @@ impl PtyManager {
         }
     }
 
+    /// Create a session running the default shell
+    #[allow(dead_code)]
     #[instrument(skip(self), fields(rows = rows, cols = cols))]
     pub fn create_session(&self, rows: u16, cols: u16) -> Result<PtyId, PtyError> {
-        self.create_session_with_env(rows, cols, None)
+        self.create_session_with_spec(rows, cols, SpawnSpec::default())
     }
 
+    /// Create a session running the default shell with the given environment
+    #[allow(dead_code)]
     #[instrument(skip(self, environment), fields(rows = rows, cols = cols))]
     pub fn create_session_with_env(
         &self,
         rows: u16,
         cols: u16,
         environment: Option<PtyEnvironment>,
+    ) -> Result<PtyId, PtyError> {
+        let spec = SpawnSpec {
+            environment,
+            ..SpawnSpec::default()
+        };
+        self.create_session_with_spec(rows, cols, spec)
+    }
+
+    /// Create a session running the program described by `spec`
+    #[instrument(skip(self, spec), fields(rows = rows, cols = cols, program = ?spec.program))]
+    pub fn create_session_with_spec(
+        &self,
+        rows: u16,
+        cols: u16,
+        spec: SpawnSpec,
     ) -> Result<PtyId, PtyError> {
         let id = Uuid::new_v4();
         debug!(session_id = %id, "Creating new PTY session");
@@ -424,7 +912,7 @@ impl PtyManager {
                 id,
                 rows,
                 cols,
-                environment,
+                spec,
                 response: response_tx,
             })
             .map_err(|e| {
@@ -444,6 +932,7 @@ impl PtyManager {
             })
     }
 
+    /// Queue input for a session, without waiting for the child to read it
     #[instrument(skip(self, data), fields(session_id = %id, bytes = data.len()))]
     pub fn write(&self, id: &PtyId, data: &[u8]) -> Result<(), PtyError> {
         // Log PTY input at trace level with preview
@@ -478,6 +967,12 @@ impl PtyManager {
         })?
     }
 
+    /// Take the output that arrived since the last call
+    ///
+    /// Polling counterpart of [`output_stream`](Self::output_stream), used by
+    /// tests and the automation engine. Once a session's output is subscribed
+    /// this only returns output buffered before the subscription.
+    #[allow(dead_code)]
     #[instrument(skip(self), fields(session_id = %id))]
     pub fn read(&self, id: &PtyId) -> Result<Vec<u8>, PtyError> {
         let (response_tx, response_rx) = mpsc::channel();
@@ -513,6 +1008,48 @@ impl PtyManager {
         Ok(result)
     }
 
+    /// Take the output channel of a session
+    ///
+    /// Each chunk is delivered as soon as the reader thread receives it. The
+    /// channel is bounded, so a consumer that falls behind slows down the
+    /// child instead of growing memory. A session can be subscribed only once.
+    #[instrument(skip(self), fields(session_id = %id))]
+    pub fn subscribe(&self, id: &PtyId) -> Result<PtyOutputStream, PtyError> {
+        let (response_tx, response_rx) = mpsc::channel();
+
+        self.tx
+            .send(PtyCommand::Subscribe {
+                id: *id,
+                response: response_tx,
+            })
+            .map_err(|e| PtyError::Channel(e.to_string()))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| PtyError::Channel(e.to_string()))?
+    }
+
+    /// Stream of a session's output that subscribes when it is first polled
+    ///
+    /// Suited for `iced::Subscription::run_with_id`, which builds the stream on
+    /// every `subscription()` call but only runs the first one per id. Chunks
+    /// queued at the same time are merged into one item. The stream ends when
+    /// the child closes the PTY or the session is closed.
+    pub fn output_stream(
+        manager: Arc<Self>,
+        id: PtyId,
+    ) -> impl Stream<Item = Vec<u8>> + Send + 'static {
+        stream::once(async move { manager.subscribe(&id) })
+            .filter_map(move |result| async move {
+                result
+                    .map_err(|e| warn!(session_id = %id, error = %e, "Cannot stream PTY output"))
+                    .ok()
+            })
+            .flatten()
+            .ready_chunks(OUTPUT_STREAM_BATCH)
+            .map(|chunks| chunks.concat())
+    }
+
     #[instrument(skip(self), fields(session_id = %id, rows = rows, cols = cols))]
     pub fn resize(&self, id: &PtyId, rows: u16, cols: u16) -> Result<(), PtyError> {
         debug!("Resizing PTY");
@@ -555,11 +1092,10 @@ impl PtyManager {
     /// Check the exit status of the child process in a PTY session.
     ///
     /// Returns:
-    /// - `Ok(Some(exit_code))` if the process has terminated with the given exit code
+    /// - `Ok(Some(exit))` if the process has terminated, with its exit code or signal
     /// - `Ok(None)` if the process is still running
     /// - `Err(PtyError)` if there was an error checking the status
-    #[allow(dead_code)]
-    pub fn check_status(&self, id: &PtyId) -> Result<Option<i32>, PtyError> {
+    pub fn check_status(&self, id: &PtyId) -> Result<Option<ChildExit>, PtyError> {
         let (response_tx, response_rx) = mpsc::channel();
 
         self.tx
@@ -573,6 +1109,44 @@ impl PtyManager {
             .recv()
             .map_err(|e| PtyError::Channel(e.to_string()))?
     }
+
+    /// Send a signal to the foreground process group of a PTY session
+    ///
+    /// On Windows only `Terminate` and `Kill` are supported, both kill the child.
+    pub fn signal(&self, id: &PtyId, signal: PtySignal) -> Result<(), PtyError> {
+        let (response_tx, response_rx) = mpsc::channel();
+
+        self.tx
+            .send(PtyCommand::Signal {
+                id: *id,
+                signal,
+                response: response_tx,
+            })
+            .map_err(|e| PtyError::Channel(e.to_string()))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| PtyError::Channel(e.to_string()))?
+    }
+
+    /// Get the process in the foreground of a PTY session
+    ///
+    /// Returns `Ok(None)` if it cannot be determined (the child has exited or
+    /// the platform has no process groups).
+    pub fn foreground_process(&self, id: &PtyId) -> Result<Option<ForegroundProcess>, PtyError> {
+        let (response_tx, response_rx) = mpsc::channel();
+
+        self.tx
+            .send(PtyCommand::Foreground {
+                id: *id,
+                response: response_tx,
+            })
+            .map_err(|e| PtyError::Channel(e.to_string()))?;
+
+        response_rx
+            .recv()
+            .map_err(|e| PtyError::Channel(e.to_string()))?
+    }
 }
 
 impl Default for PtyManager {
@@ -0,0 +1,400 @@
+//! Shell integration injected into shells at spawn time
+//!
+//! The shell is started so that it reads a wrapper of ours instead of its
+//! startup files. The wrapper runs the user's startup files as usual, then
+//! sources the integration script unless they already did (the user pasted
+//! [`integration_script`] into an rc file). Each shell has its own hook:
+//!
+//! - **bash**: POSIX mode, in which bash reads only the file `ENV` points
+//!   at. The wrapper leaves POSIX mode and reads the login or interactive
+//!   files itself, so `-l` is still passed and login shells stay real ones
+//! - **zsh**: `ZDOTDIR` pointing at wrapper `.zshenv`, `.zprofile` and
+//!   `.zshrc` files that source the user's ones from their `ZDOTDIR`
+//! - **fish**: a `vendor_conf.d` snippet found through `XDG_DATA_DIRS`, which
+//!   installs the integration at the first prompt (after `config.fish`)
+//!
+//! The wrappers restore the variables they were reached through, so programs
+//! started from the shell see the user's environment.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::shell_integration::{integration_script, ShellType};
+
+/// The user's `ENV`, while bash reads our wrapper through it
+const BASH_USER_ENV_ENV: &str = "AGTERM_USER_ENV";
+
+/// The user's `ZDOTDIR`, while zsh reads our startup files
+const ZSH_USER_ZDOTDIR_ENV: &str = "AGTERM_USER_ZDOTDIR";
+
+/// Entry added to `XDG_DATA_DIRS`, for fish to take back out
+const FISH_DATA_DIR_ENV: &str = "AGTERM_FISH_XDG_DATA_DIR";
+
+const BASH_STARTUP: &str = r#"# AgTerm: bash started in POSIX mode reads this file through ENV instead of
+# its startup files. Leave POSIX mode (and its defaults), then read the
+# user's startup files as bash would, then the shell integration.
+set +o posix
+shopt -u inherit_errexit
+[ "$HISTFILE" = ~/.sh_history ] && HISTFILE=~/.bash_history
+if [ -n "${AGTERM_USER_ENV+set}" ]; then ENV=$AGTERM_USER_ENV; else unset ENV; fi
+unset AGTERM_USER_ENV
+
+if shopt -q login_shell; then
+    [ -r /etc/profile ] && . /etc/profile
+    if [ -r ~/.bash_profile ]; then
+        . ~/.bash_profile
+    elif [ -r ~/.bash_login ]; then
+        . ~/.bash_login
+    elif [ -r ~/.profile ]; then
+        . ~/.profile
+    fi
+else
+    [ -r /etc/bash.bashrc ] && . /etc/bash.bashrc
+    [ -r ~/.bashrc ] && . ~/.bashrc
+fi
+
+# Skip if the startup files already installed the integration
+if ! declare -F __agterm_precmd >/dev/null; then
+    . "${BASH_SOURCE[0]%/*}/agterm.bash"
+fi
+"#;
+
+const ZSH_ZSHENV: &str = r#"# AgTerm: ZDOTDIR points here so zsh reads these files, each of which
+# runs the user's own file with the user's ZDOTDIR in place
+__agterm_zdotdir=$ZDOTDIR
+__agterm_user_zdotdir=$AGTERM_USER_ZDOTDIR
+unset AGTERM_USER_ZDOTDIR
+
+if [[ -n $__agterm_user_zdotdir ]]; then ZDOTDIR=$__agterm_user_zdotdir; else unset ZDOTDIR; fi
+[[ -r "${ZDOTDIR:-$HOME}/.zshenv" ]] && source "${ZDOTDIR:-$HOME}/.zshenv"
+__agterm_user_zdotdir=${ZDOTDIR-}
+ZDOTDIR=$__agterm_zdotdir
+"#;
+
+const ZSH_ZPROFILE: &str = r#"# AgTerm: the user's .zprofile
+if [[ -n $__agterm_user_zdotdir ]]; then ZDOTDIR=$__agterm_user_zdotdir; else unset ZDOTDIR; fi
+[[ -r "${ZDOTDIR:-$HOME}/.zprofile" ]] && source "${ZDOTDIR:-$HOME}/.zprofile"
+__agterm_user_zdotdir=${ZDOTDIR-}
+ZDOTDIR=$__agterm_zdotdir
+"#;
+
+const ZSH_ZSHRC: &str = r#"# AgTerm: the user's .zshrc, then the shell integration
+#
+# ZDOTDIR stays the user's from here on, so zsh reads their .zlogin.
+if [[ -n $__agterm_user_zdotdir ]]; then ZDOTDIR=$__agterm_user_zdotdir; else unset ZDOTDIR; fi
+[[ -r "${ZDOTDIR:-$HOME}/.zshrc" ]] && source "${ZDOTDIR:-$HOME}/.zshrc"
+
+# Skip if the startup files already installed the integration
+(( ${+functions[__agterm_precmd]} )) || source "$__agterm_zdotdir/agterm.zsh"
+unset __agterm_zdotdir __agterm_user_zdotdir
+"#;
+
+const FISH_VENDOR_CONF: &str = r#"# AgTerm: take our entry back out of XDG_DATA_DIRS, and install the shell
+# integration at the first prompt, once config.fish has run
+if set -q AGTERM_FISH_XDG_DATA_DIR
+    set -l dirs (string split : -- $XDG_DATA_DIRS)
+    if set -l index (contains -i -- $AGTERM_FISH_XDG_DATA_DIR $dirs)
+        set -e dirs[$index]
+    end
+    if test (count $dirs) -gt 0
+        set -gx XDG_DATA_DIRS (string join : -- $dirs)
+    else
+        set -e XDG_DATA_DIRS
+    end
+    set -e AGTERM_FISH_XDG_DATA_DIR
+end
+
+set -g __agterm_integration_script (status dirname)/../agterm.fish
+
+function __agterm_inject --on-event fish_prompt
+    functions -e __agterm_inject
+    # Skip if the startup files already installed the integration
+    if not functions -q __agterm_precmd
+        source $__agterm_integration_script
+        # The prompt event handlers of the integration start with the next prompt
+        printf '\e]133;A\a'
+    end
+    set -e __agterm_integration_script
+end
+"#;
+
+/// Changes to a shell's command line and environment that load the integration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct Injection {
+    /// Options placed before `-l` and the spec's own arguments
+    pub(super) args: Vec<String>,
+    /// Whether to still pass `-l` for a login shell
+    pub(super) login: bool,
+    /// Environment variables to set
+    pub(super) env: Vec<(String, String)>,
+}
+
+/// Directory the wrappers and scripts are written to
+///
+/// `agterm/shell-integration` in the user cache directory, rather than a
+/// shared temporary directory other users could plant files in.
+pub(super) fn default_dir() -> PathBuf {
+    dirs::cache_dir()
+        .unwrap_or_else(std::env::temp_dir)
+        .join("agterm")
+        .join("shell-integration")
+}
+
+/// Prepare the integration for `program` under `dir`
+///
+/// `lookup` finds variables of the child's environment. Returns None for
+/// shells without an injection hook (only bash, zsh and fish have one).
+pub(super) fn prepare(
+    program: &str,
+    login: bool,
+    lookup: impl Fn(&str) -> Option<String>,
+    dir: &Path,
+) -> io::Result<Option<Injection>> {
+    let name = Path::new(program)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let shell = ShellType::from_name(&name);
+
+    let injection = match shell {
+        ShellType::Bash => {
+            let dir = dir.join("bash");
+            write_files(
+                &dir,
+                &[
+                    ("agterm.bash", &integration_script(shell)),
+                    ("startup.bash", BASH_STARTUP),
+                ],
+            )?;
+            let startup = dir.join("startup.bash").to_string_lossy().into_owned();
+            let mut env = vec![("ENV".to_string(), startup)];
+            if let Some(user_env) = lookup("ENV") {
+                env.push((BASH_USER_ENV_ENV.to_string(), user_env));
+            }
+            Injection {
+                args: vec!["--posix".to_string()],
+                login,
+                env,
+            }
+        }
+        ShellType::Zsh => {
+            let dir = dir.join("zsh");
+            write_files(
+                &dir,
+                &[
+                    ("agterm.zsh", &integration_script(shell)),
+                    (".zshenv", ZSH_ZSHENV),
+                    (".zprofile", ZSH_ZPROFILE),
+                    (".zshrc", ZSH_ZSHRC),
+                ],
+            )?;
+            let mut env = vec![("ZDOTDIR".to_string(), dir.to_string_lossy().into_owned())];
+            if let Some(zdotdir) = lookup("ZDOTDIR") {
+                env.push((ZSH_USER_ZDOTDIR_ENV.to_string(), zdotdir));
+            }
+            Injection {
+                args: Vec::new(),
+                login,
+                env,
+            }
+        }
+        ShellType::Fish => {
+            let data_dir = dir.join("fish-data");
+            let fish_dir = data_dir.join("fish");
+            write_files(&fish_dir, &[("agterm.fish", &integration_script(shell))])?;
+            write_files(
+                &fish_dir.join("vendor_conf.d"),
+                &[("agterm.fish", FISH_VENDOR_CONF)],
+            )?;
+            let data_dir = data_dir.to_string_lossy().into_owned();
+            let data_dirs = match lookup("XDG_DATA_DIRS").filter(|dirs| !dirs.is_empty()) {
+                Some(dirs) => format!("{data_dir}:{dirs}"),
+                None => data_dir.clone(),
+            };
+            Injection {
+                args: Vec::new(),
+                login,
+                env: vec![
+                    ("XDG_DATA_DIRS".to_string(), data_dirs),
+                    (FISH_DATA_DIR_ENV.to_string(), data_dir),
+                ],
+            }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(injection))
+}
+
+/// Write files into `dir`, leaving ones that are already up to date alone
+/// (a shell starting in another tab may be reading them)
+fn write_files(dir: &Path, files: &[(&str, &str)]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (name, content) in files {
+        let path = dir.join(name);
+        if fs::read_to_string(&path).is_ok_and(|current| current == *content) {
+            continue;
+        }
+        // Replace atomically so a reader never sees a partial file
+        let temp = dir.join(format!(".{name}.{}", std::process::id()));
+        fs::write(&temp, content)?;
+        fs::rename(&temp, &path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    fn env_of<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| {
+            vars.iter()
+                .find(|(name, _)| *name == key)
+                .map(|(_, value)| value.to_string())
+        }
+    }
+
+    fn var<'a>(injection: &'a Injection, key: &str) -> Option<&'a str> {
+        injection
+            .env
+            .iter()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_bash_startup_file() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let env = [("ENV", "/home/user/.shinit")];
+        let injection = prepare("/bin/bash", true, env_of(&env), dir.path())
+            .unwrap()
+            .unwrap();
+
+        let startup = dir.path().join("bash").join("startup.bash");
+        assert_eq!(injection.args, ["--posix"]);
+        assert!(injection.login);
+        assert_eq!(var(&injection, "ENV"), startup.to_str());
+        assert_eq!(
+            var(&injection, BASH_USER_ENV_ENV),
+            Some("/home/user/.shinit")
+        );
+        assert!(dir.path().join("bash").join("agterm.bash").is_file());
+    }
+
+    #[test]
+    fn test_zsh_keeps_user_zdotdir() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let env = [("ZDOTDIR", "/home/user/.config/zsh")];
+        let injection = prepare("zsh", true, env_of(&env), dir.path())
+            .unwrap()
+            .unwrap();
+
+        let zdotdir = dir.path().join("zsh");
+        assert!(injection.args.is_empty());
+        assert!(injection.login);
+        assert_eq!(var(&injection, "ZDOTDIR"), zdotdir.to_str());
+        assert_eq!(
+            var(&injection, ZSH_USER_ZDOTDIR_ENV),
+            Some("/home/user/.config/zsh")
+        );
+        for file in [".zshenv", ".zprofile", ".zshrc", "agterm.zsh"] {
+            assert!(zdotdir.join(file).is_file(), "missing {file}");
+        }
+
+        let injection = prepare("zsh", false, env_of(&[]), dir.path())
+            .unwrap()
+            .unwrap();
+        assert_eq!(var(&injection, ZSH_USER_ZDOTDIR_ENV), None);
+    }
+
+    #[test]
+    fn test_fish_data_dir_prepended() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let env = [("XDG_DATA_DIRS", "/usr/local/share:/usr/share")];
+        let injection = prepare("/usr/bin/fish", false, env_of(&env), dir.path())
+            .unwrap()
+            .unwrap();
+
+        let data_dir = dir.path().join("fish-data");
+        let data_dir = data_dir.to_str().unwrap();
+        assert_eq!(
+            var(&injection, "XDG_DATA_DIRS"),
+            Some(format!("{data_dir}:/usr/local/share:/usr/share").as_str())
+        );
+        assert_eq!(var(&injection, FISH_DATA_DIR_ENV), Some(data_dir));
+        assert!(dir
+            .path()
+            .join("fish-data/fish/vendor_conf.d/agterm.fish")
+            .is_file());
+    }
+
+    #[test]
+    fn test_other_programs_untouched() {
+        let dir = tempfile::TempDir::new().unwrap();
+        for program in ["pwsh", "nu", "htop", "/usr/bin/sh"] {
+            assert_eq!(
+                prepare(program, false, env_of(&[]), dir.path()).unwrap(),
+                None
+            );
+        }
+    }
+
+    /// Run `command` in an interactive bash started through the rc wrapper
+    fn run_bash(home: &Path, login: bool, command: &str) -> String {
+        let dir = home.join("integration");
+        let injection = prepare("bash", login, env_of(&[]), &dir).unwrap().unwrap();
+        let output = Command::new("bash")
+            .args(&injection.args)
+            .args(injection.login.then_some("-l"))
+            .args(["-i", "-c", command])
+            .envs(injection.env)
+            .env("HOME", home)
+            .output()
+            .expect("run bash");
+        String::from_utf8_lossy(&output.stdout).into_owned()
+    }
+
+    #[test]
+    fn test_bash_sources_user_files_then_integration() {
+        let home = tempfile::TempDir::new().unwrap();
+        fs::write(home.path().join(".bashrc"), "USER_RC=bashrc\n").unwrap();
+        fs::write(home.path().join(".bash_profile"), "USER_RC=profile\n").unwrap();
+
+        let check = "echo \"$USER_RC $(declare -F __agterm_precmd) ${ENV-unset}\"";
+        assert!(run_bash(home.path(), false, check).contains("bashrc __agterm_precmd unset"));
+        assert!(run_bash(home.path(), true, check).contains("profile __agterm_precmd unset"));
+    }
+
+    #[test]
+    fn test_bash_login_shell_is_real() {
+        let home = tempfile::TempDir::new().unwrap();
+        fs::write(home.path().join(".bash_logout"), "echo logged out\n").unwrap();
+
+        let check = "shopt -q login_shell && echo login; shopt -oq posix || echo not posix; \
+                     echo \"$HISTFILE\"; exit";
+        let output = run_bash(home.path(), true, check);
+        assert!(output.contains("login\nnot posix\n"));
+        assert!(output.contains(&format!("{}/.bash_history", home.path().display())));
+        assert!(output.contains("logged out"));
+
+        let output = run_bash(home.path(), false, check);
+        assert!(!output.contains("login\n"));
+        assert!(!output.contains("logged out"));
+    }
+
+    #[test]
+    fn test_bash_skips_installed_integration() {
+        let home = tempfile::TempDir::new().unwrap();
+        // As if the user had pasted the integration script into .bashrc
+        fs::write(home.path().join(".bashrc"), "__agterm_precmd() { :; }\n").unwrap();
+
+        let output = run_bash(
+            home.path(),
+            false,
+            "declare -F __agterm_report_cwd || echo skipped",
+        );
+        assert!(output.contains("skipped"));
+    }
+}
@@ -2,8 +2,9 @@
 
 use iced::Color;
 use std::cmp::{max, min};
-use std::collections::VecDeque;
+use std::ops::Range;
 use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 use unicode_width::UnicodeWidthChar;
 use vte::{Params, Parser, Perform};
 
@@ -17,17 +18,61 @@ fn get_url_regex() -> &'static regex::Regex {
     })
 }
 
+mod charset;
+mod dcs;
+mod grapheme;
 mod memory;
+mod modes;
+mod persist;
+mod reflow;
 mod scrollback;
+mod spill;
+use charset::{Charset, CharsetState};
+use dcs::{DcsKind, DcsState};
 pub use memory::{MemoryStats, StringInterner};
-// Reserved for future scrollback buffer implementation
-#[allow(unused_imports)]
+pub use modes::Mode;
+use modes::ModeTable;
+pub use persist::{scrollback_version, ScrollbackSnapshot, SCROLLBACK_VERSION};
+pub use reflow::LineMap;
 pub use scrollback::{ScrollbackBuffer, ScrollbackConfig};
 
-use std::collections::HashSet;
-
-/// Maximum scrollback buffer lines
-const MAX_SCROLLBACK: usize = 10000;
+use crate::image_protocol;
+use crate::terminal::hyperlink::Hyperlink;
+use crate::terminal::keyboard;
+use crate::terminal::search::SearchSnapshot;
+use std::collections::{HashSet, VecDeque};
+
+/// Maximum depth of the kitty keyboard flag stack
+const MAX_KEYBOARD_MODE_STACK: usize = 16;
+
+/// Maximum depth of the window title stack (XTWINOPS 22/23)
+const MAX_TITLE_STACK: usize = 10;
+
+/// Maximum time a synchronized update (mode 2026) may hold back rendering
+const SYNCHRONIZED_UPDATE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Default size of a character cell in pixels (width, height), used to lay out images
+const DEFAULT_CELL_PIXEL_SIZE: (u32, u32) = (8, 18);
+
+/// Maximum number of shell marks held until they are taken
+const MAX_PENDING_SHELL_MARKS: usize = 1024;
+
+/// Shell integration sequence (OSC 7, 133, 633 or 1337) and where it was received
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellMark {
+    /// OSC command number
+    pub command: u32,
+    /// Whole OSC payload including the command number (e.g. "133;D;0"), as
+    /// [`ShellIntegration::parse_osc`](crate::shell_integration::ShellIntegration::parse_osc) takes it
+    pub data: String,
+    /// Cursor line, numbered like [`TerminalScreen::line_offset`] so it stays
+    /// valid while the scrollback is trimmed
+    pub line: usize,
+    /// Cursor column
+    pub col: usize,
+    /// Received on the alternate screen, where `line` and `col` are not in the scrollback
+    pub alternate_screen: bool,
+}
 
 /// Dirty flag system for incremental rendering optimization
 ///
@@ -165,6 +210,87 @@ impl AnsiColor {
     }
 }
 
+/// Copy a `tile_width` x `tile_height` region out of an RGBA image
+///
+/// Pixels outside the source image are transparent.
+fn crop_rgba(
+    pixels: &[u8],
+    width: u32,
+    height: u32,
+    x: u32,
+    y: u32,
+    tile_width: u32,
+    tile_height: u32,
+) -> Vec<u8> {
+    let mut tile = vec![0u8; (tile_width * tile_height * 4) as usize];
+    for row in 0..tile_height {
+        let src_y = y + row;
+        if src_y >= height || x >= width {
+            break;
+        }
+        let copy_width = min(tile_width, width - x) as usize;
+        let src = ((src_y * width + x) * 4) as usize;
+        let dst = (row * tile_width * 4) as usize;
+        tile[dst..dst + copy_width * 4].copy_from_slice(&pixels[src..src + copy_width * 4]);
+    }
+    tile
+}
+
+/// Format a color as an SGR parameter
+///
+/// `base` and `bright_base` are the first codes of the standard and bright
+/// ranges (30/90 for foreground, 40/100 for background), `extended` is the
+/// extended color introducer (38 or 48).
+fn sgr_color_param(color: AnsiColor, base: u16, bright_base: u16, extended: u16) -> String {
+    match color {
+        AnsiColor::Indexed(idx @ 0..=7) => (base + idx as u16).to_string(),
+        AnsiColor::Indexed(idx) => (bright_base + (idx as u16).saturating_sub(8)).to_string(),
+        AnsiColor::Palette256(idx) => format!("{extended};5;{idx}"),
+        AnsiColor::Rgb(r, g, b) => format!("{extended};2;{r};{g};{b}"),
+    }
+}
+
+/// Parse the color of an extended SGR color parameter (38, 48 or 58)
+///
+/// Handles the colon form with the color in subparameters (`38:2::r:g:b`,
+/// `38:2:r:g:b`, `38:5:n`) and the semicolon form where the color follows as
+/// separate parameters (`38;2;r;g;b`, `38;5;n`).
+fn parse_sgr_extended_color<'a>(
+    param: &[u16],
+    params: &mut impl Iterator<Item = &'a [u16]>,
+) -> Option<AnsiColor> {
+    if param.len() > 1 {
+        return match param[1..] {
+            // The optional color space ID precedes the components
+            [2, _, r, g, b, ..] | [2, r, g, b] => Some(AnsiColor::Rgb(r as u8, g as u8, b as u8)),
+            [5, idx, ..] => Some(AnsiColor::Palette256(idx as u8)),
+            _ => None,
+        };
+    }
+
+    match params.next()?[0] {
+        2 => {
+            let (r, g, b) = (params.next()?[0], params.next()?[0], params.next()?[0]);
+            Some(AnsiColor::Rgb(r as u8, g as u8, b as u8))
+        }
+        5 => Some(AnsiColor::Palette256(params.next()?[0] as u8)),
+        _ => None,
+    }
+}
+
+/// Push onto a title or icon stack, dropping the oldest entry when it is full
+fn push_bounded(stack: &mut Vec<Option<String>>, value: Option<String>) {
+    if stack.len() >= MAX_TITLE_STACK {
+        stack.remove(0);
+    }
+    stack.push(value);
+}
+
+/// Default tab stops for a line of `cols` columns: every 8 columns
+fn default_tab_stops(cols: usize) -> Vec<bool> {
+    (0..cols).map(|col| col > 0 && col % 8 == 0).collect()
+}
+
 /// Convert 16-color index to Iced Color
 fn indexed_to_color(idx: u8) -> Color {
     match idx {
@@ -219,8 +345,10 @@ pub enum MouseMode {
     /// No mouse reporting
     #[default]
     None,
-    /// X10 Mouse Reporting (CSI ?9h or CSI ?1000h)
+    /// X10 compatibility mode (CSI ?9h) - button presses only, without modifiers
     X10,
+    /// Normal tracking (CSI ?1000h) - button presses and releases
+    Normal,
     /// Button-Event Mouse Tracking (CSI ?1002h)
     ButtonEvent,
     /// Any-Event Mouse Tracking (CSI ?1003h)
@@ -230,11 +358,54 @@ pub enum MouseMode {
 /// Mouse encoding mode
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum MouseEncoding {
-    /// Default X10/UTF-8 encoding
+    /// Default X10 encoding (coordinates limited to 223)
     #[default]
     Default,
+    /// UTF-8 Extended Mouse Mode (CSI ?1005h)
+    Utf8,
     /// SGR Extended Mouse Mode (CSI ?1006h)
     Sgr,
+    /// urxvt Extended Mouse Mode (CSI ?1015h)
+    Urxvt,
+    /// SGR Extended Mouse Mode with pixel coordinates (CSI ?1016h)
+    SgrPixels,
+}
+
+/// Underline style (SGR 4:x)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnderlineStyle {
+    /// Straight line (SGR 4, 4:1)
+    #[default]
+    Single,
+    /// Two straight lines (SGR 21, 4:2)
+    Double,
+    /// Wavy line (SGR 4:3), used for spell check and diagnostics
+    Curly,
+    /// Dotted line (SGR 4:4)
+    Dotted,
+    /// Dashed line (SGR 4:5)
+    Dashed,
+}
+
+/// Identity of the hyperlink a cell belongs to
+///
+/// Cells with the same identity and URL form one logical link, see
+/// [`TerminalScreen::hyperlink_cells`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HyperlinkId {
+    /// OSC 8 link with an explicit `id=` parameter, grouped across lines (interned)
+    Named(Arc<String>),
+    /// OSC 8 link opened without `id=`, unique per opening sequence
+    Anonymous(u64),
+    /// URL found in the text by [`TerminalScreen::detect_urls`]
+    Detected,
+}
+
+impl HyperlinkId {
+    /// Whether the link was set by the application through OSC 8
+    pub fn is_explicit(&self) -> bool {
+        !matches!(self, HyperlinkId::Detected)
+    }
 }
 
 /// Terminal cell with character and styling
@@ -246,26 +417,40 @@ pub enum MouseEncoding {
 #[derive(Clone, Debug)]
 pub struct Cell {
     pub c: char,
+    /// Codepoints following `c` in the same grapheme cluster (combining marks,
+    /// ZWJ sequences, variation selectors) - interned, None for plain characters
+    pub extra: Option<Arc<String>>,
     pub fg: Option<AnsiColor>,
     /// Background color (reserved for future rendering enhancement)
     #[allow(dead_code)]
     pub bg: Option<AnsiColor>,
     pub bold: bool,
     pub underline: bool,
+    /// How the underline is drawn when `underline` is set
+    pub underline_style: UnderlineStyle,
+    /// Underline color (SGR 58), None = same as the text
+    pub underline_color: Option<AnsiColor>,
     /// Reverse video (reserved for future rendering enhancement)
     #[allow(dead_code)]
     pub reverse: bool,
     pub dim: bool,
     pub italic: bool,
     pub strikethrough: bool,
+    /// Blinking text (SGR 5/6)
+    pub blink: bool,
+    /// Concealed text (SGR 8)
+    pub hidden: bool,
+    /// Line above the text (SGR 53)
+    pub overline: bool,
     /// This cell is the first cell of a wide character (CJK, emoji, etc.)
     pub wide: bool,
     /// This cell is a placeholder for the second cell of a wide character
     pub placeholder: bool,
     /// Hyperlink URL (for OSC 8 or auto-detected URLs) - uses Arc for string interning
     pub hyperlink: Option<Arc<String>>,
-    /// Image data for this cell (for future image rendering support)
-    #[allow(dead_code)]
+    /// Identity grouping cells of one logical hyperlink
+    pub hyperlink_id: Option<HyperlinkId>,
+    /// Image tile drawn over this cell
     pub image: Option<ImageData>,
 }
 
@@ -273,22 +458,39 @@ impl Default for Cell {
     fn default() -> Self {
         Self {
             c: ' ',
+            extra: None,
             fg: None,
             bg: None,
             bold: false,
             underline: false,
+            underline_style: UnderlineStyle::Single,
+            underline_color: None,
             reverse: false,
             dim: false,
             italic: false,
             strikethrough: false,
+            blink: false,
+            hidden: false,
+            overline: false,
             wide: false,
             placeholder: false,
             hyperlink: None,
+            hyperlink_id: None,
             image: None,
         }
     }
 }
 
+impl Cell {
+    /// Append the grapheme cluster stored in this cell to `out`
+    pub fn push_grapheme(&self, out: &mut String) {
+        out.push(self.c);
+        if let Some(extra) = &self.extra {
+            out.push_str(extra);
+        }
+    }
+}
+
 /// Image protocol types supported by terminal emulators
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ImageProtocol {
@@ -357,6 +559,19 @@ impl ImageData {
 
 /// Saved state for alternate screen buffer switching.
 ///
+/// Cursor state saved by DECSC and restored by DECRC
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    fg: Option<AnsiColor>,
+    bg: Option<AnsiColor>,
+    bold: bool,
+    charsets: CharsetState,
+    /// DECOM is part of the saved state, like in xterm
+    origin_mode: bool,
+}
+
 /// Stores complete terminal state when entering alternate screen,
 /// allowing full restoration when returning to main screen.
 /// State saved when entering alternate screen mode
@@ -379,14 +594,23 @@ struct AlternateScreenState {
     dim: bool,
     italic: bool,
     strikethrough: bool,
+    underline_style: UnderlineStyle,
+    underline_color: Option<AnsiColor>,
+    blink: bool,
+    hidden: bool,
+    overline: bool,
     /// Scroll region
     scroll_region: Option<(usize, usize)>,
     /// Saved cursor for DECSC/DECRC
     saved_cursor: Option<(usize, usize)>,
     /// Saved cursor state (DECSC)
-    saved_cursor_state: Option<(usize, usize, Option<AnsiColor>, Option<AnsiColor>, bool)>,
-    /// Saved scrollback buffer (compressed)
-    saved_scrollback: VecDeque<CompressedLine>,
+    saved_cursor_state: Option<SavedCursor>,
+    /// Saved soft-wrap flags of the main buffer rows
+    line_wrapped: Vec<bool>,
+    /// Saved G0-G3 charset state
+    charsets: CharsetState,
+    /// Saved kitty keyboard flag stack of the main screen
+    keyboard_mode_stack: Vec<u8>,
     /// Alternate screen is active
     is_active: bool,
     /// Scrollback disabled in alternate screen (always true for alternate screen)
@@ -413,6 +637,8 @@ pub struct CompressedLine {
     uncompressed_size: usize,
     /// Compressed size in bytes (approximate)
     compressed_size: usize,
+    /// Line was soft-wrapped into the next line by auto-wrap
+    wrapped: bool,
 }
 
 impl CompressedLine {
@@ -426,6 +652,7 @@ impl CompressedLine {
                 original_length: 0,
                 uncompressed_size: 0,
                 compressed_size: 0,
+                wrapped: false,
             };
         }
 
@@ -462,11 +689,28 @@ impl CompressedLine {
             original_length,
             uncompressed_size,
             compressed_size,
+            wrapped: false,
         }
     }
 
+    /// Set the soft-wrap flag of the compressed line
+    fn with_wrapped(mut self, wrapped: bool) -> Self {
+        self.wrapped = wrapped;
+        self
+    }
+
+    /// Check if the line was soft-wrapped into the next line
+    pub fn is_wrapped(&self) -> bool {
+        self.wrapped
+    }
+
+    /// Number of cells in the line
+    pub(crate) fn len(&self) -> usize {
+        self.original_length
+    }
+
     /// Decompress the line back to a vector of cells
-    fn decompress(&self) -> Vec<Cell> {
+    pub(crate) fn decompress(&self) -> Vec<Cell> {
         let mut line = Vec::with_capacity(self.original_length);
 
         for segment in &self.segments {
@@ -508,14 +752,20 @@ impl CompressedLine {
 /// Check if two cells are equal for compression purposes
 fn cells_equal(a: &Cell, b: &Cell) -> bool {
     a.c == b.c
+        && a.extra == b.extra
         && a.fg == b.fg
         && a.bg == b.bg
         && a.bold == b.bold
         && a.underline == b.underline
+        && a.underline_style == b.underline_style
+        && a.underline_color == b.underline_color
         && a.reverse == b.reverse
         && a.dim == b.dim
         && a.italic == b.italic
         && a.strikethrough == b.strikethrough
+        && a.blink == b.blink
+        && a.hidden == b.hidden
+        && a.overline == b.overline
         && a.wide == b.wide
         && a.placeholder == b.placeholder
         && match (&a.hyperlink, &b.hyperlink) {
@@ -523,6 +773,12 @@ fn cells_equal(a: &Cell, b: &Cell) -> bool {
             (Some(a_link), Some(b_link)) => Arc::ptr_eq(a_link, b_link) || a_link == b_link,
             _ => false,
         }
+        && match (&a.hyperlink_id, &b.hyperlink_id) {
+            (Some(HyperlinkId::Named(a_id)), Some(HyperlinkId::Named(b_id))) => {
+                Arc::ptr_eq(a_id, b_id) || a_id == b_id
+            }
+            (a_id, b_id) => a_id == b_id,
+        }
         // Ignore image comparison for performance - images are rare
         && a.image.is_none() && b.image.is_none()
 }
@@ -593,8 +849,10 @@ pub struct TerminalScreen {
     rows: usize,
     /// Screen buffer (rows x cols) - only visible lines
     buffer: Vec<Vec<Cell>>,
+    /// Per-row soft-wrap flags: row continues on the next row (set by auto-wrap)
+    line_wrapped: Vec<bool>,
     /// Scrollback buffer (historical lines) - compressed for memory efficiency
-    scrollback: VecDeque<CompressedLine>,
+    scrollback: ScrollbackBuffer,
     /// Compression statistics
     compression_stats: CompressionStats,
     /// Cursor position
@@ -609,50 +867,71 @@ pub struct TerminalScreen {
     dim: bool,
     italic: bool,
     strikethrough: bool,
+    underline_style: UnderlineStyle,
+    underline_color: Option<AnsiColor>,
+    blink: bool,
+    hidden: bool,
+    overline: bool,
+    /// Active OSC 8 hyperlink URL applied to printed cells (interned)
+    current_hyperlink: Option<Arc<String>>,
+    /// Identity of the active OSC 8 link
+    current_hyperlink_id: Option<HyperlinkId>,
+    /// Counter for generating IDs of OSC 8 links opened without `id=`
+    next_hyperlink_id: u64,
     /// VTE parser
     parser: Parser,
     /// Scroll region (top, bottom) - None means full screen
     scroll_region: Option<(usize, usize)>,
     /// Saved cursor position (for save/restore)
     saved_cursor: Option<(usize, usize)>,
-    /// Saved cursor state (DECSC) - position, fg, bg, bold
-    saved_cursor_state: Option<(usize, usize, Option<AnsiColor>, Option<AnsiColor>, bool)>,
+    /// Saved cursor state (DECSC)
+    saved_cursor_state: Option<SavedCursor>,
     /// OSC sequence fields
     /// Window title (OSC 0 or OSC 2)
     window_title: Option<String>,
     /// Icon name (OSC 1)
     icon_name: Option<String>,
+    /// Saved icon names (XTWINOPS 22/23), kept apart from the titles like xterm
+    icon_stack: Vec<Option<String>>,
+    /// Saved window titles (XTWINOPS 22/23)
+    title_stack: Vec<Option<String>>,
     /// Current working directory from shell (OSC 7)
     cwd_from_shell: Option<String>,
+    /// Host name of the working directory from shell (OSC 7), e.g. after ssh
+    host_from_shell: Option<String>,
     /// Clipboard request data (OSC 52)
     clipboard_request: Option<String>,
     /// Alternate screen buffer (for applications like vim, less, etc.)
     alternate_buffer: Option<Vec<Vec<Cell>>>,
-    /// Alternate scrollback buffer (compressed)
-    alternate_scrollback: Option<VecDeque<CompressedLine>>,
+    /// Main screen scrollback, kept aside while the alternate screen is active
+    alternate_scrollback: Option<ScrollbackBuffer>,
     /// Whether we're currently using the alternate screen
     use_alternate_screen: bool,
     /// Saved main screen state (cursor pos, attributes, scroll region, etc.)
     alternate_saved_state: Option<AlternateScreenState>,
-    /// Mouse reporting mode
-    mouse_mode: MouseMode,
-    /// Mouse encoding mode
-    mouse_encoding: MouseEncoding,
-    /// Cursor visibility (DECTCEM)
-    cursor_visible: bool,
-    /// Auto-wrap mode (DECAWM) - default true
-    auto_wrap_mode: bool,
-    /// Bracketed paste mode (CSI ?2004h/l) - tracked for terminal compatibility
-    #[allow(dead_code)]
-    bracketed_paste_mode: bool,
-    /// Application cursor keys mode (DECCKM - CSI ?1h/l)
-    application_cursor_keys: bool,
+    /// ANSI and DEC private modes (SM/RM, DECSET/DECRST)
+    modes: ModeTable,
+    /// Tab stops, one flag per column (default every 8 columns)
+    tab_stops: Vec<bool>,
+    /// Last printed graphic character, repeated by REP (CSI Ps b)
+    last_printed: Option<char>,
+    /// Cell (row, col) holding the grapheme cluster printed last, which
+    /// following combining characters join (cleared by any other sequence)
+    last_grapheme: Option<(usize, usize)>,
+    /// G0-G3 character set designations and GL/GR invocation
+    charsets: CharsetState,
+    /// Kitty keyboard protocol flag stack (CSI > flags u / CSI < u)
+    keyboard_mode_stack: Vec<u8>,
+    /// Start of the open synchronized update (CSI ?2026h/l)
+    synchronized_update: Option<Instant>,
     /// Pending responses to be sent to PTY (for DA, DSR, CPR, etc.)
     pending_responses: Vec<String>,
+    /// Shell integration marks not taken yet (oldest dropped past the limit)
+    pending_shell_marks: VecDeque<ShellMark>,
+    /// Reflows of the main screen not taken yet, oldest first
+    pending_line_maps: Vec<LineMap>,
     /// Bell (BEL) triggered flag - set when \x07 is received
     bell_triggered: bool,
-    /// Cursor blink enabled (CSI ?12h/l)
-    cursor_blink_enabled: bool,
     /// Color palette (256 colors) - RGB values for customization
     /// Index 0-15: standard colors, 16-255: extended palette
     color_palette: Vec<(u8, u8, u8)>,
@@ -666,11 +945,24 @@ pub struct TerminalScreen {
     interner_cleanup_counter: usize,
     /// Dirty tracking for incremental rendering
     dirty_tracker: DirtyTracker,
+    /// DCS sequence currently being received (between hook and unhook)
+    dcs_state: Option<DcsState>,
+    /// Cursor shape requested via DECSCUSR (CSI Ps SP q), 0 = terminal default
+    cursor_shape: u16,
+    /// Image decoder for inline graphics (SIXEL)
+    image_handler: image_protocol::ImageProtocol,
+    /// Size of a character cell in pixels (width, height)
+    cell_pixel_size: (u32, u32),
 }
 
 impl TerminalScreen {
     /// Create a new terminal screen
     pub fn new(cols: usize, rows: usize) -> Self {
+        Self::with_scrollback(cols, rows, ScrollbackConfig::default())
+    }
+
+    /// Create a new terminal screen with the given scrollback limits and disk tier
+    pub fn with_scrollback(cols: usize, rows: usize, scrollback: ScrollbackConfig) -> Self {
         let cols = max(1, cols);
         let rows = max(1, rows);
 
@@ -678,7 +970,8 @@ impl TerminalScreen {
             cols,
             rows,
             buffer: vec![vec![Cell::default(); cols]; rows],
-            scrollback: VecDeque::new(),
+            line_wrapped: vec![false; rows],
+            scrollback: ScrollbackBuffer::new(scrollback),
             compression_stats: CompressionStats::new(),
             cursor_row: 0,
             cursor_col: 0,
@@ -690,33 +983,50 @@ impl TerminalScreen {
             dim: false,
             italic: false,
             strikethrough: false,
+            underline_style: UnderlineStyle::Single,
+            underline_color: None,
+            blink: false,
+            hidden: false,
+            overline: false,
+            current_hyperlink: None,
+            current_hyperlink_id: None,
+            next_hyperlink_id: 0,
             parser: Parser::new(),
             scroll_region: None,
             saved_cursor: None,
             saved_cursor_state: None,
             window_title: None,
             icon_name: None,
+            icon_stack: Vec::new(),
+            title_stack: Vec::new(),
             cwd_from_shell: None,
+            host_from_shell: None,
             clipboard_request: None,
             alternate_buffer: None,
             alternate_scrollback: None,
             use_alternate_screen: false,
             alternate_saved_state: None,
-            mouse_mode: MouseMode::None,
-            mouse_encoding: MouseEncoding::Default,
-            cursor_visible: true,
-            auto_wrap_mode: true,
-            bracketed_paste_mode: false,
-            application_cursor_keys: false,
+            modes: ModeTable::default(),
+            tab_stops: default_tab_stops(cols),
+            last_printed: None,
+            last_grapheme: None,
+            charsets: CharsetState::default(),
+            keyboard_mode_stack: Vec::new(),
+            synchronized_update: None,
             pending_responses: Vec::new(),
+            pending_shell_marks: VecDeque::new(),
+            pending_line_maps: Vec::new(),
             bell_triggered: false,
-            cursor_blink_enabled: true,
             color_palette: Self::initialize_default_palette(),
             default_fg_color: None,
             default_bg_color: None,
             string_interner: StringInterner::new(),
             interner_cleanup_counter: 0,
             dirty_tracker: DirtyTracker::new(),
+            dcs_state: None,
+            cursor_shape: 0,
+            image_handler: image_protocol::ImageProtocol::new(),
+            cell_pixel_size: DEFAULT_CELL_PIXEL_SIZE,
         }
     }
 
@@ -776,6 +1086,7 @@ impl TerminalScreen {
     pub fn resize(&mut self, cols: usize, rows: usize) {
         let cols = max(1, cols);
         let rows = max(1, rows);
+        self.last_grapheme = None;
 
         if cols == self.cols && rows == self.rows {
             return;
@@ -785,28 +1096,42 @@ impl TerminalScreen {
         let old_cols = self.cols;
         let old_rows = self.rows;
 
-        // Handle column resize first (reflow lines)
-        let mut new_buffer = Vec::new();
-
-        if cols != old_cols {
-            // Reflow each line to new width
-            for row in &self.buffer {
-                let mut new_row = Vec::with_capacity(cols);
-
-                if cols > old_cols {
-                    // Columns increased: extend with empty cells
-                    new_row.extend_from_slice(&row[..old_cols]);
+        // Handle column resize first
+        let mut new_buffer;
+        let mut new_wrapped;
+
+        if cols != old_cols && !self.use_alternate_screen {
+            // Reflow soft-wrapped lines of the main screen and its scrollback
+            // Lines already spilled to disk are reflowed when paged back in
+            let reflowed = reflow::reflow_screen(
+                self.scrollback.in_memory(),
+                &self.buffer,
+                &self.line_wrapped,
+                (self.cursor_row, self.cursor_col),
+                cols,
+                self.line_offset() + self.scrollback.disk_len(),
+            );
+            self.push_line_map(reflowed.line_map);
+            self.scrollback.replace_in_memory(reflowed.scrollback, cols);
+            (self.cursor_row, self.cursor_col) = reflowed.cursor;
+            new_buffer = reflowed.buffer;
+            new_wrapped = reflowed.wrapped;
+        } else if cols != old_cols {
+            // Alternate screen applications redraw on resize: truncate or pad rows
+            new_buffer = self
+                .buffer
+                .iter()
+                .map(|row| {
+                    let mut new_row = row[..min(row.len(), cols)].to_vec();
                     new_row.resize(cols, Cell::default());
-                } else {
-                    // Columns decreased: truncate long lines
-                    new_row.extend_from_slice(&row[..cols]);
-                }
-
-                new_buffer.push(new_row);
-            }
+                    new_row
+                })
+                .collect();
+            new_wrapped = vec![false; old_rows];
         } else {
             // No column change, just copy buffer
             new_buffer = self.buffer.clone();
+            new_wrapped = self.line_wrapped.clone();
         }
 
         // Handle row resize
@@ -815,56 +1140,51 @@ impl TerminalScreen {
             let lines_to_save = old_rows - rows;
             for i in 0..lines_to_save {
                 if i < new_buffer.len() {
-                    let compressed = CompressedLine::compress(&new_buffer[i]);
+                    let compressed =
+                        CompressedLine::compress(&new_buffer[i]).with_wrapped(new_wrapped[i]);
                     self.compression_stats.update(&compressed);
-                    self.scrollback.push_back(compressed);
+                    self.scrollback.push_line(compressed);
                 }
             }
 
             // Keep only the bottom 'rows' lines
             if new_buffer.len() > rows {
                 new_buffer.drain(0..lines_to_save);
+                new_wrapped.drain(0..lines_to_save);
             }
-
-            // Limit scrollback
-            while self.scrollback.len() > MAX_SCROLLBACK {
-                self.scrollback.pop_front();
-            }
+            self.cursor_row = self.cursor_row.saturating_sub(lines_to_save);
         } else if rows > old_rows {
             // Rows increased: restore from scrollback if available
             let lines_to_restore = min(rows - old_rows, self.scrollback.len());
 
             // Restore lines from scrollback (from the end) - decompress
-            let mut restored_lines = Vec::new();
             for _ in 0..lines_to_restore {
                 if let Some(compressed_line) = self.scrollback.pop_back() {
                     let mut line = compressed_line.decompress();
-                    // Reflow restored line to new column width
-                    if cols != old_cols {
-                        line.resize(cols, Cell::default());
-                    }
-                    restored_lines.push(line);
+                    line.resize(cols, Cell::default());
+                    new_buffer.insert(0, line);
+                    new_wrapped.insert(0, compressed_line.is_wrapped());
                 }
             }
-
-            // Insert restored lines at the beginning
-            restored_lines.reverse();
-            new_buffer.splice(0..0, restored_lines);
-
-            // If still need more rows, add empty ones at the end
-            while new_buffer.len() < rows {
-                new_buffer.push(vec![Cell::default(); cols]);
-            }
+            self.cursor_row += lines_to_restore;
         }
 
         // Ensure buffer is exactly 'rows' lines
         new_buffer.resize(rows, vec![Cell::default(); cols]);
+        new_wrapped.resize(rows, false);
 
         // Update terminal state
         self.buffer = new_buffer;
+        self.line_wrapped = new_wrapped;
         self.cols = cols;
         self.rows = rows;
 
+        // Keep custom tab stops, new columns get the default stops
+        let mut tab_stops = default_tab_stops(cols);
+        let kept = min(old_cols, cols);
+        tab_stops[..kept].copy_from_slice(&self.tab_stops[..kept]);
+        self.tab_stops = tab_stops;
+
         // Adjust cursor position to be within new bounds
         self.cursor_row = min(self.cursor_row, rows.saturating_sub(1));
         self.cursor_col = min(self.cursor_col, cols.saturating_sub(1));
@@ -872,25 +1192,31 @@ impl TerminalScreen {
         // Reset scroll region as it's no longer valid
         self.scroll_region = None;
 
-        // Also handle alternate screen buffer if active
+        // Also handle the saved main screen buffer if the alternate screen is active
         if self.use_alternate_screen {
-            if let Some(ref mut alt_buffer) = self.alternate_buffer {
-                // Resize alternate buffer similarly
-                let mut resized_alt = Vec::new();
-                for row in alt_buffer.iter() {
-                    let mut new_row = Vec::with_capacity(cols);
-                    if cols > old_cols {
-                        let copy_len = min(row.len(), old_cols);
-                        new_row.extend_from_slice(&row[..copy_len]);
-                        new_row.resize(cols, Cell::default());
-                    } else {
-                        let copy_len = min(row.len(), cols);
-                        new_row.extend_from_slice(&row[..copy_len]);
-                    }
-                    resized_alt.push(new_row);
-                }
-                resized_alt.resize(rows, vec![Cell::default(); cols]);
-                *alt_buffer = resized_alt;
+            if let (Some(main_buffer), Some(main_scrollback), Some(state)) = (
+                self.alternate_buffer.as_mut(),
+                self.alternate_scrollback.as_mut(),
+                self.alternate_saved_state.as_mut(),
+            ) {
+                let mut main_wrapped = std::mem::take(&mut state.line_wrapped);
+                main_wrapped.resize(main_buffer.len(), false);
+                let mut reflowed = reflow::reflow_screen(
+                    main_scrollback.in_memory(),
+                    main_buffer,
+                    &main_wrapped,
+                    state.cursor_pos,
+                    cols,
+                    main_scrollback.dropped_lines() + main_scrollback.disk_len(),
+                );
+                reflowed.buffer.resize(rows, vec![Cell::default(); cols]);
+                reflowed.wrapped.resize(rows, false);
+
+                *main_buffer = reflowed.buffer;
+                main_scrollback.replace_in_memory(reflowed.scrollback, cols);
+                state.line_wrapped = reflowed.wrapped;
+                state.cursor_pos = reflowed.cursor;
+                self.push_line_map(reflowed.line_map);
             }
         }
 
@@ -899,15 +1225,193 @@ impl TerminalScreen {
     }
 
     /// Get all lines (scrollback + visible) for rendering
+    ///
+    /// This reads back every line spilled to disk; use
+    /// [`TerminalScreen::get_lines`] to render a window of a long history.
     pub fn get_all_lines(&self) -> Vec<Vec<Cell>> {
-        // Decompress scrollback lines lazily
-        let mut all_lines: Vec<Vec<Cell>> = self
+        self.get_lines(0..self.total_lines())
+    }
+
+    /// Get a range of lines, numbered like [`TerminalScreen::get_all_lines`]
+    pub fn get_lines(&self, range: Range<usize>) -> Vec<Vec<Cell>> {
+        let scrollback_len = self.scrollback.len();
+        let mut lines: Vec<Vec<Cell>> = self
             .scrollback
-            .iter()
+            .iter_from(range.start)
+            .take(range.end.min(scrollback_len).saturating_sub(range.start))
             .map(|compressed| compressed.decompress())
             .collect();
-        all_lines.extend(self.buffer.clone());
-        all_lines
+        let end = range
+            .end
+            .saturating_sub(scrollback_len)
+            .min(self.buffer.len());
+        let start = range.start.saturating_sub(scrollback_len).min(end);
+        lines.extend_from_slice(&self.buffer[start..end]);
+        lines
+    }
+
+    /// Text between two (line, column) positions, lines numbered like [`Self::line_offset`]
+    ///
+    /// The end column is exclusive. Soft-wrapped lines are joined, other line
+    /// breaks become `\n` with the trailing blanks of the line trimmed.
+    pub fn text_between(&self, start: (usize, usize), end: (usize, usize)) -> String {
+        let mut text = String::new();
+        if end <= start {
+            return text;
+        }
+        let offset = self.line_offset();
+        let first = start.0.saturating_sub(offset);
+        let last = end.0.saturating_sub(offset);
+        for (index, line) in (first..).zip(self.get_lines(first..last + 1)) {
+            let from = if index == first && start.0 >= offset {
+                start.1.min(line.len())
+            } else {
+                0
+            };
+            let to = if index == last {
+                end.1.min(line.len())
+            } else {
+                line.len()
+            };
+            let mut part = String::new();
+            for cell in line[from..to.max(from)]
+                .iter()
+                .filter(|cell| !cell.placeholder)
+            {
+                cell.push_grapheme(&mut part);
+            }
+            if index == last || self.is_line_wrapped(index) {
+                text.push_str(&part);
+            } else {
+                text.push_str(part.trim_end());
+                text.push('\n');
+            }
+        }
+        text
+    }
+
+    /// Total number of lines (scrollback + visible)
+    pub fn total_lines(&self) -> usize {
+        self.scrollback.len() + self.buffer.len()
+    }
+
+    /// Number of the first line held in memory; older lines are on disk
+    pub fn first_memory_line(&self) -> usize {
+        self.scrollback.disk_len()
+    }
+
+    /// Number of lines trimmed from the top of the scrollback so far
+    ///
+    /// A line's index plus this offset stays the same while older lines are
+    /// trimmed; [`BackgroundSearch`](crate::terminal::search::BackgroundSearch)
+    /// numbers its matches this way.
+    pub fn line_offset(&self) -> usize {
+        self.scrollback.dropped_lines()
+    }
+
+    /// Changes when scrollback lines are taken back from disk, see
+    /// [`BackgroundSearch::rescan`](crate::terminal::search::BackgroundSearch::rescan)
+    pub fn disk_generation(&self) -> u64 {
+        self.scrollback.disk_generation()
+    }
+
+    /// Take the lines from line number `from` (see [`Self::line_offset`]) on for a search
+    ///
+    /// Starts at the beginning of the logical line containing `from`. Visible
+    /// rows are copied, scrollback lines are read lazily by the search thread.
+    pub fn search_snapshot(&self, from: usize) -> SearchSnapshot {
+        let offset = self.line_offset();
+        let mut start = from.saturating_sub(offset).min(self.total_lines());
+        while start > 0 && self.is_line_wrapped(start - 1) {
+            start -= 1;
+        }
+
+        let scrollback_len = self.scrollback.len();
+        let rows: Vec<CompressedLine> = self
+            .buffer
+            .iter()
+            .zip(&self.line_wrapped)
+            .skip(start.saturating_sub(scrollback_len))
+            .map(|(row, &wrapped)| CompressedLine::compress(row).with_wrapped(wrapped))
+            .collect();
+        SearchSnapshot {
+            first_line: offset + start,
+            screen_top: offset + scrollback_len,
+            len: self.total_lines() - start,
+            disk_generation: self.disk_generation(),
+            lines: Box::new(self.scrollback.read_from(start).chain(rows)),
+        }
+    }
+
+    /// Take the main screen's scrollback and visible rows for session restore
+    ///
+    /// Keeps the newest `max_lines` lines and drops blank rows below the last
+    /// row with content. While the alternate screen is active the saved main
+    /// screen is taken instead, so a full-screen application is not. Visible
+    /// rows are copied, scrollback lines are read lazily by
+    /// [`ScrollbackSnapshot::encode`].
+    pub fn scrollback_snapshot(&self, max_lines: usize) -> ScrollbackSnapshot {
+        let (scrollback, buffer, line_wrapped) =
+            match (&self.alternate_scrollback, &self.alternate_saved_state) {
+                (Some(scrollback), Some(state)) if self.use_alternate_screen => {
+                    (scrollback, &state.main_buffer, &state.line_wrapped)
+                }
+                _ => (&self.scrollback, &self.buffer, &self.line_wrapped),
+            };
+
+        let used_rows = buffer
+            .iter()
+            .rposition(|row| row.iter().any(|cell| cell.c != ' ' || cell.bg.is_some()))
+            .map_or(0, |row| row + 1);
+        let visible: Vec<CompressedLine> = buffer[..used_rows]
+            .iter()
+            .zip(line_wrapped)
+            .map(|(row, &wrapped)| CompressedLine::compress(row).with_wrapped(wrapped))
+            .collect();
+
+        let skip = (scrollback.len() + visible.len()).saturating_sub(max_lines);
+        let visible_skip = skip.saturating_sub(scrollback.len());
+        ScrollbackSnapshot {
+            lines: Box::new(
+                scrollback
+                    .read_from(skip)
+                    .chain(visible.into_iter().skip(visible_skip)),
+            ),
+        }
+    }
+
+    /// Restore lines encoded by [`ScrollbackSnapshot::encode`] into the scrollback
+    ///
+    /// A dim separator line labelled `separator` is printed at the cursor so
+    /// the new shell's output starts below it. Returns the number of lines restored.
+    pub fn restore_scrollback(&mut self, data: &[u8], separator: &str) -> std::io::Result<usize> {
+        let lines = persist::decode(data, &mut self.string_interner)?;
+        let restored = lines.len();
+        for line in lines {
+            self.compression_stats.update(&line);
+            self.scrollback.push_line(line);
+        }
+
+        let label = format!("── {separator} ");
+        let fill = self.cols.saturating_sub(label.chars().count());
+        let line: String = label
+            .chars()
+            .chain(std::iter::repeat('─').take(fill))
+            .take(self.cols)
+            .collect();
+        self.process(format!("\x1b[2m{line}\x1b[0m\r\n").as_bytes());
+        self.dirty_tracker.mark_all();
+        Ok(restored)
+    }
+
+    /// Check if a line was soft-wrapped into the next line by auto-wrap
+    ///
+    /// Lines index into [`TerminalScreen::get_all_lines`] (scrollback + visible).
+    pub fn is_line_wrapped(&self, line: usize) -> bool {
+        match line.checked_sub(self.scrollback.len()) {
+            Some(row) => self.line_wrapped.get(row).copied().unwrap_or(false),
+            None => self.scrollback.is_wrapped(line),
+        }
     }
 
     /// Auto-detect URLs in all lines and update cell hyperlinks
@@ -939,16 +1443,25 @@ impl TerminalScreen {
                     if cell.placeholder {
                         continue;
                     }
-                    if char_index >= start_col && char_index < end_col {
+                    // Explicit OSC 8 links take precedence over detected URLs
+                    if char_index >= start_col
+                        && char_index < end_col
+                        && !cell
+                            .hyperlink_id
+                            .as_ref()
+                            .is_some_and(HyperlinkId::is_explicit)
+                    {
                         cell.hyperlink = Some(Arc::clone(&interned_url));
+                        cell.hyperlink_id = Some(HyperlinkId::Detected);
                     }
                     char_index += 1;
                 }
             }
         }
 
-        // Process scrollback buffer - decompress, update, recompress
-        for compressed_line in self.scrollback.iter_mut() {
+        // Process scrollback lines in memory - decompress, update, recompress
+        let string_interner = &mut self.string_interner;
+        self.scrollback.update_in_memory(|compressed_line| {
             let mut row = compressed_line.decompress();
 
             let line_text: String = row
@@ -964,15 +1477,23 @@ impl TerminalScreen {
                 let end_col = mat.end();
 
                 // Intern the URL string
-                let interned_url = self.string_interner.intern(url);
+                let interned_url = string_interner.intern(url);
 
                 let mut char_index = 0;
                 for cell in row.iter_mut() {
                     if cell.placeholder {
                         continue;
                     }
-                    if char_index >= start_col && char_index < end_col {
+                    if char_index >= start_col
+                        && char_index < end_col
+                        && !cell
+                            .hyperlink_id
+                            .as_ref()
+                            .is_some_and(HyperlinkId::is_explicit)
+                        && cell.hyperlink.as_ref() != Some(&interned_url)
+                    {
                         cell.hyperlink = Some(Arc::clone(&interned_url));
+                        cell.hyperlink_id = Some(HyperlinkId::Detected);
                         modified = true;
                     }
                     char_index += 1;
@@ -981,9 +1502,10 @@ impl TerminalScreen {
 
             // Recompress the line if modified
             if modified {
-                *compressed_line = CompressedLine::compress(&row);
+                *compressed_line =
+                    CompressedLine::compress(&row).with_wrapped(compressed_line.is_wrapped());
             }
-        }
+        });
 
         // Periodically clean up unused interned strings (every 100 calls)
         self.interner_cleanup_counter += 1;
@@ -997,6 +1519,84 @@ impl TerminalScreen {
     pub fn cursor_position(&self) -> (usize, usize) {
         (self.cursor_row, self.cursor_col)
     }
+
+    /// Set or clear the active OSC 8 hyperlink
+    ///
+    /// Links opened without an `id=` parameter get a unique anonymous ID, so only
+    /// links that share an explicit ID are grouped across lines.
+    fn set_hyperlink(&mut self, link: Option<Hyperlink>) {
+        match link {
+            Some(link) => {
+                let id = match link.id {
+                    Some(id) => HyperlinkId::Named(self.string_interner.intern(id)),
+                    None => {
+                        self.next_hyperlink_id += 1;
+                        HyperlinkId::Anonymous(self.next_hyperlink_id)
+                    }
+                };
+                self.current_hyperlink = Some(self.string_interner.intern(link.url.to_string()));
+                self.current_hyperlink_id = Some(id);
+            }
+            None => {
+                self.current_hyperlink = None;
+                self.current_hyperlink_id = None;
+            }
+        }
+    }
+
+    /// URL of the hyperlink at (row, col), rows numbered like [`TerminalScreen::get_all_lines`]
+    pub fn hyperlink_url(&self, row: usize, col: usize) -> Option<Arc<String>> {
+        self.get_lines(row..row + 1)
+            .pop()?
+            .get(col)?
+            .hyperlink
+            .clone()
+    }
+
+    /// Get all cells belonging to the same hyperlink as the cell at (row, col)
+    ///
+    /// Rows index into [`TerminalScreen::get_all_lines`]. OSC 8 cells are grouped
+    /// by link ID and URL, so a link spanning several lines in memory is returned
+    /// as one group. A detected URL covers its run of cells on the row.
+    /// Returns an empty list if the cell has no hyperlink.
+    pub fn hyperlink_cells(&self, row: usize, col: usize) -> Vec<(usize, usize)> {
+        let Some(line) = self.get_lines(row..row + 1).pop() else {
+            return Vec::new();
+        };
+        let Some(cell) = line.get(col) else {
+            return Vec::new();
+        };
+        let (Some(url), Some(id)) = (cell.hyperlink.clone(), cell.hyperlink_id.clone()) else {
+            return Vec::new();
+        };
+        let same_link = |cell: &Cell| {
+            cell.hyperlink_id.as_ref() == Some(&id) && cell.hyperlink.as_ref() == Some(&url)
+        };
+
+        let first_memory_line = self.first_memory_line();
+        if id == HyperlinkId::Detected || row < first_memory_line {
+            let start = (0..col)
+                .rev()
+                .find(|&c| !same_link(&line[c]))
+                .map_or(0, |c| c + 1);
+            let end = (col..line.len())
+                .find(|&c| !same_link(&line[c]))
+                .unwrap_or(line.len());
+            return (start..end).map(|c| (row, c)).collect();
+        }
+
+        let mut cells = Vec::new();
+        let lines = self.get_lines(first_memory_line..self.total_lines());
+        for (offset, line) in lines.iter().enumerate() {
+            for (col, cell) in line.iter().enumerate() {
+                if same_link(cell) {
+                    cells.push((first_memory_line + offset, col));
+                }
+            }
+        }
+        cells
+    }
+
     /// Enter alternate screen buffer
     ///
     /// # Arguments
@@ -1075,6 +1675,11 @@ impl TerminalScreen {
         self.cwd_from_shell.as_deref()
     }
 
+    /// Get the host name from the last OSC 7 URI (empty for `file:///path`)
+    pub fn host_from_shell(&self) -> Option<&str> {
+        self.host_from_shell.as_deref()
+    }
+
     /// Get clipboard request data (OSC 52) - reserved for future clipboard integration
     #[allow(dead_code)]
     pub fn clipboard_request(&self) -> Option<&str> {
@@ -1092,6 +1697,25 @@ impl TerminalScreen {
         self.clipboard_request.take()
     }
 
+    /// Queue a shell integration sequence, anchored at the cursor
+    fn push_shell_mark(&mut self, command: u32, params: &[&[u8]]) {
+        let data = params
+            .iter()
+            .map(|p| String::from_utf8_lossy(p))
+            .collect::<Vec<_>>()
+            .join(";");
+        if self.pending_shell_marks.len() == MAX_PENDING_SHELL_MARKS {
+            self.pending_shell_marks.pop_front();
+        }
+        self.pending_shell_marks.push_back(ShellMark {
+            command,
+            data,
+            line: self.line_offset() + self.scrollback.len() + self.cursor_row,
+            col: self.cursor_col,
+            alternate_screen: self.use_alternate_screen,
+        });
+    }
+
     /// Parse file:// URI to extract path
     fn parse_file_uri(&self, uri: &str) -> Option<String> {
         if let Some(path_part) = uri.strip_prefix("file://") {
@@ -1130,32 +1754,74 @@ impl TerminalScreen {
         None
     }
 
-    /// Get current mouse reporting mode - reserved for future mouse interaction support
-    #[allow(dead_code)]
+    /// Get current mouse reporting mode
     pub fn mouse_mode(&self) -> MouseMode {
-        self.mouse_mode
+        if self.modes.get(Mode::AnyEventMouse) {
+            MouseMode::AnyEvent
+        } else if self.modes.get(Mode::ButtonEventMouse) {
+            MouseMode::ButtonEvent
+        } else if self.modes.get(Mode::NormalMouse) {
+            MouseMode::Normal
+        } else if self.modes.get(Mode::X10Mouse) {
+            MouseMode::X10
+        } else {
+            MouseMode::None
+        }
     }
 
-    /// Get current mouse encoding mode - reserved for future mouse interaction support
-    #[allow(dead_code)]
+    /// Get current mouse encoding mode
     pub fn mouse_encoding(&self) -> MouseEncoding {
-        self.mouse_encoding
+        if self.modes.get(Mode::SgrMouse) {
+            MouseEncoding::Sgr
+        } else if self.modes.get(Mode::SgrPixelsMouse) {
+            MouseEncoding::SgrPixels
+        } else if self.modes.get(Mode::UrxvtMouse) {
+            MouseEncoding::Urxvt
+        } else if self.modes.get(Mode::Utf8Mouse) {
+            MouseEncoding::Utf8
+        } else {
+            MouseEncoding::Default
+        }
     }
 
-    /// Check if mouse reporting is enabled - reserved for future mouse interaction support
-    #[allow(dead_code)]
+    /// Check if mouse reporting is enabled
     pub fn is_mouse_reporting_enabled(&self) -> bool {
-        self.mouse_mode != MouseMode::None
+        self.mouse_mode() != MouseMode::None
+    }
+
+    /// Report a focus change to the application if it enabled focus events (?1004)
+    ///
+    /// Queues `CSI I` (focus in) or `CSI O` (focus out) in the pending responses.
+    pub fn report_focus(&mut self, focused: bool) {
+        if self.modes.get(Mode::FocusEvents) {
+            let report = if focused { "\x1b[I" } else { "\x1b[O" };
+            self.pending_responses.push(report.to_string());
+        }
     }
 
     /// Get cursor visibility state
     pub fn cursor_visible(&self) -> bool {
-        self.cursor_visible
+        self.modes.get(Mode::ShowCursor)
     }
 
     /// Check if cursor blink is enabled
     pub fn cursor_blink_enabled(&self) -> bool {
-        self.cursor_blink_enabled
+        self.modes.get(Mode::CursorBlink)
+    }
+
+    /// Get cursor shape requested via DECSCUSR (0 = terminal default)
+    pub fn cursor_shape(&self) -> u16 {
+        self.cursor_shape
+    }
+
+    /// Get the size of a character cell in pixels (width, height)
+    pub fn cell_pixel_size(&self) -> (u32, u32) {
+        self.cell_pixel_size
+    }
+
+    /// Set the size of a character cell in pixels, used to lay out images
+    pub fn set_cell_pixel_size(&mut self, width: u32, height: u32) {
+        self.cell_pixel_size = (max(1, width), max(1, height));
     }
 
     /// Get the color palette entry
@@ -1183,18 +1849,70 @@ impl TerminalScreen {
     /// Get auto-wrap mode state - reserved for future line wrapping logic enhancement
     #[allow(dead_code)]
     pub fn auto_wrap_mode(&self) -> bool {
-        self.auto_wrap_mode
+        self.modes.get(Mode::AutoWrap)
     }
 
     /// Get bracketed paste mode state - reserved for future paste handling
     #[allow(dead_code)]
     pub fn bracketed_paste_mode(&self) -> bool {
-        self.bracketed_paste_mode
+        self.modes.get(Mode::BracketedPaste)
+    }
+
+    /// Check if a synchronized update (mode 2026) is in progress
+    ///
+    /// Rendering should be held back while this returns true. An update that is
+    /// never closed stops counting after [`SYNCHRONIZED_UPDATE_TIMEOUT`].
+    pub fn is_synchronized_update(&self) -> bool {
+        self.synchronized_update
+            .is_some_and(|start| start.elapsed() < SYNCHRONIZED_UPDATE_TIMEOUT)
     }
 
     /// Get application cursor keys mode state (DECCKM)
     pub fn application_cursor_keys(&self) -> bool {
-        self.application_cursor_keys
+        self.modes.get(Mode::ApplicationCursorKeys)
+    }
+
+    /// Check if an ANSI or DEC private mode is set
+    pub fn is_mode_set(&self, mode: Mode) -> bool {
+        self.modes.get(mode)
+    }
+
+    /// Get the active kitty keyboard protocol flags (0 = legacy encoding)
+    pub fn keyboard_flags(&self) -> u8 {
+        self.keyboard_mode_stack.last().copied().unwrap_or(0)
+    }
+
+    /// Push kitty keyboard flags (CSI > flags u), evicting the oldest entry when full
+    fn push_keyboard_flags(&mut self, flags: u16) {
+        if self.keyboard_mode_stack.len() >= MAX_KEYBOARD_MODE_STACK {
+            self.keyboard_mode_stack.remove(0);
+        }
+        self.keyboard_mode_stack
+            .push(flags as u8 & keyboard::ALL_FLAGS);
+    }
+
+    /// Pop `n` entries from the kitty keyboard flag stack (CSI < n u)
+    fn pop_keyboard_flags(&mut self, n: usize) {
+        let len = self.keyboard_mode_stack.len().saturating_sub(n);
+        self.keyboard_mode_stack.truncate(len);
+    }
+
+    /// Modify the current kitty keyboard flags (CSI = flags ; mode u)
+    ///
+    /// Mode 1 replaces the flags, 2 sets the given bits and 3 clears them.
+    fn set_keyboard_flags(&mut self, flags: u16, mode: u16) {
+        let flags = flags as u8 & keyboard::ALL_FLAGS;
+        let current = self.keyboard_flags();
+        let new = match mode {
+            1 => flags,
+            2 => current | flags,
+            3 => current & !flags,
+            _ => return,
+        };
+        match self.keyboard_mode_stack.last_mut() {
+            Some(top) => *top = new,
+            None => self.keyboard_mode_stack.push(new),
+        }
     }
 
     /// Take pending responses (for sending to PTY)
@@ -1203,6 +1921,31 @@ impl TerminalScreen {
         std::mem::take(&mut self.pending_responses)
     }
 
+    /// Take the shell integration marks received since the last call, oldest first
+    pub fn take_shell_marks(&mut self) -> Vec<ShellMark> {
+        self.pending_shell_marks.drain(..).collect()
+    }
+
+    /// Take the reflows of the main screen since the last call, oldest first
+    ///
+    /// Positions taken before, e.g. from [`TerminalScreen::take_shell_marks`],
+    /// follow their text through each map in turn.
+    pub fn take_line_maps(&mut self) -> Vec<LineMap> {
+        std::mem::take(&mut self.pending_line_maps)
+    }
+
+    /// Queue a reflow of the main screen, moving the marks not taken yet along
+    fn push_line_map(&mut self, line_map: LineMap) {
+        for mark in self
+            .pending_shell_marks
+            .iter_mut()
+            .filter(|mark| !mark.alternate_screen)
+        {
+            (mark.line, mark.col) = line_map.position(mark.line, mark.col);
+        }
+        self.pending_line_maps.push(line_map);
+    }
+
     /// Get memory usage statistics for the terminal screen
     pub fn memory_stats(&self) -> MemoryStats {
         let buffer_bytes = self
@@ -1211,11 +1954,7 @@ impl TerminalScreen {
             .map(|line| memory::line_memory_size(line))
             .sum();
 
-        let scrollback_bytes: usize = self
-            .scrollback
-            .iter()
-            .map(|compressed| compressed.compressed_size)
-            .sum();
+        let scrollback_bytes = self.scrollback.memory_usage();
 
         let interner_bytes = self.string_interner.memory_usage();
         let (interned_strings, interner_hits, interner_misses) = self.string_interner.stats();
@@ -1252,21 +1991,21 @@ impl TerminalScreen {
         for _ in 0..n {
             // Save top line to scrollback (compressed)
             if top == 0 {
-                let compressed = CompressedLine::compress(&self.buffer[top]);
+                let compressed = CompressedLine::compress(&self.buffer[top])
+                    .with_wrapped(self.line_wrapped[top]);
                 self.compression_stats.update(&compressed);
-                self.scrollback.push_back(compressed);
-                if self.scrollback.len() > MAX_SCROLLBACK {
-                    self.scrollback.pop_front();
-                }
+                self.scrollback.push_line(compressed);
             }
 
             // Shift lines up within scroll region
             for row in top..bottom {
                 self.buffer[row] = self.buffer[row + 1].clone();
+                self.line_wrapped[row] = self.line_wrapped[row + 1];
             }
 
             // Clear bottom line
             self.buffer[bottom] = vec![Cell::default(); self.cols];
+            self.line_wrapped[bottom] = false;
         }
 
         // Mark the affected region as dirty
@@ -1281,10 +2020,12 @@ impl TerminalScreen {
             // Shift lines down within scroll region
             for row in (top + 1..=bottom).rev() {
                 self.buffer[row] = self.buffer[row - 1].clone();
+                self.line_wrapped[row] = self.line_wrapped[row - 1];
             }
 
             // Clear top line
             self.buffer[top] = vec![Cell::default(); self.cols];
+            self.line_wrapped[top] = false;
         }
 
         // Mark the affected region as dirty
@@ -1309,10 +2050,12 @@ impl TerminalScreen {
             // Shift lines down from cursor to bottom
             for row in ((cursor_row + 1)..=bottom).rev() {
                 self.buffer[row] = self.buffer[row - 1].clone();
+                self.line_wrapped[row] = self.line_wrapped[row - 1];
             }
 
             // Insert blank line at cursor position
             self.buffer[cursor_row] = vec![Cell::default(); self.cols];
+            self.line_wrapped[cursor_row] = false;
         }
     }
 
@@ -1334,10 +2077,12 @@ impl TerminalScreen {
             // Shift lines up from cursor to bottom
             for row in cursor_row..bottom {
                 self.buffer[row] = self.buffer[row + 1].clone();
+                self.line_wrapped[row] = self.line_wrapped[row + 1];
             }
 
             // Clear the bottom line
             self.buffer[bottom] = vec![Cell::default(); self.cols];
+            self.line_wrapped[bottom] = false;
         }
     }
 
@@ -1364,10 +2109,41 @@ impl TerminalScreen {
         }
     }
 
-    /// Tab (move to next tab stop, assume every 8 columns)
+    /// Tab (move to next tab stop)
     fn tab(&mut self) {
-        let next_tab = ((self.cursor_col / 8) + 1) * 8;
-        self.cursor_col = min(next_tab, self.cols - 1);
+        self.tab_forward(1);
+    }
+
+    /// Move forward n tab stops (CHT), stopping at the right margin
+    fn tab_forward(&mut self, n: usize) {
+        for _ in 0..min(n, self.cols) {
+            self.cursor_col = (self.cursor_col + 1..self.cols)
+                .find(|&col| self.tab_stops[col])
+                .unwrap_or(self.cols - 1);
+        }
+    }
+
+    /// Move backward n tab stops (CBT), stopping at the left margin
+    fn tab_backward(&mut self, n: usize) {
+        for _ in 0..min(n, self.cols) {
+            self.cursor_col = (0..min(self.cursor_col, self.cols))
+                .rev()
+                .find(|&col| self.tab_stops[col])
+                .unwrap_or(0);
+        }
+    }
+
+    /// Tab Clear (TBC) - 0 clears the stop at the cursor, 3 clears all stops
+    fn clear_tab_stops(&mut self, mode: u16) {
+        match mode {
+            0 => {
+                if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+                    *stop = false;
+                }
+            }
+            3 => self.tab_stops.fill(false),
+            _ => {}
+        }
     }
 
     /// Erase in display (ED)
@@ -1379,9 +2155,11 @@ impl TerminalScreen {
                 for col in self.cursor_col..self.cols {
                     self.buffer[self.cursor_row][col] = Cell::default();
                 }
+                self.line_wrapped[self.cursor_row] = false;
                 // Clear all lines below
                 for row in (self.cursor_row + 1)..self.rows {
                     self.buffer[row] = vec![Cell::default(); self.cols];
+                    self.line_wrapped[row] = false;
                 }
             }
             1 => {
@@ -1389,6 +2167,7 @@ impl TerminalScreen {
                 // Clear all lines above
                 for row in 0..self.cursor_row {
                     self.buffer[row] = vec![Cell::default(); self.cols];
+                    self.line_wrapped[row] = false;
                 }
                 // Clear current line from beginning to cursor
                 for col in 0..=self.cursor_col {
@@ -1399,6 +2178,7 @@ impl TerminalScreen {
                 // Clear entire screen (3 also clears scrollback)
                 for row in 0..self.rows {
                     self.buffer[row] = vec![Cell::default(); self.cols];
+                    self.line_wrapped[row] = false;
                 }
                 if mode == 3 {
                     self.scrollback.clear();
@@ -1416,6 +2196,7 @@ impl TerminalScreen {
                 for col in self.cursor_col..self.cols {
                     self.buffer[self.cursor_row][col] = Cell::default();
                 }
+                self.line_wrapped[self.cursor_row] = false;
             }
             1 => {
                 // Clear from beginning to cursor
@@ -1426,11 +2207,25 @@ impl TerminalScreen {
             2 => {
                 // Clear entire line
                 self.buffer[self.cursor_row] = vec![Cell::default(); self.cols];
+                self.line_wrapped[self.cursor_row] = false;
             }
             _ => {}
         }
     }
 
+    /// Erase characters (ECH) - CSI n X
+    /// Erase n characters from the cursor position without moving the cursor
+    fn erase_characters(&mut self, n: usize) {
+        if self.cursor_row >= self.rows || self.cursor_col >= self.cols {
+            return;
+        }
+
+        let end = min(self.cols, self.cursor_col + max(1, n));
+        for col in self.cursor_col..end {
+            self.buffer[self.cursor_row][col] = Cell::default();
+        }
+    }
+
     /// Insert characters (ICH) - CSI n @
     /// Insert n blank characters at cursor position, shifting existing characters to the right
     fn insert_characters(&mut self, n: usize) {
@@ -1475,6 +2270,66 @@ impl TerminalScreen {
         }
     }
 
+    /// Set or reset a mode and apply its side effects (SM/RM, DECSET/DECRST)
+    fn set_mode(&mut self, mode: Mode, enabled: bool) {
+        // Mouse tracking and alternate screen modes replace each other
+        for &other in mode.group() {
+            self.modes.set(other, false);
+        }
+        self.modes.set(mode, enabled);
+
+        match mode {
+            Mode::Origin => {
+                // DECOM homes the cursor to the top of its new addressing area
+                self.cursor_row = self.origin_bounds().0;
+                self.cursor_col = 0;
+            }
+            Mode::ReverseScreen => self.dirty_tracker.mark_all(),
+            Mode::AlternateScreen
+            | Mode::AlternateScreenClear
+            | Mode::AlternateScreenSaveCursor
+                if !enabled =>
+            {
+                // All three modes restore the main screen completely
+                self.switch_to_normal_screen();
+            }
+            // CSI ?47h - basic switch used by some older applications
+            Mode::AlternateScreen => self.switch_to_alternate_screen(false, false),
+            // CSI ?1047h - switch and clear
+            Mode::AlternateScreenClear => self.switch_to_alternate_screen(true, false),
+            // CSI ?1049h - save cursor, switch and clear (vim, less, htop, etc.)
+            Mode::AlternateScreenSaveCursor => self.switch_to_alternate_screen(true, true),
+            Mode::SynchronizedOutput if enabled => {
+                self.synchronized_update = Some(Instant::now());
+            }
+            Mode::SynchronizedOutput => {
+                self.synchronized_update = None;
+                self.dirty_tracker.mark_all();
+            }
+            _ => {}
+        }
+    }
+
+    /// DECRPM state of a mode: 1 = set, 2 = reset
+    ///
+    /// A synchronized update that outlived its timeout reports as reset,
+    /// matching what the renderer does with it.
+    fn report_mode(&self, mode: Mode) -> u8 {
+        match mode {
+            Mode::SynchronizedOutput if !self.is_synchronized_update() => 2,
+            _ => self.modes.report(mode),
+        }
+    }
+
+    /// Rows the cursor can be addressed in (the scroll region in origin mode)
+    fn origin_bounds(&self) -> (usize, usize) {
+        if self.modes.get(Mode::Origin) {
+            self.scroll_region.unwrap_or((0, self.rows - 1))
+        } else {
+            (0, self.rows - 1)
+        }
+    }
+
     /// Switch to alternate screen buffer (CSI ?47h, ?1047h, ?1049h).
     ///
     /// Saves complete main screen state including:
@@ -1489,7 +2344,8 @@ impl TerminalScreen {
         if !self.use_alternate_screen {
             // Save complete main screen state
             self.alternate_buffer = Some(self.buffer.clone());
-            self.alternate_scrollback = Some(self.scrollback.clone());
+            let scrollback = ScrollbackBuffer::new(self.scrollback.config().clone());
+            self.alternate_scrollback = Some(std::mem::replace(&mut self.scrollback, scrollback));
             self.alternate_saved_state = Some(AlternateScreenState {
                 main_buffer: self.buffer.clone(),
                 cursor_pos: (self.cursor_row, self.cursor_col),
@@ -1501,10 +2357,17 @@ impl TerminalScreen {
                 dim: self.dim,
                 italic: self.italic,
                 strikethrough: self.strikethrough,
+                underline_style: self.underline_style,
+                underline_color: self.underline_color,
+                blink: self.blink,
+                hidden: self.hidden,
+                overline: self.overline,
                 scroll_region: self.scroll_region,
                 saved_cursor: self.saved_cursor,
                 saved_cursor_state: self.saved_cursor_state,
-                saved_scrollback: self.scrollback.clone(),
+                line_wrapped: self.line_wrapped.clone(),
+                charsets: self.charsets,
+                keyboard_mode_stack: std::mem::take(&mut self.keyboard_mode_stack),
                 is_active: true,
                 scrollback_disabled: true,
             });
@@ -1519,7 +2382,7 @@ impl TerminalScreen {
                 self.buffer = vec![vec![Cell::default(); self.cols]; self.rows];
             }
 
-            self.scrollback = VecDeque::new();
+            self.line_wrapped = vec![false; self.rows];
 
             // Reset cursor to 0,0 only for ?1049h mode (save_cursor=true)
             if save_cursor {
@@ -1539,6 +2402,12 @@ impl TerminalScreen {
             self.dim = false;
             self.italic = false;
             self.strikethrough = false;
+            self.underline_style = UnderlineStyle::Single;
+            self.underline_color = None;
+            self.blink = false;
+            self.hidden = false;
+            self.overline = false;
+            self.charsets = CharsetState::default();
 
             self.use_alternate_screen = true;
         }
@@ -1552,6 +2421,10 @@ impl TerminalScreen {
     /// - Scroll region
     /// - DECSC saved cursor state
     fn switch_to_normal_screen(&mut self) {
+        for &mode in Mode::AlternateScreen.group() {
+            self.modes.set(mode, false);
+        }
+
         if self.use_alternate_screen {
             // Restore buffer and scrollback
             if let Some(saved_buffer) = self.alternate_buffer.take() {
@@ -1563,6 +2436,8 @@ impl TerminalScreen {
 
             // Restore complete terminal state
             if let Some(state) = self.alternate_saved_state.take() {
+                self.line_wrapped = state.line_wrapped;
+                self.line_wrapped.resize(self.buffer.len(), false);
                 self.cursor_row = min(state.cursor_pos.0, self.rows.saturating_sub(1));
                 self.cursor_col = min(state.cursor_pos.1, self.cols.saturating_sub(1));
                 self.current_fg = state.current_fg;
@@ -1573,9 +2448,16 @@ impl TerminalScreen {
                 self.dim = state.dim;
                 self.italic = state.italic;
                 self.strikethrough = state.strikethrough;
+                self.underline_style = state.underline_style;
+                self.underline_color = state.underline_color;
+                self.blink = state.blink;
+                self.hidden = state.hidden;
+                self.overline = state.overline;
                 self.scroll_region = state.scroll_region;
                 self.saved_cursor = state.saved_cursor;
                 self.saved_cursor_state = state.saved_cursor_state;
+                self.charsets = state.charsets;
+                self.keyboard_mode_stack = state.keyboard_mode_stack;
             }
 
             self.use_alternate_screen = false;
@@ -1594,50 +2476,53 @@ impl TerminalScreen {
                     self.current_fg = None;
                     self.current_bg = None;
                     self.bold = false;
-                    self.underline = false;
+                    self.set_underline(None);
+                    self.underline_color = None;
                     self.reverse = false;
                     self.dim = false;
                     self.italic = false;
                     self.strikethrough = false;
+                    self.blink = false;
+                    self.hidden = false;
+                    self.overline = false;
                 }
                 1 => self.bold = true,
                 2 => self.dim = true,
                 3 => self.italic = true,
-                4 => self.underline = true,
+                4 => {
+                    // Underline, optionally with a style subparameter (SGR 4:x)
+                    let style = match param.get(1).copied().unwrap_or(1) {
+                        0 => None,
+                        2 => Some(UnderlineStyle::Double),
+                        3 => Some(UnderlineStyle::Curly),
+                        4 => Some(UnderlineStyle::Dotted),
+                        5 => Some(UnderlineStyle::Dashed),
+                        _ => Some(UnderlineStyle::Single),
+                    };
+                    self.set_underline(style);
+                }
+                5 | 6 => self.blink = true, // Slow and rapid blink
                 7 => self.reverse = true,
+                8 => self.hidden = true,
                 9 => self.strikethrough = true,
+                21 => self.set_underline(Some(UnderlineStyle::Double)),
                 22 => {
                     // Normal intensity (not bold and not dim)
                     self.bold = false;
                     self.dim = false;
                 }
                 23 => self.italic = false,
-                24 => self.underline = false,
+                24 => self.set_underline(None),
+                25 => self.blink = false,
                 27 => self.reverse = false,
+                28 => self.hidden = false,
                 29 => self.strikethrough = false,
                 // Foreground colors (30-37, 90-97)
                 30..=37 => self.current_fg = Some(AnsiColor::Indexed((value - 30) as u8)),
                 38 => {
                     // Extended foreground color
-                    if let Some(next) = iter.next() {
-                        match next[0] {
-                            2 => {
-                                // RGB
-                                if let (Some(r), Some(g), Some(b)) =
-                                    (iter.next(), iter.next(), iter.next())
-                                {
-                                    self.current_fg =
-                                        Some(AnsiColor::Rgb(r[0] as u8, g[0] as u8, b[0] as u8));
-                                }
-                            }
-                            5 => {
-                                // 256-color palette
-                                if let Some(idx) = iter.next() {
-                                    self.current_fg = Some(AnsiColor::Palette256(idx[0] as u8));
-                                }
-                            }
-                            _ => {}
-                        }
+                    if let Some(color) = parse_sgr_extended_color(param, &mut iter) {
+                        self.current_fg = Some(color);
                     }
                 }
                 39 => self.current_fg = None, // Default foreground
@@ -1645,28 +2530,20 @@ impl TerminalScreen {
                 40..=47 => self.current_bg = Some(AnsiColor::Indexed((value - 40) as u8)),
                 48 => {
                     // Extended background color
-                    if let Some(next) = iter.next() {
-                        match next[0] {
-                            2 => {
-                                // RGB
-                                if let (Some(r), Some(g), Some(b)) =
-                                    (iter.next(), iter.next(), iter.next())
-                                {
-                                    self.current_bg =
-                                        Some(AnsiColor::Rgb(r[0] as u8, g[0] as u8, b[0] as u8));
-                                }
-                            }
-                            5 => {
-                                // 256-color palette
-                                if let Some(idx) = iter.next() {
-                                    self.current_bg = Some(AnsiColor::Palette256(idx[0] as u8));
-                                }
-                            }
-                            _ => {}
-                        }
+                    if let Some(color) = parse_sgr_extended_color(param, &mut iter) {
+                        self.current_bg = Some(color);
                     }
                 }
                 49 => self.current_bg = None, // Default background
+                53 => self.overline = true,
+                55 => self.overline = false,
+                58 => {
+                    // Underline color
+                    if let Some(color) = parse_sgr_extended_color(param, &mut iter) {
+                        self.underline_color = Some(color);
+                    }
+                }
+                59 => self.underline_color = None, // Default underline color
                 // Bright foreground colors (90-97)
                 90..=97 => self.current_fg = Some(AnsiColor::Indexed((value - 90 + 8) as u8)),
                 // Bright background colors (100-107)
@@ -1675,18 +2552,337 @@ impl TerminalScreen {
             }
         }
     }
+
+    /// Turn the underline on with a style, or off with `None`
+    fn set_underline(&mut self, style: Option<UnderlineStyle>) {
+        self.underline = style.is_some();
+        self.underline_style = style.unwrap_or_default();
+    }
+
+    /// Handle an XTWINOPS request (`CSI op ; arg t`)
+    ///
+    /// Only reports and the title stack are supported, requests to move,
+    /// resize or iconify the window are ignored. Title reports (20, 21) are
+    /// not answered since they let programs inject text into the input.
+    fn window_op(&mut self, op: u16, arg: u16) {
+        let (cell_width, cell_height) = self.cell_pixel_size;
+        match op {
+            14 => {
+                // Report text area size in pixels: CSI 4 ; height ; width t
+                let height = self.rows as u32 * cell_height;
+                let width = self.cols as u32 * cell_width;
+                self.pending_responses
+                    .push(format!("\x1b[4;{height};{width}t"));
+            }
+            16 => {
+                // Report character cell size in pixels: CSI 6 ; height ; width t
+                self.pending_responses
+                    .push(format!("\x1b[6;{cell_height};{cell_width}t"));
+            }
+            18 | 19 => {
+                // Report text area (18) or screen (19) size in characters
+                let kind = if op == 18 { 8 } else { 9 };
+                self.pending_responses
+                    .push(format!("\x1b[{kind};{};{}t", self.rows, self.cols));
+            }
+            22 => {
+                // Push icon name (1), window title (2) or both (0)
+                if matches!(arg, 0 | 1) {
+                    push_bounded(&mut self.icon_stack, self.icon_name.clone());
+                }
+                if matches!(arg, 0 | 2) {
+                    push_bounded(&mut self.title_stack, self.window_title.clone());
+                }
+            }
+            23 => {
+                // Pop icon name (1), window title (2) or both (0)
+                if matches!(arg, 0 | 1) {
+                    if let Some(icon) = self.icon_stack.pop() {
+                        self.icon_name = icon;
+                    }
+                }
+                if matches!(arg, 0 | 2) {
+                    if let Some(title) = self.title_stack.pop() {
+                        self.window_title = title;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Append `c` to the last printed cell if it continues its grapheme cluster
+    ///
+    /// Returns false if `c` starts a new cluster and has to be printed normally.
+    /// The cell is widened or narrowed when the cluster width changes (VS16,
+    /// VS15, regional indicator pairs).
+    fn join_grapheme(&mut self, c: char) -> bool {
+        let Some((row, col)) = self.last_grapheme else {
+            return false;
+        };
+        if row >= self.rows || col >= self.cols {
+            return false;
+        }
+
+        let cell = &self.buffer[row][col];
+        let mut cluster = String::new();
+        cell.push_grapheme(&mut cluster);
+        if !grapheme::extends_cluster(&cluster, c) {
+            return false;
+        }
+        cluster.push(c);
+
+        let old_width = if cell.wide { 2 } else { 1 };
+        let width = grapheme::cluster_width(&cluster);
+        let extra = cluster.split_off(cell.c.len_utf8());
+        self.buffer[row][col].extra = Some(self.string_interner.intern(extra));
+
+        if width == 2 && old_width == 1 && col + 1 < self.cols {
+            // Widen: the next column becomes the placeholder
+            if self.buffer[row][col + 1].wide && col + 2 < self.cols {
+                self.buffer[row][col + 2] = Cell::default();
+            }
+            self.buffer[row][col].wide = true;
+            self.buffer[row][col + 1] = Cell {
+                placeholder: true,
+                ..self.attribute_cell(' ')
+            };
+            self.cursor_col = min(col + 2, self.cols);
+        } else if width == 1 && old_width == 2 {
+            // Narrow: release the placeholder column
+            self.buffer[row][col].wide = false;
+            if col + 1 < self.cols {
+                self.buffer[row][col + 1] = self.attribute_cell(' ');
+            }
+            self.cursor_col = col + 1;
+        }
+
+        self.dirty_tracker.mark_line(row);
+        true
+    }
+
+    /// Build a cell for `c` with the current text attributes and hyperlink
+    fn attribute_cell(&self, c: char) -> Cell {
+        Cell {
+            c,
+            extra: None,
+            fg: self.current_fg,
+            bg: self.current_bg,
+            bold: self.bold,
+            underline: self.underline,
+            underline_style: self.underline_style,
+            underline_color: self.underline_color,
+            reverse: self.reverse,
+            dim: self.dim,
+            italic: self.italic,
+            strikethrough: self.strikethrough,
+            blink: self.blink,
+            hidden: self.hidden,
+            overline: self.overline,
+            wide: false,
+            placeholder: false,
+            hyperlink: self.current_hyperlink.clone(),
+            hyperlink_id: self.current_hyperlink_id.clone(),
+            image: None,
+        }
+    }
+
+    /// Build the SGR parameter string describing the current text attributes
+    ///
+    /// Used for DECRQSS replies, e.g. `0;1;31` for bold red text.
+    fn sgr_report(&self) -> String {
+        let mut parts = vec!["0".to_string()];
+        if self.bold {
+            parts.push("1".to_string());
+        }
+        if self.dim {
+            parts.push("2".to_string());
+        }
+        if self.italic {
+            parts.push("3".to_string());
+        }
+        if self.underline {
+            parts.push(match self.underline_style {
+                UnderlineStyle::Single => "4".to_string(),
+                UnderlineStyle::Double => "4:2".to_string(),
+                UnderlineStyle::Curly => "4:3".to_string(),
+                UnderlineStyle::Dotted => "4:4".to_string(),
+                UnderlineStyle::Dashed => "4:5".to_string(),
+            });
+        }
+        if self.blink {
+            parts.push("5".to_string());
+        }
+        if self.reverse {
+            parts.push("7".to_string());
+        }
+        if self.hidden {
+            parts.push("8".to_string());
+        }
+        if self.strikethrough {
+            parts.push("9".to_string());
+        }
+        if self.overline {
+            parts.push("53".to_string());
+        }
+        if let Some(fg) = self.current_fg {
+            parts.push(sgr_color_param(fg, 30, 90, 38));
+        }
+        if let Some(bg) = self.current_bg {
+            parts.push(sgr_color_param(bg, 40, 100, 48));
+        }
+        if let Some(color) = self.underline_color {
+            parts.push(match color {
+                AnsiColor::Indexed(idx) | AnsiColor::Palette256(idx) => format!("58;5;{idx}"),
+                AnsiColor::Rgb(r, g, b) => format!("58;2;{r};{g};{b}"),
+            });
+        }
+        parts.join(";")
+    }
+
+    /// Dispatch a completed DCS sequence
+    fn finish_dcs(&mut self, state: DcsState) {
+        if state.overflowed {
+            tracing::debug!(kind = ?state.kind, "Dropping oversized DCS payload");
+            return;
+        }
+
+        let payload = match state.kind {
+            DcsKind::Sixel => Default::default(),
+            _ => String::from_utf8_lossy(&state.payload),
+        };
+        match state.kind {
+            DcsKind::RequestStatusString => self.report_status_string(&payload),
+            DcsKind::RequestTermcap => self.report_termcap(&payload),
+            DcsKind::Sixel => self.display_sixel(&state.params, &state.payload),
+            DcsKind::Unsupported => {
+                tracing::debug!(params = ?state.params, "Ignoring unsupported DCS sequence");
+            }
+        }
+    }
+
+    /// Decode a SIXEL image and place it into cells at the cursor
+    ///
+    /// The image is split into cell-sized RGBA tiles stored in `Cell::image`.
+    /// Like xterm with sixel scrolling enabled, the screen scrolls as needed and
+    /// the cursor ends up on the line below the image, in the starting column.
+    fn display_sixel(&mut self, params: &[u16], data: &[u8]) {
+        // Cells own their tiles, so the image is not kept in the handler cache
+        let image = match self.image_handler.decode_sixel_dcs(params, data) {
+            Ok(image) => image,
+            Err(e) => {
+                tracing::debug!(error = %e, "Failed to decode SIXEL image");
+                return;
+            }
+        };
+
+        let Some((width, height)) = image.dimensions else {
+            return;
+        };
+        let (cell_width, cell_height) = self.cell_pixel_size;
+        let image_cols = width.div_ceil(cell_width) as usize;
+        let image_rows = height.div_ceil(cell_height) as usize;
+        let start_col = min(self.cursor_col, self.cols - 1);
+
+        for tile_row in 0..image_rows {
+            if tile_row > 0 {
+                self.new_line();
+            }
+            for tile_col in 0..image_cols {
+                let col = start_col + tile_col;
+                if col >= self.cols {
+                    break;
+                }
+                let tile = crop_rgba(
+                    &image.data,
+                    width,
+                    height,
+                    tile_col as u32 * cell_width,
+                    tile_row as u32 * cell_height,
+                    cell_width,
+                    cell_height,
+                );
+                self.buffer[self.cursor_row][col] = Cell {
+                    image: Some(ImageData {
+                        width: cell_width,
+                        height: cell_height,
+                        data: tile,
+                        protocol: ImageProtocol::Sixel,
+                        id: image.placement_id,
+                    }),
+                    ..Cell::default()
+                };
+            }
+            self.dirty_tracker.mark_line(self.cursor_row);
+        }
+
+        self.new_line();
+        self.cursor_col = start_col;
+    }
+
+    /// Answer a DECRQSS request (DCS $ q Pt ST)
+    ///
+    /// Valid requests are answered with `DCS 1 $ r <setting> ST`, unknown ones
+    /// with `DCS 0 $ r ST`.
+    fn report_status_string(&mut self, setting: &str) {
+        let reply = match setting {
+            "m" => Some(format!("{}m", self.sgr_report())),
+            "r" => {
+                let (top, bottom) = self.scroll_region.unwrap_or((0, self.rows - 1));
+                Some(format!("{};{}r", top + 1, bottom + 1))
+            }
+            " q" => Some(format!("{} q", self.cursor_shape)),
+            _ => None,
+        };
+
+        let response = match reply {
+            Some(reply) => format!("\x1bP1$r{reply}\x1b\\"),
+            None => "\x1bP0$r\x1b\\".to_string(),
+        };
+        self.pending_responses.push(response);
+    }
+
+    /// Answer an XTGETTCAP request (DCS + q <hex>;<hex>... ST)
+    ///
+    /// Each requested capability is answered separately with
+    /// `DCS 1 + r <hex-name>=<hex-value> ST`, or `DCS 0 + r <hex-name> ST`
+    /// when the capability is unknown.
+    fn report_termcap(&mut self, names: &str) {
+        for hex_name in names.split(';').filter(|n| !n.is_empty()) {
+            let value = dcs::hex_decode(hex_name)
+                .and_then(|bytes| String::from_utf8(bytes).ok())
+                .and_then(|name| dcs::termcap_value(&name));
+
+            let response = match value {
+                Some("") => format!("\x1bP1+r{hex_name}\x1b\\"),
+                Some(value) => format!(
+                    "\x1bP1+r{hex_name}={}\x1b\\",
+                    dcs::hex_encode(value.as_bytes())
+                ),
+                None => format!("\x1bP0+r{hex_name}\x1b\\"),
+            };
+            self.pending_responses.push(response);
+        }
+    }
 }
 
 impl Perform for TerminalScreen {
     fn print(&mut self, c: char) {
+        let c = self.charsets.translate(c);
+        if self.join_grapheme(c) {
+            return;
+        }
+        self.last_printed = Some(c);
+
         // Get character width (1 for regular, 2 for wide characters like CJK)
         let width = UnicodeWidthChar::width(c).unwrap_or(1);
 
         // Handle line wrapping based on auto_wrap_mode
         // For wide characters, check if we have enough space (need 2 columns)
         if self.cursor_col >= self.cols || (width == 2 && self.cursor_col >= self.cols - 1) {
-            if self.auto_wrap_mode {
-                // Auto-wrap enabled: move to next line
+            if self.modes.get(Mode::AutoWrap) {
+                // Auto-wrap enabled: mark the row as soft-wrapped and move to next line
+                self.line_wrapped[self.cursor_row] = true;
                 self.cursor_col = 0;
                 self.new_line();
             } else {
@@ -1701,6 +2897,11 @@ impl Perform for TerminalScreen {
 
         // Write character
         if self.cursor_row < self.rows && self.cursor_col < self.cols {
+            // Insert mode (IRM) shifts the rest of the line right instead of overwriting
+            if self.modes.get(Mode::Insert) {
+                self.insert_characters(width);
+            }
+
             // If this is overwriting a wide character placeholder, clear the wide char too
             if self.buffer[self.cursor_row][self.cursor_col].placeholder && self.cursor_col > 0 {
                 self.buffer[self.cursor_row][self.cursor_col - 1] = Cell::default();
@@ -1712,61 +2913,26 @@ impl Perform for TerminalScreen {
                 self.buffer[self.cursor_row][self.cursor_col + 1] = Cell::default();
             }
 
+            self.last_grapheme = Some((self.cursor_row, self.cursor_col));
             if width == 2 {
                 // Wide character: write to first cell and placeholder to second cell
                 self.buffer[self.cursor_row][self.cursor_col] = Cell {
-                    c,
-                    fg: self.current_fg,
-                    bg: self.current_bg,
-                    bold: self.bold,
-                    underline: self.underline,
-                    reverse: self.reverse,
-                    dim: self.dim,
-                    italic: self.italic,
-                    strikethrough: self.strikethrough,
                     wide: true,
-                    placeholder: false,
-                    hyperlink: None,
-                    image: None,
+                    ..self.attribute_cell(c)
                 };
 
                 // Write placeholder to next cell if there's space
                 if self.cursor_col + 1 < self.cols {
                     self.buffer[self.cursor_row][self.cursor_col + 1] = Cell {
-                        c: ' ', // Placeholder character
-                        fg: self.current_fg,
-                        bg: self.current_bg,
-                        bold: self.bold,
-                        underline: self.underline,
-                        reverse: self.reverse,
-                        dim: self.dim,
-                        italic: self.italic,
-                        strikethrough: self.strikethrough,
-                        wide: false,
                         placeholder: true,
-                        hyperlink: None,
-                        image: None,
+                        ..self.attribute_cell(' ')
                     };
                 }
 
                 self.cursor_col += 2;
             } else {
                 // Regular character: write normally
-                self.buffer[self.cursor_row][self.cursor_col] = Cell {
-                    c,
-                    fg: self.current_fg,
-                    bg: self.current_bg,
-                    bold: self.bold,
-                    underline: self.underline,
-                    reverse: self.reverse,
-                    dim: self.dim,
-                    italic: self.italic,
-                    strikethrough: self.strikethrough,
-                    wide: false,
-                    placeholder: false,
-                    hyperlink: None,
-                    image: None,
-                };
+                self.buffer[self.cursor_row][self.cursor_col] = self.attribute_cell(c);
                 self.cursor_col += 1;
             }
 
@@ -1776,10 +2942,15 @@ impl Perform for TerminalScreen {
     }
 
     fn execute(&mut self, byte: u8) {
+        self.last_grapheme = None;
+        self.last_printed = None;
         match byte {
             b'\n' => {
-                // Line Feed (LF)
+                // Line Feed (LF), also a carriage return in LNM mode
                 self.new_line();
+                if self.modes.get(Mode::LineFeedNewLine) {
+                    self.carriage_return();
+                }
             }
             b'\r' => {
                 // Carriage Return (CR)
@@ -1797,23 +2968,47 @@ impl Perform for TerminalScreen {
                 // Bell (BEL) - set flag for notification
                 self.bell_triggered = true;
             }
+            0x0E => {
+                // Shift Out (SO) - invoke G1 into GL
+                self.charsets.invoke_gl(1);
+            }
+            0x0F => {
+                // Shift In (SI) - invoke G0 into GL
+                self.charsets.invoke_gl(0);
+            }
             _ => {}
         }
     }
 
-    fn hook(&mut self, _params: &Params, _intermediates: &[u8], _ignore: bool, _action: char) {
-        // DCS sequences - not implemented yet
+    fn hook(&mut self, params: &Params, intermediates: &[u8], ignore: bool, action: char) {
+        self.last_grapheme = None;
+        self.last_printed = None;
+        // DCS sequences: ESC P <params> <intermediates> <action> <data> ST
+        let kind = if ignore {
+            DcsKind::Unsupported
+        } else {
+            DcsKind::from_hook(intermediates, action)
+        };
+        let params = params.iter().map(|p| p[0]).collect();
+        self.dcs_state = Some(DcsState::new(kind, params));
     }
 
-    fn put(&mut self, _byte: u8) {
-        // DCS data - not implemented yet
+    fn put(&mut self, byte: u8) {
+        // DCS data - buffered until the string terminator
+        if let Some(state) = self.dcs_state.as_mut() {
+            state.put(byte);
+        }
     }
 
     fn unhook(&mut self) {
-        // End of DCS - not implemented yet
+        // End of DCS - dispatch the buffered sequence
+        if let Some(state) = self.dcs_state.take() {
+            self.finish_dcs(state);
+        }
     }
 
     fn osc_dispatch(&mut self, params: &[&[u8]], _bell_terminated: bool) {
+        self.last_printed = None;
         // OSC sequences: ESC ] <command> ; <data> ST (or BEL)
         // Parse OSC command number and data
         if params.is_empty() {
@@ -1824,6 +3019,18 @@ impl Perform for TerminalScreen {
         let command_str = String::from_utf8_lossy(params[0]);
         let command = command_str.parse::<u16>().unwrap_or(0);
 
+        // Of OSC 1337 only the shell integration keys are queued, not e.g. File= images
+        let shell_mark = match command {
+            7 | 133 | 633 => true,
+            1337 => params[1..].iter().any(|param| {
+                param.starts_with(b"CurrentDir=") || param.starts_with(b"RemoteHost=")
+            }),
+            _ => false,
+        };
+        if shell_mark {
+            self.push_shell_mark(command.into(), params);
+        }
+
         match command {
             0 => {
                 // OSC 0 ; title - Set icon name and window title
@@ -1853,6 +3060,10 @@ impl Perform for TerminalScreen {
                     // Format: file://hostname/path or file:///path
                     if let Some(path) = self.parse_file_uri(&cwd_uri) {
                         self.cwd_from_shell = Some(path);
+                        self.host_from_shell = cwd_uri
+                            .strip_prefix("file://")
+                            .and_then(|rest| rest.split_once('/'))
+                            .map(|(host, _)| host.to_string());
                     }
                 }
             }
@@ -1924,6 +3135,17 @@ impl Perform for TerminalScreen {
                     }
                 }
             }
+            8 if params.len() > 2 => {
+                // OSC 8 ; params ; URI - Start (non-empty URI) or end (empty URI) a hyperlink
+                // The URI itself may contain ';', which VTE splits into extra params
+                let uri = params[2..]
+                    .iter()
+                    .map(|p| String::from_utf8_lossy(p))
+                    .collect::<Vec<_>>()
+                    .join(";");
+                let data = format!("{};{}", String::from_utf8_lossy(params[1]), uri);
+                self.set_hyperlink(Hyperlink::parse_osc8(&data));
+            }
             52 => {
                 // OSC 52 ; c ; <base64-data> - Clipboard operations
                 // c = clipboard selection (usually 'c' for clipboard, 'p' for primary)
@@ -1957,106 +3179,23 @@ impl Perform for TerminalScreen {
     }
 
     fn csi_dispatch(&mut self, params: &Params, intermediates: &[u8], _ignore: bool, action: char) {
+        self.last_grapheme = None;
+        // REP only repeats a graphic character printed right before it
+        let last_printed = self.last_printed.take();
         // Check for private mode sequences (CSI ? ...)
         let is_private = intermediates.contains(&b'?');
 
-        if is_private && action == 'h' {
-            // Set private mode (DEC Private Mode Set - DECSET)
-            for param in params.iter() {
-                match param[0] {
-                    1 => {
-                        // DECCKM - Application Cursor Keys (CSI ?1h)
-                        self.application_cursor_keys = true;
-                    }
-                    9 | 1000 => {
-                        // X10 Mouse Reporting (CSI ?9h or CSI ?1000h)
-                        self.mouse_mode = MouseMode::X10;
-                    }
-                    12 => {
-                        // AT&T 610 - Start Blinking Cursor (CSI ?12h)
-                        self.cursor_blink_enabled = true;
-                    }
-                    1002 => {
-                        // Button-Event Mouse Tracking (CSI ?1002h)
-                        self.mouse_mode = MouseMode::ButtonEvent;
-                    }
-                    1003 => {
-                        // Any-Event Mouse Tracking (CSI ?1003h)
-                        self.mouse_mode = MouseMode::AnyEvent;
-                    }
-                    25 => {
-                        // DECTCEM - Show Cursor (CSI ?25h)
-                        self.cursor_visible = true;
-                    }
-                    1006 => {
-                        // SGR Extended Mouse Mode (CSI ?1006h)
-                        self.mouse_encoding = MouseEncoding::Sgr;
-                    }
-                    47 => {
-                        // CSI ?47h - Switch to alternate screen (basic mode)
-                        // Used by some older applications
-                        self.switch_to_alternate_screen(false, false);
-                    }
-                    1047 => {
-                        // CSI ?1047h - Switch to alternate screen and clear it
-                        // More common in modern terminals
-                        self.switch_to_alternate_screen(true, false);
-                    }
-                    1049 => {
-                        // CSI ?1049h - Save cursor, switch to alternate screen and clear
-                        // Most complete mode - used by vim, less, htop, etc.
-                        // The cursor is saved in the state and reset to 0,0 in alternate screen
-                        self.switch_to_alternate_screen(true, true);
-                    }
-                    2004 => {
-                        // CSI ?2004h - Enable bracketed paste mode
-                        self.bracketed_paste_mode = true;
-                    }
-                    _ => {
-                        // Other private modes not implemented yet
-                    }
-                }
-            }
-            return;
-        }
-
-        if is_private && action == 'l' {
-            // Reset private mode (DEC Private Mode Reset - DECRST)
+        if (action == 'h' || action == 'l') && (is_private || intermediates.is_empty()) {
+            // Set/Reset Mode (SM/RM) or DEC Private Mode Set/Reset (DECSET/DECRST)
+            let enabled = action == 'h';
             for param in params.iter() {
-                match param[0] {
-                    1 => {
-                        // DECCKM - Normal Cursor Keys (CSI ?1l)
-                        self.application_cursor_keys = false;
-                    }
-                    9 | 1000 | 1002 | 1003 => {
-                        // Disable mouse reporting (CSI ?9l, ?1000l, ?1002l, ?1003l)
-                        self.mouse_mode = MouseMode::None;
-                    }
-                    12 => {
-                        // AT&T 610 - Stop Blinking Cursor (CSI ?12l)
-                        self.cursor_blink_enabled = false;
-                    }
-                    25 => {
-                        // DECTCEM - Hide Cursor (CSI ?25l)
-                        self.cursor_visible = false;
-                    }
-                    1006 => {
-                        // Disable SGR Extended Mouse Mode (CSI ?1006l)
-                        self.mouse_encoding = MouseEncoding::Default;
-                    }
-                    47 | 1047 | 1049 => {
-                        // CSI ?47l, ?1047l, ?1049l - Switch back to normal screen
-                        // All three modes restore the main screen completely
-                        // The saved state includes cursor position and all attributes
-                        self.switch_to_normal_screen();
-                    }
-                    2004 => {
-                        // CSI ?2004l - Disable bracketed paste mode
-                        self.bracketed_paste_mode = false;
-                    }
-                    _ => {
-                        // Other private modes not implemented yet
-                    }
+                let mode = if is_private {
+                    Mode::from_dec(param[0])
+                } else {
+                    Mode::from_ansi(param[0])
+                };
+                if let Some(mode) = mode {
+                    self.set_mode(mode, enabled);
                 }
             }
             return;
@@ -2086,6 +3225,78 @@ impl Perform for TerminalScreen {
                 let n = params.iter().next().map(|p| p[0] as usize).unwrap_or(1);
                 self.cursor_col = self.cursor_col.saturating_sub(n);
             }
+            'E' => {
+                // Cursor Next Line (CNL)
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                // Stops at the bottom margin unless starting below it
+                let bottom = match self.scroll_region {
+                    Some((_, bottom)) if self.cursor_row <= bottom => bottom,
+                    _ => self.rows - 1,
+                };
+                self.cursor_row = min(bottom, self.cursor_row + n);
+                self.cursor_col = 0;
+            }
+            'F' => {
+                // Cursor Previous Line (CPL)
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                // Stops at the top margin unless starting above it
+                let top = match self.scroll_region {
+                    Some((top, _)) if self.cursor_row >= top => top,
+                    _ => 0,
+                };
+                self.cursor_row = max(top, self.cursor_row.saturating_sub(n));
+                self.cursor_col = 0;
+            }
+            'G' | '`' => {
+                // Cursor Horizontal Absolute (CHA) or Horizontal Position Absolute (HPA)
+                let col = params
+                    .iter()
+                    .next()
+                    .map(|p| p[0].saturating_sub(1) as usize)
+                    .unwrap_or(0);
+                self.cursor_col = min(col, self.cols - 1);
+            }
+            'd' => {
+                // Vertical Position Absolute (VPA)
+                let row = params
+                    .iter()
+                    .next()
+                    .map(|p| p[0].saturating_sub(1) as usize)
+                    .unwrap_or(0);
+                let (top, bottom) = self.origin_bounds();
+                self.cursor_row = min(top + row, bottom);
+            }
+            'I' => {
+                // Cursor Horizontal Forward Tabulation (CHT)
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                self.tab_forward(n);
+            }
+            'Z' => {
+                // Cursor Backward Tabulation (CBT)
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                self.tab_backward(n);
+            }
+            'g' => {
+                // Tab Clear (TBC)
+                let mode = params.iter().next().map(|p| p[0]).unwrap_or(0);
+                self.clear_tab_stops(mode);
+            }
             'H' | 'f' => {
                 // Cursor Position (CUP) or Horizontal Vertical Position (HVP)
                 let mut iter = params.iter();
@@ -2097,7 +3308,8 @@ impl Perform for TerminalScreen {
                     .next()
                     .map(|p| p[0].saturating_sub(1) as usize)
                     .unwrap_or(0);
-                self.cursor_row = min(row, self.rows - 1);
+                let (top, bottom) = self.origin_bounds();
+                self.cursor_row = min(top + row, bottom);
                 self.cursor_col = min(col, self.cols - 1);
             }
             'J' => {
@@ -2126,11 +3338,57 @@ impl Perform for TerminalScreen {
                     .map(|p| p[0].saturating_sub(1) as usize)
                     .unwrap_or(self.rows - 1);
                 self.scroll_region = Some((min(top, self.rows - 1), min(bottom, self.rows - 1)));
+                // DECSTBM homes the cursor (to the region top in origin mode)
+                self.cursor_row = self.origin_bounds().0;
+                self.cursor_col = 0;
             }
             's' => {
                 // Save Cursor Position (SCOSC)
                 self.saved_cursor = Some((self.cursor_row, self.cursor_col));
             }
+            'p' if intermediates == [b'?', b'$'] => {
+                // Request DEC private mode (DECRQM) - CSI ? Ps $ p
+                // Response: CSI ? Ps ; Pm $ y (0 = not recognized, 1 = set, 2 = reset)
+                let number = params.iter().next().map(|p| p[0]).unwrap_or(0);
+                let state = Mode::from_dec(number).map_or(0, |mode| self.report_mode(mode));
+                self.pending_responses
+                    .push(format!("\x1b[?{};{}$y", number, state));
+            }
+            'p' if intermediates == [b'$'] => {
+                // Request ANSI mode (DECRQM) - CSI Ps $ p
+                // Response: CSI Ps ; Pm $ y
+                let number = params.iter().next().map(|p| p[0]).unwrap_or(0);
+                let state = Mode::from_ansi(number).map_or(0, |mode| self.report_mode(mode));
+                self.pending_responses
+                    .push(format!("\x1b[{};{}$y", number, state));
+            }
+            'u' if intermediates == [b'?'] => {
+                // Query kitty keyboard flags - CSI ? u
+                // Response: CSI ? flags u
+                let flags = self.keyboard_flags();
+                self.pending_responses.push(format!("\x1b[?{}u", flags));
+            }
+            'u' if intermediates == [b'>'] => {
+                // Push kitty keyboard flags - CSI > flags u
+                let flags = params.iter().next().map(|p| p[0]).unwrap_or(0);
+                self.push_keyboard_flags(flags);
+            }
+            'u' if intermediates == [b'<'] => {
+                // Pop kitty keyboard flags - CSI < n u
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                self.pop_keyboard_flags(n);
+            }
+            'u' if intermediates == [b'='] => {
+                // Set kitty keyboard flags - CSI = flags ; mode u
+                let mut iter = params.iter();
+                let flags = iter.next().map(|p| p[0]).unwrap_or(0);
+                let mode = iter.next().map(|p| p[0]).unwrap_or(1);
+                self.set_keyboard_flags(flags, mode);
+            }
             'u' => {
                 // Restore Cursor Position (SCORC)
                 if let Some((row, col)) = self.saved_cursor {
@@ -2176,6 +3434,38 @@ impl Perform for TerminalScreen {
                 let n = params.iter().next().map(|p| p[0] as usize).unwrap_or(1);
                 self.delete_characters(n);
             }
+            'X' => {
+                // Erase Characters (ECH)
+                let n = params.iter().next().map(|p| p[0] as usize).unwrap_or(1);
+                self.erase_characters(n);
+            }
+            'b' => {
+                // Repeat Preceding Graphic Character (REP), limited to one screen
+                let n = params
+                    .iter()
+                    .next()
+                    .map(|p| max(1, p[0] as usize))
+                    .unwrap_or(1);
+                if let Some(c) = last_printed {
+                    for _ in 0..min(n, self.cols * self.rows) {
+                        self.print(c);
+                    }
+                }
+            }
+            'q' if intermediates == [b' '] => {
+                // Set Cursor Style (DECSCUSR) - CSI Ps SP q
+                // Odd shapes blink, even shapes are steady, 0 is the blinking default
+                self.cursor_shape = params.iter().next().map(|p| p[0]).unwrap_or(0);
+                let blink = self.cursor_shape == 0 || self.cursor_shape % 2 == 1;
+                self.modes.set(Mode::CursorBlink, blink);
+            }
+            't' if intermediates.is_empty() => {
+                // Window manipulation (XTWINOPS) - CSI Ps ; Ps ; Ps t
+                let mut iter = params.iter().map(|p| p[0]);
+                let op = iter.next().unwrap_or(0);
+                let arg = iter.next().unwrap_or(0);
+                self.window_op(op, arg);
+            }
             'c' => {
                 // Device Attributes (DA)
                 if intermediates.contains(&b'>') {
@@ -2201,10 +3491,10 @@ impl Perform for TerminalScreen {
                         // CPR - Cursor Position Report
                         // Response: CSI <row> ; <col> R
                         // Note: VT100 uses 1-based indexing
-                        let row = self.cursor_row + 1;
+                        // In origin mode the row is relative to the scroll region
+                        let row = self.cursor_row.saturating_sub(self.origin_bounds().0) + 1;
                         let col = self.cursor_col + 1;
-                        self.pending_responses
-                            .push(format!("\x1b[{row};{col}R"));
+                        self.pending_responses.push(format!("\x1b[{row};{col}R"));
                     }
                     _ => {
                         // Unknown DSR request - ignore
@@ -2217,28 +3507,75 @@ impl Perform for TerminalScreen {
         }
     }
 
-    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, byte: u8) {
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        self.last_grapheme = None;
+        self.last_printed = None;
+        if let [slot @ (b'(' | b')' | b'*' | b'+')] = intermediates {
+            // SCS - Designate G0-G3 Character Set (ESC ( F, ESC ) F, ESC * F, ESC + F)
+            if let Some(charset) = Charset::from_final_byte(byte) {
+                let slot = match slot {
+                    b'(' => 0,
+                    b')' => 1,
+                    b'*' => 2,
+                    _ => 3,
+                };
+                self.charsets.designate(slot, charset);
+            }
+            return;
+        }
+
         match byte {
+            b'n' => {
+                // LS2 - Locking Shift 2 (ESC n): invoke G2 into GL
+                self.charsets.invoke_gl(2);
+            }
+            b'o' => {
+                // LS3 - Locking Shift 3 (ESC o): invoke G3 into GL
+                self.charsets.invoke_gl(3);
+            }
+            b'~' | b'}' | b'|' => {
+                // LS1R/LS2R/LS3R - Locking Shifts into GR (ESC ~, ESC }, ESC |)
+                // GR only exists for 8-bit input; UTF-8 Latin-1 text is never remapped
+            }
+            b'N' => {
+                // SS2 - Single Shift 2 (ESC N): next character from G2
+                self.charsets.single_shift(2);
+            }
+            b'O' => {
+                // SS3 - Single Shift 3 (ESC O): next character from G3
+                self.charsets.single_shift(3);
+            }
+            b'H' if intermediates.is_empty() => {
+                // HTS - Horizontal Tab Set (ESC H)
+                if let Some(stop) = self.tab_stops.get_mut(self.cursor_col) {
+                    *stop = true;
+                }
+            }
             b'7' => {
                 // DECSC - Save Cursor (ESC 7)
                 // Save cursor position and attributes
-                self.saved_cursor_state = Some((
-                    self.cursor_row,
-                    self.cursor_col,
-                    self.current_fg,
-                    self.current_bg,
-                    self.bold,
-                ));
+                self.saved_cursor_state = Some(SavedCursor {
+                    row: self.cursor_row,
+                    col: self.cursor_col,
+                    fg: self.current_fg,
+                    bg: self.current_bg,
+                    bold: self.bold,
+                    charsets: self.charsets,
+                    origin_mode: self.modes.get(Mode::Origin),
+                });
             }
             b'8' => {
                 // DECRC - Restore Cursor (ESC 8)
                 // Restore cursor position and attributes
-                if let Some((row, col, fg, bg, bold)) = self.saved_cursor_state {
-                    self.cursor_row = min(row, self.rows - 1);
-                    self.cursor_col = min(col, self.cols - 1);
-                    self.current_fg = fg;
-                    self.current_bg = bg;
-                    self.bold = bold;
+                if let Some(saved) = self.saved_cursor_state {
+                    self.cursor_row = min(saved.row, self.rows - 1);
+                    self.cursor_col = min(saved.col, self.cols - 1);
+                    self.current_fg = saved.fg;
+                    self.current_bg = saved.bg;
+                    self.bold = saved.bold;
+                    self.charsets = saved.charsets;
+                    // Set directly: going through set_mode would home the cursor
+                    self.modes.set(Mode::Origin, saved.origin_mode);
                 }
             }
             b'M' => {
@@ -2283,8 +3620,16 @@ impl Perform for TerminalScreen {
             }
             b'c' => {
                 // RIS - Reset to Initial State (ESC c)
-                // Full terminal reset
+                // Full terminal reset, back on the main screen
+                self.switch_to_normal_screen();
+                self.modes = ModeTable::default();
                 self.buffer = vec![vec![Cell::default(); self.cols]; self.rows];
+                self.line_wrapped = vec![false; self.rows];
+                self.tab_stops = default_tab_stops(self.cols);
+                self.last_printed = None;
+                self.charsets = CharsetState::default();
+                self.keyboard_mode_stack.clear();
+                self.synchronized_update = None;
                 self.scrollback.clear();
                 self.cursor_row = 0;
                 self.cursor_col = 0;
@@ -2296,11 +3641,19 @@ impl Perform for TerminalScreen {
                 self.dim = false;
                 self.italic = false;
                 self.strikethrough = false;
+                self.underline_style = UnderlineStyle::Single;
+                self.underline_color = None;
+                self.blink = false;
+                self.hidden = false;
+                self.overline = false;
                 self.scroll_region = None;
                 self.saved_cursor = None;
                 self.saved_cursor_state = None;
-                self.cursor_visible = true;
-                self.auto_wrap_mode = true;
+                self.cursor_shape = 0;
+                self.icon_stack.clear();
+                self.title_stack.clear();
+                self.current_hyperlink = None;
+                self.current_hyperlink_id = None;
             }
             _ => {
                 // Unknown ESC sequence - ignore
@@ -2325,6 +3678,29 @@ impl crate::terminal::bracket::GetChar for TerminalScreen {
     }
 }
 
+// Selection text extraction support
+impl crate::terminal::selection::GetLineText for TerminalScreen {
+    /// Text of a line (scrollback first, like `get_all_lines`), one grapheme
+    /// cluster per cell with the placeholders of wide characters skipped
+    fn get_line_text(&self, line: usize) -> Option<String> {
+        let text = |cells: &[Cell]| {
+            let mut text = String::with_capacity(cells.len());
+            for cell in cells.iter().filter(|cell| !cell.placeholder) {
+                cell.push_grapheme(&mut text);
+            }
+            text
+        };
+        match line.checked_sub(self.scrollback.len()) {
+            None => Some(text(&self.scrollback.get(line)?)),
+            Some(row) => self.buffer.get(row).map(|cells| text(cells)),
+        }
+    }
+
+    fn line_count(&self) -> usize {
+        self.scrollback.len() + self.buffer.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2338,20 +3714,20 @@ mod tests {
     }
 
     #[test]
-    fn test_mouse_mode_x10_enable() {
+    fn test_mouse_mode_normal_enable() {
         let mut screen = TerminalScreen::new(80, 24);
-        // CSI ?1000h - Enable X10 mouse reporting
+        // CSI ?1000h - Enable normal mouse tracking
         screen.process(b"\x1b[?1000h");
-        assert_eq!(screen.mouse_mode(), MouseMode::X10);
+        assert_eq!(screen.mouse_mode(), MouseMode::Normal);
         assert!(screen.is_mouse_reporting_enabled());
     }
 
     #[test]
-    fn test_mouse_mode_x10_disable() {
+    fn test_mouse_mode_normal_disable() {
         let mut screen = TerminalScreen::new(80, 24);
         // Enable then disable
         screen.process(b"\x1b[?1000h");
-        assert_eq!(screen.mouse_mode(), MouseMode::X10);
+        assert_eq!(screen.mouse_mode(), MouseMode::Normal);
         screen.process(b"\x1b[?1000l");
         assert_eq!(screen.mouse_mode(), MouseMode::None);
         assert!(!screen.is_mouse_reporting_enabled());
@@ -2386,12 +3762,26 @@ mod tests {
         assert_eq!(screen.mouse_encoding(), MouseEncoding::Default);
     }
 
+    #[test]
+    fn test_mouse_encoding_extended_modes() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b[?1005h");
+        assert_eq!(screen.mouse_encoding(), MouseEncoding::Utf8);
+        // The last encoding set wins
+        screen.process(b"\x1b[?1015h");
+        assert_eq!(screen.mouse_encoding(), MouseEncoding::Urxvt);
+        screen.process(b"\x1b[?1016h");
+        assert_eq!(screen.mouse_encoding(), MouseEncoding::SgrPixels);
+        screen.process(b"\x1b[?1016l");
+        assert_eq!(screen.mouse_encoding(), MouseEncoding::Default);
+    }
+
     #[test]
     fn test_mouse_mode_multiple_enables() {
         let mut screen = TerminalScreen::new(80, 24);
-        // Enable X10
+        // Enable normal tracking
         screen.process(b"\x1b[?1000h");
-        assert_eq!(screen.mouse_mode(), MouseMode::X10);
+        assert_eq!(screen.mouse_mode(), MouseMode::Normal);
         // Upgrade to ButtonEvent
         screen.process(b"\x1b[?1002h");
         assert_eq!(screen.mouse_mode(), MouseMode::ButtonEvent);
@@ -2403,9 +3793,9 @@ mod tests {
     #[test]
     fn test_mouse_mode_with_sgr_encoding() {
         let mut screen = TerminalScreen::new(80, 24);
-        // Enable X10 mouse + SGR encoding
+        // Enable normal mouse + SGR encoding
         screen.process(b"\x1b[?1000h\x1b[?1006h");
-        assert_eq!(screen.mouse_mode(), MouseMode::X10);
+        assert_eq!(screen.mouse_mode(), MouseMode::Normal);
         assert_eq!(screen.mouse_encoding(), MouseEncoding::Sgr);
         assert!(screen.is_mouse_reporting_enabled());
     }
@@ -2413,7 +3803,11 @@ mod tests {
     #[test]
     fn test_mouse_mode_csi_9() {
         let mut screen = TerminalScreen::new(80, 24);
-        // CSI ?9h - Alternative X10 mouse reporting
+        // CSI ?9h - X10 compatibility mode, distinct from ?1000
+        screen.process(b"\x1b[?9h");
+        assert_eq!(screen.mouse_mode(), MouseMode::X10);
+        screen.process(b"\x1b[?1000h");
+        assert_eq!(screen.mouse_mode(), MouseMode::Normal);
         screen.process(b"\x1b[?9h");
         assert_eq!(screen.mouse_mode(), MouseMode::X10);
         screen.process(b"\x1b[?9l");
@@ -2582,16 +3976,16 @@ mod tests {
 
         // Set up screen with various state
         screen.process(b"Some text\r\n");
+        screen.process(b"\x1b[5;10r"); // Set scroll region
         screen.process(b"\x1b[10;20H"); // Move cursor
         screen.process(b"\x1b[1;4;31;42m"); // Bold, underline, red fg, green bg
-        screen.process(b"\x1b[5;10r"); // Set scroll region
         screen.process(b"\x1b[s"); // Save cursor
         screen.process(b"\x1b[?25l"); // Hide cursor
 
         assert_eq!(screen.cursor_position(), (9, 19));
         assert!(screen.bold);
         assert!(screen.underline);
-        assert!(!screen.cursor_visible);
+        assert!(!screen.cursor_visible());
 
         // Reset (ESC c)
         screen.process(b"\x1bc");
@@ -2603,7 +3997,7 @@ mod tests {
         assert!(!screen.reverse);
         assert_eq!(screen.current_fg, None);
         assert_eq!(screen.current_bg, None);
-        assert!(screen.cursor_visible);
+        assert!(screen.cursor_visible());
         assert_eq!(screen.scroll_region, None);
         assert_eq!(screen.saved_cursor, None);
         assert_eq!(screen.saved_cursor_state, None);
@@ -4898,3 +6292,1176 @@ mod dirty_tracker_tests {
         assert!(screen.dirty_tracker().needs_full_redraw());
     }
 }
+
+#[cfg(test)]
+mod dcs_tests {
+    use super::*;
+
+    #[test]
+    fn test_decrqss_sgr_default() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1bP$qm\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP1$r0m\x1b\\"]);
+    }
+
+    #[test]
+    fn test_decrqss_sgr_with_attributes() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b[1;4;31;48;5;200m");
+        screen.process(b"\x1bP$qm\x1b\\");
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec!["\x1bP1$r0;1;4;31;48;5;200m\x1b\\"]
+        );
+
+        screen.process(b"\x1b[0;38;2;10;20;30;101m\x1bP$qm\x1b\\");
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec!["\x1bP1$r0;38;2;10;20;30;101m\x1b\\"]
+        );
+    }
+
+    #[test]
+    fn test_decrqss_scroll_region() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1bP$qr\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP1$r1;24r\x1b\\"]);
+
+        screen.process(b"\x1b[5;10r\x1bP$qr\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP1$r5;10r\x1b\\"]);
+    }
+
+    #[test]
+    fn test_decrqss_cursor_style() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b[4 q");
+        assert_eq!(screen.cursor_shape(), 4);
+        screen.process(b"\x1bP$q q\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP1$r4 q\x1b\\"]);
+    }
+
+    #[test]
+    fn test_decrqss_unknown_setting() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1bP$qxyz\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP0$r\x1b\\"]);
+    }
+
+    #[test]
+    fn test_xtgettcap_known_and_unknown() {
+        let mut screen = TerminalScreen::new(80, 24);
+        // "TN" = 544E, "Co" = 436F, "xx" = 7878
+        screen.process(b"\x1bP+q544E;436F;7878\x1b\\");
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec![
+                "\x1bP1+r544E=787465726D2D323536636F6C6F72\x1b\\",
+                "\x1bP1+r436F=323536\x1b\\",
+                "\x1bP0+r7878\x1b\\",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_xtgettcap_boolean_capability() {
+        let mut screen = TerminalScreen::new(80, 24);
+        // "Tc" = 5463
+        screen.process(b"\x1bP+q5463\x1b\\");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1bP1+r5463\x1b\\"]);
+    }
+
+    #[test]
+    fn test_unsupported_dcs_is_consumed() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1bP1000pIGNORED\x1b\\AB");
+        assert!(screen.take_pending_responses().is_empty());
+        assert_eq!(screen.buffer[0][0].c, 'A');
+        assert_eq!(screen.buffer[0][1].c, 'B');
+    }
+}
+
+#[cfg(test)]
+mod sixel_tests {
+    use super::*;
+
+    #[test]
+    fn test_sixel_placed_in_cells() {
+        let mut screen = TerminalScreen::new(20, 10);
+        screen.set_cell_pixel_size(4, 6);
+        screen.process(b"AB");
+
+        // 10x12 pixel red image: 3 columns x 2 rows of 4x6 cells
+        screen.process(b"\x1bPq\"1;1;10;12#1;2;100;0;0#1!10~-!10~\x1b\\");
+
+        for row in 0..2 {
+            for col in 2..5 {
+                let image = screen.buffer[row][col].image.as_ref().unwrap();
+                assert_eq!((image.width, image.height), (4, 6));
+                assert_eq!(image.protocol, ImageProtocol::Sixel);
+                assert_eq!(image.data.len(), 4 * 6 * 4);
+            }
+            assert!(screen.buffer[row][5].image.is_none());
+        }
+        assert_eq!(screen.buffer[0][0].c, 'A');
+
+        // Top-left pixel is red, padding outside the 10px width is transparent
+        let first = screen.buffer[0][2].image.as_ref().unwrap();
+        assert_eq!(&first.data[0..4], &[255, 0, 0, 255]);
+        let last = screen.buffer[0][4].image.as_ref().unwrap();
+        assert_eq!(&last.data[8..12], &[0, 0, 0, 0]);
+
+        // Cursor moves below the image, back to the starting column
+        assert_eq!(screen.cursor_position(), (2, 2));
+    }
+
+    #[test]
+    fn test_sixel_scrolls_at_bottom() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.set_cell_pixel_size(1, 6);
+        screen.process(b"\x1b[3;1H");
+
+        // Two rows of cells high: forces one scroll for the image plus one for the cursor
+        screen.process(b"\x1bPq#1~-~\x1b\\");
+        assert!(screen.buffer[0][0].image.is_some());
+        assert!(screen.buffer[1][0].image.is_some());
+        assert_eq!(screen.cursor_position(), (2, 0));
+        assert_eq!(screen.scrollback_size(), 2);
+    }
+
+    #[test]
+    fn test_invalid_sixel_is_ignored() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1bPq\x1b\\X");
+        assert_eq!(screen.buffer[0][0].c, 'X');
+        assert!(screen.buffer[0][0].image.is_none());
+    }
+}
+
+#[cfg(test)]
+mod hyperlink_tests {
+    use super::*;
+
+    #[test]
+    fn test_osc8_sets_cell_hyperlink() {
+        let mut screen = TerminalScreen::new(40, 5);
+        screen.process(b"a\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x1b\\b");
+
+        assert!(screen.buffer[0][0].hyperlink.is_none());
+        for col in 1..5 {
+            let cell = &screen.buffer[0][col];
+            assert_eq!(
+                cell.hyperlink.as_deref().map(String::as_str),
+                Some("https://example.com")
+            );
+            assert!(cell.hyperlink_id.is_some());
+        }
+        assert!(screen.buffer[0][5].hyperlink.is_none());
+        assert!(screen.buffer[0][5].hyperlink_id.is_none());
+    }
+
+    #[test]
+    fn test_osc8_uri_with_semicolon_and_bel_terminator() {
+        let mut screen = TerminalScreen::new(40, 5);
+        screen.process(b"\x1b]8;id=x;https://example.com/a;b\x07X\x1b]8;;\x07");
+
+        let cell = &screen.buffer[0][0];
+        assert_eq!(
+            cell.hyperlink.as_deref().map(String::as_str),
+            Some("https://example.com/a;b")
+        );
+        assert_eq!(
+            cell.hyperlink_id,
+            Some(HyperlinkId::Named(Arc::new("x".to_string())))
+        );
+    }
+
+    #[test]
+    fn test_osc8_survives_sgr_reset() {
+        let mut screen = TerminalScreen::new(40, 5);
+        screen.process(b"\x1b]8;;https://example.com\x1b\\\x1b[1mA\x1b[0mB\x1b]8;;\x1b\\");
+
+        assert!(screen.buffer[0][0].hyperlink.is_some());
+        assert!(screen.buffer[0][1].hyperlink.is_some());
+    }
+
+    #[test]
+    fn test_hyperlink_cells_groups_by_id() {
+        let mut screen = TerminalScreen::new(10, 5);
+        // Same explicit id on two lines forms one link
+        screen.process(b"\x1b]8;id=1;https://example.com\x1b\\ab\x1b]8;;\x1b\\\r\n");
+        screen.process(b"\x1b]8;id=1;https://example.com\x1b\\cd\x1b]8;;\x1b\\\r\n");
+        // Same URL without an id is a separate link
+        screen.process(b"\x1b]8;;https://example.com\x1b\\ef\x1b]8;;\x1b\\");
+
+        let offset = screen.get_all_lines().len() - screen.rows;
+        let group = screen.hyperlink_cells(offset, 0);
+        assert_eq!(
+            group,
+            vec![(offset, 0), (offset, 1), (offset + 1, 0), (offset + 1, 1)]
+        );
+        assert_eq!(
+            screen.hyperlink_cells(offset + 2, 0),
+            vec![(offset + 2, 0), (offset + 2, 1)]
+        );
+        assert!(screen.hyperlink_cells(offset + 3, 0).is_empty());
+    }
+
+    #[test]
+    fn test_hyperlink_cells_detected_url() {
+        let mut screen = TerminalScreen::new(60, 3);
+        screen.process(b"see https://a.example and https://b.example");
+        screen.detect_urls();
+
+        let offset = screen.get_all_lines().len() - screen.rows;
+        assert_eq!(
+            screen.buffer[0][4].hyperlink_id,
+            Some(HyperlinkId::Detected)
+        );
+        let group = screen.hyperlink_cells(offset, 10);
+        assert_eq!(group.first(), Some(&(offset, 4)));
+        assert_eq!(group.last(), Some(&(offset, 20)));
+        assert_eq!(
+            screen
+                .hyperlink_url(offset, 30)
+                .as_deref()
+                .map(String::as_str),
+            Some("https://b.example")
+        );
+        assert!(screen.hyperlink_cells(offset, 0).is_empty());
+    }
+
+    #[test]
+    fn test_detect_urls_keeps_explicit_link() {
+        let mut screen = TerminalScreen::new(60, 5);
+        screen.process(b"\x1b]8;;https://target.example\x1b\\https://shown.example\x1b]8;;\x1b\\");
+        screen.detect_urls();
+
+        assert_eq!(
+            screen.buffer[0][0].hyperlink.as_deref().map(String::as_str),
+            Some("https://target.example")
+        );
+    }
+
+    #[test]
+    fn test_ris_clears_active_hyperlink() {
+        let mut screen = TerminalScreen::new(40, 5);
+        screen.process(b"\x1b]8;;https://example.com\x1b\\\x1bcA");
+
+        assert!(screen.buffer[0][0].hyperlink.is_none());
+    }
+}
+
+#[cfg(test)]
+mod reflow_tests {
+    use super::*;
+
+    fn row_text(line: &[Cell]) -> String {
+        line.iter()
+            .filter(|cell| !cell.placeholder)
+            .map(|cell| cell.c)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_auto_wrap_sets_wrapped_flag() {
+        let mut screen = TerminalScreen::new(5, 3);
+        screen.process(b"abcdefg\r\nxy");
+
+        assert!(screen.is_line_wrapped(0));
+        assert!(!screen.is_line_wrapped(1));
+        assert!(!screen.is_line_wrapped(2));
+    }
+
+    #[test]
+    fn test_wrapped_flag_survives_scrollback() {
+        let mut screen = TerminalScreen::new(5, 2);
+        screen.process(b"abcdefg\r\nx\r\ny");
+
+        assert_eq!(screen.scrollback.len(), 2);
+        assert!(screen.is_line_wrapped(0));
+        assert!(!screen.is_line_wrapped(1));
+    }
+
+    #[test]
+    fn test_erase_line_clears_wrapped_flag() {
+        let mut screen = TerminalScreen::new(5, 3);
+        screen.process(b"abcdefg\x1b[1;1H\x1b[2K");
+
+        assert!(!screen.is_line_wrapped(0));
+    }
+
+    #[test]
+    fn test_narrow_then_widen_restores_long_line() {
+        let mut screen = TerminalScreen::new(20, 5);
+        screen.process(b"error: something bad\r\n$ ");
+
+        screen.resize(8, 5);
+        let lines = screen.get_all_lines();
+        let texts: Vec<String> = lines.iter().map(|line| row_text(line)).collect();
+        assert_eq!(&texts[..4], &["error: s", "omething", " bad", "$"]);
+        assert_eq!(screen.cursor_position(), (3, 2));
+
+        screen.resize(20, 5);
+        let lines = screen.get_all_lines();
+        assert_eq!(row_text(&lines[0]), "error: something bad");
+        assert_eq!(row_text(&lines[1]), "$");
+        assert_eq!(screen.cursor_position(), (1, 2));
+    }
+
+    #[test]
+    fn test_reflow_pushes_into_scrollback() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"0123456789\r\nabc\r\n$ ");
+
+        screen.resize(5, 3);
+        assert_eq!(screen.scrollback.len(), 1);
+        assert!(screen.scrollback.is_wrapped(0));
+        assert_eq!(row_text(&screen.buffer[0]), "56789");
+        assert_eq!(row_text(&screen.buffer[2]), "$");
+        assert_eq!(screen.cursor_position(), (2, 2));
+
+        screen.resize(10, 3);
+        assert!(screen.scrollback.is_empty());
+        assert_eq!(row_text(&screen.buffer[0]), "0123456789");
+        assert_eq!(screen.cursor_position(), (2, 2));
+    }
+
+    #[test]
+    fn test_reflow_keeps_cursor_on_logical_character() {
+        let mut screen = TerminalScreen::new(10, 4);
+        screen.process(b"0123456789abcdef\x1b[2;3H");
+        assert_eq!(screen.cursor_position(), (1, 2));
+
+        // Cursor is on 'c', offset 12 of the logical line
+        screen.resize(4, 6);
+        let (row, col) = screen.cursor_position();
+        let lines = screen.get_all_lines();
+        let offset = lines.len() - screen.rows;
+        assert_eq!(lines[offset + row][col].c, 'c');
+    }
+
+    #[test]
+    fn test_alternate_screen_resize_reflows_main_screen() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"0123456789abc");
+        screen.process(b"\x1b[?1049h");
+        screen.resize(20, 3);
+        screen.process(b"\x1b[?1049l");
+
+        assert_eq!(row_text(&screen.buffer[0]), "0123456789abc");
+        assert_eq!(screen.cursor_position(), (0, 13));
+    }
+
+    #[test]
+    fn test_reflow_moves_shell_marks() {
+        let mut screen = TerminalScreen::new(10, 4);
+        screen.process(b"0123456789abc\r\n$ \x1b]133;B\x07");
+
+        screen.resize(5, 4);
+        let marks = screen.take_shell_marks();
+        assert_eq!((marks[0].line, marks[0].col), (3, 2));
+
+        // Positions taken before the resize are moved by the line map
+        let maps = screen.take_line_maps();
+        assert_eq!(maps.len(), 1);
+        assert_eq!(maps[0].position(2, 2), (3, 2));
+        assert!(screen.take_line_maps().is_empty());
+    }
+}
+
+#[cfg(test)]
+mod charset_tests {
+    use super::*;
+
+    fn row_text(screen: &TerminalScreen, row: usize) -> String {
+        screen.buffer[row].iter().map(|cell| cell.c).collect()
+    }
+
+    #[test]
+    fn test_dec_special_graphics_in_g0() {
+        let mut screen = TerminalScreen::new(6, 2);
+        screen.process(b"\x1b(0lqqk\x1b(Bok");
+
+        assert_eq!(row_text(&screen, 0), "┌──┐ok");
+    }
+
+    #[test]
+    fn test_shift_out_to_g1() {
+        let mut screen = TerminalScreen::new(6, 2);
+        // tmux/ncurses style: designate G1 once, switch with SO/SI
+        screen.process(b"\x1b)0x\x0ex\x0fx");
+
+        assert_eq!(row_text(&screen, 0), "x│x   ");
+    }
+
+    #[test]
+    fn test_uk_charset() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process(b"\x1b(A#1");
+
+        assert_eq!(row_text(&screen, 0), "£1  ");
+    }
+
+    #[test]
+    fn test_single_shift_g2() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process(b"\x1b*0\x1bNqq");
+
+        assert_eq!(row_text(&screen, 0), "─q  ");
+    }
+
+    #[test]
+    fn test_decsc_decrc_restores_charsets() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process(b"\x1b(0\x1b7\x1b(Bq\x1b8q");
+
+        assert_eq!(row_text(&screen, 0), "─   ");
+    }
+
+    #[test]
+    fn test_alternate_screen_restores_charsets() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process(b"\x1b(0\x1b[?1049hq\x1b[?1049l");
+        assert_eq!(row_text(&screen, 0), "    ");
+
+        screen.process(b"q");
+        assert_eq!(row_text(&screen, 0), "─   ");
+    }
+
+    #[test]
+    fn test_utf8_unchanged_after_gr_designation() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process("\x1b*0\x1b+0\x1b}é\x1b|ñq".as_bytes());
+
+        assert_eq!(row_text(&screen, 0), "éñq ");
+    }
+
+    #[test]
+    fn test_ris_resets_charsets() {
+        let mut screen = TerminalScreen::new(4, 2);
+        screen.process(b"\x1b(0\x1bcq");
+
+        assert_eq!(row_text(&screen, 0), "q   ");
+    }
+}
+
+#[cfg(test)]
+mod keyboard_protocol_tests {
+    use super::*;
+
+    #[test]
+    fn test_keyboard_flags_default_off() {
+        let mut screen = TerminalScreen::new(10, 3);
+        assert_eq!(screen.keyboard_flags(), 0);
+
+        screen.process(b"\x1b[?u");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?0u"]);
+    }
+
+    #[test]
+    fn test_push_pop_keyboard_flags() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[>1u\x1b[>11u");
+        assert_eq!(screen.keyboard_flags(), 11);
+
+        screen.process(b"\x1b[?u");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?11u"]);
+
+        screen.process(b"\x1b[<u");
+        assert_eq!(screen.keyboard_flags(), 1);
+        screen.process(b"\x1b[<5u");
+        assert_eq!(screen.keyboard_flags(), 0);
+    }
+
+    #[test]
+    fn test_set_keyboard_flags_modes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[>1u");
+
+        screen.process(b"\x1b[=8;2u");
+        assert_eq!(screen.keyboard_flags(), 9);
+        screen.process(b"\x1b[=1;3u");
+        assert_eq!(screen.keyboard_flags(), 8);
+        screen.process(b"\x1b[=3u");
+        assert_eq!(screen.keyboard_flags(), 3);
+    }
+
+    #[test]
+    fn test_keyboard_flag_stack_is_bounded() {
+        let mut screen = TerminalScreen::new(10, 3);
+        for _ in 0..MAX_KEYBOARD_MODE_STACK + 4 {
+            screen.process(b"\x1b[>1u");
+        }
+        assert_eq!(screen.keyboard_mode_stack.len(), MAX_KEYBOARD_MODE_STACK);
+    }
+
+    #[test]
+    fn test_alternate_screen_has_own_keyboard_stack() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[>1u\x1b[?1049h");
+        assert_eq!(screen.keyboard_flags(), 0);
+
+        screen.process(b"\x1b[>31u");
+        assert_eq!(screen.keyboard_flags(), 31);
+
+        screen.process(b"\x1b[?1049l");
+        assert_eq!(screen.keyboard_flags(), 1);
+    }
+
+    #[test]
+    fn test_scorc_still_restores_cursor() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[2;3H\x1b[s\x1b[H\x1b[u");
+        assert_eq!(screen.cursor_position(), (1, 2));
+
+        screen.process(b"\x1b[>1u\x1bc");
+        assert_eq!(screen.keyboard_flags(), 0);
+    }
+}
+
+#[cfg(test)]
+mod synchronized_update_tests {
+    use super::*;
+
+    #[test]
+    fn test_synchronized_update_begin_end() {
+        let mut screen = TerminalScreen::new(10, 3);
+        assert!(!screen.is_synchronized_update());
+
+        screen.process(b"\x1b[?2026h");
+        assert!(screen.is_synchronized_update());
+
+        screen.process(b"\x1b[?2026l");
+        assert!(!screen.is_synchronized_update());
+    }
+
+    #[test]
+    fn test_synchronized_update_times_out() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?2026h");
+        screen.synchronized_update = Some(Instant::now() - SYNCHRONIZED_UPDATE_TIMEOUT);
+
+        assert!(!screen.is_synchronized_update());
+    }
+
+    #[test]
+    fn test_synchronized_update_reset_by_ris() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?2026h\x1bc");
+
+        assert!(!screen.is_synchronized_update());
+    }
+
+    #[test]
+    fn test_decrqm_reports_mode_2026() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?2026$p");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?2026;2$y"]);
+
+        screen.process(b"\x1b[?2026h\x1b[?2026$p");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?2026;1$y"]);
+    }
+
+    #[test]
+    fn test_decrqm_2026_after_timeout() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?2026h");
+        screen.synchronized_update = Some(Instant::now() - SYNCHRONIZED_UPDATE_TIMEOUT);
+
+        screen.process(b"\x1b[?2026$p");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?2026;2$y"]);
+    }
+
+    #[test]
+    fn test_decrqm_unknown_mode() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?9999$p");
+
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?9999;0$y"]);
+    }
+}
+
+#[cfg(test)]
+mod mode_tests {
+    use super::*;
+
+    fn row_text(screen: &TerminalScreen, row: usize) -> String {
+        screen.get_all_lines()[row]
+            .iter()
+            .map(|cell| cell.c)
+            .collect()
+    }
+
+    #[test]
+    fn test_insert_mode_irm() {
+        let mut screen = TerminalScreen::new(8, 2);
+        screen.process(b"abcdef\r\x1b[4hXY");
+        assert_eq!(row_text(&screen, 0), "XYabcdef");
+        assert!(screen.is_mode_set(Mode::Insert));
+
+        screen.process(b"\x1b[4lZ");
+        assert_eq!(row_text(&screen, 0), "XYZbcdef");
+    }
+
+    #[test]
+    fn test_linefeed_newline_mode_lnm() {
+        let mut screen = TerminalScreen::new(8, 3);
+        screen.process(b"ab\n");
+        assert_eq!(screen.cursor_position(), (1, 2));
+
+        screen.process(b"\x1b[20hcd\n");
+        assert_eq!(screen.cursor_position(), (2, 0));
+    }
+
+    #[test]
+    fn test_origin_mode_decom() {
+        let mut screen = TerminalScreen::new(10, 10);
+        screen.process(b"\x1b[3;6r\x1b[?6h");
+        // Setting DECOM homes the cursor to the top of the region
+        assert_eq!(screen.cursor_position(), (2, 0));
+
+        screen.process(b"\x1b[2;4H");
+        assert_eq!(screen.cursor_position(), (3, 3));
+
+        // Addressing is clamped to the region
+        screen.process(b"\x1b[9;1H");
+        assert_eq!(screen.cursor_position(), (5, 0));
+        screen.process(b"\x1b[1d");
+        assert_eq!(screen.cursor_position(), (2, 0));
+
+        // CPR reports the row relative to the region
+        screen.process(b"\x1b[6n");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[1;1R"]);
+
+        screen.process(b"\x1b[?6l");
+        assert_eq!(screen.cursor_position(), (0, 0));
+        screen.process(b"\x1b[9;1H");
+        assert_eq!(screen.cursor_position(), (8, 0));
+    }
+
+    #[test]
+    fn test_decstbm_homes_cursor() {
+        let mut screen = TerminalScreen::new(10, 10);
+        screen.process(b"\x1b[5;5H\x1b[3;6r");
+        assert_eq!(screen.cursor_position(), (0, 0));
+
+        // In origin mode the home position is the top of the region
+        screen.process(b"\x1b[?6h\x1b[3;4H\x1b[2;8r");
+        assert_eq!(screen.cursor_position(), (1, 0));
+    }
+
+    #[test]
+    fn test_decsc_decrc_save_origin_mode() {
+        let mut screen = TerminalScreen::new(10, 10);
+        screen.process(b"\x1b[3;6r\x1b[?6h\x1b[2;2H\x1b7");
+        screen.process(b"\x1b[?6l\x1b[9;9H");
+        assert!(!screen.is_mode_set(Mode::Origin));
+
+        screen.process(b"\x1b8");
+        assert!(screen.is_mode_set(Mode::Origin));
+        assert_eq!(screen.cursor_position(), (3, 1));
+
+        // Restoring a state saved outside origin mode turns DECOM back off
+        screen.process(b"\x1b[?6l\x1b7\x1b[?6h\x1b8");
+        assert!(!screen.is_mode_set(Mode::Origin));
+    }
+
+    #[test]
+    fn test_reverse_screen_decscnm() {
+        let mut screen = TerminalScreen::new(10, 3);
+        assert!(!screen.is_mode_set(Mode::ReverseScreen));
+
+        screen.process(b"\x1b[?5h");
+        assert!(screen.is_mode_set(Mode::ReverseScreen));
+        screen.process(b"\x1b[?5l");
+        assert!(!screen.is_mode_set(Mode::ReverseScreen));
+    }
+
+    #[test]
+    fn test_decrqm_private_modes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?7$p\x1b[?6$p\x1b[?2004h\x1b[?2004$p");
+
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec!["\x1b[?7;1$y", "\x1b[?6;2$y", "\x1b[?2004;1$y"]
+        );
+    }
+
+    #[test]
+    fn test_decrqm_ansi_modes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4h\x1b[4$p\x1b[20$p\x1b[99$p");
+
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec!["\x1b[4;1$y", "\x1b[20;2$y", "\x1b[99;0$y"]
+        );
+    }
+
+    #[test]
+    fn test_mouse_modes_replace_each_other() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[?1000h\x1b[?1003h");
+        assert!(!screen.is_mode_set(Mode::NormalMouse));
+        assert_eq!(screen.mouse_mode(), MouseMode::AnyEvent);
+
+        screen.process(b"\x1b[?1000$p");
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[?1000;2$y"]);
+    }
+
+    #[test]
+    fn test_ris_resets_modes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4h\x1b[?6h\x1b[?7l\x1b[?1049h");
+        screen.process(b"\x1bc");
+
+        assert!(!screen.is_mode_set(Mode::Insert));
+        assert!(!screen.is_mode_set(Mode::Origin));
+        assert!(screen.is_mode_set(Mode::AutoWrap));
+        assert!(!screen.is_alternate_screen());
+    }
+}
+
+#[cfg(test)]
+mod extended_sgr_tests {
+    use super::*;
+
+    fn first_cell(screen: &TerminalScreen) -> Cell {
+        screen.get_all_lines()[0][0].clone()
+    }
+
+    #[test]
+    fn test_underline_styles() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4:3mA");
+        let cell = first_cell(&screen);
+        assert!(cell.underline);
+        assert_eq!(cell.underline_style, UnderlineStyle::Curly);
+
+        screen.process(b"\x1b[4:0m\rA");
+        assert!(!first_cell(&screen).underline);
+
+        screen.process(b"\x1b[21m\rA");
+        let cell = first_cell(&screen);
+        assert!(cell.underline);
+        assert_eq!(cell.underline_style, UnderlineStyle::Double);
+
+        screen.process(b"\x1b[24m\rA");
+        assert!(!first_cell(&screen).underline);
+    }
+
+    #[test]
+    fn test_underline_color() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4;58:2::255:128:0mA");
+        assert_eq!(
+            first_cell(&screen).underline_color,
+            Some(AnsiColor::Rgb(255, 128, 0))
+        );
+
+        screen.process(b"\x1b[58;5;196m\rA");
+        assert_eq!(
+            first_cell(&screen).underline_color,
+            Some(AnsiColor::Palette256(196))
+        );
+
+        screen.process(b"\x1b[59m\rA");
+        assert_eq!(first_cell(&screen).underline_color, None);
+    }
+
+    #[test]
+    fn test_colon_truecolor_forms() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[38:2:10:20:30mA");
+        assert_eq!(first_cell(&screen).fg, Some(AnsiColor::Rgb(10, 20, 30)));
+
+        // Form with an (empty) colour space id
+        screen.process(b"\x1b[48:2::40:50:60m\rA");
+        assert_eq!(first_cell(&screen).bg, Some(AnsiColor::Rgb(40, 50, 60)));
+    }
+
+    #[test]
+    fn test_blink_hidden_overline() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[5;8;53mA");
+        let cell = first_cell(&screen);
+        assert!(cell.blink && cell.hidden && cell.overline);
+
+        screen.process(b"\x1b[25;28;55m\rA");
+        let cell = first_cell(&screen);
+        assert!(!cell.blink && !cell.hidden && !cell.overline);
+
+        // Rapid blink is treated like slow blink
+        screen.process(b"\x1b[6m\rA");
+        assert!(first_cell(&screen).blink);
+    }
+
+    #[test]
+    fn test_sgr_reset_clears_extended_attributes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4:4;5;8;53;58;5;1m\x1b[0mA");
+        let cell = first_cell(&screen);
+        assert!(!cell.underline && !cell.blink && !cell.hidden && !cell.overline);
+        assert_eq!(cell.underline_style, UnderlineStyle::Single);
+        assert_eq!(cell.underline_color, None);
+    }
+
+    #[test]
+    fn test_compression_preserves_extended_attributes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4:3;58;2;1;2;3;5;53mAB\x1b[0mCD");
+        let line = screen.get_all_lines()[0].clone();
+
+        let restored = CompressedLine::compress(&line).decompress();
+        assert_eq!(restored[1].underline_style, UnderlineStyle::Curly);
+        assert_eq!(restored[1].underline_color, Some(AnsiColor::Rgb(1, 2, 3)));
+        assert!(restored[1].blink && restored[1].overline);
+        assert!(!restored[2].blink);
+    }
+
+    #[test]
+    fn test_decrqss_reports_extended_attributes() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"\x1b[4:3;5;8;53;58:5:9m\x1bP$qm\x1b\\");
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec!["\x1bP1$r0;4:3;5;8;53;58;5;9m\x1b\\"]
+        );
+    }
+}
+
+#[cfg(test)]
+mod grapheme_tests {
+    use super::*;
+    use crate::terminal::selection::{Selection, SelectionMode};
+
+    fn cluster(screen: &TerminalScreen, row: usize, col: usize) -> String {
+        let mut text = String::new();
+        screen.get_all_lines()[row][col].push_grapheme(&mut text);
+        text
+    }
+
+    #[test]
+    fn test_combining_mark_joins_base_cell() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process("e\u{301}\u{323}x".as_bytes());
+
+        assert_eq!(cluster(&screen, 0, 0), "e\u{301}\u{323}");
+        assert_eq!(cluster(&screen, 0, 1), "x");
+        assert_eq!(screen.cursor_position(), (0, 2));
+    }
+
+    #[test]
+    fn test_hangul_jamo_sequence() {
+        let mut screen = TerminalScreen::new(10, 3);
+        // 한 written as conjoining jamo (as in NFD file names)
+        screen.process("\u{1112}\u{1161}\u{11ab}\u{1100}\u{1173}\u{11af}".as_bytes());
+
+        let lines = screen.get_all_lines();
+        assert_eq!(cluster(&screen, 0, 0), "\u{1112}\u{1161}\u{11ab}");
+        assert!(lines[0][0].wide && lines[0][1].placeholder);
+        assert_eq!(cluster(&screen, 0, 2), "\u{1100}\u{1173}\u{11af}");
+        assert_eq!(screen.cursor_position(), (0, 4));
+    }
+
+    #[test]
+    fn test_zwj_sequence_and_flags() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process("\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}\u{1f1f0}\u{1f1f7}".as_bytes());
+
+        assert_eq!(
+            cluster(&screen, 0, 0),
+            "\u{1f468}\u{200d}\u{1f469}\u{200d}\u{1f467}"
+        );
+        assert_eq!(cluster(&screen, 0, 2), "\u{1f1f0}\u{1f1f7}");
+        assert!(screen.get_all_lines()[0][2].wide);
+        assert_eq!(screen.cursor_position(), (0, 4));
+    }
+
+    #[test]
+    fn test_variation_selectors_change_width() {
+        let mut screen = TerminalScreen::new(10, 3);
+        // VS16 widens a narrow base
+        screen.process("\u{2764}\u{fe0f}x".as_bytes());
+        let lines = screen.get_all_lines();
+        assert!(lines[0][0].wide && lines[0][1].placeholder);
+        assert_eq!(cluster(&screen, 0, 2), "x");
+
+        // VS15 narrows a wide base
+        screen.process("\r\n\u{231a}\u{fe0e}y".as_bytes());
+        let lines = screen.get_all_lines();
+        assert!(!lines[1][0].wide);
+        assert_eq!(cluster(&screen, 1, 1), "y");
+    }
+
+    #[test]
+    fn test_combining_mark_after_cursor_movement_starts_new_cell() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process("e\x1b[1C\u{301}".as_bytes());
+
+        assert_eq!(cluster(&screen, 0, 0), "e");
+        assert_eq!(cluster(&screen, 0, 2), "\u{301}");
+    }
+
+    #[test]
+    fn test_clusters_survive_compression() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process("e\u{301}e\u{301}e".as_bytes());
+        let line = screen.get_all_lines()[0].clone();
+
+        let restored = CompressedLine::compress(&line).decompress();
+        assert_eq!(
+            restored[0].extra.as_deref().map(String::as_str),
+            Some("\u{301}")
+        );
+        assert_eq!(
+            restored[1].extra.as_deref().map(String::as_str),
+            Some("\u{301}")
+        );
+        assert_eq!(restored[2].extra, None);
+    }
+
+    #[test]
+    fn test_selection_extracts_clusters() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process("ab\u{1112}\u{1161}\u{11ab}e\u{301}".as_bytes());
+
+        let mut selection = Selection::new();
+        selection.start(0, 2, SelectionMode::Character);
+        selection.extend(0, 4);
+        assert_eq!(
+            selection.extract_text(&screen),
+            "\u{1112}\u{1161}\u{11ab}e\u{301}"
+        );
+    }
+}
+
+#[cfg(test)]
+mod window_ops_tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_reports_only_when_enabled() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.report_focus(true);
+        assert!(screen.take_pending_responses().is_empty());
+
+        screen.process(b"\x1b[?1004h");
+        screen.report_focus(true);
+        screen.report_focus(false);
+        assert_eq!(screen.take_pending_responses(), vec!["\x1b[I", "\x1b[O"]);
+
+        screen.process(b"\x1b[?1004l");
+        screen.report_focus(false);
+        assert!(screen.take_pending_responses().is_empty());
+    }
+
+    #[test]
+    fn test_decscusr_sets_shape_and_blink() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b[6 q");
+        assert_eq!(screen.cursor_shape(), 6);
+        assert!(!screen.cursor_blink_enabled());
+
+        screen.process(b"\x1b[3 q");
+        assert_eq!(screen.cursor_shape(), 3);
+        assert!(screen.cursor_blink_enabled());
+
+        screen.process(b"\x1b[0 q");
+        assert_eq!(screen.cursor_shape(), 0);
+        assert!(screen.cursor_blink_enabled());
+    }
+
+    #[test]
+    fn test_size_reports() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.set_cell_pixel_size(9, 18);
+        screen.process(b"\x1b[18t\x1b[19t\x1b[14t\x1b[16t");
+        assert_eq!(
+            screen.take_pending_responses(),
+            vec![
+                "\x1b[8;24;80t",
+                "\x1b[9;24;80t",
+                "\x1b[4;432;720t",
+                "\x1b[6;18;9t"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_title_reports_are_not_answered() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b]2;secret\x07\x1b[21t\x1b[20t");
+        assert!(screen.take_pending_responses().is_empty());
+    }
+
+    #[test]
+    fn test_title_push_and_pop() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b]0;shell\x07\x1b[22;0t\x1b]2;vim\x07");
+        assert_eq!(screen.window_title(), Some("vim"));
+
+        screen.process(b"\x1b[23;0t");
+        assert_eq!(screen.window_title(), Some("shell"));
+
+        // Popping the icon name leaves the title alone
+        screen.process(b"\x1b[22;1t\x1b]2;top\x07\x1b[23;1t");
+        assert_eq!(screen.window_title(), Some("top"));
+
+        // Popping an empty stack is a no-op
+        screen.process(b"\x1b[23;0t");
+        assert_eq!(screen.window_title(), Some("top"));
+    }
+
+    #[test]
+    fn test_icon_and_title_stacks_are_separate() {
+        let mut screen = TerminalScreen::new(80, 24);
+        screen.process(b"\x1b]1;icon\x07\x1b]2;title\x07");
+
+        // Pushing only the icon name and popping both leaves the title alone
+        screen.process(b"\x1b[22;1t\x1b]0;changed\x07\x1b[23;0t");
+        assert_eq!(screen.icon_name(), Some("icon"));
+        assert_eq!(screen.window_title(), Some("changed"));
+
+        // A title pushed before an icon name is still popped after it
+        screen.process(b"\x1b[22;2t\x1b[22;1t\x1b]0;other\x07\x1b[23;1t\x1b[23;2t");
+        assert_eq!(screen.icon_name(), Some("icon"));
+        assert_eq!(screen.window_title(), Some("changed"));
+    }
+}
+
+#[cfg(test)]
+mod scrollback_restore_tests {
+    use super::*;
+
+    fn line_text(line: &[Cell]) -> String {
+        line.iter()
+            .map(|cell| cell.c)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_save_and_restore_scrollback() {
+        let mut screen = TerminalScreen::new(20, 3);
+        screen.process(b"one\r\ntwo\r\n\x1b[1mthree\x1b[0m\r\nfour");
+        assert_eq!(screen.scrollback_size(), 1);
+        let data = screen.scrollback_snapshot(100).encode();
+
+        let mut restored = TerminalScreen::new(20, 3);
+        assert_eq!(restored.restore_scrollback(&data, "restored").unwrap(), 4);
+        let lines = restored.get_all_lines();
+        let texts: Vec<String> = lines.iter().map(|line| line_text(line)).collect();
+        assert_eq!(&texts[..4], ["one", "two", "three", "four"]);
+        assert!(lines[2][0].bold);
+
+        // The separator is printed at the top and the shell starts below it
+        assert!(texts[4].starts_with("── restored ──"));
+        assert!(lines[4][0].dim);
+        assert_eq!(restored.cursor_position(), (1, 0));
+    }
+
+    #[test]
+    fn test_save_scrollback_limits_lines() {
+        let mut screen = TerminalScreen::new(20, 3);
+        screen.process(b"one\r\ntwo\r\nthree\r\nfour");
+        let data = screen.scrollback_snapshot(2).encode();
+
+        let mut restored = TerminalScreen::new(20, 3);
+        assert_eq!(restored.restore_scrollback(&data, "restored").unwrap(), 2);
+        assert_eq!(line_text(&restored.get_all_lines()[0]), "three");
+    }
+
+    #[test]
+    fn test_save_scrollback_skips_alternate_screen() {
+        let mut screen = TerminalScreen::new(20, 3);
+        screen.process(b"$ vim\r\n\x1b[?1049hediting");
+        let data = screen.scrollback_snapshot(100).encode();
+
+        let mut restored = TerminalScreen::new(20, 3);
+        assert_eq!(restored.restore_scrollback(&data, "restored").unwrap(), 1);
+        assert_eq!(line_text(&restored.get_all_lines()[0]), "$ vim");
+    }
+
+    #[test]
+    fn test_restore_rejects_corrupt_data() {
+        let mut screen = TerminalScreen::new(20, 3);
+        assert!(screen.restore_scrollback(b"garbage", "restored").is_err());
+        assert_eq!(screen.scrollback_size(), 0);
+    }
+}
+
+#[cfg(test)]
+mod scrollback_spill_tests {
+    use super::*;
+    use crate::terminal::selection::GetLineText;
+
+    fn spilling_screen(dir: &std::path::Path) -> TerminalScreen {
+        TerminalScreen::with_scrollback(
+            20,
+            3,
+            ScrollbackConfig {
+                max_lines: 0,
+                disk_dir: Some(dir.to_path_buf()),
+                memory_lines: 10,
+            },
+        )
+    }
+
+    fn line_text(line: &[Cell]) -> String {
+        line.iter()
+            .map(|cell| cell.c)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_history_beyond_memory_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut screen = spilling_screen(dir.path());
+        for i in 0..100 {
+            screen.process(format!("line {i}\r\n").as_bytes());
+        }
+
+        // 98 lines scrolled off, the oldest of them on disk
+        assert_eq!(screen.scrollback_size(), 98);
+        assert_eq!(screen.total_lines(), 101);
+        assert!(screen.first_memory_line() >= 88);
+        assert_eq!(screen.memory_stats().scrollback_lines, 98);
+
+        let lines = screen.get_lines(0..3);
+        let texts: Vec<String> = lines.iter().map(|line| line_text(line)).collect();
+        assert_eq!(texts, ["line 0", "line 1", "line 2"]);
+        let lines = screen.get_lines(97..101);
+        let texts: Vec<String> = lines.iter().map(|line| line_text(line)).collect();
+        assert_eq!(texts, ["line 97", "line 98", "line 99", ""]);
+        assert_eq!(
+            screen.get_line_text(5).as_deref().map(str::trim_end),
+            Some("line 5")
+        );
+    }
+
+    #[test]
+    fn test_spilled_history_survives_alternate_screen() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut screen = spilling_screen(dir.path());
+        for i in 0..50 {
+            screen.process(format!("line {i}\r\n").as_bytes());
+        }
+        let total = screen.total_lines();
+
+        screen.process(b"\x1b[?1049hfull screen app\x1b[?1049l");
+        assert_eq!(screen.total_lines(), total);
+        assert_eq!(line_text(&screen.get_lines(0..1)[0]), "line 0");
+
+        // Erasing the scrollback (ED 3) removes the lines on disk too
+        screen.process(b"\x1b[3J");
+        assert_eq!(screen.scrollback_size(), 0);
+        assert_eq!(screen.first_memory_line(), 0);
+    }
+}
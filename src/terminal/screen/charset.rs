@@ -0,0 +1,185 @@
+//! Character set designation and invocation (ISO 2022 subset)
+//!
+//! Applications designate a character set into one of the four slots G0-G3
+//! (`ESC ( F`, `ESC ) F`, `ESC * F`, `ESC + F`) and invoke a slot into GL with
+//! SI/SO and the locking shifts, or for a single character with SS2/SS3.
+//! Input is decoded as UTF-8, so there is no 8-bit GR range to map: code
+//! points U+00A0-U+00FF are Latin-1 text and always pass through, and the
+//! GR locking shifts (LS1R-LS3R) are ignored.
+//! Only the sets still used in practice are supported:
+//! - `B` - US ASCII (default)
+//! - `0` - DEC Special Graphics (line drawing)
+//! - `A` - United Kingdom (`#` is replaced by `£`)
+
+/// A designatable 94-character set
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Charset {
+    /// US ASCII - characters are passed through
+    #[default]
+    Ascii,
+    /// DEC Special Graphics - line drawing and symbols
+    DecSpecialGraphics,
+    /// United Kingdom national replacement set
+    Uk,
+}
+
+impl Charset {
+    /// Look up a charset from the final byte of a designation sequence
+    pub(crate) fn from_final_byte(byte: u8) -> Option<Self> {
+        match byte {
+            b'B' => Some(Charset::Ascii),
+            b'0' => Some(Charset::DecSpecialGraphics),
+            b'A' => Some(Charset::Uk),
+            _ => None,
+        }
+    }
+
+    /// Translate a character from the 94-character range through this set
+    fn translate(self, c: char) -> char {
+        match self {
+            Charset::Ascii => c,
+            Charset::Uk if c == '#' => '£',
+            Charset::Uk => c,
+            Charset::DecSpecialGraphics => match c {
+                '_' => '\u{00a0}',
+                '`' => '◆',
+                'a' => '▒',
+                'b' => '␉',
+                'c' => '␌',
+                'd' => '␍',
+                'e' => '␊',
+                'f' => '°',
+                'g' => '±',
+                'h' => '␤',
+                'i' => '␋',
+                'j' => '┘',
+                'k' => '┐',
+                'l' => '┌',
+                'm' => '└',
+                'n' => '┼',
+                'o' => '⎺',
+                'p' => '⎻',
+                'q' => '─',
+                'r' => '⎼',
+                's' => '⎽',
+                't' => '├',
+                'u' => '┤',
+                'v' => '┴',
+                'w' => '┬',
+                'x' => '│',
+                'y' => '≤',
+                'z' => '≥',
+                '{' => 'π',
+                '|' => '≠',
+                '}' => '£',
+                '~' => '·',
+                _ => c,
+            },
+        }
+    }
+}
+
+/// G0-G3 designations and their GL invocation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) struct CharsetState {
+    /// Charsets designated into G0-G3
+    slots: [Charset; 4],
+    /// Slot invoked into GL (0x20-0x7F)
+    gl: usize,
+    /// Slot used for the next character only (SS2/SS3)
+    single_shift: Option<usize>,
+}
+
+impl CharsetState {
+    /// Designate a charset into slot G0-G3
+    pub(crate) fn designate(&mut self, slot: usize, charset: Charset) {
+        if let Some(target) = self.slots.get_mut(slot) {
+            *target = charset;
+        }
+    }
+
+    /// Invoke a slot into GL (SI, SO, LS2, LS3)
+    pub(crate) fn invoke_gl(&mut self, slot: usize) {
+        self.gl = slot.min(3);
+    }
+
+    /// Use a slot for the next printed character only (SS2, SS3)
+    pub(crate) fn single_shift(&mut self, slot: usize) {
+        self.single_shift = Some(slot.min(3));
+    }
+
+    /// Translate a printed character, consuming any pending single shift
+    pub(crate) fn translate(&mut self, c: char) -> char {
+        let single_shift = self.single_shift.take();
+        match c {
+            '\u{20}'..='\u{7e}' => self.slots[single_shift.unwrap_or(self.gl)].translate(c),
+            _ => c,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_passes_through() {
+        let mut state = CharsetState::default();
+        assert_eq!(state.translate('q'), 'q');
+        assert_eq!(state.translate('#'), '#');
+        assert_eq!(state.translate('é'), 'é');
+    }
+
+    #[test]
+    fn test_dec_special_graphics() {
+        let mut state = CharsetState::default();
+        state.designate(0, Charset::DecSpecialGraphics);
+        let line: String = "lqqk".chars().map(|c| state.translate(c)).collect();
+        assert_eq!(line, "┌──┐");
+        assert_eq!(state.translate('A'), 'A');
+    }
+
+    #[test]
+    fn test_shift_in_shift_out() {
+        let mut state = CharsetState::default();
+        state.designate(1, Charset::DecSpecialGraphics);
+        assert_eq!(state.translate('x'), 'x');
+        state.invoke_gl(1);
+        assert_eq!(state.translate('x'), '│');
+        state.invoke_gl(0);
+        assert_eq!(state.translate('x'), 'x');
+    }
+
+    #[test]
+    fn test_single_shift_applies_once() {
+        let mut state = CharsetState::default();
+        state.designate(2, Charset::Uk);
+        state.single_shift(2);
+        assert_eq!(state.translate('#'), '£');
+        assert_eq!(state.translate('#'), '#');
+    }
+
+    #[test]
+    fn test_latin1_passes_through() {
+        let mut state = CharsetState::default();
+        state.designate(2, Charset::DecSpecialGraphics);
+        state.designate(3, Charset::DecSpecialGraphics);
+        // U+00F1 would be 'q' with the high bit set in an 8-bit GR mapping
+        assert_eq!(state.translate('\u{f1}'), 'ñ');
+        state.single_shift(2);
+        assert_eq!(state.translate('é'), 'é');
+        // The single shift was consumed by the Latin-1 character
+        assert_eq!(state.translate('q'), 'q');
+    }
+
+    #[test]
+    fn test_from_final_byte() {
+        assert_eq!(
+            Charset::from_final_byte(b'0'),
+            Some(Charset::DecSpecialGraphics)
+        );
+        assert_eq!(Charset::from_final_byte(b'A'), Some(Charset::Uk));
+        assert_eq!(Charset::from_final_byte(b'B'), Some(Charset::Ascii));
+        assert_eq!(Charset::from_final_byte(b'<'), None);
+    }
+}
@@ -0,0 +1,175 @@
+//! Device Control String (DCS) state machine
+//!
+//! VTE hands DCS sequences to the screen in three steps: `hook` (final byte and
+//! parameters), `put` (payload bytes) and `unhook` (string terminator). This
+//! module buffers the payload between those calls and provides the helpers
+//! needed to answer the queries AgTerm supports:
+//! - DECRQSS (`DCS $ q <setting> ST`) - request selection or setting
+//! - XTGETTCAP (`DCS + q <hex-names> ST`) - request termcap/terminfo strings
+//! - SIXEL graphics (`DCS P1 ; P2 ; P3 q <sixel-data> ST`)
+
+/// Maximum payload size for query-style DCS sequences (DECRQSS, XTGETTCAP)
+const MAX_QUERY_PAYLOAD: usize = 4096;
+
+/// Maximum payload size for SIXEL images (16MB)
+const MAX_SIXEL_PAYLOAD: usize = 16 * 1024 * 1024;
+
+/// Kind of DCS sequence currently being received
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DcsKind {
+    /// DECRQSS - `DCS $ q Pt ST`
+    RequestStatusString,
+    /// XTGETTCAP - `DCS + q Pt ST`
+    RequestTermcap,
+    /// SIXEL graphics - `DCS P1 ; P2 ; P3 q <data> ST`
+    Sixel,
+    /// Any other DCS sequence - payload is discarded
+    Unsupported,
+}
+
+impl DcsKind {
+    /// Classify a DCS sequence from its intermediates and final byte
+    pub(crate) fn from_hook(intermediates: &[u8], action: char) -> Self {
+        match (intermediates, action) {
+            ([b'$'], 'q') => DcsKind::RequestStatusString,
+            ([b'+'], 'q') => DcsKind::RequestTermcap,
+            ([], 'q') => DcsKind::Sixel,
+            _ => DcsKind::Unsupported,
+        }
+    }
+
+    /// Maximum number of payload bytes buffered for this kind
+    fn max_payload(&self) -> usize {
+        match self {
+            DcsKind::RequestStatusString | DcsKind::RequestTermcap => MAX_QUERY_PAYLOAD,
+            DcsKind::Sixel => MAX_SIXEL_PAYLOAD,
+            DcsKind::Unsupported => 0,
+        }
+    }
+}
+
+/// In-flight DCS sequence (between `hook` and `unhook`)
+#[derive(Debug, Clone)]
+pub(crate) struct DcsState {
+    /// What kind of sequence this is
+    pub kind: DcsKind,
+    /// Numeric parameters passed to `hook`
+    pub params: Vec<u16>,
+    /// Buffered payload bytes
+    pub payload: Vec<u8>,
+    /// Set when the payload exceeded the size limit and was truncated
+    pub overflowed: bool,
+}
+
+impl DcsState {
+    /// Start a new DCS sequence
+    pub(crate) fn new(kind: DcsKind, params: Vec<u16>) -> Self {
+        Self {
+            kind,
+            params,
+            payload: Vec::new(),
+            overflowed: false,
+        }
+    }
+
+    /// Append a payload byte, respecting the per-kind size limit
+    ///
+    /// Unsupported sequences never buffer anything, so they are not flagged
+    /// as overflowed either.
+    pub(crate) fn put(&mut self, byte: u8) {
+        if self.kind == DcsKind::Unsupported {
+            return;
+        }
+        if self.payload.len() < self.kind.max_payload() {
+            self.payload.push(byte);
+        } else {
+            self.overflowed = true;
+        }
+    }
+}
+
+/// Encode bytes as uppercase hexadecimal (XTGETTCAP wire format)
+pub(crate) fn hex_encode(data: &[u8]) -> String {
+    data.iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// Decode a hexadecimal string, returning `None` on malformed input
+pub(crate) fn hex_decode(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// Look up a terminfo capability answered through XTGETTCAP
+///
+/// Returns `Some("")` for boolean capabilities, which are reported without a value.
+pub(crate) fn termcap_value(name: &str) -> Option<&'static str> {
+    let value = match name {
+        "TN" | "name" => "xterm-256color",
+        "Co" | "colors" => "256",
+        "RGB" => "8/8/8",
+        "Tc" => "",
+        "setrgbf" => "\x1b[38;2;%p1%d;%p2%d;%p3%dm",
+        "setrgbb" => "\x1b[48;2;%p1%d;%p2%d;%p3%dm",
+        "sitm" => "\x1b[3m",
+        "ritm" => "\x1b[23m",
+        "smxx" => "\x1b[9m",
+        "rmxx" => "\x1b[29m",
+        "Ss" => "\x1b[%p1%d q",
+        "Se" => "\x1b[0 q",
+        "Ms" => "\x1b]52;%p1%s;%p2%s\x07",
+        "BE" => "\x1b[?2004h",
+        "BD" => "\x1b[?2004l",
+        _ => return None,
+    };
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dcs_kind_from_hook() {
+        assert_eq!(DcsKind::from_hook(b"$", 'q'), DcsKind::RequestStatusString);
+        assert_eq!(DcsKind::from_hook(b"+", 'q'), DcsKind::RequestTermcap);
+        assert_eq!(DcsKind::from_hook(b"", 'q'), DcsKind::Sixel);
+        assert_eq!(DcsKind::from_hook(b"", 'p'), DcsKind::Unsupported);
+        assert_eq!(DcsKind::from_hook(b"$", 'p'), DcsKind::Unsupported);
+    }
+
+    #[test]
+    fn test_payload_limit() {
+        let mut state = DcsState::new(DcsKind::RequestStatusString, vec![]);
+        for _ in 0..MAX_QUERY_PAYLOAD + 10 {
+            state.put(b'x');
+        }
+        assert_eq!(state.payload.len(), MAX_QUERY_PAYLOAD);
+        assert!(state.overflowed);
+
+        let mut unsupported = DcsState::new(DcsKind::Unsupported, vec![]);
+        unsupported.put(b'x');
+        assert!(unsupported.payload.is_empty());
+        assert!(!unsupported.overflowed);
+    }
+
+    #[test]
+    fn test_hex_roundtrip() {
+        assert_eq!(hex_encode(b"TN"), "544E");
+        assert_eq!(hex_decode("544E"), Some(b"TN".to_vec()));
+        assert_eq!(hex_decode("544e"), Some(b"TN".to_vec()));
+        assert_eq!(hex_decode("544"), None);
+        assert_eq!(hex_decode("ZZ"), None);
+    }
+
+    #[test]
+    fn test_termcap_value() {
+        assert_eq!(termcap_value("TN"), Some("xterm-256color"));
+        assert_eq!(termcap_value("Tc"), Some(""));
+        assert_eq!(termcap_value("unknown"), None);
+    }
+}
@@ -0,0 +1,103 @@
+//! Grapheme cluster handling for printed text
+//!
+//! A cell holds one grapheme cluster: its base character in `Cell::c` and any
+//! following codepoints of the same cluster in `Cell::extra`. This keeps
+//! combining accents, Hangul jamo sequences, flags (regional indicator pairs),
+//! ZWJ emoji sequences and variation selectors in the cell of their base
+//! character instead of spilling into cells of their own.
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+/// VARIATION SELECTOR-15 - request text presentation
+const TEXT_PRESENTATION: char = '\u{fe0e}';
+/// VARIATION SELECTOR-16 - request emoji presentation
+const EMOJI_PRESENTATION: char = '\u{fe0f}';
+
+/// Check if appending `c` to `cluster` keeps it a single grapheme cluster
+pub(crate) fn extends_cluster(cluster: &str, c: char) -> bool {
+    // ASCII never continues a cluster (CR LF is never printed)
+    if c.is_ascii() || cluster.is_empty() {
+        return false;
+    }
+    let mut joined = String::with_capacity(cluster.len() + c.len_utf8());
+    joined.push_str(cluster);
+    joined.push(c);
+    joined.graphemes(true).nth(1).is_none()
+}
+
+/// Number of columns (1 or 2) a grapheme cluster occupies
+///
+/// The width of the base character decides, except that VS16 widens a
+/// cluster to emoji presentation, VS15 narrows it to text presentation and a
+/// pair of regional indicators forms a (wide) flag.
+pub(crate) fn cluster_width(cluster: &str) -> usize {
+    let mut chars = cluster.chars();
+    let Some(base) = chars.next() else {
+        return 1;
+    };
+
+    if cluster.contains(EMOJI_PRESENTATION) {
+        return 2;
+    }
+    if cluster.contains(TEXT_PRESENTATION) {
+        return 1;
+    }
+    if is_regional_indicator(base) && chars.any(is_regional_indicator) {
+        return 2;
+    }
+    UnicodeWidthChar::width(base).unwrap_or(1).clamp(1, 2)
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combining_marks_extend() {
+        assert!(extends_cluster("e", '\u{301}'));
+        assert!(extends_cluster("e\u{301}", '\u{323}'));
+        assert!(!extends_cluster("e", 'x'));
+        assert!(!extends_cluster("e", 'é'));
+        assert!(!extends_cluster("", '\u{301}'));
+    }
+
+    #[test]
+    fn test_hangul_jamo_extend() {
+        // ᄒ + ᅡ + ᆫ composes 한
+        assert!(extends_cluster("\u{1112}", '\u{1161}'));
+        assert!(extends_cluster("\u{1112}\u{1161}", '\u{11ab}'));
+        assert!(!extends_cluster("\u{1112}\u{1161}\u{11ab}", '\u{1100}'));
+        assert_eq!(cluster_width("\u{1112}\u{1161}\u{11ab}"), 2);
+    }
+
+    #[test]
+    fn test_zwj_sequence_extends() {
+        // 👨 ZWJ 👩
+        assert!(extends_cluster("\u{1f468}", '\u{200d}'));
+        assert!(extends_cluster("\u{1f468}\u{200d}", '\u{1f469}'));
+        assert!(!extends_cluster("\u{1f468}", '\u{1f469}'));
+        assert_eq!(cluster_width("\u{1f468}\u{200d}\u{1f469}"), 2);
+    }
+
+    #[test]
+    fn test_regional_indicator_pairs() {
+        assert!(extends_cluster("\u{1f1f0}", '\u{1f1f7}'));
+        assert!(!extends_cluster("\u{1f1f0}\u{1f1f7}", '\u{1f1fa}'));
+        assert_eq!(cluster_width("\u{1f1f0}\u{1f1f7}"), 2);
+    }
+
+    #[test]
+    fn test_variation_selector_width() {
+        // ❤ is narrow by default
+        assert_eq!(cluster_width("\u{2764}"), 1);
+        assert_eq!(cluster_width("\u{2764}\u{fe0f}"), 2);
+        // ⌚ is wide by default
+        assert_eq!(cluster_width("\u{231a}"), 2);
+        assert_eq!(cluster_width("\u{231a}\u{fe0e}"), 1);
+    }
+}
@@ -123,6 +123,7 @@ impl CompressedLine {
 #[allow(dead_code)] // Used by CompressedLine (reserved for future)
 fn is_default_cell(cell: &super::Cell) -> bool {
     cell.c == ' '
+        && cell.extra.is_none()
         && cell.fg.is_none()
         && cell.bg.is_none()
         && !cell.bold
@@ -131,6 +132,10 @@ fn is_default_cell(cell: &super::Cell) -> bool {
         && !cell.dim
         && !cell.italic
         && !cell.strikethrough
+        && !cell.blink
+        && !cell.hidden
+        && !cell.overline
+        && cell.underline_color.is_none()
         && !cell.wide
         && !cell.placeholder
         && cell.hyperlink.is_none()
@@ -0,0 +1,216 @@
+//! Terminal mode registry
+//!
+//! Every mode that can be changed with SM/RM (`CSI Ps h/l`) or DECSET/DECRST
+//! (`CSI ? Ps h/l`) is listed here, so the set/reset handlers and DECRQM
+//! (`CSI Ps $ p`, `CSI ? Ps $ p`) share one table.
+
+/// A mode settable with SM/RM (ANSI) or DECSET/DECRST (DEC private)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// IRM - insert printed characters instead of replacing (ANSI 4)
+    Insert,
+    /// LNM - line feed also returns the carriage (ANSI 20)
+    LineFeedNewLine,
+    /// DECCKM - application cursor keys (?1)
+    ApplicationCursorKeys,
+    /// DECSCNM - reverse video for the whole screen (?5)
+    ReverseScreen,
+    /// DECOM - cursor addressing relative to the scroll region (?6)
+    Origin,
+    /// DECAWM - wrap at the right margin (?7)
+    AutoWrap,
+    /// X10 mouse reporting (?9)
+    X10Mouse,
+    /// AT&T 610 - blinking cursor (?12)
+    CursorBlink,
+    /// DECTCEM - cursor visible (?25)
+    ShowCursor,
+    /// Alternate screen buffer (?47)
+    AlternateScreen,
+    /// Normal mouse tracking (?1000)
+    NormalMouse,
+    /// Button-event mouse tracking (?1002)
+    ButtonEventMouse,
+    /// Any-event mouse tracking (?1003)
+    AnyEventMouse,
+    /// Focus in/out reports (?1004)
+    FocusEvents,
+    /// UTF-8 mouse encoding (?1005)
+    Utf8Mouse,
+    /// SGR mouse encoding (?1006)
+    SgrMouse,
+    /// urxvt mouse encoding (?1015)
+    UrxvtMouse,
+    /// SGR mouse encoding with pixel coordinates (?1016)
+    SgrPixelsMouse,
+    /// Alternate screen buffer, cleared on entry (?1047)
+    AlternateScreenClear,
+    /// Save the cursor and switch to the cleared alternate screen (?1049)
+    AlternateScreenSaveCursor,
+    /// Bracketed paste (?2004)
+    BracketedPaste,
+    /// Synchronized output (?2026)
+    SynchronizedOutput,
+}
+
+impl Mode {
+    /// Look up an ANSI mode number (SM/RM)
+    pub fn from_ansi(number: u16) -> Option<Self> {
+        match number {
+            4 => Some(Mode::Insert),
+            20 => Some(Mode::LineFeedNewLine),
+            _ => None,
+        }
+    }
+
+    /// Look up a DEC private mode number (DECSET/DECRST)
+    pub fn from_dec(number: u16) -> Option<Self> {
+        match number {
+            1 => Some(Mode::ApplicationCursorKeys),
+            5 => Some(Mode::ReverseScreen),
+            6 => Some(Mode::Origin),
+            7 => Some(Mode::AutoWrap),
+            9 => Some(Mode::X10Mouse),
+            12 => Some(Mode::CursorBlink),
+            25 => Some(Mode::ShowCursor),
+            47 => Some(Mode::AlternateScreen),
+            1000 => Some(Mode::NormalMouse),
+            1002 => Some(Mode::ButtonEventMouse),
+            1003 => Some(Mode::AnyEventMouse),
+            1004 => Some(Mode::FocusEvents),
+            1005 => Some(Mode::Utf8Mouse),
+            1006 => Some(Mode::SgrMouse),
+            1015 => Some(Mode::UrxvtMouse),
+            1016 => Some(Mode::SgrPixelsMouse),
+            1047 => Some(Mode::AlternateScreenClear),
+            1049 => Some(Mode::AlternateScreenSaveCursor),
+            2004 => Some(Mode::BracketedPaste),
+            2026 => Some(Mode::SynchronizedOutput),
+            _ => None,
+        }
+    }
+
+    /// Modes that replace each other, including this one (empty if none)
+    ///
+    /// Mouse tracking modes, mouse encodings and alternate screen modes are
+    /// mutually exclusive.
+    pub(crate) fn group(self) -> &'static [Mode] {
+        const MOUSE_TRACKING: &[Mode] = &[
+            Mode::X10Mouse,
+            Mode::NormalMouse,
+            Mode::ButtonEventMouse,
+            Mode::AnyEventMouse,
+        ];
+        const MOUSE_ENCODING: &[Mode] = &[
+            Mode::Utf8Mouse,
+            Mode::SgrMouse,
+            Mode::UrxvtMouse,
+            Mode::SgrPixelsMouse,
+        ];
+        const ALTERNATE_SCREEN: &[Mode] = &[
+            Mode::AlternateScreen,
+            Mode::AlternateScreenClear,
+            Mode::AlternateScreenSaveCursor,
+        ];
+        if MOUSE_TRACKING.contains(&self) {
+            MOUSE_TRACKING
+        } else if MOUSE_ENCODING.contains(&self) {
+            MOUSE_ENCODING
+        } else if ALTERNATE_SCREEN.contains(&self) {
+            ALTERNATE_SCREEN
+        } else {
+            &[]
+        }
+    }
+
+    /// Bit of this mode in a [`ModeTable`]
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// Set/reset state of every [`Mode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ModeTable {
+    bits: u32,
+}
+
+impl Default for ModeTable {
+    fn default() -> Self {
+        Self {
+            bits: Mode::AutoWrap.bit() | Mode::CursorBlink.bit() | Mode::ShowCursor.bit(),
+        }
+    }
+}
+
+impl ModeTable {
+    /// Check if a mode is set
+    pub(crate) fn get(&self, mode: Mode) -> bool {
+        self.bits & mode.bit() != 0
+    }
+
+    /// Set or reset a mode
+    pub(crate) fn set(&mut self, mode: Mode, enabled: bool) {
+        if enabled {
+            self.bits |= mode.bit();
+        } else {
+            self.bits &= !mode.bit();
+        }
+    }
+
+    /// DECRPM state of a mode: 1 = set, 2 = reset
+    pub(crate) fn report(&self, mode: Mode) -> u8 {
+        if self.get(mode) {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let table = ModeTable::default();
+        assert!(table.get(Mode::AutoWrap));
+        assert!(table.get(Mode::ShowCursor));
+        assert!(table.get(Mode::CursorBlink));
+        assert!(!table.get(Mode::Insert));
+        assert!(!table.get(Mode::Origin));
+    }
+
+    #[test]
+    fn test_set_and_reset() {
+        let mut table = ModeTable::default();
+        table.set(Mode::Origin, true);
+        assert!(table.get(Mode::Origin));
+        assert_eq!(table.report(Mode::Origin), 1);
+
+        table.set(Mode::Origin, false);
+        assert!(!table.get(Mode::Origin));
+        assert_eq!(table.report(Mode::Origin), 2);
+    }
+
+    #[test]
+    fn test_mode_numbers() {
+        assert_eq!(Mode::from_ansi(4), Some(Mode::Insert));
+        assert_eq!(Mode::from_ansi(20), Some(Mode::LineFeedNewLine));
+        assert_eq!(Mode::from_ansi(1), None);
+        assert_eq!(Mode::from_dec(6), Some(Mode::Origin));
+        assert_eq!(Mode::from_dec(2026), Some(Mode::SynchronizedOutput));
+        assert_eq!(Mode::from_dec(4), None);
+    }
+
+    #[test]
+    fn test_groups() {
+        assert!(Mode::NormalMouse.group().contains(&Mode::AnyEventMouse));
+        assert!(Mode::SgrMouse.group().contains(&Mode::UrxvtMouse));
+        assert!(Mode::AlternateScreen
+            .group()
+            .contains(&Mode::AlternateScreenSaveCursor));
+        assert!(Mode::Origin.group().is_empty());
+    }
+}
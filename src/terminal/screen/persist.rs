@@ -0,0 +1,390 @@
+//! Compact binary encoding of screen lines for session restore
+//!
+//! Lines are written in their run-length encoded form, so a scrollback full
+//! of blank padding costs a few bytes per line. Integers are LEB128 varints
+//! and strings are length-prefixed UTF-8. Image cells are not persisted.
+//!
+//! ```text
+//! file    = "AGSB" version:u8 line_count line*
+//! line    = flags:u8 segment_count segment*
+//! segment = repeat cell
+//! cell    = char attrs:varint underline_style:u8
+//!           [extra] [fg] [bg] [underline_color] [hyperlink] [hyperlink_id]
+//! hyperlink_id = 0 name:string | 1 anonymous:varint | 2
+//! ```
+
+use std::io;
+use std::sync::Arc;
+
+use super::{AnsiColor, Cell, CompressedLine, HyperlinkId, StringInterner, UnderlineStyle};
+
+/// File signature
+const MAGIC: &[u8; 4] = b"AGSB";
+
+/// Version of the encoding, bumped on incompatible changes
+pub const SCROLLBACK_VERSION: u8 = 2;
+
+/// Line flag: soft-wrapped into the next line
+const LINE_WRAPPED: u8 = 1;
+
+/// Longest line accepted when decoding (PTY sizes are 16-bit)
+const MAX_LINE_CELLS: usize = u16::MAX as usize;
+
+// Cell attribute bits; the optional fields follow in this order when present
+const BOLD: u32 = 1 << 0;
+const UNDERLINE: u32 = 1 << 1;
+const REVERSE: u32 = 1 << 2;
+const DIM: u32 = 1 << 3;
+const ITALIC: u32 = 1 << 4;
+const STRIKETHROUGH: u32 = 1 << 5;
+const BLINK: u32 = 1 << 6;
+const HIDDEN: u32 = 1 << 7;
+const OVERLINE: u32 = 1 << 8;
+const WIDE: u32 = 1 << 9;
+const PLACEHOLDER: u32 = 1 << 10;
+const HAS_EXTRA: u32 = 1 << 11;
+const HAS_FG: u32 = 1 << 12;
+const HAS_BG: u32 = 1 << 13;
+const HAS_UNDERLINE_COLOR: u32 = 1 << 14;
+const HAS_HYPERLINK: u32 = 1 << 15;
+const HAS_HYPERLINK_ID: u32 = 1 << 16;
+
+/// Lines of a screen taken for session restore, see
+/// [`TerminalScreen::scrollback_snapshot`](super::TerminalScreen::scrollback_snapshot)
+///
+/// Can be encoded on another thread, which also reads the lines on disk.
+pub struct ScrollbackSnapshot {
+    /// The lines, oldest first
+    pub(super) lines: Box<dyn Iterator<Item = CompressedLine> + Send>,
+}
+
+impl ScrollbackSnapshot {
+    /// Encode the lines for
+    /// [`TerminalScreen::restore_scrollback`](super::TerminalScreen::restore_scrollback)
+    pub fn encode(self) -> Vec<u8> {
+        let lines: Vec<CompressedLine> = self.lines.collect();
+        encode(&lines.iter().collect::<Vec<_>>())
+    }
+}
+
+/// Version of the encoding `data` was written with, None if it is not encoded lines
+pub fn scrollback_version(data: &[u8]) -> Option<u8> {
+    data.strip_prefix(MAGIC.as_slice())?.first().copied()
+}
+
+/// Encode lines, oldest first
+pub(crate) fn encode(lines: &[&CompressedLine]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(MAGIC);
+    out.push(SCROLLBACK_VERSION);
+    write_varint(&mut out, lines.len() as u64);
+    for line in lines {
+        out.push(if line.wrapped { LINE_WRAPPED } else { 0 });
+        write_varint(&mut out, line.segments.len() as u64);
+        for segment in &line.segments {
+            write_varint(&mut out, segment.count as u64);
+            write_cell(&mut out, &segment.cell);
+        }
+    }
+    out
+}
+
+/// Decode lines written by [`encode`], sharing strings through `interner`
+pub(crate) fn decode(
+    data: &[u8],
+    interner: &mut StringInterner,
+) -> io::Result<Vec<CompressedLine>> {
+    let mut reader = Reader { data, pos: 0 };
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(invalid("not a scrollback file"));
+    }
+    let version = reader.u8()?;
+    if version != SCROLLBACK_VERSION {
+        return Err(invalid(format!("unsupported scrollback version {version}")));
+    }
+
+    let line_count = reader.len()?;
+    let mut lines = Vec::with_capacity(line_count);
+    for _ in 0..line_count {
+        let wrapped = reader.u8()? & LINE_WRAPPED != 0;
+        let segment_count = reader.len()?;
+        let mut cells = Vec::new();
+        for _ in 0..segment_count {
+            let count = reader.varint()?;
+            if cells.len() as u64 + count > MAX_LINE_CELLS as u64 {
+                return Err(invalid("line too long"));
+            }
+            let count = count as usize;
+            let cell = reader.cell(interner)?;
+            cells.extend(std::iter::repeat(cell).take(count));
+        }
+        lines.push(CompressedLine::compress(&cells).with_wrapped(wrapped));
+    }
+    Ok(lines)
+}
+
+fn write_cell(out: &mut Vec<u8>, cell: &Cell) {
+    let flags = [
+        (cell.bold, BOLD),
+        (cell.underline, UNDERLINE),
+        (cell.reverse, REVERSE),
+        (cell.dim, DIM),
+        (cell.italic, ITALIC),
+        (cell.strikethrough, STRIKETHROUGH),
+        (cell.blink, BLINK),
+        (cell.hidden, HIDDEN),
+        (cell.overline, OVERLINE),
+        (cell.wide, WIDE),
+        (cell.placeholder, PLACEHOLDER),
+        (cell.extra.is_some(), HAS_EXTRA),
+        (cell.fg.is_some(), HAS_FG),
+        (cell.bg.is_some(), HAS_BG),
+        (cell.underline_color.is_some(), HAS_UNDERLINE_COLOR),
+        (cell.hyperlink.is_some(), HAS_HYPERLINK),
+        (cell.hyperlink_id.is_some(), HAS_HYPERLINK_ID),
+    ];
+    let attrs = flags
+        .iter()
+        .filter(|(set, _)| *set)
+        .fold(0, |attrs, (_, bit)| attrs | bit);
+
+    write_varint(out, cell.c as u64);
+    write_varint(out, attrs as u64);
+    out.push(cell.underline_style as u8);
+    if let Some(extra) = &cell.extra {
+        write_str(out, extra);
+    }
+    for color in [cell.fg, cell.bg, cell.underline_color]
+        .into_iter()
+        .flatten()
+    {
+        match color {
+            AnsiColor::Indexed(index) => out.extend_from_slice(&[0, index]),
+            AnsiColor::Palette256(index) => out.extend_from_slice(&[1, index]),
+            AnsiColor::Rgb(r, g, b) => out.extend_from_slice(&[2, r, g, b]),
+        }
+    }
+    if let Some(url) = &cell.hyperlink {
+        write_str(out, url);
+    }
+    match &cell.hyperlink_id {
+        Some(HyperlinkId::Named(name)) => {
+            out.push(0);
+            write_str(out, name);
+        }
+        Some(HyperlinkId::Anonymous(id)) => {
+            out.push(1);
+            write_varint(out, *id);
+        }
+        Some(HyperlinkId::Detected) => out.push(2),
+        None => {}
+    }
+}
+
+fn write_str(out: &mut Vec<u8>, text: &str) {
+    write_varint(out, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+/// Cursor over encoded bytes, failing on truncated or malformed input
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|&end| end <= self.data.len())
+            .ok_or_else(|| invalid("truncated scrollback file"))?;
+        let bytes = &self.data[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn varint(&mut self) -> io::Result<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.u8()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(invalid("varint too long"))
+    }
+
+    /// A count of items or bytes that follow, bounded by the remaining
+    /// input so corrupt files cannot request huge allocations
+    fn len(&mut self) -> io::Result<usize> {
+        let len = self.varint()?;
+        usize::try_from(len)
+            .ok()
+            .filter(|&len| len <= self.data.len() - self.pos)
+            .ok_or_else(|| invalid("length out of range"))
+    }
+
+    fn string(&mut self, interner: &mut StringInterner) -> io::Result<Arc<String>> {
+        let len = self.len()?;
+        let text = std::str::from_utf8(self.bytes(len)?).map_err(|e| invalid(e.to_string()))?;
+        Ok(interner.intern(text.to_string()))
+    }
+
+    fn color(&mut self) -> io::Result<AnsiColor> {
+        match self.u8()? {
+            0 => Ok(AnsiColor::Indexed(self.u8()?)),
+            1 => Ok(AnsiColor::Palette256(self.u8()?)),
+            2 => Ok(AnsiColor::Rgb(self.u8()?, self.u8()?, self.u8()?)),
+            tag => Err(invalid(format!("unknown color tag {tag}"))),
+        }
+    }
+
+    fn hyperlink_id(&mut self, interner: &mut StringInterner) -> io::Result<HyperlinkId> {
+        match self.u8()? {
+            0 => Ok(HyperlinkId::Named(self.string(interner)?)),
+            1 => Ok(HyperlinkId::Anonymous(self.varint()?)),
+            2 => Ok(HyperlinkId::Detected),
+            tag => Err(invalid(format!("unknown hyperlink id tag {tag}"))),
+        }
+    }
+
+    fn cell(&mut self, interner: &mut StringInterner) -> io::Result<Cell> {
+        let c = u32::try_from(self.varint()?)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| invalid("invalid character"))?;
+        let attrs = self.varint()? as u32;
+        let underline_style = match self.u8()? {
+            0 => UnderlineStyle::Single,
+            1 => UnderlineStyle::Double,
+            2 => UnderlineStyle::Curly,
+            3 => UnderlineStyle::Dotted,
+            4 => UnderlineStyle::Dashed,
+            style => return Err(invalid(format!("unknown underline style {style}"))),
+        };
+        let has = |bit| attrs & bit != 0;
+
+        let extra = if has(HAS_EXTRA) {
+            Some(self.string(interner)?)
+        } else {
+            None
+        };
+        let mut color = |bit| has(bit).then(|| self.color()).transpose();
+        let fg = color(HAS_FG)?;
+        let bg = color(HAS_BG)?;
+        let underline_color = color(HAS_UNDERLINE_COLOR)?;
+        let hyperlink = if has(HAS_HYPERLINK) {
+            Some(self.string(interner)?)
+        } else {
+            None
+        };
+        let hyperlink_id = if has(HAS_HYPERLINK_ID) {
+            Some(self.hyperlink_id(interner)?)
+        } else {
+            None
+        };
+
+        Ok(Cell {
+            c,
+            extra,
+            fg,
+            bg,
+            bold: has(BOLD),
+            underline: has(UNDERLINE),
+            underline_style,
+            underline_color,
+            reverse: has(REVERSE),
+            dim: has(DIM),
+            italic: has(ITALIC),
+            strikethrough: has(STRIKETHROUGH),
+            blink: has(BLINK),
+            hidden: has(HIDDEN),
+            overline: has(OVERLINE),
+            wide: has(WIDE),
+            placeholder: has(PLACEHOLDER),
+            hyperlink,
+            hyperlink_id,
+            image: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str) -> Vec<Cell> {
+        text.chars()
+            .map(|c| Cell {
+                c,
+                ..Cell::default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut styled = line("ab");
+        styled[0].bold = true;
+        styled[0].fg = Some(AnsiColor::Rgb(255, 128, 0));
+        styled[0].underline = true;
+        styled[0].underline_style = UnderlineStyle::Curly;
+        styled[1].extra = Some(Arc::new("\u{301}".to_string()));
+        styled[1].hyperlink = Some(Arc::new("https://example.com".to_string()));
+        styled[1].hyperlink_id = Some(HyperlinkId::Anonymous(7));
+        styled[0].hyperlink = Some(Arc::new("https://example.org".to_string()));
+        styled[0].hyperlink_id = Some(HyperlinkId::Named(Arc::new("x".to_string())));
+        styled[1].bg = Some(AnsiColor::Palette256(208));
+
+        let lines = vec![
+            CompressedLine::compress(&line("hello   ")).with_wrapped(true),
+            CompressedLine::compress(&styled),
+            CompressedLine::compress(&[]),
+        ];
+        let data = encode(&lines.iter().collect::<Vec<_>>());
+        let decoded = decode(&data, &mut StringInterner::new()).unwrap();
+
+        assert_eq!(decoded.len(), 3);
+        assert!(decoded[0].is_wrapped());
+        assert!(!decoded[1].is_wrapped());
+        for (decoded, original) in decoded.iter().zip(&lines) {
+            let (decoded, original) = (decoded.decompress(), original.decompress());
+            assert_eq!(decoded.len(), original.len());
+            assert!(decoded
+                .iter()
+                .zip(&original)
+                .all(|(a, b)| super::super::cells_equal(a, b)));
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let mut interner = StringInterner::new();
+        assert!(decode(b"", &mut interner).is_err());
+        assert!(decode(b"XXXX\x01\x00", &mut interner).is_err());
+        assert!(decode(b"AGSB\x09\x00", &mut interner).is_err());
+
+        let data = encode(&[&CompressedLine::compress(&line("abc"))]);
+        assert!(decode(&data[..data.len() - 1], &mut interner).is_err());
+        // Line count far beyond the input size
+        assert!(decode(b"AGSB\x02\xff\xff\xff\xff\x0f", &mut interner).is_err());
+    }
+}
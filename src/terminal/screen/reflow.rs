@@ -0,0 +1,395 @@
+//! Line reflow on column resize
+//!
+//! Rows soft-wrapped by auto-wrap are joined back into logical lines and
+//! re-split at the new width, so long lines survive narrowing a pane and
+//! re-join when it is widened again. The cursor follows the logical character
+//! it was on.
+
+use super::{cells_equal, Cell, CompressedLine};
+use std::collections::VecDeque;
+
+/// A row of cells with its soft-wrap flag
+type Row = (Vec<Cell>, bool);
+
+/// Screen contents after reflowing to a new width
+#[derive(Debug)]
+pub(super) struct ReflowedScreen {
+    /// Scrollback lines (oldest first)
+    pub scrollback: VecDeque<CompressedLine>,
+    /// Visible rows, the same number as before the reflow
+    pub buffer: Vec<Vec<Cell>>,
+    /// Soft-wrap flags of the visible rows
+    pub wrapped: Vec<bool>,
+    /// Cursor position (row, col) in the visible rows
+    pub cursor: (usize, usize),
+    /// Where the reflowed lines moved
+    pub line_map: LineMap,
+}
+
+/// Where the lines of a screen moved when it was reflowed
+///
+/// Lines are numbered like [`TerminalScreen::line_offset`](super::TerminalScreen::line_offset),
+/// so positions kept outside the screen (shell marks) can follow their text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineMap {
+    /// Number of the first reflowed line, older lines (on disk) keep their numbers
+    first_line: usize,
+    /// New (line, col) of the start of each reflowed line, relative to `first_line`
+    starts: Vec<(usize, usize)>,
+    /// Width the lines were reflowed to
+    cols: usize,
+}
+
+impl LineMap {
+    /// New number of `line`
+    pub fn line(&self, line: usize) -> usize {
+        self.position(line, 0).0
+    }
+
+    /// New position of the character at (`line`, `col`)
+    pub fn position(&self, line: usize, col: usize) -> (usize, usize) {
+        let Some(index) = line.checked_sub(self.first_line) else {
+            return (line, col);
+        };
+        let (row, start) = match (self.starts.get(index), self.starts.last()) {
+            (Some(&start), _) => start,
+            // Blank rows below the content move with the last line
+            (None, Some(&(last, _))) => (last + 1 + index - self.starts.len(), 0),
+            (None, None) => return (line, col),
+        };
+        let offset = start + col;
+        (
+            self.first_line + row + offset / self.cols,
+            offset % self.cols,
+        )
+    }
+}
+
+/// Reflow scrollback and visible rows to `cols` columns
+///
+/// The top of the screen stays on the same logical line: narrowing grows the
+/// content downwards into blank rows and only then pushes lines into the
+/// scrollback, widening leaves blank rows at the bottom. Blank rows below the
+/// cursor are dropped and recreated as padding. `first_line` is the number of
+/// the first scrollback line, for the returned [`LineMap`].
+pub(super) fn reflow_screen(
+    scrollback: &VecDeque<CompressedLine>,
+    buffer: &[Vec<Cell>],
+    wrapped: &[bool],
+    cursor: (usize, usize),
+    cols: usize,
+    first_line: usize,
+) -> ReflowedScreen {
+    let rows = buffer.len();
+    let cursor_row = cursor.0.min(rows.saturating_sub(1));
+
+    // Last row worth keeping: the cursor row or any content below it
+    let last_row = (cursor_row..rows)
+        .rev()
+        .find(|&row| {
+            row == cursor_row
+                || wrapped.get(row).copied().unwrap_or(false)
+                || !is_blank_row(&buffer[row])
+        })
+        .unwrap_or(0);
+
+    let mut lines: Vec<Row> = scrollback
+        .iter()
+        .map(|line| (line.decompress(), line.is_wrapped()))
+        .collect();
+    let screen_top = lines.len();
+    lines.extend(
+        buffer
+            .iter()
+            .take(last_row + 1)
+            .enumerate()
+            .map(|(row, cells)| (cells.clone(), wrapped.get(row).copied().unwrap_or(false))),
+    );
+
+    let mut positions = [(screen_top, 0), (screen_top + cursor_row, cursor.1)];
+    let mut starts = Vec::with_capacity(lines.len());
+    let mut all = reflow_lines(&lines, &mut positions, &mut starts, cols);
+    let [(mut top, _), (cursor_row, cursor_col)] = positions;
+
+    // Keep the cursor on screen, then fit as much content below it as possible
+    if cursor_row >= top + rows {
+        top = cursor_row + 1 - rows;
+    }
+    if all.len() - top > rows {
+        top = (all.len() - rows).min(cursor_row);
+    }
+    all.truncate(top + rows);
+
+    let mut visible = all.split_off(top);
+    visible.resize(rows, (vec![Cell::default(); cols], false));
+    let (buffer, wrapped) = visible.into_iter().unzip();
+
+    let scrollback = all
+        .into_iter()
+        .map(|(cells, wrapped)| CompressedLine::compress(&cells).with_wrapped(wrapped))
+        .collect();
+
+    ReflowedScreen {
+        scrollback,
+        buffer,
+        wrapped,
+        cursor: (cursor_row - top, cursor_col),
+        line_map: LineMap {
+            first_line,
+            starts,
+            cols: cols.max(1),
+        },
+    }
+}
+
+/// Reflow scrollback lines on their own to `cols` columns
+///
+/// For lines paged back in from disk, which start and end on logical line
+/// boundaries.
+pub(super) fn reflow_scrollback(lines: &[CompressedLine], cols: usize) -> Vec<CompressedLine> {
+    let rows: Vec<Row> = lines
+        .iter()
+        .map(|line| (line.decompress(), line.is_wrapped()))
+        .collect();
+    let mut out = reflow_lines(&rows, &mut [], &mut Vec::new(), cols);
+    // A logical line continuing past the last line stays wrapped
+    if let (Some(last), Some(&(_, true))) = (out.last_mut(), rows.last()) {
+        last.1 = true;
+    }
+    out.into_iter()
+        .map(|(cells, wrapped)| CompressedLine::compress(&cells).with_wrapped(wrapped))
+        .collect()
+}
+
+/// Re-split rows into `cols` wide rows along their logical lines
+///
+/// Each (row, col) in `positions` is updated to where the same logical
+/// character ends up. Positions past the end of a line's content stay on the
+/// line's last row. `starts` receives the new position of the start of each
+/// of `lines`.
+fn reflow_lines(
+    lines: &[Row],
+    positions: &mut [(usize, usize)],
+    starts: &mut Vec<(usize, usize)>,
+    cols: usize,
+) -> Vec<Row> {
+    let mut out: Vec<Row> = Vec::with_capacity(lines.len());
+    let mut index = 0;
+
+    while index < lines.len() {
+        // Join soft-wrapped rows into one logical line
+        let mut logical: Vec<Cell> = Vec::new();
+        let mut offsets: Vec<(usize, usize)> = Vec::new();
+        let mut row_offsets: Vec<usize> = Vec::new();
+        loop {
+            let (cells, wrapped) = &lines[index];
+            row_offsets.push(logical.len());
+            for (i, &(row, col)) in positions.iter().enumerate() {
+                if row == index {
+                    offsets.push((i, logical.len() + col));
+                }
+            }
+
+            // A wide character that did not fit leaves a blank cell before the wrap
+            let next_starts_wide = lines
+                .get(index + 1)
+                .and_then(|(next, _)| next.first())
+                .is_some_and(|cell| cell.wide);
+            let mut cells = cells.as_slice();
+            if *wrapped && next_starts_wide && cells.last().is_some_and(is_blank) {
+                cells = &cells[..cells.len() - 1];
+            }
+            logical.extend_from_slice(cells);
+
+            index += 1;
+            if !*wrapped || index == lines.len() {
+                break;
+            }
+        }
+
+        // Trailing blanks are not part of the line's content
+        let len = logical
+            .iter()
+            .rposition(|cell| !is_blank(cell))
+            .map_or(0, |pos| pos + 1);
+        logical.truncate(len);
+
+        // Split the logical line at the new width
+        let first_out = out.len();
+        let mut row_starts = vec![0];
+        let mut row = Vec::with_capacity(cols);
+        let mut row_start = 0;
+        for (offset, cell) in logical.into_iter().enumerate() {
+            let wide_at_edge = cell.wide && cols > 1 && row.len() == cols - 1;
+            if row.len() == cols || wide_at_edge {
+                row.resize(cols, Cell::default());
+                out.push((std::mem::replace(&mut row, Vec::with_capacity(cols)), true));
+                row_start = offset;
+                row_starts.push(offset);
+            }
+            for &(i, _) in offsets.iter().filter(|&&(_, o)| o == offset) {
+                positions[i] = (out.len(), row.len());
+            }
+            row.push(cell);
+        }
+        for &(i, offset) in offsets.iter().filter(|&&(_, o)| o >= len) {
+            positions[i] = (out.len(), (offset - row_start).min(cols - 1));
+        }
+        for offset in row_offsets {
+            let split = row_starts.partition_point(|&start| start <= offset) - 1;
+            starts.push((
+                first_out + split,
+                (offset - row_starts[split]).min(cols.saturating_sub(1)),
+            ));
+        }
+
+        row.resize(cols, Cell::default());
+        out.push((row, false));
+    }
+
+    out
+}
+
+/// Check if a cell is indistinguishable from an erased cell
+fn is_blank(cell: &Cell) -> bool {
+    cell.image.is_none() && cells_equal(cell, &Cell::default())
+}
+
+/// Check if every cell of a row is blank
+fn is_blank_row(row: &[Cell]) -> bool {
+    row.iter().all(is_blank)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(text: &str, cols: usize, wrapped: bool) -> Row {
+        let mut cells: Vec<Cell> = text
+            .chars()
+            .map(|c| Cell {
+                c,
+                ..Default::default()
+            })
+            .collect();
+        cells.resize(cols, Cell::default());
+        (cells, wrapped)
+    }
+
+    fn text(row: &Row) -> String {
+        row.0
+            .iter()
+            .map(|cell| cell.c)
+            .collect::<String>()
+            .trim_end()
+            .to_string()
+    }
+
+    #[test]
+    fn test_reflow_narrower_splits_lines() {
+        let lines = vec![row("abcdefgh", 10, false), row("xy", 10, false)];
+        let mut positions = [(1, 2)];
+        let out = reflow_lines(&lines, &mut positions, &mut Vec::new(), 4);
+
+        let texts: Vec<String> = out.iter().map(text).collect();
+        assert_eq!(texts, vec!["abcd", "efgh", "xy"]);
+        assert!(out[0].1);
+        assert!(!out[1].1);
+        assert!(!out[2].1);
+        assert_eq!(positions, [(2, 2)]);
+    }
+
+    #[test]
+    fn test_reflow_wider_joins_wrapped_rows() {
+        let lines = vec![
+            row("abcd", 4, true),
+            row("efgh", 4, true),
+            row("ij", 4, false),
+        ];
+        let mut positions = [(1, 1)];
+        let out = reflow_lines(&lines, &mut positions, &mut Vec::new(), 10);
+
+        assert_eq!(out.len(), 1);
+        assert_eq!(text(&out[0]), "abcdefghij");
+        assert!(!out[0].1);
+        // Position stays on 'f'
+        assert_eq!(positions, [(0, 5)]);
+    }
+
+    #[test]
+    fn test_reflow_keeps_hard_line_breaks() {
+        let lines = vec![row("ab", 4, false), row("cd", 4, false)];
+        let out = reflow_lines(&lines, &mut [], &mut Vec::new(), 10);
+
+        let texts: Vec<String> = out.iter().map(text).collect();
+        assert_eq!(texts, vec!["ab", "cd"]);
+    }
+
+    #[test]
+    fn test_reflow_moves_wide_char_off_edge() {
+        let mut cells = row("ab", 4, false).0;
+        cells[2] = Cell {
+            c: '한',
+            wide: true,
+            ..Default::default()
+        };
+        cells[3] = Cell {
+            placeholder: true,
+            ..Default::default()
+        };
+        let out = reflow_lines(&[(cells, false)], &mut [], &mut Vec::new(), 3);
+
+        assert_eq!(out.len(), 2);
+        assert!(out[0].1);
+        assert!(!out[0].0[2].wide);
+        assert!(out[1].0[0].wide);
+        assert!(out[1].0[1].placeholder);
+    }
+
+    #[test]
+    fn test_reflow_screen_grows_into_blank_rows() {
+        let buffer: Vec<Vec<Cell>> = vec![
+            row("0123456789", 10, false).0,
+            row("$", 10, false).0,
+            row("", 10, false).0,
+        ];
+        let reflowed = reflow_screen(&VecDeque::new(), &buffer, &[false; 3], (1, 2), 5, 0);
+
+        assert!(reflowed.scrollback.is_empty());
+        assert_eq!(reflowed.wrapped, vec![true, false, false]);
+        assert_eq!(reflowed.buffer[1][0].c, '5');
+        assert_eq!(reflowed.buffer[2][0].c, '$');
+        assert_eq!(reflowed.cursor, (2, 2));
+    }
+
+    #[test]
+    fn test_reflow_screen_pushes_top_into_scrollback() {
+        let buffer: Vec<Vec<Cell>> = vec![row("0123456789", 10, false).0, row("$", 10, false).0];
+        let reflowed = reflow_screen(&VecDeque::new(), &buffer, &[false; 2], (1, 2), 5, 0);
+
+        assert_eq!(reflowed.scrollback.len(), 1);
+        assert!(reflowed.scrollback[0].is_wrapped());
+        assert_eq!(reflowed.buffer[0][0].c, '5');
+        assert_eq!(reflowed.cursor, (1, 2));
+    }
+
+    #[test]
+    fn test_reflow_screen_maps_lines() {
+        let scrollback: VecDeque<CompressedLine> = [row("0123456789", 10, false)]
+            .iter()
+            .map(|(cells, wrapped)| CompressedLine::compress(cells).with_wrapped(*wrapped))
+            .collect();
+        let buffer: Vec<Vec<Cell>> = vec![row("abcdefg", 10, false).0, row("$", 10, false).0];
+        let reflowed = reflow_screen(&scrollback, &buffer, &[false; 2], (1, 1), 5, 100);
+        let map = &reflowed.line_map;
+
+        // Older lines keep their numbers
+        assert_eq!(map.line(42), 42);
+        assert_eq!(map.line(100), 100);
+        assert_eq!(map.line(101), 102);
+        assert_eq!(map.position(101, 6), (103, 1));
+        assert_eq!(map.line(102), 104);
+        // Blank rows below the cursor move with it
+        assert_eq!(map.line(103), 105);
+    }
+}
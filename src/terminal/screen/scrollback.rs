@@ -6,42 +6,63 @@
 //! - Line compression using RLE
 //! - Full-text search across scrollback history
 //! - Automatic trimming when limits are exceeded
+//! - An optional disk tier: beyond `memory_lines`, the oldest lines are
+//!   spilled in chunks to a [`SpillFile`] and paged back when read
+//!
+//! Chunks end on a logical line boundary, so reflowing the lines in memory
+//! never splits a line between the tiers. Lines on disk keep the width they
+//! were spilled at until they are paged back in, when they are reflowed.
 
-use super::{Cell, CompressedLine};
+use super::spill::{self, SpillFile};
+use super::{reflow, Cell, CompressedLine};
+use std::borrow::Cow;
 use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Largest number of lines written to disk at once
+const SPILL_CHUNK_LINES: usize = 1000;
 
 /// Scrollback buffer configuration
 #[derive(Debug, Clone)]
 pub struct ScrollbackConfig {
     /// Maximum number of lines to keep (0 = unlimited)
     pub max_lines: usize,
-    /// Enable RLE compression for scrollback lines
-    pub compression_enabled: bool,
-    /// Save scrollback to file on exit (future feature)
-    pub save_to_file: bool,
+    /// Directory of the disk tier (None = keep all lines in memory)
+    pub disk_dir: Option<PathBuf>,
+    /// Lines kept in memory before the oldest are spilled to disk
+    pub memory_lines: usize,
 }
 
 impl Default for ScrollbackConfig {
     fn default() -> Self {
         Self {
             max_lines: 10000,
-            compression_enabled: true,
-            save_to_file: false,
+            disk_dir: None,
+            memory_lines: 1000,
         }
     }
 }
 
 /// Enhanced scrollback buffer with configurable limits and compression
+///
+/// Lines are indexed from the oldest line, whether it is on disk or in memory.
 #[derive(Debug)]
 pub struct ScrollbackBuffer {
-    /// Compressed lines stored in FIFO order (oldest first)
+    /// Compressed lines stored in FIFO order (oldest first), after those on disk
     lines: VecDeque<CompressedLine>,
+    /// Older lines spilled to disk, created on the first spill
+    disk: Option<SpillFile>,
     /// Configuration
     config: ScrollbackConfig,
     /// Total uncompressed bytes (for memory tracking)
     total_uncompressed_bytes: usize,
     /// Total compressed bytes (for memory tracking)
     total_compressed_bytes: usize,
+    /// Lines removed from the front since the buffer was created
+    dropped: usize,
+    /// Width the lines in memory were last reflowed to (None = never reflowed)
+    cols: Option<usize>,
 }
 
 impl ScrollbackBuffer {
@@ -49,12 +70,22 @@ impl ScrollbackBuffer {
     pub fn new(config: ScrollbackConfig) -> Self {
         Self {
             lines: VecDeque::new(),
+            disk: None,
             config,
             total_uncompressed_bytes: 0,
             total_compressed_bytes: 0,
+            dropped: 0,
+            cols: None,
         }
     }
 
+    /// Remove disk tier files in `dir` left behind by processes that crashed
+    ///
+    /// Returns the number of files removed.
+    pub fn remove_stale_files(dir: &Path) -> std::io::Result<usize> {
+        spill::remove_stale(dir)
+    }
+
     /// Create a scrollback buffer with default configuration
     pub fn with_max_lines(max_lines: usize) -> Self {
         Self::new(ScrollbackConfig {
@@ -65,45 +96,83 @@ impl ScrollbackBuffer {
 
     /// Push a new line to the scrollback buffer
     ///
-    /// The line is compressed if compression is enabled.
-    /// If the buffer exceeds max_lines, the oldest line is removed.
+    /// The line is compressed. If the buffer exceeds max_lines, the oldest
+    /// line is removed.
     pub fn push(&mut self, line: &[Cell]) {
-        let compressed = if self.config.compression_enabled {
-            CompressedLine::compress(line)
-        } else {
-            // Even if compression is disabled, we still use CompressedLine
-            // for consistency, but the line won't be actually compressed
-            CompressedLine::compress(line)
-        };
+        self.push_wrapped(line, false);
+    }
+
+    /// Push a new line, recording whether it was soft-wrapped into the next line
+    pub fn push_wrapped(&mut self, line: &[Cell], wrapped: bool) {
+        self.push_line(CompressedLine::compress(line).with_wrapped(wrapped));
+    }
 
+    /// Push an already compressed line
+    pub fn push_line(&mut self, line: CompressedLine) {
         // Update memory tracking
-        self.total_uncompressed_bytes += compressed.uncompressed_size();
-        self.total_compressed_bytes += compressed.compressed_size();
+        self.total_uncompressed_bytes += line.uncompressed_size();
+        self.total_compressed_bytes += line.compressed_size();
 
-        self.lines.push_back(compressed);
+        self.lines.push_back(line);
         self.trim_to_limit();
+        self.spill();
     }
 
     /// Get a line by index (0 = oldest line)
     pub fn get(&self, index: usize) -> Option<Vec<Cell>> {
-        self.lines
-            .get(index)
+        self.get_compressed(index)
             .map(|compressed| compressed.decompress())
     }
 
+    /// Check if the line at index was soft-wrapped into the next line
+    pub fn is_wrapped(&self, index: usize) -> bool {
+        self.get_compressed(index)
+            .is_some_and(|line| line.is_wrapped())
+    }
+
     /// Get a compressed line by index without decompressing
-    pub fn get_compressed(&self, index: usize) -> Option<&CompressedLine> {
-        self.lines.get(index)
+    ///
+    /// Lines on disk are read back (a chunk at a time) and returned owned.
+    pub fn get_compressed(&self, index: usize) -> Option<Cow<'_, CompressedLine>> {
+        match index.checked_sub(self.disk_len()) {
+            Some(index) => self.lines.get(index).map(Cow::Borrowed),
+            None => {
+                let disk = self.disk.as_ref()?;
+                match disk.get(index) {
+                    Ok(line) => line.map(Cow::Owned),
+                    Err(e) => {
+                        tracing::warn!(path = %disk.path().display(), error = %e, "Failed to read scrollback line");
+                        None
+                    }
+                }
+            }
+        }
     }
 
     /// Get the number of lines in the buffer
     pub fn len(&self) -> usize {
-        self.lines.len()
+        self.disk_len() + self.lines.len()
     }
 
     /// Check if the buffer is empty
     pub fn is_empty(&self) -> bool {
-        self.lines.is_empty()
+        self.len() == 0
+    }
+
+    /// Number of lines on disk, which come before the lines in memory
+    pub fn disk_len(&self) -> usize {
+        self.disk.as_ref().map_or(0, SpillFile::len)
+    }
+
+    /// Changes when lines are taken back from disk, which stales readers
+    /// returned by [`ScrollbackBuffer::read_from`] (they return blank lines then)
+    pub fn disk_generation(&self) -> u64 {
+        self.disk.as_ref().map_or(0, SpillFile::generation)
+    }
+
+    /// Size of the disk tier in bytes
+    pub fn disk_usage(&self) -> u64 {
+        self.disk.as_ref().map_or(0, SpillFile::disk_usage)
     }
 
     /// Get total memory usage in bytes
@@ -140,6 +209,7 @@ impl ScrollbackBuffer {
     pub fn set_config(&mut self, config: ScrollbackConfig) {
         self.config = config;
         self.trim_to_limit();
+        self.spill();
     }
 
     /// Trim the buffer to the configured maximum size
@@ -149,24 +219,79 @@ impl ScrollbackBuffer {
             return;
         }
 
-        while self.lines.len() > self.config.max_lines {
-            if let Some(removed) = self.lines.pop_front() {
-                // Update memory tracking
-                self.total_uncompressed_bytes = self
-                    .total_uncompressed_bytes
-                    .saturating_sub(removed.uncompressed_size());
-                self.total_compressed_bytes = self
-                    .total_compressed_bytes
-                    .saturating_sub(removed.compressed_size());
+        let excess = self.len().saturating_sub(self.config.max_lines);
+        if excess == 0 {
+            return;
+        }
+        self.dropped += excess;
+        let from_disk = excess.min(self.disk_len());
+        if let Some(disk) = self.disk.as_mut().filter(|_| from_disk > 0) {
+            if let Err(e) = disk.drop_front(from_disk) {
+                tracing::warn!(path = %disk.path().display(), error = %e, "Failed to trim scrollback file");
             }
         }
+        for _ in from_disk..excess {
+            self.pop_memory_front();
+        }
+    }
+
+    /// Move the oldest in-memory lines to disk once there are more than `memory_lines`
+    ///
+    /// Lines are written in chunks so the file is appended to rarely. If the
+    /// disk tier cannot be used it is disabled and lines stay in memory.
+    fn spill(&mut self) {
+        let Some(dir) = self.config.disk_dir.clone() else {
+            return;
+        };
+        if self.lines.len() <= self.config.memory_lines {
+            return;
+        }
+
+        if self.disk.is_none() {
+            match SpillFile::create(&dir) {
+                Ok(disk) => self.disk = Some(disk),
+                Err(e) => {
+                    tracing::warn!(dir = %dir.display(), error = %e, "Failed to create scrollback file, keeping scrollback in memory");
+                    self.config.disk_dir = None;
+                    return;
+                }
+            }
+        }
+        let Some(disk) = self.disk.as_mut() else {
+            return;
+        };
+
+        let count = SPILL_CHUNK_LINES
+            .clamp(1, self.config.memory_lines.max(1))
+            .min(self.lines.len());
+        // End the chunk with a logical line, forwards if possible
+        let line_end = |i: usize| !self.lines[i].is_wrapped();
+        let count = (count - 1..self.lines.len())
+            .find(|&i| line_end(i))
+            .or_else(|| (0..count - 1).rev().find(|&i| line_end(i)))
+            .map_or(count, |i| i + 1);
+        let chunk: Vec<&CompressedLine> = self.lines.range(..count).collect();
+        if let Err(e) = disk.append(&chunk) {
+            tracing::warn!(path = %disk.path().display(), error = %e, "Failed to write scrollback file, keeping scrollback in memory");
+            self.config.disk_dir = None;
+            return;
+        }
+        for _ in 0..count {
+            self.pop_memory_front();
+        }
     }
 
     /// Clear all lines from the buffer
     pub fn clear(&mut self) {
+        self.dropped += self.len();
         self.lines.clear();
         self.total_uncompressed_bytes = 0;
         self.total_compressed_bytes = 0;
+        if let Some(disk) = &mut self.disk {
+            if let Err(e) = disk.clear() {
+                tracing::warn!(path = %disk.path().display(), error = %e, "Failed to truncate scrollback file");
+            }
+        }
     }
 
     /// Search for a pattern in the scrollback buffer
@@ -174,23 +299,27 @@ impl ScrollbackBuffer {
     /// Returns a vector of (line_index, column_index) tuples indicating
     /// where the pattern was found.
     ///
-    /// Note: This requires decompressing all lines, which may be slow
-    /// for large buffers.
+    /// Note: This requires decompressing all lines (reading those on disk),
+    /// which may be slow for large buffers.
     pub fn search(&self, pattern: &str) -> Vec<(usize, usize)> {
         let mut results = Vec::new();
         let pattern_lower = pattern.to_lowercase();
 
-        for (line_idx, compressed_line) in self.lines.iter().enumerate() {
-            let line = compressed_line.decompress();
-            let text: String = line.iter().map(|cell| cell.c).collect();
+        for (line_idx, compressed_line) in self.iter().enumerate() {
+            // One grapheme cluster per cell, so columns are counted in clusters
+            let mut text = String::new();
+            for cell in compressed_line.decompress() {
+                cell.push_grapheme(&mut text);
+            }
             let text_lower = text.to_lowercase();
 
             // Find all occurrences in this line
             let mut start = 0;
             while let Some(pos) = text_lower[start..].find(&pattern_lower) {
                 let abs_pos = start + pos;
-                results.push((line_idx, abs_pos));
-                start = abs_pos + 1;
+                let column = text_lower[..abs_pos].graphemes(true).count();
+                results.push((line_idx, column));
+                start = abs_pos + pattern_lower.chars().next().map_or(1, char::len_utf8);
             }
         }
 
@@ -201,31 +330,119 @@ impl ScrollbackBuffer {
     pub fn search_case_sensitive(&self, pattern: &str) -> Vec<(usize, usize)> {
         let mut results = Vec::new();
 
-        for (line_idx, compressed_line) in self.lines.iter().enumerate() {
-            let line = compressed_line.decompress();
-            let text: String = line.iter().map(|cell| cell.c).collect();
+        for (line_idx, compressed_line) in self.iter().enumerate() {
+            let mut text = String::new();
+            for cell in compressed_line.decompress() {
+                cell.push_grapheme(&mut text);
+            }
 
             // Find all occurrences in this line
             let mut start = 0;
             while let Some(pos) = text[start..].find(pattern) {
                 let abs_pos = start + pos;
-                results.push((line_idx, abs_pos));
-                start = abs_pos + 1;
+                let column = text[..abs_pos].graphemes(true).count();
+                results.push((line_idx, column));
+                start = abs_pos + pattern.chars().next().map_or(1, char::len_utf8);
             }
         }
 
         results
     }
 
-    /// Get an iterator over all compressed lines
-    pub fn iter(&self) -> impl Iterator<Item = &CompressedLine> {
-        self.lines.iter()
+    /// Get an iterator over all compressed lines, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = Cow<'_, CompressedLine>> {
+        self.iter_from(0)
+    }
+
+    /// Get an iterator over the compressed lines from `index` on
+    ///
+    /// Lines on disk are read back one chunk at a time.
+    pub fn iter_from(&self, index: usize) -> impl Iterator<Item = Cow<'_, CompressedLine>> {
+        let disk_len = self.disk_len();
+        let disk_lines = self
+            .disk
+            .iter()
+            .filter(move |_| index < disk_len)
+            .flat_map(move |disk| disk.lines_from(index))
+            .map(Cow::Owned);
+        let memory_lines = self
+            .lines
+            .range(index.saturating_sub(disk_len).min(self.lines.len())..)
+            .map(Cow::Borrowed);
+        disk_lines.chain(memory_lines)
+    }
+
+    /// Number of lines removed from the front (trimmed, popped or cleared)
+    ///
+    /// Adding it to an index gives a line number that stays the same as
+    /// older lines are removed.
+    pub fn dropped_lines(&self) -> usize {
+        self.dropped
+    }
+
+    /// Copy of the lines from `index` on that can be read on another thread
+    ///
+    /// Lines in memory are cloned, lines on disk are read lazily through a
+    /// file handle of the iterator's own.
+    pub fn read_from(&self, index: usize) -> impl Iterator<Item = CompressedLine> + Send {
+        let disk_len = self.disk_len();
+        let disk_lines = self
+            .disk
+            .as_ref()
+            .filter(|_| index < disk_len)
+            .map(|disk| disk.read_from(index));
+        let memory_lines: Vec<CompressedLine> = self
+            .lines
+            .range(index.saturating_sub(disk_len).min(self.lines.len())..)
+            .cloned()
+            .collect();
+        disk_lines.into_iter().flatten().chain(memory_lines)
+    }
+
+    /// Lines held in memory, the newest `len() - disk_len()` lines
+    pub fn in_memory(&self) -> &VecDeque<CompressedLine> {
+        &self.lines
+    }
+
+    /// Replace the lines held in memory after reflowing them to `cols` columns
+    pub fn replace_in_memory(&mut self, lines: VecDeque<CompressedLine>, cols: usize) {
+        self.lines = lines;
+        self.cols = Some(cols);
+        self.recount();
+        self.trim_to_limit();
+        self.spill();
+    }
+
+    /// Apply `f` to every line held in memory
+    pub fn update_in_memory(&mut self, f: impl FnMut(&mut CompressedLine)) {
+        self.lines.iter_mut().for_each(f);
+        self.recount();
     }
 
     /// Pop the most recent line from the buffer
     ///
-    /// Returns None if the buffer is empty.
+    /// Pages the newest chunk back in from disk when no lines are left in
+    /// memory, reflowing it if the lines in memory were reflowed since it was
+    /// spilled. Returns None if the buffer is empty.
     pub fn pop_back(&mut self) -> Option<CompressedLine> {
+        if self.lines.is_empty() {
+            if let Some(disk) = &mut self.disk {
+                let chunk_cols = disk.last_chunk_cols();
+                match disk.pop_chunk() {
+                    Ok(mut lines) => {
+                        if let Some(cols) = self.cols.filter(|&cols| Some(cols) != chunk_cols) {
+                            lines = reflow::reflow_scrollback(&lines, cols);
+                        }
+                        self.lines.extend(lines);
+                        self.recount();
+                    }
+                    Err(e) => {
+                        tracing::warn!(path = %disk.path().display(), error = %e, "Failed to read scrollback file");
+                    }
+                }
+            }
+        }
+
         if let Some(removed) = self.lines.pop_back() {
             // Update memory tracking
             self.total_uncompressed_bytes = self
@@ -244,6 +461,26 @@ impl ScrollbackBuffer {
     ///
     /// Returns None if the buffer is empty.
     pub fn pop_front(&mut self) -> Option<CompressedLine> {
+        if !self.is_empty() {
+            self.dropped += 1;
+        }
+        if let Some(disk) = self.disk.as_mut().filter(|disk| disk.len() > 0) {
+            let line = disk.get(0).and_then(|line| {
+                disk.drop_front(1)?;
+                Ok(line)
+            });
+            return match line {
+                Ok(line) => line,
+                Err(e) => {
+                    tracing::warn!(path = %disk.path().display(), error = %e, "Failed to read scrollback file");
+                    None
+                }
+            };
+        }
+        self.pop_memory_front()
+    }
+
+    fn pop_memory_front(&mut self) -> Option<CompressedLine> {
         if let Some(removed) = self.lines.pop_front() {
             // Update memory tracking
             self.total_uncompressed_bytes = self
@@ -258,19 +495,11 @@ impl ScrollbackBuffer {
         }
     }
 
-    /// Clone the internal buffer (for alternate screen switching)
-    pub fn clone_lines(&self) -> VecDeque<CompressedLine> {
-        self.lines.clone()
-    }
-
-    /// Restore from a cloned buffer (for alternate screen switching)
-    pub fn restore_from(&mut self, lines: VecDeque<CompressedLine>) {
-        // Recalculate memory usage
-        self.total_uncompressed_bytes = lines.iter().map(|line| line.uncompressed_size()).sum();
-        self.total_compressed_bytes = lines.iter().map(|line| line.compressed_size()).sum();
-
-        self.lines = lines;
-        self.trim_to_limit();
+    /// Recalculate the memory usage of the lines in memory
+    fn recount(&mut self) {
+        self.total_uncompressed_bytes =
+            self.lines.iter().map(|line| line.uncompressed_size()).sum();
+        self.total_compressed_bytes = self.lines.iter().map(|line| line.compressed_size()).sum();
     }
 }
 
@@ -304,6 +533,18 @@ mod tests {
         assert_eq!(text, "Line 1");
     }
 
+    #[test]
+    fn test_wrapped_flag() {
+        let mut buffer = ScrollbackBuffer::with_max_lines(10);
+
+        buffer.push_wrapped(&create_test_line("long line part 1"), true);
+        buffer.push(&create_test_line("part 2"));
+
+        assert!(buffer.is_wrapped(0));
+        assert!(!buffer.is_wrapped(1));
+        assert!(!buffer.is_wrapped(2));
+    }
+
     #[test]
     fn test_max_lines_limit() {
         let mut buffer = ScrollbackBuffer::with_max_lines(3);
@@ -381,6 +622,16 @@ mod tests {
         assert_eq!(results.len(), 1);
     }
 
+    #[test]
+    fn test_search_reports_cell_columns() {
+        let mut buffer = ScrollbackBuffer::with_max_lines(10);
+
+        buffer.push(&create_test_line("한글 world"));
+
+        assert_eq!(buffer.search("world"), vec![(0, 3)]);
+        assert_eq!(buffer.search_case_sensitive("글"), vec![(0, 1)]);
+    }
+
     #[test]
     fn test_memory_tracking() {
         let mut buffer = ScrollbackBuffer::with_max_lines(10);
@@ -458,4 +709,146 @@ mod tests {
         assert_eq!(text, "Line 1");
         assert_eq!(buffer.len(), 1);
     }
+
+    fn spilling_buffer(dir: &std::path::Path, max_lines: usize) -> ScrollbackBuffer {
+        ScrollbackBuffer::new(ScrollbackConfig {
+            max_lines,
+            disk_dir: Some(dir.to_path_buf()),
+            memory_lines: 4,
+        })
+    }
+
+    fn line_text(line: &CompressedLine) -> String {
+        line.decompress().iter().map(|c| c.c).collect()
+    }
+
+    #[test]
+    fn test_spill_to_disk() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut buffer = spilling_buffer(dir.path(), 0);
+
+        for i in 0..10 {
+            buffer.push_wrapped(&create_test_line(&format!("Line {}", i)), i == 1);
+        }
+
+        assert_eq!(buffer.len(), 10);
+        assert_eq!(buffer.disk_len(), 8);
+        assert_eq!(buffer.in_memory().len(), 2);
+        assert!(buffer.disk_usage() > 0);
+        assert!(buffer.is_wrapped(1));
+        assert!(!buffer.is_wrapped(2));
+
+        let texts: Vec<String> = buffer.iter().map(|line| line_text(&line)).collect();
+        let expected: Vec<String> = (0..10).map(|i| format!("Line {}", i)).collect();
+        assert_eq!(texts, expected);
+        let texts: Vec<String> = buffer.iter_from(7).map(|line| line_text(&line)).collect();
+        assert_eq!(texts, ["Line 7", "Line 8", "Line 9"]);
+        assert_eq!(buffer.search("line 3"), vec![(3, 0)]);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.disk_usage(), 0);
+    }
+
+    #[test]
+    fn test_default_config_spills() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ScrollbackConfig {
+            disk_dir: Some(dir.path().to_path_buf()),
+            ..Default::default()
+        };
+        let (max_lines, memory_lines) = (config.max_lines, config.memory_lines);
+        let mut buffer = ScrollbackBuffer::new(config);
+
+        for i in 0..max_lines + 1 {
+            buffer.push(&create_test_line(&format!("Line {}", i)));
+        }
+
+        assert_eq!(buffer.len(), max_lines);
+        assert!(buffer.disk_len() > 0);
+        assert!(buffer.in_memory().len() <= memory_lines);
+        assert_eq!(line_text(&buffer.get_compressed(0).unwrap()), "Line 1");
+    }
+
+    #[test]
+    fn test_spill_trim_and_pop() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut buffer = spilling_buffer(dir.path(), 8);
+
+        for i in 0..10 {
+            buffer.push(&create_test_line(&format!("Line {}", i)));
+        }
+
+        // The oldest lines are trimmed from disk
+        assert_eq!(buffer.len(), 8);
+        let first = buffer.pop_front().unwrap();
+        assert_eq!(line_text(&first), "Line 2");
+
+        // Popping past the lines in memory pages the newest chunk back in
+        let texts: Vec<String> = std::iter::from_fn(|| buffer.pop_back())
+            .map(|line| line_text(&line))
+            .collect();
+        let expected: Vec<String> = (3..10).rev().map(|i| format!("Line {}", i)).collect();
+        assert_eq!(texts, expected);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    fn test_spill_ends_chunks_with_logical_lines() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut buffer = spilling_buffer(dir.path(), 0);
+
+        // Lines 3-6 are one logical line across the first chunk's end
+        for i in 0..10 {
+            let wrapped = (3..6).contains(&i);
+            buffer.push_wrapped(&create_test_line(&format!("Line {}", i)), wrapped);
+        }
+
+        assert_eq!(buffer.disk_len(), 7);
+        assert!(!buffer.is_wrapped(buffer.disk_len() - 1));
+        assert_eq!(line_text(&buffer.in_memory()[0]), "Line 7");
+    }
+
+    #[test]
+    fn test_chunk_reflowed_when_paged_back() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut buffer = spilling_buffer(dir.path(), 0);
+        for i in 0..6 {
+            buffer.push(&create_test_line(&format!("Line {} abc", i)));
+        }
+        assert_eq!(buffer.disk_len(), 4);
+
+        // The lines in memory were reflowed to 6 columns
+        let reflowed = buffer
+            .in_memory()
+            .iter()
+            .flat_map(|line| reflow::reflow_scrollback(std::slice::from_ref(line), 6))
+            .collect();
+        buffer.replace_in_memory(reflowed, 6);
+        for _ in 0..4 {
+            buffer.pop_back();
+        }
+
+        // So are those paged back in from disk
+        let line = buffer.pop_back().unwrap();
+        assert_eq!(line_text(&line), " abc  ");
+        assert!(!line.is_wrapped());
+        let line = buffer.pop_back().unwrap();
+        assert_eq!(line_text(&line), "Line 3");
+        assert!(line.is_wrapped());
+        assert_eq!(buffer.len(), 6);
+    }
+
+    #[test]
+    fn test_spill_file_removed_on_drop() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut buffer = spilling_buffer(dir.path(), 0);
+        for i in 0..10 {
+            buffer.push(&create_test_line(&format!("Line {}", i)));
+        }
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        drop(buffer);
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
 }
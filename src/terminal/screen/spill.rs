@@ -0,0 +1,510 @@
+//! Disk tier of the scrollback buffer
+//!
+//! The oldest scrollback lines are written in chunks to an append-only file
+//! (in the [`persist`](super::persist) encoding) and read back a chunk at a
+//! time when they are needed, with the last chunk read kept in memory. Once
+//! more than half of the file holds trimmed lines, the rest is moved to a
+//! fresh file. The file is deleted when the buffer is dropped, i.e. when its
+//! tab closes; files left behind by a crash are removed on the next start.
+
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use super::{persist, CompressedLine, StringInterner};
+
+/// A run of lines stored contiguously in the file
+#[derive(Debug, Clone, Copy)]
+struct Chunk {
+    /// Byte offset in the file
+    offset: u64,
+    /// Encoded length in bytes
+    len: u64,
+    /// Number of the chunk's first line, counted from the first line ever spilled
+    first_line: u64,
+    /// Number of lines in the chunk
+    lines: usize,
+    /// Width of the widest line, the terminal width when they were spilled
+    cols: usize,
+}
+
+/// Append-only file holding the oldest lines of a scrollback buffer
+#[derive(Debug)]
+pub(crate) struct SpillFile {
+    path: PathBuf,
+    file: File,
+    chunks: VecDeque<Chunk>,
+    /// Number of the oldest line still in the buffer (lines before it were trimmed)
+    first_line: u64,
+    /// Number the next spilled line gets
+    end_line: u64,
+    /// Lines of the chunk read last, by the number of its first line
+    cache: Mutex<Option<(u64, Arc<Vec<CompressedLine>>)>>,
+    /// Bumped when written bytes may be overwritten, i.e. the file is truncated
+    generation: Arc<AtomicU64>,
+}
+
+impl SpillFile {
+    /// Create a new spill file in `dir`
+    pub(crate) fn create(dir: &Path) -> io::Result<Self> {
+        let (path, file) = create_file(dir)?;
+        Ok(Self {
+            path,
+            file,
+            chunks: VecDeque::new(),
+            first_line: 0,
+            end_line: 0,
+            cache: Mutex::new(None),
+            generation: Arc::new(AtomicU64::new(0)),
+        })
+    }
+
+    /// Number of lines on disk
+    pub(crate) fn len(&self) -> usize {
+        (self.end_line - self.first_line) as usize
+    }
+
+    /// Path of the file
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of times the file was truncated, see [`SpillFile::read_from`]
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Size of the file in bytes
+    pub(crate) fn disk_usage(&self) -> u64 {
+        self.chunks
+            .back()
+            .map_or(0, |chunk| chunk.offset + chunk.len)
+    }
+
+    /// Append lines as a new chunk
+    pub(crate) fn append(&mut self, lines: &[&CompressedLine]) -> io::Result<()> {
+        if lines.is_empty() {
+            return Ok(());
+        }
+        let data = persist::encode(lines);
+        let offset = self.disk_usage();
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.write_all(&data)?;
+
+        self.chunks.push_back(Chunk {
+            offset,
+            len: data.len() as u64,
+            first_line: self.end_line,
+            lines: lines.len(),
+            cols: lines.iter().map(|line| line.len()).max().unwrap_or(0),
+        });
+        self.end_line += lines.len() as u64;
+        Ok(())
+    }
+
+    /// Read the line at `index` (0 = oldest line on disk)
+    #[allow(dead_code)]
+    pub(crate) fn get(&self, index: usize) -> io::Result<Option<CompressedLine>> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+        let number = self.first_line + index as u64;
+        let position = self
+            .chunks
+            .partition_point(|chunk| chunk.first_line + chunk.lines as u64 <= number);
+        let chunk = self.chunks[position];
+        let lines = self.read_chunk(chunk)?;
+        Ok(lines.get((number - chunk.first_line) as usize).cloned())
+    }
+
+    /// Read the lines from `index` on, one chunk at a time
+    pub(crate) fn lines_from(&self, index: usize) -> impl Iterator<Item = CompressedLine> + '_ {
+        let start = self.first_line + index as u64;
+        self.chunks
+            .iter()
+            .filter(move |chunk| chunk.first_line + chunk.lines as u64 > start)
+            .flat_map(move |&chunk| {
+                let skip = start.saturating_sub(chunk.first_line) as usize;
+                let lines = self.read_chunk(chunk).unwrap_or_else(|e| {
+                    tracing::warn!(path = %self.path.display(), error = %e, "Failed to read scrollback chunk");
+                    Arc::new(vec![CompressedLine::compress(&[]); chunk.lines])
+                });
+                (skip..chunk.lines).map(move |i| lines[i].clone())
+            })
+    }
+
+    /// Read the lines from `index` on through a file handle of their own
+    ///
+    /// The iterator owns its handle and a copy of the chunk list, so it can
+    /// run on another thread while lines are spilled or trimmed. Chunks that
+    /// cannot be read are returned as blank lines to keep the numbering, and
+    /// so are chunks read after the file was truncated, since their bytes may
+    /// have been overwritten by other lines since.
+    pub(crate) fn read_from(&self, index: usize) -> impl Iterator<Item = CompressedLine> + Send {
+        let start = self.first_line + index as u64;
+        let chunks: Vec<Chunk> = self
+            .chunks
+            .iter()
+            .filter(|chunk| chunk.first_line + chunk.lines as u64 > start)
+            .copied()
+            .collect();
+        let path = self.path.clone();
+        let file = File::open(&path);
+        let mut interner = StringInterner::new();
+        let generation = Arc::clone(&self.generation);
+        let snapshot = self.generation();
+
+        chunks.into_iter().flat_map(move |chunk| {
+            let skip = start.saturating_sub(chunk.first_line) as usize;
+            let read = match &file {
+                Ok(file) => read_chunk(file, chunk, &mut interner),
+                Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+            };
+            let blank = || vec![CompressedLine::compress(&[]); chunk.lines];
+            let lines = match read {
+                // Bytes read after the file was truncated may belong to other lines
+                _ if generation.load(Ordering::Acquire) != snapshot => blank(),
+                Ok(lines) => lines,
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Failed to read scrollback chunk");
+                    blank()
+                }
+            };
+            lines.into_iter().skip(skip)
+        })
+    }
+
+    /// Forget the `count` oldest lines, truncating the file once it holds none
+    ///
+    /// Once more than half of the file is trimmed lines, the remaining chunks
+    /// are moved to a fresh file so it does not grow without bound.
+    pub(crate) fn drop_front(&mut self, count: usize) -> io::Result<()> {
+        self.first_line = (self.first_line + count as u64).min(self.end_line);
+        while self
+            .chunks
+            .front()
+            .is_some_and(|chunk| chunk.first_line + chunk.lines as u64 <= self.first_line)
+        {
+            self.chunks.pop_front();
+        }
+        match self.chunks.front() {
+            None => self.clear(),
+            Some(front) if front.offset * 2 > self.disk_usage() => self.compact(),
+            Some(_) => Ok(()),
+        }
+    }
+
+    /// Move the chunks to a fresh file, leaving out the trimmed ones before them
+    ///
+    /// Readers of the old file keep their handle to it.
+    fn compact(&mut self) -> io::Result<()> {
+        let Some(start) = self.chunks.front().map(|chunk| chunk.offset) else {
+            return Ok(());
+        };
+        let dir = self.path.parent().unwrap_or(Path::new("."));
+        let (path, mut file) = create_file(dir)?;
+        let copied = (|| {
+            let mut old = &self.file;
+            old.seek(SeekFrom::Start(start))?;
+            io::copy(&mut old.take(self.disk_usage() - start), &mut file)
+        })();
+        if let Err(e) = copied {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+
+        for chunk in &mut self.chunks {
+            chunk.offset -= start;
+        }
+        self.file = file;
+        let old_path = std::mem::replace(&mut self.path, path);
+        if let Err(e) = fs::remove_file(&old_path) {
+            tracing::warn!(path = %old_path.display(), error = %e, "Failed to remove scrollback file");
+        }
+        Ok(())
+    }
+
+    /// Width of the lines of the newest chunk, see [`SpillFile::pop_chunk`]
+    pub(crate) fn last_chunk_cols(&self) -> Option<usize> {
+        self.chunks.back().map(|chunk| chunk.cols)
+    }
+
+    /// Take the newest chunk back out of the file
+    pub(crate) fn pop_chunk(&mut self) -> io::Result<Vec<CompressedLine>> {
+        let Some(chunk) = self.chunks.back().copied() else {
+            return Ok(Vec::new());
+        };
+        let lines = self.read_chunk(chunk)?;
+        self.chunks.pop_back();
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.file.set_len(chunk.offset)?;
+        *self.cache.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+
+        let skip = self.first_line.saturating_sub(chunk.first_line) as usize;
+        self.end_line = chunk.first_line.max(self.first_line);
+        self.first_line = self.first_line.min(self.end_line);
+        Ok(lines[skip..].to_vec())
+    }
+
+    /// Remove all lines and truncate the file
+    pub(crate) fn clear(&mut self) -> io::Result<()> {
+        self.chunks.clear();
+        self.first_line = self.end_line;
+        *self.cache.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        self.file.set_len(0)
+    }
+
+    fn read_chunk(&self, chunk: Chunk) -> io::Result<Arc<Vec<CompressedLine>>> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((first_line, lines)) = &*cache {
+            if *first_line == chunk.first_line {
+                return Ok(Arc::clone(lines));
+            }
+        }
+
+        let lines = Arc::new(read_chunk(&self.file, chunk, &mut StringInterner::new())?);
+        *cache = Some((chunk.first_line, Arc::clone(&lines)));
+        Ok(lines)
+    }
+}
+
+/// Prefix of spill file names, followed by the pid of the process writing them
+const FILE_PREFIX: &str = "scrollback-";
+
+/// Create a spill file in `dir`, readable by the user only
+fn create_file(dir: &Path) -> io::Result<(PathBuf, File)> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!(
+        "{FILE_PREFIX}{}-{}.bin",
+        std::process::id(),
+        uuid::Uuid::new_v4()
+    ));
+    let mut options = OpenOptions::new();
+    options.read(true).write(true).create_new(true);
+    // Scrollback may hold anything printed to the terminal
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let file = options.open(&path)?;
+    Ok((path, file))
+}
+
+/// Remove the spill files in `dir` of processes that are no longer running
+///
+/// A crashed process leaves its files behind. Returns the number removed.
+pub(crate) fn remove_stale(dir: &Path) -> io::Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        let pid = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .filter(|name| name.ends_with(".bin"))
+            .and_then(|name| name.strip_prefix(FILE_PREFIX))
+            .and_then(|rest| rest.split('-').next())
+            .and_then(|pid| pid.parse::<u32>().ok());
+        if pid.is_some_and(|pid| !process_running(pid)) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+/// Check if a process is running
+fn process_running(pid: u32) -> bool {
+    if pid == std::process::id() {
+        return true;
+    }
+    #[cfg(unix)]
+    {
+        // Signal 0 only checks that the process exists
+        let signalled = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+        // EPERM: it exists but belongs to another user
+        signalled || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH)
+    }
+    #[cfg(not(unix))]
+    {
+        // No cheap check, keep the file
+        true
+    }
+}
+
+fn read_chunk(
+    mut file: &File,
+    chunk: Chunk,
+    interner: &mut StringInterner,
+) -> io::Result<Vec<CompressedLine>> {
+    let mut data = vec![0; chunk.len as usize];
+    file.seek(SeekFrom::Start(chunk.offset))?;
+    file.read_exact(&mut data)?;
+    persist::decode(&data, interner)
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        if let Err(e) = fs::remove_file(&self.path) {
+            tracing::warn!(path = %self.path.display(), error = %e, "Failed to remove scrollback file");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::screen::Cell;
+
+    fn line(text: &str) -> CompressedLine {
+        let cells: Vec<Cell> = text
+            .chars()
+            .map(|c| Cell {
+                c,
+                ..Cell::default()
+            })
+            .collect();
+        CompressedLine::compress(&cells)
+    }
+
+    fn text(line: &CompressedLine) -> String {
+        line.decompress().iter().map(|cell| cell.c).collect()
+    }
+
+    fn spill(file: &mut SpillFile, range: std::ops::Range<usize>) {
+        let lines: Vec<CompressedLine> = range.map(|i| line(&format!("line {i}"))).collect();
+        file.append(&lines.iter().collect::<Vec<_>>()).unwrap();
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut file = SpillFile::create(dir.path()).unwrap();
+        spill(&mut file, 0..3);
+        spill(&mut file, 3..5);
+
+        assert_eq!(file.len(), 5);
+        assert_eq!(text(&file.get(0).unwrap().unwrap()), "line 0");
+        assert_eq!(text(&file.get(4).unwrap().unwrap()), "line 4");
+        assert!(file.get(5).unwrap().is_none());
+        let texts: Vec<String> = file.lines_from(2).map(|line| text(&line)).collect();
+        assert_eq!(texts, ["line 2", "line 3", "line 4"]);
+        let reader = file.read_from(1);
+        spill(&mut file, 5..6);
+        let texts: Vec<String> = reader.map(|line| text(&line)).collect();
+        assert_eq!(texts, ["line 1", "line 2", "line 3", "line 4"]);
+    }
+
+    #[test]
+    fn test_reader_skips_chunks_rewritten_after_pop() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut file = SpillFile::create(dir.path()).unwrap();
+        spill(&mut file, 0..3);
+        spill(&mut file, 3..5);
+
+        let reader = file.read_from(0);
+        file.pop_chunk().unwrap();
+        let lines: Vec<CompressedLine> = (10..12).map(|i| line(&format!("line {i}"))).collect();
+        file.append(&lines.iter().collect::<Vec<_>>()).unwrap();
+
+        // The popped chunk's bytes now hold other lines, so nothing read
+        // after the truncation is trusted; the numbering is kept
+        let texts: Vec<String> = reader.map(|line| text(&line)).collect();
+        assert_eq!(texts.len(), 5);
+        assert!(texts.iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn test_drop_front_and_pop_chunk() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut file = SpillFile::create(dir.path()).unwrap();
+        spill(&mut file, 0..3);
+        spill(&mut file, 3..5);
+
+        file.drop_front(4).unwrap();
+        assert_eq!(file.len(), 1);
+        assert_eq!(text(&file.get(0).unwrap().unwrap()), "line 4");
+
+        let lines = file.pop_chunk().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(text(&lines[0]), "line 4");
+        assert_eq!(file.len(), 0);
+        assert_eq!(file.disk_usage(), 0);
+    }
+
+    #[test]
+    fn test_trimmed_lines_compacted() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let mut file = SpillFile::create(dir.path()).unwrap();
+        spill(&mut file, 0..3);
+        spill(&mut file, 3..6);
+        spill(&mut file, 6..9);
+        let path = file.path().to_path_buf();
+        let size = file.disk_usage();
+
+        // A third of the file trimmed: kept
+        file.drop_front(3).unwrap();
+        assert_eq!(file.path(), path);
+
+        // Two thirds: the rest is moved to a fresh file
+        file.drop_front(4).unwrap();
+        assert_ne!(file.path(), path);
+        assert!(!path.exists());
+        assert!(file.disk_usage() < size / 2);
+        assert_eq!(fs::metadata(file.path()).unwrap().len(), file.disk_usage());
+        let texts: Vec<String> = file.lines_from(0).map(|line| text(&line)).collect();
+        assert_eq!(texts, ["line 7", "line 8"]);
+        spill(&mut file, 9..10);
+        assert_eq!(text(&file.get(2).unwrap().unwrap()), "line 9");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_file_readable_by_user_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = SpillFile::create(dir.path()).unwrap();
+        let mode = fs::metadata(file.path()).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_remove_stale_files() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let live = SpillFile::create(dir.path()).unwrap();
+
+        // A process that has exited
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        child.wait().unwrap();
+        let name = format!("{FILE_PREFIX}{}-1234.bin", child.id());
+        let stale = dir.path().join(name);
+        fs::write(&stale, b"").unwrap();
+        let other = dir.path().join("notes.txt");
+        fs::write(&other, b"").unwrap();
+
+        assert_eq!(remove_stale(dir.path()).unwrap(), 1);
+        assert!(!stale.exists());
+        assert!(live.path().exists());
+        assert!(other.exists());
+        assert_eq!(remove_stale(&dir.path().join("missing")).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_file_removed_on_drop() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let file = SpillFile::create(dir.path()).unwrap();
+        let path = file.path().to_path_buf();
+        assert!(path.exists());
+        drop(file);
+        assert!(!path.exists());
+    }
+}
@@ -1,20 +1,68 @@
 //! Terminal text search functionality
 //!
-//! Provides regex-based search through terminal buffer content
+//! Provides regex-based search through terminal buffer content, either a
+//! line at a time ([`SearchState`]) or over the whole scrollback on a
+//! background thread ([`BackgroundSearch`]).
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
 
 use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::terminal::screen::{CompressedLine, TerminalScreen};
+
+/// Lines searched between two reports of a background search
+const SEARCH_CHUNK_LINES: usize = 2000;
 
 /// A single search match location
 #[derive(Debug, Clone, PartialEq)]
 pub struct SearchMatch {
     /// Line number (0-indexed)
     pub line: usize,
-    /// Start column (0-indexed, byte offset)
+    /// Start column (0-indexed, in grapheme clusters)
     pub start_col: usize,
-    /// End column (0-indexed, byte offset)
+    /// Line the match ends on, after `line` when it runs across soft-wrapped lines
+    pub end_line: usize,
+    /// End column on `end_line` (0-indexed, in grapheme clusters, exclusive)
     pub end_col: usize,
 }
 
+/// Compile a search query into a regex
+///
+/// Plain queries are matched literally. A whole-word query must not be
+/// preceded or followed by a word character.
+pub fn compile_query(
+    query: &str,
+    regex_mode: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+) -> Result<Regex, regex::Error> {
+    let mut pattern = if regex_mode {
+        format!("(?:{query})")
+    } else {
+        regex::escape(query)
+    };
+    if whole_word {
+        let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+        // Plain queries only get a boundary next to word characters, so that
+        // e.g. "-v" still matches after a space
+        if regex_mode || is_word(query.chars().next()) {
+            pattern = format!(r"\b{pattern}");
+        }
+        if regex_mode || is_word(query.chars().next_back()) {
+            pattern = format!(r"{pattern}\b");
+        }
+    }
+    if !case_sensitive {
+        pattern = format!("(?i){pattern}");
+    }
+    Regex::new(&pattern)
+}
+
 /// Search state for terminal content
 #[derive(Debug)]
 pub struct SearchState {
@@ -24,6 +72,8 @@ pub struct SearchState {
     pub regex_mode: bool,
     /// Whether search is case-sensitive
     pub case_sensitive: bool,
+    /// Whether matches must be whole words
+    pub whole_word: bool,
     /// All matches found
     pub matches: Vec<SearchMatch>,
     /// Currently selected match index
@@ -45,6 +95,7 @@ impl SearchState {
             query: String::new(),
             regex_mode: false,
             case_sensitive: false,
+            whole_word: false,
             matches: Vec::new(),
             current_match: None,
             compiled_regex: None,
@@ -71,6 +122,12 @@ impl SearchState {
         self.compile_pattern();
     }
 
+    /// Toggle whole-word matching
+    pub fn toggle_whole_word(&mut self) {
+        self.whole_word = !self.whole_word;
+        self.compile_pattern();
+    }
+
     /// Compile the search pattern
     fn compile_pattern(&mut self) {
         if self.query.is_empty() {
@@ -78,19 +135,13 @@ impl SearchState {
             return;
         }
 
-        let pattern = if self.regex_mode {
-            self.query.clone()
-        } else {
-            regex::escape(&self.query)
-        };
-
-        let pattern = if self.case_sensitive {
-            pattern
-        } else {
-            format!("(?i){pattern}")
-        };
-
-        self.compiled_regex = Regex::new(&pattern).ok();
+        self.compiled_regex = compile_query(
+            &self.query,
+            self.regex_mode,
+            self.case_sensitive,
+            self.whole_word,
+        )
+        .ok();
     }
 
     /// Check if we have a valid pattern
@@ -102,11 +153,14 @@ impl SearchState {
     pub fn find_in_line(&self, line_num: usize, text: &str) -> Vec<SearchMatch> {
         let mut matches = Vec::new();
         if let Some(regex) = &self.compiled_regex {
+            // Columns count grapheme clusters, like the cells of the line
+            let column = |byte: usize| text[..byte].graphemes(true).count();
             for m in regex.find_iter(text) {
                 matches.push(SearchMatch {
                     line: line_num,
-                    start_col: m.start(),
-                    end_col: m.end(),
+                    start_col: column(m.start()),
+                    end_line: line_num,
+                    end_col: column(m.end()),
                 });
             }
         }
@@ -171,20 +225,384 @@ impl SearchState {
     pub fn is_match_at(&self, line: usize, col: usize) -> bool {
         self.matches
             .iter()
-            .any(|m| m.line == line && col >= m.start_col && col < m.end_col)
+            .any(|m| (line, col) >= (m.line, m.start_col) && (line, col) < (m.end_line, m.end_col))
     }
 
     /// Check if a position is within the current match
     pub fn is_current_match_at(&self, line: usize, col: usize) -> bool {
         self.current().is_some_and(|m| {
-            m.line == line && col >= m.start_col && col < m.end_col
+            (line, col) >= (m.line, m.start_col) && (line, col) < (m.end_line, m.end_col)
         })
     }
 }
 
+/// Lines a background search runs over
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SearchScope {
+    /// All lines
+    #[default]
+    All,
+    /// Only logical lines starting in these line ranges, e.g. the output of commands
+    CommandOutput(Vec<Range<usize>>),
+}
+
+impl SearchScope {
+    /// Check if the logical line starting at `line` is searched
+    pub fn contains(&self, line: usize) -> bool {
+        match self {
+            SearchScope::All => true,
+            SearchScope::CommandOutput(ranges) => ranges.iter().any(|range| range.contains(&line)),
+        }
+    }
+}
+
+/// Options of a background search
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// Whether the query is a regex
+    pub regex_mode: bool,
+    /// Whether search is case-sensitive
+    pub case_sensitive: bool,
+    /// Whether matches must be whole words
+    pub whole_word: bool,
+    /// Lines to search
+    pub scope: SearchScope,
+}
+
+/// Lines to search, taken from a screen by
+/// [`TerminalScreen::search_snapshot`](crate::terminal::screen::TerminalScreen::search_snapshot)
+///
+/// Lines are numbered like the screen's lines plus its
+/// [`line_offset`](crate::terminal::screen::TerminalScreen::line_offset), so
+/// numbers stay the same while old lines are trimmed.
+pub struct SearchSnapshot {
+    /// Number of the first line, the start of a logical line
+    pub(crate) first_line: usize,
+    /// Number of the first row of the visible screen, which may still change
+    pub(crate) screen_top: usize,
+    /// Number of lines
+    pub(crate) len: usize,
+    /// [`TerminalScreen::disk_generation`] when the snapshot was taken
+    pub(crate) disk_generation: u64,
+    /// The lines, oldest first
+    pub(crate) lines: Box<dyn Iterator<Item = CompressedLine> + Send>,
+}
+
+/// Progress reported by the search thread
+#[derive(Debug)]
+enum SearchEvent {
+    /// Matches found in the lines searched since the last report
+    Matches {
+        matches: Vec<SearchMatch>,
+        searched: usize,
+    },
+    /// All lines were searched
+    Finished,
+}
+
+/// A running search thread
+#[derive(Debug)]
+struct Worker {
+    events: Receiver<SearchEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+/// Search over a whole scrollback on a background thread
+///
+/// Lines are searched in chunks and the matches are streamed back, to be
+/// collected with [`BackgroundSearch::poll`]. Soft-wrapped lines are joined,
+/// so a match can run from one line into the next.
+#[derive(Debug)]
+pub struct BackgroundSearch {
+    regex: Regex,
+    options: SearchOptions,
+    /// Matches found so far, ordered by position
+    matches: Vec<SearchMatch>,
+    /// Currently selected match index
+    current_match: Option<usize>,
+    /// Lines of the current snapshot searched so far
+    searched: usize,
+    /// Lines in the current snapshot
+    total: usize,
+    /// First line that may still change (the top of the screen when searched)
+    rescan_from: usize,
+    /// First line and disk generation of the current snapshot
+    snapshot_start: usize,
+    disk_generation: u64,
+    worker: Option<Worker>,
+}
+
+impl BackgroundSearch {
+    /// Create a search, failing if the query is not a valid regex
+    pub fn new(query: &str, options: SearchOptions) -> Result<Self, regex::Error> {
+        let regex = compile_query(
+            query,
+            options.regex_mode,
+            options.case_sensitive,
+            options.whole_word,
+        )?;
+        Ok(Self {
+            regex,
+            options,
+            matches: Vec::new(),
+            current_match: None,
+            searched: 0,
+            total: 0,
+            rescan_from: 0,
+            snapshot_start: 0,
+            disk_generation: 0,
+            worker: None,
+        })
+    }
+
+    /// Search a snapshot on a background thread, stopping any running search
+    ///
+    /// Matches from the snapshot's first line on are dropped and found again,
+    /// matches before it are kept. Taking the next snapshot from the top of the
+    /// screen, as [`BackgroundSearch::rescan`] does, therefore only searches the
+    /// screen and new output again, and earlier results stay put.
+    pub fn start(&mut self, snapshot: SearchSnapshot) {
+        self.cancel();
+        self.matches.retain(|m| m.line < snapshot.first_line);
+        self.current_match = self.current_match.filter(|&i| i < self.matches.len());
+        self.searched = 0;
+        self.total = snapshot.len;
+        self.rescan_from = snapshot.screen_top;
+        self.snapshot_start = snapshot.first_line;
+        self.disk_generation = snapshot.disk_generation;
+
+        let (sender, events) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let regex = self.regex.clone();
+        let scope = self.options.scope.clone();
+        let cancelled = Arc::clone(&cancel);
+        let spawned = thread::Builder::new()
+            .name("scrollback-search".to_string())
+            .spawn(move || search_lines(snapshot, &regex, &scope, &sender, &cancelled));
+        match spawned {
+            Ok(_) => self.worker = Some(Worker { events, cancel }),
+            Err(e) => tracing::warn!(error = %e, "Failed to start scrollback search"),
+        }
+    }
+
+    /// Search the lines of `screen` that changed since the last snapshot again
+    ///
+    /// Matches on lines trimmed since are dropped and only the screen and new
+    /// output are searched, unless lines were taken back from disk since the
+    /// last snapshot: it may have read lines as blank then, so all of its
+    /// lines are searched again. The previous search should have finished.
+    pub fn rescan(&mut self, screen: &TerminalScreen) {
+        let from = if screen.disk_generation() == self.disk_generation {
+            self.rescan_from
+        } else {
+            self.snapshot_start
+        };
+        self.discard_before(screen.line_offset());
+        self.start(screen.search_snapshot(from));
+    }
+
+    /// Collect the matches found since the last call
+    ///
+    /// Returns true if new matches were added.
+    pub fn poll(&mut self) -> bool {
+        let Some(worker) = &self.worker else {
+            return false;
+        };
+
+        let mut changed = false;
+        loop {
+            match worker.events.try_recv() {
+                Ok(SearchEvent::Matches { matches, searched }) => {
+                    changed |= !matches.is_empty();
+                    self.matches.extend(matches);
+                    self.searched = searched;
+                }
+                Ok(SearchEvent::Finished) | Err(mpsc::TryRecvError::Disconnected) => {
+                    self.worker = None;
+                    break;
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+            }
+        }
+        changed
+    }
+
+    /// Stop the running search, keeping the matches found so far
+    pub fn cancel(&mut self) {
+        if let Some(worker) = self.worker.take() {
+            worker.cancel.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Check if the search thread is still running
+    pub fn is_running(&self) -> bool {
+        self.worker.is_some()
+    }
+
+    /// Lines searched and lines to search in the current snapshot
+    pub fn progress(&self) -> (usize, usize) {
+        (self.searched, self.total)
+    }
+
+    /// Change the lines searched from the next snapshot on
+    pub fn set_scope(&mut self, scope: SearchScope) {
+        self.options.scope = scope;
+    }
+
+    /// All matches found so far, ordered by position
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    /// Drop the matches on lines before `line`, e.g. lines trimmed from the scrollback
+    pub fn discard_before(&mut self, line: usize) {
+        let removed = self.matches.partition_point(|m| m.line < line);
+        self.matches.drain(..removed);
+        self.current_match = self.current_match.and_then(|i| i.checked_sub(removed));
+    }
+
+    /// Navigate to next match
+    pub fn next_match(&mut self) -> Option<&SearchMatch> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        self.current_match = Some(match self.current_match {
+            Some(i) => (i + 1) % self.matches.len(),
+            None => 0,
+        });
+        self.current()
+    }
+
+    /// Navigate to previous match
+    pub fn prev_match(&mut self) -> Option<&SearchMatch> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        self.current_match = Some(match self.current_match {
+            Some(0) | None => self.matches.len() - 1,
+            Some(i) => i - 1,
+        });
+        self.current()
+    }
+
+    /// Get current match
+    pub fn current(&self) -> Option<&SearchMatch> {
+        self.current_match.and_then(|i| self.matches.get(i))
+    }
+
+    /// Index of the current match in [`BackgroundSearch::matches`]
+    pub fn current_index(&self) -> Option<usize> {
+        self.current_match
+    }
+}
+
+impl Drop for BackgroundSearch {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Soft-wrapped lines joined into one logical line
+#[derive(Debug, Default)]
+struct LogicalLine {
+    /// Number of the first line
+    first_line: usize,
+    /// Number of lines joined so far
+    rows: usize,
+    text: String,
+    /// Byte offset in `text`, line and column of each grapheme cluster
+    clusters: Vec<(usize, usize, usize)>,
+}
+
+impl LogicalLine {
+    fn push(&mut self, number: usize, line: &CompressedLine) {
+        if self.rows == 0 {
+            self.first_line = number;
+        }
+        self.rows += 1;
+        let cells = line.decompress();
+        for (col, cell) in cells.iter().filter(|cell| !cell.placeholder).enumerate() {
+            self.clusters.push((self.text.len(), number, col));
+            cell.push_grapheme(&mut self.text);
+        }
+    }
+
+    fn find(&self, regex: &Regex, matches: &mut Vec<SearchMatch>) {
+        for m in regex.find_iter(self.text.trim_end()) {
+            if m.is_empty() {
+                continue;
+            }
+            let start = self
+                .clusters
+                .partition_point(|&(byte, ..)| byte <= m.start())
+                - 1;
+            let end = self.clusters.partition_point(|&(byte, ..)| byte < m.end()) - 1;
+            let (_, line, start_col) = self.clusters[start];
+            let (_, end_line, end_col) = self.clusters[end];
+            matches.push(SearchMatch {
+                line,
+                start_col,
+                end_line,
+                end_col: end_col + 1,
+            });
+        }
+    }
+
+    fn clear(&mut self) {
+        self.rows = 0;
+        self.text.clear();
+        self.clusters.clear();
+    }
+}
+
+/// Body of the search thread
+fn search_lines(
+    snapshot: SearchSnapshot,
+    regex: &Regex,
+    scope: &SearchScope,
+    sender: &Sender<SearchEvent>,
+    cancel: &AtomicBool,
+) {
+    let mut logical = LogicalLine::default();
+    let mut matches = Vec::new();
+    let mut searched = 0;
+
+    for (number, line) in (snapshot.first_line..).zip(snapshot.lines) {
+        if cancel.load(Ordering::Relaxed) {
+            return;
+        }
+        logical.push(number, &line);
+        if !line.is_wrapped() {
+            if scope.contains(logical.first_line) {
+                logical.find(regex, &mut matches);
+            }
+            logical.clear();
+        }
+
+        searched += 1;
+        if searched % SEARCH_CHUNK_LINES == 0 {
+            let matches = std::mem::take(&mut matches);
+            if sender
+                .send(SearchEvent::Matches { matches, searched })
+                .is_err()
+            {
+                return;
+            }
+        }
+    }
+    if logical.rows > 0 && scope.contains(logical.first_line) {
+        logical.find(regex, &mut matches);
+    }
+
+    let _ = sender.send(SearchEvent::Matches { matches, searched });
+    let _ = sender.send(SearchEvent::Finished);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::terminal::screen::{ScrollbackConfig, TerminalScreen};
+    use std::time::{Duration, Instant};
 
     #[test]
     fn test_search_basic() {
@@ -197,6 +615,17 @@ mod tests {
         assert_eq!(matches[0].end_col, 14);
     }
 
+    #[test]
+    fn test_search_columns_count_graphemes() {
+        let mut state = SearchState::new();
+        state.set_query("x".to_string());
+
+        // "é" as e + combining acute and a flag are one column each
+        let matches = state.find_in_line(0, "e\u{301}\u{1f1f0}\u{1f1f7}x");
+        assert_eq!(matches[0].start_col, 2);
+        assert_eq!(matches[0].end_col, 3);
+    }
+
     #[test]
     fn test_search_multiple_matches() {
         let mut state = SearchState::new();
@@ -291,4 +720,139 @@ mod tests {
         assert!(!state.is_match_at(0, 5));
         assert!(!state.is_match_at(1, 0));
     }
+
+    #[test]
+    fn test_search_whole_word() {
+        let mut state = SearchState::new();
+        state.set_query("cat".to_string());
+        state.toggle_whole_word();
+
+        let matches = state.find_in_line(0, "cat concat cat.");
+        let starts: Vec<usize> = matches.iter().map(|m| m.start_col).collect();
+        assert_eq!(starts, [0, 11]);
+
+        // Non-word characters at the edges of the query match anywhere
+        let regex = compile_query("-x", false, false, true).unwrap();
+        assert!(regex.is_match("a-x"));
+        assert!(!regex.is_match("a-xy"));
+    }
+
+    fn finish(search: &mut BackgroundSearch) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while search.is_running() {
+            search.poll();
+            assert!(Instant::now() < deadline, "search did not finish");
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_background_search_spans_wrapped_lines() {
+        let mut screen = TerminalScreen::new(10, 3);
+        screen.process(b"one\r\n0123456needle789\r\nneedle");
+
+        let mut search = BackgroundSearch::new("needle", SearchOptions::default()).unwrap();
+        search.start(screen.search_snapshot(0));
+        finish(&mut search);
+
+        // "one" scrolled into the scrollback
+        assert_eq!(search.progress(), (4, 4));
+        assert_eq!(
+            search.matches(),
+            [
+                SearchMatch {
+                    line: 1,
+                    start_col: 7,
+                    end_line: 2,
+                    end_col: 3,
+                },
+                SearchMatch {
+                    line: 3,
+                    start_col: 0,
+                    end_line: 3,
+                    end_col: 6,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_background_search_keeps_results_on_new_output() {
+        let config = ScrollbackConfig {
+            max_lines: 2,
+            ..ScrollbackConfig::default()
+        };
+        let mut screen = TerminalScreen::with_scrollback(20, 2, config);
+        screen.process(b"match a\r\nmatch b\r\n");
+
+        let mut search = BackgroundSearch::new("match", SearchOptions::default()).unwrap();
+        search.start(screen.search_snapshot(0));
+        finish(&mut search);
+        assert_eq!(search.matches().len(), 2);
+        search.next_match();
+        assert_eq!(search.current().map(|m| m.line), Some(0));
+
+        // New output trims "match a", the other matches keep their line numbers
+        screen.process(b"match c\r\nother\r\n");
+        search.rescan(&screen);
+        finish(&mut search);
+
+        let lines: Vec<usize> = search.matches().iter().map(|m| m.line).collect();
+        assert_eq!(lines, [1, 2]);
+        assert_eq!(screen.line_offset(), 1);
+        assert_eq!(search.current(), None);
+    }
+
+    #[test]
+    fn test_background_search_rescans_lines_taken_back_from_disk() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ScrollbackConfig {
+            max_lines: 0,
+            disk_dir: Some(dir.path().to_path_buf()),
+            memory_lines: 10,
+        };
+        let mut screen = TerminalScreen::with_scrollback(20, 3, config);
+        for i in 0..100 {
+            screen.process(format!("line {i}\r\n").as_bytes());
+        }
+        let mut search = BackgroundSearch::new("line", SearchOptions::default()).unwrap();
+        search.start(screen.search_snapshot(0));
+        finish(&mut search);
+        assert_eq!(search.matches().len(), 100);
+
+        // Growing the screen pages lines back in from disk
+        let generation = screen.disk_generation();
+        screen.resize(20, 60);
+        assert_ne!(screen.disk_generation(), generation);
+        search.rescan(&screen);
+        finish(&mut search);
+        assert_eq!(search.progress().1, screen.total_lines());
+        assert_eq!(search.matches().len(), 100);
+    }
+
+    #[test]
+    fn test_background_search_command_output_scope() {
+        let mut screen = TerminalScreen::new(20, 4);
+        screen.process(b"$ echo error\r\nerror\r\n$ ");
+
+        let options = SearchOptions {
+            scope: SearchScope::CommandOutput(vec![1..2, 3..4]),
+            ..SearchOptions::default()
+        };
+        let mut search = BackgroundSearch::new("error", options).unwrap();
+        search.start(screen.search_snapshot(0));
+        finish(&mut search);
+
+        let lines: Vec<usize> = search.matches().iter().map(|m| m.line).collect();
+        assert_eq!(lines, [1]);
+    }
+
+    #[test]
+    fn test_background_search_invalid_regex() {
+        let options = SearchOptions {
+            regex_mode: true,
+            ..SearchOptions::default()
+        };
+        assert!(BackgroundSearch::new("(", options).is_err());
+    }
 }
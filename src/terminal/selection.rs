@@ -5,8 +5,12 @@
 //! - Word-based selection (double click)
 //! - Line-based selection (triple click)
 //! - Text extraction from selection ranges
+//!
+//! Columns count grapheme clusters of the line text, so a base character with
+//! its combining marks or an emoji sequence is selected as a whole.
 
 use std::cmp::min;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// A point in the terminal grid (line, column)
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,9 +41,10 @@ impl Ord for SelectionPoint {
 }
 
 /// Selection mode for mouse interaction
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub enum SelectionMode {
     /// No selection
+    #[default]
     None,
     /// Character-level selection (click and drag)
     Character,
@@ -49,12 +54,6 @@ pub enum SelectionMode {
     Line,
 }
 
-impl Default for SelectionMode {
-    fn default() -> Self {
-        Self::None
-    }
-}
-
 /// Text selection state
 #[derive(Debug, Clone)]
 pub struct Selection {
@@ -145,10 +144,10 @@ impl Selection {
         // Handle single-line selection
         if start.line == end.line {
             if let Some(line_text) = screen.get_line_text(start.line) {
-                let chars: Vec<char> = line_text.chars().collect();
-                let start_col = min(start.col, chars.len());
-                let end_col = min(end.col, chars.len());
-                result.push_str(&chars[start_col..end_col].iter().collect::<String>());
+                let graphemes: Vec<&str> = line_text.graphemes(true).collect();
+                let start_col = min(start.col, graphemes.len());
+                let end_col = min(end.col, graphemes.len());
+                result.push_str(&graphemes[start_col..end_col].concat());
             }
             return result;
         }
@@ -160,16 +159,16 @@ impl Selection {
             }
 
             if let Some(line_text) = screen.get_line_text(line_idx) {
-                let chars: Vec<char> = line_text.chars().collect();
+                let graphemes: Vec<&str> = line_text.graphemes(true).collect();
 
                 if line_idx == start.line {
                     // First line: from start.col to end
-                    let start_col = min(start.col, chars.len());
-                    result.push_str(&chars[start_col..].iter().collect::<String>());
+                    let start_col = min(start.col, graphemes.len());
+                    result.push_str(&graphemes[start_col..].concat());
                 } else if line_idx == end.line {
                     // Last line: from beginning to end.col
-                    let end_col = min(end.col, chars.len());
-                    result.push_str(&chars[..end_col].iter().collect::<String>());
+                    let end_col = min(end.col, graphemes.len());
+                    result.push_str(&graphemes[..end_col].concat());
                 } else {
                     // Middle lines: entire line
                     result.push_str(&line_text);
@@ -187,26 +186,10 @@ impl Selection {
 
     /// Expand selection to word boundaries (for double-click)
     pub fn expand_to_word<T: GetLineText>(&mut self, screen: &T, line: usize, col: usize) {
-        if let Some(line_text) = screen.get_line_text(line) {
-            let chars: Vec<char> = line_text.chars().collect();
-            if col >= chars.len() {
-                return;
-            }
-
-            // Find word boundaries
-            let mut start_col = col;
-            let mut end_col = col;
-
-            // Expand backwards
-            while start_col > 0 && is_word_char(chars[start_col - 1]) {
-                start_col -= 1;
-            }
-
-            // Expand forwards
-            while end_col < chars.len() && is_word_char(chars[end_col]) {
-                end_col += 1;
-            }
-
+        let Some(line_text) = screen.get_line_text(line) else {
+            return;
+        };
+        if let Some((start_col, end_col)) = word_bounds(&line_text, col) {
             self.start = SelectionPoint::new(line, start_col);
             self.end = SelectionPoint::new(line, end_col);
             self.mode = SelectionMode::Word;
@@ -218,7 +201,7 @@ impl Selection {
     pub fn expand_to_line<T: GetLineText>(&mut self, screen: &T, line: usize) {
         if let Some(line_text) = screen.get_line_text(line) {
             self.start = SelectionPoint::new(line, 0);
-            self.end = SelectionPoint::new(line, line_text.chars().count());
+            self.end = SelectionPoint::new(line, line_text.graphemes(true).count());
             self.mode = SelectionMode::Line;
             self.active = true;
         }
@@ -240,9 +223,34 @@ pub trait GetLineText {
     fn line_count(&self) -> usize;
 }
 
-/// Helper function to determine if a character is part of a word
-fn is_word_char(c: char) -> bool {
-    c.is_alphanumeric() || c == '_' || c == '-'
+/// Columns of the word at `col` in `text`, the end exclusive
+///
+/// Columns count grapheme clusters. Returns None past the end of the text
+/// and when the grapheme cluster at `col` is not part of a word.
+pub fn word_bounds(text: &str, col: usize) -> Option<(usize, usize)> {
+    let graphemes: Vec<&str> = text.graphemes(true).collect();
+    if !is_word_grapheme(graphemes.get(col)?) {
+        return None;
+    }
+    let start = graphemes[..col]
+        .iter()
+        .rposition(|g| !is_word_grapheme(g))
+        .map_or(0, |i| i + 1);
+    let end = graphemes[col..]
+        .iter()
+        .position(|g| !is_word_grapheme(g))
+        .map_or(graphemes.len(), |i| col + i);
+    Some((start, end))
+}
+
+/// Helper function to determine if a grapheme cluster is part of a word
+///
+/// The base character decides, so accented letters count as word characters.
+fn is_word_grapheme(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_' || c == '-')
 }
 
 #[cfg(test)]
@@ -346,6 +354,36 @@ mod tests {
         assert_eq!(text, "World");
     }
 
+    #[test]
+    fn test_extract_grapheme_clusters() {
+        // "cafe" + combining acute, then a ZWJ family emoji
+        let screen = MockScreen {
+            lines: vec!["cafe\u{301} \u{1f468}\u{200d}\u{1f469} x".to_string()],
+        };
+
+        let mut sel = Selection::new();
+        sel.start(0, 3, SelectionMode::Character);
+        sel.extend(0, 6);
+        assert_eq!(
+            sel.extract_text(&screen),
+            "e\u{301} \u{1f468}\u{200d}\u{1f469}"
+        );
+
+        sel.expand_to_word(&screen, 0, 1);
+        assert_eq!(sel.extract_text(&screen), "cafe\u{301}");
+    }
+
+    #[test]
+    fn test_word_bounds() {
+        let text = "cafe\u{301} foo_bar-1 \u{1f468}\u{200d}\u{1f469}";
+        assert_eq!(word_bounds(text, 0), Some((0, 4)));
+        assert_eq!(word_bounds(text, 3), Some((0, 4)));
+        assert_eq!(word_bounds(text, 7), Some((5, 14)));
+        assert_eq!(word_bounds(text, 4), None);
+        assert_eq!(word_bounds(text, 15), None);
+        assert_eq!(word_bounds(text, 16), None);
+    }
+
     #[test]
     fn test_expand_to_line() {
         let screen = MockScreen {
@@ -10,16 +10,34 @@
 //! 2. Smart cache invalidation: Selective cache clearing based on change type
 //! 3. Memory pre-allocation: Reuse buffers and pre-allocate based on known sizes
 
-use iced::mouse;
+use iced::advanced::image::Handle;
 use iced::widget::canvas::{self, Cache, Frame, Geometry, Text};
+use iced::{keyboard, mouse};
 use iced::{Color, Font, Point, Rectangle, Renderer, Size, Theme};
 
+use crate::mouse_actions::{
+    ClickDetector, MouseAction, MouseButton, MouseEvent, MouseModifiers, MousePosition,
+    ScrollDirection,
+};
+use crate::terminal::screen::{ImageProtocol, MouseMode, TerminalScreen, UnderlineStyle};
+use crate::terminal::search::SearchMatch;
+use crate::terminal::selection::word_bounds;
 use crate::StyledSpan;
 use std::time::{Duration, Instant};
+use unicode_segmentation::UnicodeSegmentation;
 
 // Re-export Selection types from terminal::selection module
 pub use crate::terminal::selection::{Selection, SelectionMode, SelectionPoint};
 
+/// Number of cells covered by span text (one grapheme cluster per cell)
+fn column_count(text: &str) -> usize {
+    if text.is_ascii() {
+        text.len()
+    } else {
+        text.graphemes(true).count()
+    }
+}
+
 /// Extract selected text from terminal lines
 pub fn get_selected_text(lines: &[Vec<StyledSpan>], selection: &Selection) -> String {
     if !selection.active {
@@ -48,7 +66,7 @@ pub fn get_selected_text(lines: &[Vec<StyledSpan>], selection: &Selection) -> St
             // First line of multi-line selection
             let max_col = lines[row]
                 .iter()
-                .map(|span| span.text.chars().count())
+                .map(|span| column_count(&span.text))
                 .sum::<usize>();
             (start.col, max_col)
         } else if row == end.line {
@@ -58,7 +76,7 @@ pub fn get_selected_text(lines: &[Vec<StyledSpan>], selection: &Selection) -> St
             // Middle line - select entire line
             let max_col = lines[row]
                 .iter()
-                .map(|span| span.text.chars().count())
+                .map(|span| column_count(&span.text))
                 .sum::<usize>();
             (0, max_col)
         };
@@ -68,7 +86,7 @@ pub fn get_selected_text(lines: &[Vec<StyledSpan>], selection: &Selection) -> St
         for span in &lines[row] {
             // Skip placeholder cells (second cell of wide characters)
             let span_text = &span.text;
-            let span_len = span_text.chars().count();
+            let span_len = column_count(span_text);
             let span_end = current_col + span_len;
 
             // Check if this span overlaps with selection
@@ -85,10 +103,9 @@ pub fn get_selected_text(lines: &[Vec<StyledSpan>], selection: &Selection) -> St
                     span_len
                 };
 
-                // Extract characters in range
-                let chars: Vec<char> = span_text.chars().collect();
-                for i in copy_start..copy_end.min(chars.len()) {
-                    result.push(chars[i]);
+                // Extract grapheme clusters in range
+                for grapheme in span_text.graphemes(true).take(copy_end).skip(copy_start) {
+                    result.push_str(grapheme);
                 }
             }
 
@@ -128,6 +145,8 @@ pub struct CursorState {
     pub style: CursorStyle,
     pub visible: bool,
     pub blink_on: bool,
+    /// Cursor color of the theme
+    pub color: Color,
 }
 
 /// Terminal rendering configuration
@@ -181,6 +200,24 @@ pub struct TerminalCanvasState {
     pub is_dragging: bool,
     /// Last clicked URL (for Cmd+Click handling)
     pub clicked_url: Option<String>,
+    /// Counts left clicks, a double-click selects a word
+    click_detector: ClickDetector,
+    /// Cell under the mouse pointer (line number, column)
+    hovered_cell: Option<(usize, usize)>,
+    /// Cells of the hyperlink under the mouse pointer (line number, column)
+    hovered_link: Vec<(usize, usize)>,
+    /// Keyboard modifiers (Shift bypasses mouse reporting)
+    modifiers: keyboard::Modifiers,
+    /// Button held since a reported press
+    reported_button: Option<MouseButton>,
+    /// Screen cell of the last reported event, to report motion once per cell
+    last_reported_cell: Option<(usize, usize)>,
+    /// Pixel wheel delta not yet reported as a wheel event
+    wheel_pixels: f32,
+    /// Line number of the first line, to keep the view in place when it changes
+    first_line: usize,
+    /// Last scroll request handled, see [`TerminalCanvas::with_scroll_request`]
+    scroll_request: u64,
     /// Performance tracking (debug mode)
     #[cfg(debug_assertions)]
     last_frame_time: Option<Duration>,
@@ -201,6 +238,15 @@ impl Default for TerminalCanvasState {
             selection: None,
             is_dragging: false,
             clicked_url: None,
+            click_detector: ClickDetector::new(),
+            hovered_cell: None,
+            hovered_link: Vec::new(),
+            modifiers: keyboard::Modifiers::empty(),
+            reported_button: None,
+            last_reported_cell: None,
+            wheel_pixels: 0.0,
+            first_line: 0,
+            scroll_request: 0,
             #[cfg(debug_assertions)]
             last_frame_time: None,
             #[cfg(debug_assertions)]
@@ -260,11 +306,13 @@ impl TerminalCanvasState {
     }
 }
 
-/// Message emitted by terminal canvas (for future use)
+/// Message emitted by terminal canvas
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub enum TerminalCanvasMessage {
+    /// Wheel scrolling reached the top of the lines (offset in pixels)
     Scrolled(f32),
+    /// Mouse event to report to the application (screen coordinates)
+    Mouse(MouseEvent),
 }
 
 /// Terminal canvas program
@@ -275,9 +323,23 @@ pub struct TerminalCanvas<'a> {
     pub font: Font,
     pub cursor: Option<CursorState>,
     pub font_size: f32,
-    pub search_matches: &'a [(usize, usize, usize)], // (line, start_col, end_col)
+    pub search_matches: &'a [SearchMatch],
     pub current_match_index: Option<usize>,
+    /// Added to line indices to get the line numbers of `search_matches`
+    pub search_line_offset: usize,
+    /// Line to center in the view and the request's serial number
+    pub scroll_request: Option<(usize, u64)>,
     pub bracket_match: Option<crate::terminal::bracket::BracketMatch>,
+    /// Blink phase for SGR 5 text (hidden while false)
+    pub text_blink_on: bool,
+    /// Mouse tracking requested by the application
+    pub mouse_mode: MouseMode,
+    /// Index of the first screen line in `lines` (below the scrollback)
+    pub screen_top: usize,
+    /// Line number of `lines[0]`; older lines exist when it is not 0
+    pub first_line: usize,
+    /// Screen the lines were rendered from, for hyperlink hit-testing and images
+    pub screen: Option<&'a TerminalScreen>,
 }
 
 impl<'a> TerminalCanvas<'a> {
@@ -296,7 +358,14 @@ impl<'a> TerminalCanvas<'a> {
             font_size: config::BASE_FONT_SIZE,
             search_matches: &[],
             current_match_index: None,
+            search_line_offset: 0,
+            scroll_request: None,
             bracket_match: None,
+            text_blink_on: true,
+            mouse_mode: MouseMode::None,
+            screen_top: 0,
+            first_line: 0,
+            screen: None,
         }
     }
 
@@ -313,13 +382,24 @@ impl<'a> TerminalCanvas<'a> {
     }
 
     /// Set search matches for highlighting
+    ///
+    /// Matches are numbered by line index plus `line_offset`, like the
+    /// screen's [`line_offset`](TerminalScreen::line_offset) numbers them.
     pub fn with_search_matches(
         mut self,
-        matches: &'a [(usize, usize, usize)],
+        matches: &'a [SearchMatch],
         current_index: Option<usize>,
+        line_offset: usize,
     ) -> Self {
         self.search_matches = matches;
         self.current_match_index = current_index;
+        self.search_line_offset = line_offset;
+        self
+    }
+
+    /// Center the line at `line` in the view once per `serial`
+    pub fn with_scroll_request(mut self, request: Option<(usize, u64)>) -> Self {
+        self.scroll_request = request;
         self
     }
 
@@ -332,10 +412,132 @@ impl<'a> TerminalCanvas<'a> {
         self
     }
 
+    /// Set the blink phase for blinking text
+    pub fn with_text_blink(mut self, blink_on: bool) -> Self {
+        self.text_blink_on = blink_on;
+        self
+    }
+
+    /// Report mouse events to the application instead of handling them locally
+    pub fn with_mouse_reporting(mut self, mode: MouseMode, screen_top: usize) -> Self {
+        self.mouse_mode = mode;
+        self.screen_top = screen_top;
+        self
+    }
+
+    /// Set the line number of the first line, when older lines are not rendered
+    pub fn with_first_line(mut self, first_line: usize) -> Self {
+        self.first_line = first_line;
+        self
+    }
+
+    /// Set the screen the lines were rendered from, enabling hyperlink clicks and images
+    pub fn with_screen(mut self, screen: &'a TerminalScreen) -> Self {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// Text of the line at `row`, one grapheme cluster per column
+    fn line_text(&self, row: usize) -> String {
+        self.lines
+            .get(row)
+            .map(|spans| spans.iter().map(|span| span.text.as_str()).collect())
+            .unwrap_or_default()
+    }
+
     fn content_height(&self) -> f32 {
         self.lines.len() as f32 * config::line_height(self.font_size)
     }
 
+    /// Convert a canvas position to a mouse position on the screen
+    ///
+    /// Returns `None` for positions in the scrollback.
+    fn screen_position(&self, position: Point, scroll_offset: f32) -> Option<MousePosition> {
+        let line_height = config::line_height(self.font_size);
+        let x = (position.x - config::PADDING_LEFT).max(0.0);
+        let y = (position.y - config::PADDING_TOP + scroll_offset).max(0.0);
+
+        let line = (y / line_height) as usize;
+        if line < self.screen_top || self.lines.is_empty() {
+            return None;
+        }
+        let row = line.min(self.lines.len() - 1) - self.screen_top;
+        let col = (x / config::char_width(self.font_size)) as usize;
+        let screen_y = y - self.screen_top as f32 * line_height;
+        Some(MousePosition::new(x, screen_y, col, row, false))
+    }
+
+    /// Turn a canvas event into a mouse report while the application tracks the mouse
+    fn mouse_report(
+        &self,
+        state: &mut TerminalCanvasState,
+        event: &canvas::Event,
+        position: Point,
+    ) -> Option<MouseEvent> {
+        let position = self.screen_position(position, state.scroll_offset)?;
+        let action = match event {
+            canvas::Event::Mouse(mouse::Event::ButtonPressed(button)) => {
+                let button = convert_mouse_button(*button)?;
+                state.reported_button = Some(button);
+                MouseAction::Click(button)
+            }
+            canvas::Event::Mouse(mouse::Event::ButtonReleased(button)) => {
+                let button = convert_mouse_button(*button)?;
+                state.reported_button = None;
+                MouseAction::Release(button)
+            }
+            canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                let action = match state.reported_button {
+                    Some(button)
+                        if matches!(
+                            self.mouse_mode,
+                            MouseMode::ButtonEvent | MouseMode::AnyEvent
+                        ) =>
+                    {
+                        MouseAction::Drag(button)
+                    }
+                    None if self.mouse_mode == MouseMode::AnyEvent => MouseAction::Move,
+                    _ => return None,
+                };
+                if state.last_reported_cell == Some((position.row, position.col)) {
+                    return None;
+                }
+                action
+            }
+            canvas::Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let y = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => *y,
+                    mouse::ScrollDelta::Pixels { y, .. } => {
+                        // Trackpads scroll in pixels: report one wheel event per line
+                        state.wheel_pixels += *y;
+                        let line_height = config::line_height(self.font_size);
+                        if state.wheel_pixels.abs() < line_height {
+                            return None;
+                        }
+                        std::mem::replace(&mut state.wheel_pixels, 0.0)
+                    }
+                };
+                if y > 0.0 {
+                    MouseAction::Scroll(ScrollDirection::Up)
+                } else if y < 0.0 {
+                    MouseAction::Scroll(ScrollDirection::Down)
+                } else {
+                    return None;
+                }
+            }
+            _ => return None,
+        };
+
+        state.last_reported_cell = Some((position.row, position.col));
+        let modifiers = MouseModifiers {
+            shift: state.modifiers.shift(),
+            ctrl: state.modifiers.control(),
+            alt: state.modifiers.alt(),
+            meta: state.modifiers.logo(),
+        };
+        Some(MouseEvent::new(action, position, modifiers, 1))
+    }
+
     fn visible_range(&self, scroll_offset: f32, viewport_height: f32) -> (usize, usize) {
         let first = (scroll_offset / config::line_height(self.font_size)).floor() as usize;
         let visible_count =
@@ -357,7 +559,7 @@ impl<'a> TerminalCanvas<'a> {
         let max_col = if row < self.lines.len() {
             self.lines[row]
                 .iter()
-                .map(|span| span.text.chars().count())
+                .map(|span| column_count(&span.text))
                 .sum::<usize>()
                 .saturating_sub(1)
         } else {
@@ -370,22 +572,90 @@ impl<'a> TerminalCanvas<'a> {
 
     /// Get hyperlink at the given cell position
     pub fn get_hyperlink_at(&self, row: usize, col: usize) -> Option<String> {
-        if row >= self.lines.len() {
-            return None;
+        let url = self.screen?.hyperlink_url(self.first_line + row, col)?;
+        Some(url.as_ref().clone())
+    }
+
+    /// Track the hyperlink under the pointer, so all of its cells are highlighted
+    fn update_hovered_link(&self, state: &mut TerminalCanvasState, row: usize, col: usize) {
+        let cell = (self.first_line + row, col);
+        if state.hovered_cell == Some(cell) {
+            return;
         }
+        state.hovered_cell = Some(cell);
+        state.hovered_link = match self.screen {
+            Some(screen) => screen.hyperlink_cells(cell.0, cell.1),
+            None => Vec::new(),
+        };
+    }
 
-        let line = &self.lines[row];
-        let mut current_col = 0;
+    fn draw_hovered_link(&self, frame: &mut Frame, state: &TerminalCanvasState, bounds: Rectangle) {
+        let (first_visible, last_visible) = self.visible_range(state.scroll_offset, bounds.height);
+        let line_height = config::line_height(self.font_size);
+        let char_width = config::char_width(self.font_size);
+        let y_offset = -(state.scroll_offset % line_height);
 
-        for span in line {
-            let span_len = span.text.chars().count();
-            if col >= current_col && col < current_col + span_len {
-                return span.hyperlink.as_ref().map(|arc| arc.as_ref().clone());
+        for &(line, col) in &state.hovered_link {
+            let Some(row) = line.checked_sub(self.first_line) else {
+                continue;
+            };
+            if row < first_visible || row >= last_visible {
+                continue;
             }
-            current_col += span_len;
+            let y = config::PADDING_TOP + y_offset + (row - first_visible) as f32 * line_height;
+            let x = config::PADDING_LEFT + col as f32 * char_width;
+            frame.fill_rectangle(
+                Point::new(x, y),
+                Size::new(char_width, line_height),
+                Color::from_rgba(0.3, 0.8, 0.9, 0.2),
+            );
         }
+    }
 
-        None
+    fn draw_search_matches(
+        &self,
+        frame: &mut Frame,
+        state: &TerminalCanvasState,
+        bounds: Rectangle,
+    ) {
+        let (first_visible, last_visible) = self.visible_range(state.scroll_offset, bounds.height);
+        let line_height = config::line_height(self.font_size);
+        let char_width = config::char_width(self.font_size);
+        let y_offset = -(state.scroll_offset % line_height);
+        let cols = self.screen.map_or(0, |screen| screen.dimensions().0);
+
+        // Matches are ordered by position, so only the visible ones are visited
+        let number = |row: usize| self.search_line_offset + self.first_line + row;
+        let first = self
+            .search_matches
+            .partition_point(|m| m.end_line < number(first_visible));
+        for (index, m) in self.search_matches.iter().enumerate().skip(first) {
+            if m.line >= number(last_visible) {
+                break;
+            }
+            let color = if Some(index) == self.current_match_index {
+                Color::from_rgba(0.95, 0.6, 0.2, 0.55)
+            } else {
+                Color::from_rgba(0.95, 0.77, 0.36, 0.3)
+            };
+            for line in m.line..=m.end_line {
+                let Some(row) = line.checked_sub(number(0)) else {
+                    continue;
+                };
+                if row < first_visible || row >= last_visible {
+                    continue;
+                }
+                let start = if line == m.line { m.start_col } else { 0 };
+                let end = if line == m.end_line { m.end_col } else { cols };
+                let y = config::PADDING_TOP + y_offset + (row - first_visible) as f32 * line_height;
+                let x = config::PADDING_LEFT + start as f32 * char_width;
+                frame.fill_rectangle(
+                    Point::new(x, y),
+                    Size::new(end.saturating_sub(start) as f32 * char_width, line_height),
+                    color,
+                );
+            }
+        }
     }
 
     fn draw_selection(&self, frame: &mut Frame, state: &TerminalCanvasState, bounds: Rectangle) {
@@ -424,7 +694,7 @@ impl<'a> TerminalCanvas<'a> {
                 let max_col = if row < self.lines.len() {
                     self.lines[row]
                         .iter()
-                        .map(|span| span.text.chars().count())
+                        .map(|span| column_count(&span.text))
                         .sum::<usize>()
                 } else {
                     0
@@ -438,7 +708,7 @@ impl<'a> TerminalCanvas<'a> {
                 let max_col = if row < self.lines.len() {
                     self.lines[row]
                         .iter()
-                        .map(|span| span.text.chars().count())
+                        .map(|span| column_count(&span.text))
                         .sum::<usize>()
                 } else {
                     0
@@ -580,6 +850,52 @@ impl<'a> TerminalCanvas<'a> {
         }
     }
 
+    /// Image tiles of the visible cells, each with the cell it covers
+    ///
+    /// Tiles are read from the screen (spans carry no images) and scaled to
+    /// the cell size. Only SIXEL tiles, which are decoded to RGBA, are returned.
+    fn visible_images(
+        &self,
+        state: &TerminalCanvasState,
+        bounds: Rectangle,
+    ) -> Vec<(Rectangle, canvas::Image)> {
+        let Some(screen) = self.screen else {
+            return Vec::new();
+        };
+        let (first, last) = self.visible_range(state.scroll_offset, bounds.height);
+        let line_height = config::line_height(self.font_size);
+        let char_width = config::char_width(self.font_size);
+        let y_offset = -(state.scroll_offset % line_height);
+
+        let lines = screen.get_lines(self.first_line + first..self.first_line + last);
+        let mut images = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            let y = config::PADDING_TOP + y_offset + i as f32 * line_height;
+            for (col, cell) in line.iter().enumerate() {
+                let Some(image) = cell
+                    .image
+                    .as_ref()
+                    .filter(|image| image.protocol == ImageProtocol::Sixel)
+                else {
+                    continue;
+                };
+                let x = config::PADDING_LEFT + col as f32 * char_width;
+                let handle = Handle::from_rgba(image.width, image.height, image.data.clone());
+                images.push((
+                    Rectangle::new(Point::new(x, y), Size::new(char_width, line_height)),
+                    canvas::Image::new(handle),
+                ));
+            }
+        }
+        images
+    }
+
+    fn draw_images(&self, frame: &mut Frame, state: &TerminalCanvasState, bounds: Rectangle) {
+        for (cell_bounds, image) in self.visible_images(state, bounds) {
+            frame.draw_image(cell_bounds, image);
+        }
+    }
+
     fn draw_line(&self, frame: &mut Frame, spans: &[StyledSpan], y: f32) {
         if spans.is_empty() {
             return;
@@ -594,9 +910,14 @@ impl<'a> TerminalCanvas<'a> {
         let mut current_bg: Option<Color> = None;
         let mut current_bold = false;
         let mut current_underline = false;
+        let mut current_underline_style = UnderlineStyle::Single;
+        let mut current_underline_color: Option<Color> = None;
         let mut current_dim = false;
         let mut current_italic = false;
         let mut current_strikethrough = false;
+        let mut current_blink = false;
+        let mut current_overline = false;
+        let mut current_hyperlink: Option<std::sync::Arc<String>> = None;
         let mut segment_start_x = x;
 
         for span in spans {
@@ -604,31 +925,21 @@ impl<'a> TerminalCanvas<'a> {
                 continue;
             }
 
-            let mut color = span.color.unwrap_or(self.default_color);
-
-            // Apply dim effect by reducing alpha
-            if span.dim {
-                color = Color::from_rgba(color.r, color.g, color.b, color.a * 0.5);
-            }
-
-            // Apply italic effect (color shift as Iced doesn't support italic directly)
-            if span.italic {
-                color = Color::from_rgba(
-                    color.r * 0.9,
-                    color.g * 0.9 + 0.1,
-                    color.b * 0.9 + 0.1,
-                    color.a,
-                );
-            }
+            let color = self.span_color(span);
 
             // Check if we can merge with current segment
             let can_merge = current_color == Some(color)
                 && current_bg == span.bg
                 && current_bold == span.bold
                 && current_underline == span.underline
+                && current_underline_style == span.underline_style
+                && current_underline_color == span.underline_color
                 && current_dim == span.dim
                 && current_italic == span.italic
                 && current_strikethrough == span.strikethrough
+                && current_blink == span.blink
+                && current_overline == span.overline
+                && current_hyperlink == span.hyperlink
                 && !merged_text.is_empty();
 
             if can_merge {
@@ -644,10 +955,14 @@ impl<'a> TerminalCanvas<'a> {
                         bg: current_bg,
                         bold: current_bold,
                         underline: current_underline,
+                        underline_style: current_underline_style,
+                        underline_color: current_underline_color,
                         dim: current_dim,
                         italic: current_italic,
                         strikethrough: current_strikethrough,
-                        hyperlink: None,
+                        blink: current_blink,
+                        overline: current_overline,
+                        hyperlink: current_hyperlink.clone(),
                     };
                     self.draw_text_segment(
                         frame,
@@ -658,7 +973,7 @@ impl<'a> TerminalCanvas<'a> {
                         &span_data,
                     );
 
-                    let char_count = merged_text.chars().count();
+                    let char_count = column_count(&merged_text);
                     segment_start_x += char_count as f32 * config::char_width(self.font_size);
                     x = segment_start_x;
                     merged_text.clear();
@@ -670,9 +985,14 @@ impl<'a> TerminalCanvas<'a> {
                 current_bg = span.bg;
                 current_bold = span.bold;
                 current_underline = span.underline;
+                current_underline_style = span.underline_style;
+                current_underline_color = span.underline_color;
                 current_dim = span.dim;
                 current_italic = span.italic;
                 current_strikethrough = span.strikethrough;
+                current_blink = span.blink;
+                current_overline = span.overline;
+                current_hyperlink = span.hyperlink.clone();
                 segment_start_x = x;
             }
         }
@@ -686,10 +1006,14 @@ impl<'a> TerminalCanvas<'a> {
                 bg: current_bg,
                 bold: current_bold,
                 underline: current_underline,
+                underline_style: current_underline_style,
+                underline_color: current_underline_color,
                 dim: current_dim,
                 italic: current_italic,
                 strikethrough: current_strikethrough,
-                hyperlink: None,
+                blink: current_blink,
+                overline: current_overline,
+                hyperlink: current_hyperlink,
             };
             self.draw_text_segment(
                 frame,
@@ -711,7 +1035,7 @@ impl<'a> TerminalCanvas<'a> {
         color: Color,
         span: &StyledSpan,
     ) {
-        let char_count = text.chars().count();
+        let char_count = column_count(text);
         let text_width = char_count as f32 * config::char_width(self.font_size);
         let line_height = config::line_height(self.font_size);
 
@@ -720,6 +1044,50 @@ impl<'a> TerminalCanvas<'a> {
             frame.fill_rectangle(Point::new(x, y), Size::new(text_width, line_height), bg);
         }
 
+        // Blinking text is drawn per frame by draw_blinking_text
+        if span.blink {
+            return;
+        }
+
+        self.draw_glyphs(frame, text, x, y, color, span);
+    }
+
+    /// Foreground color of a span with dim and italic applied
+    fn span_color(&self, span: &StyledSpan) -> Color {
+        let mut color = span.color.unwrap_or(self.default_color);
+
+        // Apply dim effect by reducing alpha
+        if span.dim {
+            color = Color::from_rgba(color.r, color.g, color.b, color.a * 0.5);
+        }
+
+        // Apply italic effect (color shift as Iced doesn't support italic directly)
+        if span.italic {
+            color = Color::from_rgba(
+                color.r * 0.9,
+                color.g * 0.9 + 0.1,
+                color.b * 0.9 + 0.1,
+                color.a,
+            );
+        }
+
+        color
+    }
+
+    /// Draw the text of a segment and its line decorations
+    fn draw_glyphs(
+        &self,
+        frame: &mut Frame,
+        text: &str,
+        x: f32,
+        y: f32,
+        color: Color,
+        span: &StyledSpan,
+    ) {
+        let char_count = column_count(text);
+        let text_width = char_count as f32 * config::char_width(self.font_size);
+        let line_height = config::line_height(self.font_size);
+
         // Use cyan color for hyperlinks
         let display_color = if span.hyperlink.is_some() {
             Color::from_rgb(0.3, 0.8, 0.8) // Cyan for URLs
@@ -741,14 +1109,27 @@ impl<'a> TerminalCanvas<'a> {
 
         // Draw underline for URLs or underlined text
         if span.underline || span.hyperlink.is_some() {
-            let underline_y = y + line_height - 2.0;
-            frame.fill_rectangle(
-                Point::new(x, underline_y),
-                Size::new(text_width, 1.0),
-                display_color,
+            let style = if span.underline {
+                span.underline_style
+            } else {
+                UnderlineStyle::Single
+            };
+            let underline_color = span.underline_color.unwrap_or(display_color);
+            self.draw_underline(
+                frame,
+                x,
+                y + line_height - 2.0,
+                text_width,
+                style,
+                underline_color,
             );
         }
 
+        // Draw overline
+        if span.overline {
+            frame.fill_rectangle(Point::new(x, y), Size::new(text_width, 1.0), display_color);
+        }
+
         // Draw strikethrough
         if span.strikethrough {
             let strikethrough_y = y + line_height / 2.0;
@@ -760,6 +1141,93 @@ impl<'a> TerminalCanvas<'a> {
         }
     }
 
+    fn draw_underline(
+        &self,
+        frame: &mut Frame,
+        x: f32,
+        y: f32,
+        width: f32,
+        style: UnderlineStyle,
+        color: Color,
+    ) {
+        match style {
+            UnderlineStyle::Single => {
+                frame.fill_rectangle(Point::new(x, y), Size::new(width, 1.0), color);
+            }
+            UnderlineStyle::Double => {
+                frame.fill_rectangle(Point::new(x, y), Size::new(width, 1.0), color);
+                frame.fill_rectangle(Point::new(x, y - 2.0), Size::new(width, 1.0), color);
+            }
+            UnderlineStyle::Curly => {
+                // One wave per cell
+                let period = config::char_width(self.font_size);
+                let wave = canvas::Path::new(|builder| {
+                    builder.move_to(Point::new(x, y));
+                    let mut offset = 0.0;
+                    let mut crest = true;
+                    while offset < width {
+                        let step = (period / 2.0).min(width - offset);
+                        let peak = if crest { y - 2.0 } else { y + 1.0 };
+                        builder.quadratic_curve_to(
+                            Point::new(x + offset + step / 2.0, peak),
+                            Point::new(x + offset + step, y),
+                        );
+                        offset += step;
+                        crest = !crest;
+                    }
+                });
+                frame.stroke(
+                    &wave,
+                    canvas::Stroke::default().with_color(color).with_width(1.0),
+                );
+            }
+            UnderlineStyle::Dotted | UnderlineStyle::Dashed => {
+                let (on, off) = if style == UnderlineStyle::Dotted {
+                    (1.0, 2.0)
+                } else {
+                    (3.0, 2.0)
+                };
+                let mut offset = 0.0;
+                while offset < width {
+                    let length = f32::min(on, width - offset);
+                    frame.fill_rectangle(Point::new(x + offset, y), Size::new(length, 1.0), color);
+                    offset += on + off;
+                }
+            }
+        }
+    }
+
+    /// Draw blinking text when the blink phase is on
+    ///
+    /// Blinking spans only get their background in the cached text layer, so
+    /// the glyphs are drawn here on the per-frame layer next to the cursor.
+    fn draw_blinking_text(
+        &self,
+        frame: &mut Frame,
+        state: &TerminalCanvasState,
+        bounds: Rectangle,
+    ) {
+        if !self.text_blink_on {
+            return;
+        }
+
+        let line_height = config::line_height(self.font_size);
+        let char_width = config::char_width(self.font_size);
+        let (first, last) = self.visible_range(state.scroll_offset, bounds.height);
+        let y_offset = -(state.scroll_offset % line_height);
+
+        for (i, line_idx) in (first..last).enumerate() {
+            let y = (config::PADDING_TOP + y_offset + (i as f32 * line_height)).round();
+            let mut x = config::PADDING_LEFT;
+            for span in &self.lines[line_idx] {
+                if span.blink && !span.text.is_empty() {
+                    self.draw_glyphs(frame, &span.text, x, y, self.span_color(span), span);
+                }
+                x += column_count(&span.text) as f32 * char_width;
+            }
+        }
+    }
+
     fn draw_cursor(&self, frame: &mut Frame, state: &TerminalCanvasState, bounds: Rectangle) {
         let cursor = match &self.cursor {
             Some(c) if c.visible && c.blink_on => c,
@@ -780,7 +1248,10 @@ impl<'a> TerminalCanvas<'a> {
             + y_offset
             + (visible_row as f32 * config::line_height(self.font_size));
 
-        let cursor_color = Color::from_rgba(0.9, 0.9, 0.9, 0.9);
+        let cursor_color = Color {
+            a: 0.9,
+            ..cursor.color
+        };
 
         match cursor.style {
             CursorStyle::Block => {
@@ -791,7 +1262,10 @@ impl<'a> TerminalCanvas<'a> {
                         config::char_width(self.font_size),
                         config::line_height(self.font_size),
                     ),
-                    Color::from_rgba(0.9, 0.9, 0.9, 0.7),
+                    Color {
+                        a: 0.7,
+                        ..cursor.color
+                    },
                 );
             }
             CursorStyle::Underline => {
@@ -814,9 +1288,21 @@ impl<'a> TerminalCanvas<'a> {
     }
 }
 
+/// Map an iced mouse button to a reportable button
+fn convert_mouse_button(button: mouse::Button) -> Option<MouseButton> {
+    match button {
+        mouse::Button::Left => Some(MouseButton::Left),
+        mouse::Button::Right => Some(MouseButton::Right),
+        mouse::Button::Middle => Some(MouseButton::Middle),
+        mouse::Button::Back => Some(MouseButton::Back),
+        mouse::Button::Forward => Some(MouseButton::Forward),
+        mouse::Button::Other(_) => None,
+    }
+}
+
 impl<'a, Message> canvas::Program<Message> for TerminalCanvas<'a>
 where
-    Message: Clone,
+    Message: Clone + From<TerminalCanvasMessage>,
 {
     type State = TerminalCanvasState;
 
@@ -835,9 +1321,47 @@ where
             }
         }
 
+        // Keep the view and selection on the same lines when lines are
+        // paged in above them or dropped from the top
+        if state.first_line != self.first_line {
+            let shift = |line: usize| (line + state.first_line).checked_sub(self.first_line);
+            let line_height = config::line_height(self.font_size);
+            state.scroll_offset = (state.scroll_offset
+                + (state.first_line as f32 - self.first_line as f32) * line_height)
+                .max(0.0);
+            if let Some(selection) = &mut state.selection {
+                match (shift(selection.start.line), shift(selection.end.line)) {
+                    (Some(start), Some(end)) => {
+                        selection.start.line = start;
+                        selection.end.line = end;
+                    }
+                    _ => state.selection = None,
+                }
+            }
+            state.first_line = self.first_line;
+            state.cache.clear();
+        }
+
+        // Center a requested line, e.g. the current search match
+        if let Some((line, serial)) = self.scroll_request {
+            if serial != state.scroll_request {
+                state.scroll_request = serial;
+                if let Some(row) = line.checked_sub(self.first_line) {
+                    let line_height = config::line_height(self.font_size);
+                    let max_scroll = (self.content_height() - bounds.height).max(0.0);
+                    state.scroll_offset = (row as f32 * line_height
+                        - (bounds.height - line_height) / 2.0)
+                        .clamp(0.0, max_scroll);
+                    state.cache.clear();
+                }
+            }
+        }
+
         // Content change detection with streaming mode
         if state.content_version != self.content_version {
             state.content_version = self.content_version;
+            state.hovered_cell = None;
+            state.hovered_link.clear();
             let now = Instant::now();
 
             // Check for rapid updates (streaming mode detection)
@@ -864,6 +1388,29 @@ where
             }
         }
 
+        if let canvas::Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) = event {
+            state.modifiers = modifiers;
+            return (canvas::event::Status::Ignored, None);
+        }
+
+        // While the application tracks the mouse, events go to the PTY.
+        // Holding Shift keeps local selection and scrolling available.
+        if self.mouse_mode != MouseMode::None
+            && !state.modifiers.shift()
+            && matches!(event, canvas::Event::Mouse(_))
+        {
+            let report = cursor
+                .position_in(bounds)
+                .and_then(|position| self.mouse_report(state, &event, position));
+            return match report {
+                Some(report) => (
+                    canvas::event::Status::Captured,
+                    Some(TerminalCanvasMessage::Mouse(report).into()),
+                ),
+                None => (canvas::event::Status::Ignored, None),
+            };
+        }
+
         match event {
             canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
                 if let Some(position) = cursor.position() {
@@ -877,10 +1424,30 @@ where
                         return (canvas::event::Status::Captured, None);
                     }
 
+                    let click = MouseEvent::new(
+                        MouseAction::Click(MouseButton::Left),
+                        MousePosition::new(position.x, position.y, col, row, false),
+                        MouseModifiers::none(),
+                        1,
+                    );
+                    let double_click = state.click_detector.detect(&click) == 2;
+
                     let mut selection = Selection::new();
-                    selection.start(row, col, SelectionMode::Character);
+                    // Double-click selects the word under the pointer (ends are inclusive here)
+                    let word = double_click
+                        .then(|| word_bounds(&self.line_text(row), col))
+                        .flatten();
+                    match word {
+                        Some((start, end)) => {
+                            selection.start(row, start, SelectionMode::Word);
+                            selection.extend(row, end - 1);
+                        }
+                        None => {
+                            selection.start(row, col, SelectionMode::Character);
+                            state.is_dragging = true;
+                        }
+                    }
                     state.selection = Some(selection);
-                    state.is_dragging = true;
                     // Don't clear cache for selection start - selection overlay is separate
                     if !state.streaming_mode {
                         state.cache.clear();
@@ -890,6 +1457,13 @@ where
                 (canvas::event::Status::Ignored, None)
             }
             canvas::Event::Mouse(mouse::Event::CursorMoved { .. }) => {
+                if !state.is_dragging {
+                    if let Some(position) = cursor.position() {
+                        let (row, col) =
+                            self.pixel_to_cell(position.x, position.y, state.scroll_offset);
+                        self.update_hovered_link(state, row, col);
+                    }
+                }
                 if state.is_dragging {
                     if let Some(position) = cursor.position() {
                         let (row, col) =
@@ -945,6 +1519,13 @@ where
                     }
                 }
 
+                // Ask for older lines when scrolling up past the first one
+                if scroll_amount < 0.0 && new_offset <= 0.0 && self.first_line > 0 {
+                    return (
+                        canvas::event::Status::Captured,
+                        Some(TerminalCanvasMessage::Scrolled(new_offset).into()),
+                    );
+                }
                 (canvas::event::Status::Captured, None)
             }
             _ => (canvas::event::Status::Ignored, None),
@@ -975,10 +1556,14 @@ where
             // Draw bracket highlights
             self.draw_bracket_highlights(&mut frame, state, bounds);
 
-            // Draw visible lines directly
+            // Draw visible lines and images directly
             self.draw_lines(&mut frame, state, bounds);
+            self.draw_images(&mut frame, state, bounds);
+            self.draw_blinking_text(&mut frame, state, bounds);
 
-            // Draw cursor
+            // Draw search matches, cursor and hovered link
+            self.draw_search_matches(&mut frame, state, bounds);
+            self.draw_hovered_link(&mut frame, state, bounds);
             self.draw_cursor(&mut frame, state, bounds);
 
             return vec![frame.into_geometry()];
@@ -995,12 +1580,17 @@ where
             // Draw bracket highlights
             self.draw_bracket_highlights(frame, state, bounds);
 
-            // Draw visible lines
+            // Draw visible lines and images
             self.draw_lines(frame, state, bounds);
+            self.draw_images(frame, state, bounds);
         });
 
-        // Cursor is drawn separately (no cache) for blinking support
+        // Search matches, cursor, hovered link and blinking text are drawn
+        // separately (no cache)
         let mut cursor_frame = Frame::new(renderer, bounds.size());
+        self.draw_search_matches(&mut cursor_frame, state, bounds);
+        self.draw_hovered_link(&mut cursor_frame, state, bounds);
+        self.draw_blinking_text(&mut cursor_frame, state, bounds);
         self.draw_cursor(&mut cursor_frame, state, bounds);
         let cursor_geometry = cursor_frame.into_geometry();
 
@@ -1024,3 +1614,78 @@ where
         vec![text_geometry, cursor_geometry]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_click_selects_word() {
+        let mut screen = TerminalScreen::new(40, 2);
+        screen.process("ls cafe\u{301}_menu.txt".as_bytes());
+        let lines: Vec<Vec<StyledSpan>> = screen
+            .get_all_lines()
+            .iter()
+            .map(|cells| crate::cells_to_styled_spans(cells))
+            .collect();
+        let terminal = TerminalCanvas::new(&lines, 0, Color::WHITE, Font::MONOSPACE);
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(400.0, 100.0));
+        let char_width = config::char_width(config::BASE_FONT_SIZE);
+        // Over the "m" of "menu"
+        let position = Point::new(
+            config::PADDING_LEFT + 8.5 * char_width,
+            config::PADDING_TOP + 1.0,
+        );
+
+        let mut state = TerminalCanvasState::default();
+        let press = || canvas::Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+        let release = || canvas::Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left));
+        let cursor = mouse::Cursor::Available(position);
+        for event in [press(), release(), press(), release()] {
+            canvas::Program::<TerminalCanvasMessage>::update(
+                &terminal, &mut state, event, bounds, cursor,
+            );
+        }
+
+        let selection = state.selection.as_ref().unwrap();
+        assert_eq!(selection.mode, SelectionMode::Word);
+        assert_eq!(get_selected_text(&lines, selection), "cafe\u{301}_menu");
+    }
+
+    #[test]
+    fn test_sixel_tiles_drawn_over_their_cells() {
+        let mut screen = TerminalScreen::new(20, 4);
+        screen.set_cell_pixel_size(4, 6);
+        screen.process(b"AB");
+        // 10x12 pixel image: 3 columns x 2 rows of 4x6 cells
+        screen.process(b"\x1bPq\"1;1;10;12#1;2;100;0;0#1!10~-!10~\x1b\\");
+
+        let lines = vec![Vec::new(); 4];
+        let canvas =
+            TerminalCanvas::new(&lines, 0, Color::WHITE, Font::MONOSPACE).with_screen(&screen);
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(400.0, 200.0));
+        let images = canvas.visible_images(&TerminalCanvasState::default(), bounds);
+
+        let line_height = config::line_height(config::BASE_FONT_SIZE);
+        let char_width = config::char_width(config::BASE_FONT_SIZE);
+        assert_eq!(images.len(), 6);
+        for (i, (cell_bounds, image)) in images.iter().enumerate() {
+            let (row, col) = (i / 3, 2 + i % 3);
+            let x = config::PADDING_LEFT + col as f32 * char_width;
+            let y = config::PADDING_TOP + row as f32 * line_height;
+            let cell = Rectangle::new(Point::new(x, y), Size::new(char_width, line_height));
+            assert_eq!(*cell_bounds, cell);
+            assert!(matches!(
+                &image.handle,
+                Handle::Rgba { width: 4, height: 6, pixels, .. } if pixels.len() == 4 * 6 * 4
+            ));
+        }
+
+        // Nothing is drawn once the image is scrolled out of view
+        let state = TerminalCanvasState {
+            scroll_offset: 2.0 * line_height,
+            ..TerminalCanvasState::default()
+        };
+        assert!(canvas.visible_images(&state, bounds).is_empty());
+    }
+}
@@ -1,2 +1,3 @@
 pub mod palette;
+pub mod search_bar;
 pub mod status_bar;
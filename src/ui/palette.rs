@@ -139,6 +139,7 @@ impl CommandPalette {
             PaletteItem::new("clear_screen", "Clear Screen", "View").with_shortcut("Cmd+K"),
             PaletteItem::new("scroll_to_top", "Scroll to Top", "View"),
             PaletteItem::new("scroll_to_bottom", "Scroll to Bottom", "View"),
+            PaletteItem::new("search", "Search Scrollback", "View").with_shortcut("Cmd+Shift+F"),
             // Font
             PaletteItem::new("increase_font", "Increase Font Size", "Font")
                 .with_shortcut("Cmd++"),
@@ -153,6 +154,12 @@ impl CommandPalette {
             // Clipboard
             PaletteItem::new("copy", "Copy Selection", "Clipboard").with_shortcut("Cmd+C"),
             PaletteItem::new("paste", "Paste", "Clipboard").with_shortcut("Cmd+V"),
+            // Signals to the foreground job
+            PaletteItem::new("signal_interrupt", "Interrupt Process (SIGINT)", "Process"),
+            PaletteItem::new("signal_suspend", "Suspend Process (SIGTSTP)", "Process"),
+            PaletteItem::new("signal_continue", "Continue Process (SIGCONT)", "Process"),
+            PaletteItem::new("signal_terminate", "Terminate Process (SIGTERM)", "Process"),
+            PaletteItem::new("signal_kill", "Kill Process (SIGKILL)", "Process"),
         ];
 
         let mut palette = Self::new();
@@ -161,6 +168,12 @@ impl CommandPalette {
         palette
     }
 
+    /// Add a command after construction (e.g. one per SSH host)
+    pub fn add_item(&mut self, item: PaletteItem) {
+        self.items.push(item);
+        self.filter_items();
+    }
+
     /// Get the number of filtered items
     pub fn filtered_count(&self) -> usize {
         self.filtered_items.len()
@@ -0,0 +1,312 @@
+//! Scrollback search bar for AgTerm terminal emulator
+//!
+//! Holds the query and options typed into the search bar (Cmd+Shift+F) and keeps a
+//! [`BackgroundSearch`] of the active tab's lines up to date with them.
+
+use iced::widget::text_input::Id as TextInputId;
+use iced::widget::{button, container, row, text, text_input};
+use iced::{Alignment, Border, Color, Element, Length};
+
+use crate::terminal::screen::TerminalScreen;
+use crate::terminal::search::{BackgroundSearch, SearchOptions, SearchScope};
+
+/// Get the search bar input ID
+pub fn search_input_id() -> TextInputId {
+    TextInputId::new("scrollback_search_input")
+}
+
+/// Messages for the search bar
+#[derive(Debug, Clone)]
+pub enum SearchBarMessage {
+    /// Search input changed
+    InputChanged(String),
+    /// Toggle regex queries
+    ToggleRegex,
+    /// Toggle case sensitive matching
+    ToggleCaseSensitive,
+    /// Toggle whole word matching
+    ToggleWholeWord,
+    /// Toggle searching only the output of commands
+    ToggleCommandOutput,
+    /// Select the next match
+    Next,
+    /// Select the previous match
+    Prev,
+    /// Close the search bar
+    Close,
+}
+
+/// Query and options typed into the scrollback search bar
+#[derive(Debug, Default)]
+pub struct SearchBar {
+    query: String,
+    regex_mode: bool,
+    case_sensitive: bool,
+    whole_word: bool,
+    /// Only search the output of commands, as marked by the shell
+    command_output: bool,
+    /// Why the query is not a valid regex
+    error: Option<String>,
+}
+
+impl SearchBar {
+    /// Create an empty search bar
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Change the query or an option
+    ///
+    /// Returns true if running searches are out of date. Selecting matches and
+    /// closing the bar are left to the caller.
+    pub fn update(&mut self, message: SearchBarMessage) -> bool {
+        match message {
+            SearchBarMessage::InputChanged(query) => self.query = query,
+            SearchBarMessage::ToggleRegex => self.regex_mode ^= true,
+            SearchBarMessage::ToggleCaseSensitive => self.case_sensitive ^= true,
+            SearchBarMessage::ToggleWholeWord => self.whole_word ^= true,
+            SearchBarMessage::ToggleCommandOutput => self.command_output ^= true,
+            SearchBarMessage::Next | SearchBarMessage::Prev | SearchBarMessage::Close => {
+                return false;
+            }
+        }
+        self.error = None;
+        true
+    }
+
+    fn options(&self, output_scope: impl FnOnce() -> SearchScope) -> SearchOptions {
+        SearchOptions {
+            regex_mode: self.regex_mode,
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+            scope: if self.command_output {
+                output_scope()
+            } else {
+                SearchScope::All
+            },
+        }
+    }
+
+    /// Search `screen` for the query
+    ///
+    /// Collects the matches found so far and, once the search has finished and
+    /// the screen `changed` since, searches the changed lines. `output_scope`
+    /// gives the lines of command output, as marked by the shell.
+    ///
+    /// Returns true if `search` was started or rescanned over the current lines.
+    pub fn search(
+        &mut self,
+        search: &mut Option<BackgroundSearch>,
+        screen: &TerminalScreen,
+        changed: bool,
+        output_scope: impl FnOnce() -> SearchScope,
+    ) -> bool {
+        if self.query.is_empty() || self.error.is_some() {
+            return false;
+        }
+
+        match search {
+            Some(search) => {
+                search.poll();
+                if search.is_running() || !changed {
+                    return false;
+                }
+                if self.command_output {
+                    search.set_scope(output_scope());
+                }
+                search.rescan(screen);
+            }
+            None => match BackgroundSearch::new(&self.query, self.options(output_scope)) {
+                Ok(mut started) => {
+                    started.start(screen.search_snapshot(screen.line_offset()));
+                    *search = Some(started);
+                }
+                Err(e) => {
+                    // The last line of a regex error says what is wrong
+                    let error = e.to_string();
+                    self.error = error.lines().last().map(str::to_string);
+                    return false;
+                }
+            },
+        }
+        true
+    }
+
+    /// Render the search bar with its options and the match count of `search`
+    pub fn view<'a>(&'a self, search: Option<&BackgroundSearch>) -> Element<'a, SearchBarMessage> {
+        let bg_primary = Color::from_rgb(0.09, 0.09, 0.11);
+        let bg_secondary = Color::from_rgb(0.12, 0.12, 0.15);
+        let bg_block = Color::from_rgb(0.14, 0.14, 0.18);
+        let bg_block_hover = Color::from_rgb(0.18, 0.18, 0.22);
+        let text_primary = Color::from_rgb(0.93, 0.93, 0.95);
+        let text_secondary = Color::from_rgb(0.6, 0.62, 0.68);
+        let text_muted = Color::from_rgb(0.45, 0.47, 0.52);
+        let border_color = Color::from_rgb(0.22, 0.22, 0.28);
+        let accent_blue = Color::from_rgb(0.36, 0.54, 0.98);
+        let accent_red = Color::from_rgb(0.92, 0.39, 0.45);
+
+        let search_button = |label, message, active: bool| {
+            button(text(label).size(12))
+                .padding([4, 8])
+                .style(move |_, status| {
+                    let bg = match status {
+                        _ if active => accent_blue,
+                        button::Status::Hovered => bg_block_hover,
+                        _ => bg_block,
+                    };
+                    button::Style {
+                        background: Some(bg.into()),
+                        text_color: text_primary,
+                        border: Border {
+                            radius: 4.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                })
+                .on_press(message)
+        };
+
+        let input = text_input("Search scrollback", &self.query)
+            .id(search_input_id())
+            .on_input(SearchBarMessage::InputChanged)
+            .on_submit(SearchBarMessage::Next)
+            .padding([4, 8])
+            .size(13)
+            .style(move |_, status| text_input::Style {
+                background: bg_secondary.into(),
+                border: Border {
+                    color: if matches!(status, text_input::Status::Focused) {
+                        accent_blue
+                    } else {
+                        border_color
+                    },
+                    width: 1.0,
+                    radius: 4.0.into(),
+                },
+                icon: text_primary,
+                placeholder: text_muted,
+                value: text_primary,
+                selection: accent_blue,
+            });
+
+        let (status, status_color) = match (&self.error, search) {
+            (Some(error), _) => (error.clone(), accent_red),
+            (None, Some(search)) if search.is_running() => {
+                let (searched, total) = search.progress();
+                let percent = searched * 100 / total.max(1);
+                (format!("Searching… {percent}%"), text_secondary)
+            }
+            (None, Some(search)) if search.matches().is_empty() => {
+                ("No matches".to_string(), text_secondary)
+            }
+            (None, Some(search)) => {
+                let current = search.current_index().map_or(0, |index| index + 1);
+                let count = search.matches().len();
+                (format!("{current}/{count}"), text_secondary)
+            }
+            (None, None) => (String::new(), text_secondary),
+        };
+
+        container(
+            row![
+                input,
+                search_button(
+                    "Aa",
+                    SearchBarMessage::ToggleCaseSensitive,
+                    self.case_sensitive
+                ),
+                search_button("W", SearchBarMessage::ToggleWholeWord, self.whole_word),
+                search_button(".*", SearchBarMessage::ToggleRegex, self.regex_mode),
+                search_button(
+                    "Output",
+                    SearchBarMessage::ToggleCommandOutput,
+                    self.command_output
+                ),
+                text(status).size(12).color(status_color),
+                search_button("↑", SearchBarMessage::Prev, false),
+                search_button("↓", SearchBarMessage::Next, false),
+                search_button("✕", SearchBarMessage::Close, false),
+            ]
+            .spacing(6)
+            .align_y(Alignment::Center),
+        )
+        .padding([6, 12])
+        .width(Length::Fill)
+        .style(move |_| container::Style {
+            background: Some(bg_primary.into()),
+            border: Border {
+                color: border_color,
+                width: 1.0,
+                radius: 0.0.into(),
+            },
+            ..Default::default()
+        })
+        .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, Instant};
+
+    fn finish(search: &mut Option<BackgroundSearch>) {
+        let search = search.as_mut().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while search.is_running() {
+            search.poll();
+            assert!(Instant::now() < deadline, "search did not finish");
+            std::thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn test_search_bar_update() {
+        let mut bar = SearchBar::new();
+        assert!(bar.update(SearchBarMessage::InputChanged("needle".to_string())));
+        assert!(bar.update(SearchBarMessage::ToggleRegex));
+        assert_eq!(bar.query, "needle");
+        assert!(bar.regex_mode);
+
+        assert!(!bar.update(SearchBarMessage::Next));
+        assert!(!bar.update(SearchBarMessage::Close));
+        assert_eq!(bar.query, "needle");
+    }
+
+    #[test]
+    fn test_search_bar_searches_screen() {
+        let mut screen = TerminalScreen::new(20, 5);
+        screen.process(b"hay\r\nneedle\r\nhay needle");
+
+        let mut bar = SearchBar::new();
+        let mut search = None;
+        assert!(!bar.search(&mut search, &screen, true, || SearchScope::All));
+        assert!(search.is_none());
+
+        bar.update(SearchBarMessage::InputChanged("needle".to_string()));
+        assert!(bar.search(&mut search, &screen, true, || SearchScope::All));
+        finish(&mut search);
+        assert_eq!(search.as_ref().unwrap().matches().len(), 2);
+
+        // Nothing changed since the search finished
+        assert!(!bar.search(&mut search, &screen, false, || SearchScope::All));
+    }
+
+    #[test]
+    fn test_search_bar_invalid_regex() {
+        let screen = TerminalScreen::new(20, 5);
+        let mut bar = SearchBar::new();
+        bar.update(SearchBarMessage::ToggleRegex);
+        bar.update(SearchBarMessage::InputChanged("(".to_string()));
+
+        let mut search = None;
+        assert!(!bar.search(&mut search, &screen, true, || SearchScope::All));
+        assert!(search.is_none());
+        assert!(bar.error.is_some());
+
+        // Editing the query clears the error
+        bar.update(SearchBarMessage::InputChanged("()".to_string()));
+        assert!(bar.error.is_none());
+    }
+}
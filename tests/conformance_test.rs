@@ -0,0 +1,45 @@
+//! Conformance tests replaying the `.vt` fixtures in `tests/fixtures`
+//!
+//! Each fixture is fed through `TerminalScreen::process` and the screen dump
+//! is compared with the `.snap` file next to it. To add a fixture or accept a
+//! behavior change, run with `AGTERM_BLESS=1` and review the snapshot diff:
+//!
+//! ```text
+//! AGTERM_BLESS=1 cargo test --test conformance_test
+//! ```
+
+use std::path::Path;
+
+use agterm::conformance::{check_fixture, fixtures_in, snapshot_path, Outcome, BLESS_ENV};
+
+#[test]
+fn test_fixtures_match_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    let bless = std::env::var_os(BLESS_ENV).is_some_and(|value| value != "0");
+
+    let fixtures = fixtures_in(&dir).expect("read fixture directory");
+    assert!(!fixtures.is_empty(), "no fixtures in {}", dir.display());
+
+    let mut failures = Vec::new();
+    for fixture in &fixtures {
+        match check_fixture(fixture, bless).expect("check fixture") {
+            Outcome::Match | Outcome::Blessed => {}
+            Outcome::Missing { actual } => failures.push(format!(
+                "{}: no snapshot, expected {}:\n{actual}",
+                fixture.display(),
+                snapshot_path(fixture).display()
+            )),
+            Outcome::Mismatch { diff } => {
+                failures.push(format!("{}: snapshot differs:\n{diff}", fixture.display()))
+            }
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} fixtures failed (rerun with {BLESS_ENV}=1 to update snapshots)\n\n{}",
+        failures.len(),
+        fixtures.len(),
+        failures.join("\n")
+    );
+}
@@ -113,4 +113,39 @@ mod tests {
         assert_eq!(pty_env.unset.len(), 1);
         assert_eq!(pty_env.unset[0], "OLD_VAR");
     }
+
+    #[test]
+    fn test_pty_environment_resolve() {
+        let mut variables = HashMap::new();
+        variables.insert("TERM".to_string(), "xterm".to_string());
+        variables.insert("TEST_VAR".to_string(), "test_value".to_string());
+
+        let pty_env = PtyEnvironment {
+            inherit_env: true,
+            variables,
+            unset: vec!["PATH".to_string(), "COLORTERM".to_string()],
+        };
+        let resolved = pty_env.resolve();
+
+        // Unset removes inherited and terminal variables
+        assert!(!resolved.contains_key("PATH"));
+        assert!(!resolved.contains_key("COLORTERM"));
+        // Explicit variables override terminal variables
+        assert_eq!(resolved.get("TERM"), Some(&"xterm".to_string()));
+        assert_eq!(resolved.get("TEST_VAR"), Some(&"test_value".to_string()));
+        assert_eq!(resolved.get("TERM_PROGRAM"), Some(&"agterm".to_string()));
+    }
+
+    #[test]
+    fn test_pty_environment_resolve_without_inherit() {
+        let pty_env = PtyEnvironment {
+            inherit_env: false,
+            variables: HashMap::new(),
+            unset: Vec::new(),
+        };
+        let resolved = pty_env.resolve();
+
+        assert!(!resolved.contains_key("PATH"));
+        assert_eq!(resolved.get("TERM"), Some(&"xterm-256color".to_string()));
+    }
 }
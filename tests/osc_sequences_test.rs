@@ -1,6 +1,6 @@
 // Integration test for OSC sequence handling
 
-use agterm::terminal::screen::TerminalScreen;
+use agterm::terminal::screen::{ShellMark, TerminalScreen};
 
 #[test]
 fn test_osc_window_title() {
@@ -80,12 +80,90 @@ fn test_osc_file_uri_parsing() {
     // Test file:///absolute/path
     screen.process(b"\x1b]7;file:///absolute/path\x1b\\");
     assert_eq!(screen.cwd_from_shell(), Some("/absolute/path"));
+    assert_eq!(screen.host_from_shell(), Some(""));
 
     // Test file://hostname/path/to/dir
     screen.process(b"\x1b]7;file://hostname/path/to/dir\x1b\\");
     assert_eq!(screen.cwd_from_shell(), Some("/path/to/dir"));
+    assert_eq!(screen.host_from_shell(), Some("hostname"));
 
     // Test file:/single/slash
     screen.process(b"\x1b]7;file:/single/slash\x1b\\");
     assert_eq!(screen.cwd_from_shell(), Some("/single/slash"));
+    assert_eq!(screen.host_from_shell(), None);
+}
+
+#[test]
+fn test_osc_shell_marks() {
+    let mut screen = TerminalScreen::new(80, 24);
+
+    screen
+        .process(b"\x1b]133;A\x07$ \x1b]133;B\x07echo hi\r\n\x1b]633;E;echo hi\x07\x1b]133;C\x07");
+    let marks = screen.take_shell_marks();
+    let summary: Vec<(u32, &str)> = marks
+        .iter()
+        .map(|mark| (mark.command, mark.data.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (133, "133;A"),
+            (133, "133;B"),
+            (633, "633;E;echo hi"),
+            (133, "133;C")
+        ]
+    );
+    assert_eq!(
+        marks[1],
+        ShellMark {
+            command: 133,
+            data: "133;B".to_string(),
+            line: 0,
+            col: 2,
+            alternate_screen: false,
+        }
+    );
+    assert_eq!(screen.text_between((0, 2), (1, 0)), "echo hi\n");
+    assert!(screen.take_shell_marks().is_empty());
+
+    // Other OSC sequences are not shell marks
+    screen.process(b"\x1b]2;title\x07");
+    assert!(screen.take_shell_marks().is_empty());
+}
+
+#[test]
+fn test_osc_1337_shell_marks() {
+    let mut screen = TerminalScreen::new(80, 24);
+
+    // Inline images and other OSC 1337 keys are not shell integration
+    screen.process(b"\x1b]1337;File=inline=1:AAAA\x07");
+    screen.process(b"\x1b]1337;SetMark\x07");
+    screen.process(b"\x1b]1337;CurrentDir=/srv\x07");
+    let marks = screen.take_shell_marks();
+    let summary: Vec<&str> = marks.iter().map(|mark| mark.data.as_str()).collect();
+    assert_eq!(summary, ["1337;CurrentDir=/srv"]);
+}
+
+#[test]
+fn test_shell_marks_on_alternate_screen() {
+    let mut screen = TerminalScreen::new(80, 24);
+
+    // Marks on the alternate screen are flagged, their line is not in the scrollback
+    screen.process(b"\x1b[?1049h\x1b]133;A\x07\x1b[?1049l\x1b]133;A\x07");
+    let flags: Vec<bool> = screen
+        .take_shell_marks()
+        .iter()
+        .map(|mark| mark.alternate_screen)
+        .collect();
+    assert_eq!(flags, [true, false]);
+}
+
+#[test]
+fn test_text_between_wrapped_lines() {
+    let mut screen = TerminalScreen::new(10, 5);
+
+    // A command line longer than the screen is wide
+    screen.process(b"$ 0123456789abc\r\nout");
+    assert_eq!(screen.text_between((0, 2), (1, 5)), "0123456789abc");
+    assert_eq!(screen.text_between((0, 0), (2, 3)), "$ 0123456789abc\nout");
 }
@@ -0,0 +1,56 @@
+//! Snapshot tests of the generated shell integration scripts
+//!
+//! Each script is compared with `tests/fixtures/shell_integration/<shell>.snap`.
+//! To accept a change to a script, run with `AGTERM_BLESS=1` and review the
+//! snapshot diff:
+//!
+//! ```text
+//! AGTERM_BLESS=1 cargo test --test shell_integration_test
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use agterm::conformance::{diff, BLESS_ENV};
+use agterm::shell_integration::{integration_script, ShellType};
+
+const SHELLS: &[(ShellType, &str)] = &[
+    (ShellType::Bash, "bash"),
+    (ShellType::Zsh, "zsh"),
+    (ShellType::Fish, "fish"),
+    (ShellType::PowerShell, "powershell"),
+    (ShellType::Nushell, "nushell"),
+];
+
+#[test]
+fn test_scripts_match_snapshots() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/shell_integration");
+    let bless = std::env::var_os(BLESS_ENV).is_some_and(|value| value != "0");
+
+    let mut failures = Vec::new();
+    for &(shell, name) in SHELLS {
+        let actual = integration_script(shell);
+        let snapshot = dir.join(format!("{name}.snap"));
+
+        if bless {
+            fs::write(&snapshot, &actual).expect("write snapshot");
+            continue;
+        }
+        match fs::read_to_string(&snapshot) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => failures.push(format!(
+                "{name}: snapshot differs:\n{}",
+                diff(&expected, &actual)
+            )),
+            Err(e) => failures.push(format!("{}: {e}", snapshot.display())),
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} of {} scripts failed (rerun with {BLESS_ENV}=1 to update snapshots)\n\n{}",
+        failures.len(),
+        SHELLS.len(),
+        failures.join("\n")
+    );
+}
@@ -746,6 +746,7 @@ fn test_session_config_custom() {
         max_backups: 5,
         session_file: Some(PathBuf::from("/custom/session.json")),
         prompt_on_recovery: true,
+        scrollback_lines: 500,
     };
 
     assert!(!session_config.restore_on_startup);
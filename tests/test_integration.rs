@@ -391,15 +391,15 @@ fn test_mouse_mode_persistence() {
 
     // Enable mouse reporting
     screen.process(b"\x1b[?1000h");
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
 
     // Mouse mode should persist through other operations
     screen.process(b"Hello, World!\r\n");
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
 
     // Clear screen shouldn't affect mouse mode
     screen.process(b"\x1b[2J");
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
 }
 
 #[test]
@@ -410,7 +410,7 @@ fn test_mouse_encoding_with_mode() {
     screen.process(b"\x1b[?1000h");
     screen.process(b"\x1b[?1006h");
 
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
     assert_eq!(screen.mouse_encoding(), MouseEncoding::Sgr);
 }
 
@@ -961,13 +961,13 @@ fn test_mouse_mode_with_alternate_screen() {
 
     // Enable mouse on main screen
     screen.process(b"\x1b[?1000h");
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
 
     // Enter alternate screen
     screen.process(b"\x1b[?1049h");
 
     // Mouse mode should persist
-    assert_eq!(screen.mouse_mode(), MouseMode::X10);
+    assert_eq!(screen.mouse_mode(), MouseMode::Normal);
 
     // Disable mouse on alternate screen
     screen.process(b"\x1b[?1000l");
@@ -0,0 +1,208 @@
+use agterm::terminal::screen::TerminalScreen;
+
+fn line_text(screen: &TerminalScreen, row: usize) -> String {
+    screen.get_all_lines()[row]
+        .iter()
+        .map(|cell| cell.c)
+        .collect()
+}
+
+#[test]
+fn test_default_tab_stops() {
+    let mut screen = TerminalScreen::new(30, 3);
+    screen.process(b"\t");
+    assert_eq!(screen.cursor_position(), (0, 8));
+    screen.process(b"\t\t");
+    assert_eq!(screen.cursor_position(), (0, 24));
+
+    // No stop left: tab stops at the right margin
+    screen.process(b"\t");
+    assert_eq!(screen.cursor_position(), (0, 29));
+}
+
+#[test]
+fn test_set_tab_stop_hts() {
+    let mut screen = TerminalScreen::new(30, 3);
+    // Set a stop at column 4: ESC [ 5 G ESC H
+    screen.process(b"\x1b[5G\x1bH\r");
+
+    screen.process(b"\t");
+    assert_eq!(screen.cursor_position(), (0, 4));
+    screen.process(b"\t");
+    assert_eq!(screen.cursor_position(), (0, 8));
+}
+
+#[test]
+fn test_clear_tab_stop_tbc() {
+    let mut screen = TerminalScreen::new(30, 3);
+    // Clear the stop at column 8: ESC [ 9 G ESC [ g
+    screen.process(b"\x1b[9G\x1b[g\r\t");
+    assert_eq!(screen.cursor_position(), (0, 16));
+
+    // Clear all stops: ESC [ 3 g
+    screen.process(b"\x1b[3g\r\t");
+    assert_eq!(screen.cursor_position(), (0, 29));
+}
+
+#[test]
+fn test_tab_stops_reset_by_ris() {
+    let mut screen = TerminalScreen::new(30, 3);
+    screen.process(b"\x1b[3g\x1bc\t");
+    assert_eq!(screen.cursor_position(), (0, 8));
+}
+
+#[test]
+fn test_cursor_forward_tabulation_cht() {
+    let mut screen = TerminalScreen::new(40, 3);
+    // ESC [ 2 I moves forward two stops
+    screen.process(b"\x1b[2I");
+    assert_eq!(screen.cursor_position(), (0, 16));
+
+    // Default parameter is 1
+    screen.process(b"\x1b[I");
+    assert_eq!(screen.cursor_position(), (0, 24));
+}
+
+#[test]
+fn test_cursor_backward_tabulation_cbt() {
+    let mut screen = TerminalScreen::new(40, 3);
+    screen.process(b"\x1b[1;20H\x1b[Z");
+    assert_eq!(screen.cursor_position(), (0, 16));
+
+    screen.process(b"\x1b[2Z");
+    assert_eq!(screen.cursor_position(), (0, 0));
+
+    // Already at the left margin
+    screen.process(b"\x1b[Z");
+    assert_eq!(screen.cursor_position(), (0, 0));
+}
+
+#[test]
+fn test_cursor_horizontal_absolute_cha_hpa() {
+    let mut screen = TerminalScreen::new(20, 5);
+    screen.process(b"\x1b[3;1H\x1b[10G");
+    assert_eq!(screen.cursor_position(), (2, 9));
+
+    screen.process(b"\x1b[4`");
+    assert_eq!(screen.cursor_position(), (2, 3));
+
+    // Default parameter and clamping
+    screen.process(b"\x1b[G");
+    assert_eq!(screen.cursor_position(), (2, 0));
+    screen.process(b"\x1b[99G");
+    assert_eq!(screen.cursor_position(), (2, 19));
+}
+
+#[test]
+fn test_vertical_position_absolute_vpa() {
+    let mut screen = TerminalScreen::new(20, 5);
+    screen.process(b"\x1b[1;6H\x1b[4d");
+    assert_eq!(screen.cursor_position(), (3, 5));
+
+    screen.process(b"\x1b[d");
+    assert_eq!(screen.cursor_position(), (0, 5));
+    screen.process(b"\x1b[99d");
+    assert_eq!(screen.cursor_position(), (4, 5));
+}
+
+#[test]
+fn test_cursor_next_and_previous_line() {
+    let mut screen = TerminalScreen::new(20, 5);
+    screen.process(b"\x1b[2;10H\x1b[2E");
+    assert_eq!(screen.cursor_position(), (3, 0));
+
+    screen.process(b"\x1b[1;10H\x1b[E");
+    assert_eq!(screen.cursor_position(), (1, 0));
+
+    screen.process(b"\x1b[5;10H\x1b[3F");
+    assert_eq!(screen.cursor_position(), (1, 0));
+
+    // Clamped at the screen edges
+    screen.process(b"\x1b[9F");
+    assert_eq!(screen.cursor_position(), (0, 0));
+    screen.process(b"\x1b[9E");
+    assert_eq!(screen.cursor_position(), (4, 0));
+}
+
+#[test]
+fn test_cursor_next_and_previous_line_outside_scroll_region() {
+    let mut screen = TerminalScreen::new(20, 10);
+    // Scroll region rows 3-6 (0-based 2-5)
+    screen.process(b"\x1b[3;6r");
+
+    // Inside the region: stops at the margins
+    screen.process(b"\x1b[4;1H\x1b[9E");
+    assert_eq!(screen.cursor_position(), (5, 0));
+    screen.process(b"\x1b[9F");
+    assert_eq!(screen.cursor_position(), (2, 0));
+
+    // Below the region: moves down to the screen edge, never back up
+    screen.process(b"\x1b[8;1H\x1b[9E");
+    assert_eq!(screen.cursor_position(), (9, 0));
+    // Above the region: moves up to the screen edge, never back down
+    screen.process(b"\x1b[2;1H\x1b[9F");
+    assert_eq!(screen.cursor_position(), (0, 0));
+
+    // Outside the region and moving towards it: stops at the margin
+    screen.process(b"\x1b[1;1H\x1b[9E");
+    assert_eq!(screen.cursor_position(), (5, 0));
+    screen.process(b"\x1b[9;1H\x1b[9F");
+    assert_eq!(screen.cursor_position(), (2, 0));
+}
+
+#[test]
+fn test_erase_characters_ech() {
+    let mut screen = TerminalScreen::new(10, 3);
+    screen.process(b"ABCDEFGHIJ");
+
+    // Erase 3 characters at column 2: ESC [ 1 ; 3 H ESC [ 3 X
+    screen.process(b"\x1b[1;3H\x1b[3X");
+    assert_eq!(line_text(&screen, 0), "AB   FGHIJ");
+    // Cursor does not move
+    assert_eq!(screen.cursor_position(), (0, 2));
+
+    // Default parameter erases one character
+    screen.process(b"\x1b[1;8H\x1b[X");
+    assert_eq!(line_text(&screen, 0), "AB   FG IJ");
+
+    // Erasing past the end of the line stops at the margin
+    screen.process(b"\x1b[1;9H\x1b[50X");
+    assert_eq!(line_text(&screen, 0), "AB   FG   ");
+}
+
+#[test]
+fn test_repeat_preceding_character_rep() {
+    let mut screen = TerminalScreen::new(10, 3);
+    screen.process(b"A\x1b[3b");
+    assert_eq!(line_text(&screen, 0), "AAAA      ");
+    assert_eq!(screen.cursor_position(), (0, 4));
+
+    // Repeated characters wrap like printed ones
+    screen.process(b"B\x1b[6b");
+    assert_eq!(line_text(&screen, 0), "AAAABBBBBB");
+    assert_eq!(line_text(&screen, 1), "B         ");
+}
+
+#[test]
+fn test_repeat_without_preceding_character() {
+    let mut screen = TerminalScreen::new(10, 3);
+    screen.process(b"\x1b[5b");
+    assert_eq!(line_text(&screen, 0), "          ");
+    assert_eq!(screen.cursor_position(), (0, 0));
+}
+
+#[test]
+fn test_repeat_after_control_or_escape_sequence() {
+    let mut screen = TerminalScreen::new(10, 3);
+    // A control character in between: nothing to repeat
+    screen.process(b"A\r\x1b[3b");
+    assert_eq!(line_text(&screen, 0), "A         ");
+
+    // An escape sequence in between: nothing to repeat either
+    screen.process(b"\x1b[2;1HB\x1b[1m\x1b[3b");
+    assert_eq!(line_text(&screen, 1), "B         ");
+
+    // A repetition can itself be repeated
+    screen.process(b"\x1b[3;1HC\x1b[2b\x1b[2b");
+    assert_eq!(line_text(&screen, 2), "CCCCC     ");
+}